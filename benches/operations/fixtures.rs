@@ -1,5 +1,5 @@
 use dlt_explorer::dlt::payload::{DLT_SCOD_UTF8, DLT_TYPE_INFO_STRG};
-use dlt_explorer::dlt::v2::test_helpers::V2MessageBuilder;
+use dlt_explorer::dlt::writer::MessageBuilder;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct BenchmarkSizes {
@@ -146,12 +146,13 @@ pub fn build_v1_message(storage_ecu: [u8; 4], payload: &[u8], mcnt: u8) -> Vec<u
 }
 
 pub fn build_v2_message(storage_ecu: [u8; 4], payload_text: &str) -> Vec<u8> {
-    V2MessageBuilder::new()
+    MessageBuilder::new()
         .with_storage_ecu(&storage_ecu)
         .with_apid("BENC")
         .with_ctid("SCAN")
         .with_verbose_string(payload_text)
-        .build()
+        .build(2)
+        .unwrap()
 }
 
 pub fn build_v1_dataset(spec: ScenarioSpec) -> Vec<u8> {
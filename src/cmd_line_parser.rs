@@ -1,25 +1,201 @@
 pub use clap::Parser;
+use clap::{Subcommand, ValueEnum};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::desktop::PresetOptions;
+use crate::dlt::merged::{MergeMode, parse_ecu_offset};
+use crate::dlt::stream::{DEFAULT_BAUD_RATE, LiveSource};
+use crate::dlt::time_window::{TimeBound, TimeClock, TimeWindow, parse_time_bound};
+use crate::output::OutputFormat;
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Path to DLT files
     pub paths: Option<Vec<PathBuf>>,
 
+    /// Stream live messages from a dlt-daemon at HOST[:PORT] (default port 3490)
+    #[arg(long, value_name = "HOST[:PORT]", conflicts_with_all = ["paths", "udp", "serial"])]
+    pub tcp: Option<String>,
+
+    /// Receive live messages over UDP on [ADDR:]PORT; a multicast ADDR joins the group
+    #[arg(long, value_name = "[ADDR:]PORT", conflicts_with_all = ["paths", "serial"])]
+    pub udp: Option<String>,
+
+    /// Read live messages with DLS serial headers from a serial device or pty
+    #[arg(long, value_name = "DEVICE", conflicts_with = "paths")]
+    pub serial: Option<PathBuf>,
+
+    /// Baud rate for --serial
+    #[arg(long, default_value_t = DEFAULT_BAUD_RATE, requires = "serial")]
+    pub baud_rate: u32,
+
+    /// Keep reading messages appended to the files, like `tail -f`
+    #[arg(long, default_value_t = false, conflicts_with = "merge")]
+    pub follow: bool,
+
     /// Path to DLT filter file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub filter: Option<PathBuf>,
 
+    /// Apply the saved filter preset NAME, as saved in the desktop
+    #[arg(long, value_name = "NAME", global = true)]
+    pub preset: Option<String>,
+
+    /// Filter preset file to use instead of the one in the user config directory
+    #[arg(long, value_name = "FILE", global = true)]
+    pub preset_file: Option<PathBuf>,
+
     /// Use terminal as output
     #[arg(short, long, default_value_t = false)]
     pub terminal: bool,
 
     /// Sort DLT files by name
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     pub sort: bool,
 
     /// Maximum number of rows to print in terminal mode
     #[arg(short, long)]
     pub limit: Option<usize>,
+
+    /// Select rows from TIME on: SECONDS, +SECONDS after the first row, or YYYY-MM-DD HH:MM:SS UTC
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub from: Option<TimeBound>,
+
+    /// Select rows before this time, in the same formats as --from
+    #[arg(long, value_name = "TIME", value_parser = parse_time_bound)]
+    pub to: Option<TimeBound>,
+
+    /// Timestamp --from and --to apply to
+    #[arg(long, value_enum, default_value_t = TimeSource::Storage)]
+    pub time: TimeSource,
+
+    /// Row format in terminal mode
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output_format: OutputFormat,
+
+    /// Row order across files [default: path order; storage for mixed versions]
+    #[arg(short, long, value_enum, global = true)]
+    pub merge: Option<MergeOrder>,
+
+    /// Clock offset for `--merge message`, as ECU=SECONDS (repeatable)
+    #[arg(
+        long = "ecu-offset",
+        value_name = "ECU=SECONDS",
+        value_parser = parse_ecu_offset,
+        global = true
+    )]
+    pub ecu_offsets: Vec<(String, i64)>,
+
+    /// FIBEX file describing non-verbose messages (repeatable)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub fibex: Vec<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Write the (filtered) messages to a new DLT file, byte-exact with the source frames
+    Export {
+        /// Path of the DLT file to write
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Path to DLT files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Write the (filtered) messages to a new DLT file in the other protocol version
+    Convert {
+        /// Path of the DLT file to write
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Protocol version to write
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        to: u8,
+
+        /// Path to DLT files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Print message counts and messages lost to message counter gaps
+    Stats {
+        /// Path to DLT files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+/// Row order selectable with `--merge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeOrder {
+    /// Path order, then on-disk order within each file
+    Concat,
+    /// Merge files by storage header timestamp
+    Storage,
+    /// Merge files by message timestamp, shifted by --ecu-offset
+    Message,
+}
+
+/// Timestamp selectable with `--time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeSource {
+    /// Storage header timestamp, wall time of reception
+    Storage,
+    /// Message header timestamp, time since ECU start
+    Message,
+}
+
+impl Cli {
+    /// The live input requested with `--tcp`, `--udp` or `--serial`, if any.
+    pub fn live_source(&self) -> Option<LiveSource> {
+        if let Some(address) = &self.tcp {
+            return Some(LiveSource::Tcp(address.clone()));
+        }
+        if let Some(address) = &self.udp {
+            return Some(LiveSource::Udp(address.clone()));
+        }
+        self.serial.as_ref().map(|path| LiveSource::Serial {
+            path: path.clone(),
+            baud_rate: self.baud_rate,
+        })
+    }
+
+    /// The time window requested with `--from` and `--to`, if any.
+    pub fn time_window(&self) -> Option<TimeWindow> {
+        if self.from.is_none() && self.to.is_none() {
+            return None;
+        }
+        let clock = match self.time {
+            TimeSource::Storage => TimeClock::Storage,
+            TimeSource::Message => TimeClock::Message,
+        };
+        Some(TimeWindow {
+            clock,
+            from: self.from,
+            to: self.to,
+        })
+    }
+
+    pub(crate) fn preset_options(&self) -> PresetOptions {
+        PresetOptions {
+            name: self.preset.clone(),
+            file: self.preset_file.clone(),
+        }
+    }
+
+    /// The merge mode requested on the command line, if any.
+    pub fn merge_mode(&self) -> Option<MergeMode> {
+        self.merge.map(|order| match order {
+            MergeOrder::Concat => MergeMode::Concatenate,
+            MergeOrder::Storage => MergeMode::StorageTimestamp,
+            MergeOrder::Message => MergeMode::MessageTimestamp {
+                ecu_offsets_ns: self.ecu_offsets.iter().cloned().collect::<HashMap<_, _>>(),
+            },
+        })
+    }
 }
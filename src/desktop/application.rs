@@ -1,6 +1,16 @@
-use crate::desktop::retained::{RetainedDataSet, StructuredFilter, load_retained_dataset};
+use crate::desktop::preset::{FilterPreset, PresetLibrary};
+use crate::desktop::retained::{
+    LoadOptions, RetainedDataSet, StructuredFilter, live_retained_dataset, load_retained_dataset,
+};
+use crate::dlt::control::{ControlMessage, RequestLog};
+use crate::dlt::fibex::FibexDatabase;
+use crate::dlt::filter::FilterSet;
+use crate::dlt::merged::MergeMode;
+use crate::dlt::stream::LiveSource;
+use crate::dlt::time_window::{TimeBound, TimeClock};
 use anyhow::Result;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub(crate) type LoadGeneration = u64;
 
@@ -10,7 +20,7 @@ pub(crate) enum DesktopIntent {
     OpenFilesCancelled,
     LoadSucceeded {
         generation: LoadGeneration,
-        data: RetainedDataSet,
+        data: Box<RetainedDataSet>,
     },
     LoadFailed {
         generation: LoadGeneration,
@@ -19,6 +29,48 @@ pub(crate) enum DesktopIntent {
     ResetRequested,
     StructuredFilterUpdated(StructuredFilter),
     StructuredFilterCleared,
+    FilterFileLoaded {
+        path: PathBuf,
+        filter_set: FilterSet,
+    },
+    FilterFileFailed(String),
+    FilterFileCleared,
+    MergeModeChanged(Option<MergeMode>),
+    FibexLoaded {
+        paths: Vec<PathBuf>,
+        database: Arc<FibexDatabase>,
+    },
+    FibexFailed(String),
+    FibexCleared,
+    ExportVisibleRequested(PathBuf),
+    LiveConnectRequested(LiveSource),
+    LiveSourceInvalid(String),
+    LiveFramesReceived {
+        generation: LoadGeneration,
+        version: u8,
+        frames: Vec<u8>,
+    },
+    LiveClosed {
+        generation: LoadGeneration,
+        error: Option<String>,
+    },
+    LiveDisconnectRequested,
+    ControlRequestSent(ControlMessage),
+    ControlSendFailed(String),
+    RowIndexSelected(usize),
+    GoToTimeRequested(TimeClock, TimeBound),
+    FollowToggled(bool),
+    FollowRefreshRequested,
+    PresetLibraryLoaded {
+        path: Option<PathBuf>,
+        library: PresetLibrary,
+    },
+    PresetFailed(String),
+    PresetSelected(String),
+    PresetSaved(String),
+    PresetDeleted(String),
+    PresetsImported(PathBuf),
+    PresetsExported(PathBuf),
     RenderedSearchQueryUpdated(String),
     RenderedSearchCleared,
     RenderedSearchPrevious,
@@ -43,6 +95,29 @@ pub(crate) struct DesktopModel {
     retained: Option<RetainedDataSet>,
     active_load_generation: Option<LoadGeneration>,
     next_load_generation: LoadGeneration,
+    filter_path: Option<PathBuf>,
+    filter_set: Option<FilterSet>,
+    filter_error: Option<String>,
+    merge_mode: Option<MergeMode>,
+    fibex_paths: Vec<PathBuf>,
+    fibex: Option<Arc<FibexDatabase>>,
+    fibex_error: Option<String>,
+    export_status: Option<String>,
+    live_generation: Option<LoadGeneration>,
+    live_source: Option<LiveSource>,
+    live_status: Option<String>,
+    control_requests: RequestLog,
+    control_status: Option<String>,
+    follow: bool,
+    follow_error: Option<String>,
+    presets: PresetLibrary,
+    /// File the preset library is saved to; `None` when there is no user
+    /// config directory.
+    presets_path: Option<PathBuf>,
+    /// Last applied preset; its filter is applied again to every load until
+    /// the Structured Filter is changed.
+    active_preset: Option<FilterPreset>,
+    preset_status: Option<String>,
 }
 
 impl Default for DesktopModel {
@@ -52,6 +127,25 @@ impl Default for DesktopModel {
             retained: None,
             active_load_generation: None,
             next_load_generation: 0,
+            filter_path: None,
+            filter_set: None,
+            filter_error: None,
+            merge_mode: None,
+            fibex_paths: Vec::new(),
+            fibex: None,
+            fibex_error: None,
+            export_status: None,
+            live_generation: None,
+            live_source: None,
+            live_status: None,
+            control_requests: RequestLog::default(),
+            control_status: None,
+            follow: false,
+            follow_error: None,
+            presets: PresetLibrary::default(),
+            presets_path: None,
+            active_preset: None,
+            preset_status: None,
         }
     }
 }
@@ -61,15 +155,102 @@ impl DesktopModel {
         &self.state
     }
 
+    pub(crate) fn filter_path(&self) -> Option<&PathBuf> {
+        self.filter_path.as_ref()
+    }
+
+    pub(crate) fn filter_set(&self) -> Option<&FilterSet> {
+        self.filter_set.as_ref()
+    }
+
+    pub(crate) fn filter_error(&self) -> Option<&str> {
+        self.filter_error.as_deref()
+    }
+
+    /// Row order for the next load; `None` uses the library default.
+    pub(crate) fn merge_mode(&self) -> Option<&MergeMode> {
+        self.merge_mode.as_ref()
+    }
+
+    /// FIBEX files whose descriptions decode non-verbose messages on the next load.
+    pub(crate) fn fibex_paths(&self) -> &[PathBuf] {
+        &self.fibex_paths
+    }
+
+    pub(crate) fn fibex_error(&self) -> Option<&str> {
+        self.fibex_error.as_deref()
+    }
+
+    /// Merge mode and FIBEX descriptions to open files with.
+    pub(crate) fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            merge: self.merge_mode.clone(),
+            fibex: self.fibex.clone(),
+            follow: self.follow,
+        }
+    }
+
+    /// Outcome of the last "Export visible" action, for the toolbar.
+    pub(crate) fn export_status(&self) -> Option<&str> {
+        self.export_status.as_deref()
+    }
+
     pub(crate) fn active_load_generation(&self) -> Option<LoadGeneration> {
         self.active_load_generation
     }
 
+    /// Generation of the connected live stream, if any; frames from other
+    /// generations are stale and ignored.
+    pub(crate) fn live_generation(&self) -> Option<LoadGeneration> {
+        self.live_generation
+    }
+
+    /// Connection state of the last live stream, for the toolbar.
+    pub(crate) fn live_status(&self) -> Option<&str> {
+        self.live_status.as_deref()
+    }
+
+    /// Control requests sent on the current live connection.
+    pub(crate) fn control_requests(&self) -> &RequestLog {
+        &self.control_requests
+    }
+
+    /// Outcome of the last control request action, for the control panel.
+    pub(crate) fn control_status(&self) -> Option<&str> {
+        self.control_status.as_deref()
+    }
+
+    /// Whether loaded files are polled for appended messages.
+    pub(crate) fn follow(&self) -> bool {
+        self.follow
+    }
+
+    /// Why following stopped, for the toolbar.
+    pub(crate) fn follow_error(&self) -> Option<&str> {
+        self.follow_error.as_deref()
+    }
+
+    pub(crate) fn presets(&self) -> &[FilterPreset] {
+        self.presets.presets()
+    }
+
+    pub(crate) fn active_preset_name(&self) -> Option<&str> {
+        self.active_preset
+            .as_ref()
+            .map(|preset| preset.name.as_str())
+    }
+
+    /// Outcome of the last preset action, for the toolbar.
+    pub(crate) fn preset_status(&self) -> Option<&str> {
+        self.preset_status.as_deref()
+    }
+
     pub(crate) fn apply_intent(&mut self, intent: DesktopIntent) {
         match intent {
             DesktopIntent::OpenFilesRequested => {
                 self.next_load_generation = self.next_load_generation.saturating_add(1);
                 self.active_load_generation = Some(self.next_load_generation);
+                self.stop_live();
                 self.state = DesktopAppState::Loading;
             }
             DesktopIntent::OpenFilesCancelled => {
@@ -80,6 +261,12 @@ impl DesktopModel {
                     return;
                 }
 
+                let mut data = *data;
+                if self.filter_set.is_some() {
+                    data.set_filter_set(self.filter_set.clone());
+                }
+                self.apply_active_preset(&mut data);
+
                 self.active_load_generation = None;
                 self.retained = Some(data);
                 self.state = DesktopAppState::Loaded;
@@ -100,15 +287,259 @@ impl DesktopModel {
                 self.reset_idle();
             }
             DesktopIntent::StructuredFilterUpdated(filter) => {
+                if self
+                    .active_preset
+                    .as_ref()
+                    .is_some_and(|preset| preset.filter != filter)
+                {
+                    self.active_preset = None;
+                }
                 if let Some(data) = self.retained.as_mut() {
                     data.set_structured_filter(filter);
                 }
             }
             DesktopIntent::StructuredFilterCleared => {
+                self.active_preset = None;
                 if let Some(data) = self.retained.as_mut() {
                     data.clear_filter();
                 }
             }
+            DesktopIntent::FilterFileLoaded { path, filter_set } => {
+                if let Some(data) = self.retained.as_mut() {
+                    data.set_filter_set(Some(filter_set.clone()));
+                }
+                self.filter_path = Some(path);
+                self.filter_set = Some(filter_set);
+                self.filter_error = None;
+            }
+            DesktopIntent::FilterFileFailed(message) => {
+                self.filter_error = Some(message);
+            }
+            DesktopIntent::FilterFileCleared => {
+                if let Some(data) = self.retained.as_mut() {
+                    data.set_filter_set(None);
+                }
+                self.filter_path = None;
+                self.filter_set = None;
+                self.filter_error = None;
+            }
+            DesktopIntent::MergeModeChanged(mode) => {
+                self.merge_mode = mode;
+            }
+            DesktopIntent::FibexLoaded { paths, database } => {
+                self.fibex_paths = paths;
+                self.fibex = Some(database);
+                self.fibex_error = None;
+            }
+            DesktopIntent::FibexFailed(message) => {
+                self.fibex_error = Some(message);
+            }
+            DesktopIntent::FibexCleared => {
+                self.fibex_paths.clear();
+                self.fibex = None;
+                self.fibex_error = None;
+            }
+            DesktopIntent::ExportVisibleRequested(path) => {
+                let Some(data) = self.retained.as_ref() else {
+                    return;
+                };
+                self.export_status = Some(match data.export_visible(&path) {
+                    Ok(summary) => {
+                        format!("Exported {} rows to {}", summary.rows, path.display())
+                    }
+                    Err(err) => format!("Export failed: {err:#}"),
+                });
+            }
+            DesktopIntent::LiveConnectRequested(source) => {
+                self.next_load_generation = self.next_load_generation.saturating_add(1);
+                self.active_load_generation = None;
+                self.live_generation = Some(self.next_load_generation);
+                self.live_status = Some(format!("Connecting to {source}"));
+                self.live_source = Some(source);
+                self.control_requests = RequestLog::default();
+                self.control_status = None;
+                self.retained = None;
+                self.state = DesktopAppState::Loading;
+            }
+            DesktopIntent::LiveFramesReceived {
+                generation,
+                version,
+                frames,
+            } => {
+                if self.live_generation != Some(generation) {
+                    return;
+                }
+
+                if self.retained.is_none() {
+                    let source = self
+                        .live_source
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    match live_retained_dataset(source.clone(), version, &self.load_options()) {
+                        Ok(mut data) => {
+                            data.set_filter_set(self.filter_set.clone());
+                            self.apply_active_preset(&mut data);
+                            self.retained = Some(data);
+                            self.live_status = Some(format!("Streaming from {source}"));
+                            self.state = DesktopAppState::Loaded;
+                        }
+                        Err(err) => {
+                            self.live_generation = None;
+                            self.state = DesktopAppState::Error(format!("{err:#}"));
+                            return;
+                        }
+                    }
+                }
+
+                let Some(data) = self.retained.as_mut() else {
+                    return;
+                };
+                let first_row = data.message_count();
+                if let Err(err) = data.append_frames(&frames) {
+                    self.live_generation = None;
+                    self.live_status = Some(format!("Stream stopped: {err:#}"));
+                }
+                if self.control_requests.pending_count() > 0 {
+                    for (row, response) in data.control_responses(first_row..data.message_count()) {
+                        self.control_requests.match_response(row, response);
+                    }
+                }
+            }
+            DesktopIntent::LiveClosed { generation, error } => {
+                if self.live_generation != Some(generation) {
+                    return;
+                }
+
+                self.live_generation = None;
+                let status = match error {
+                    Some(message) => format!("Disconnected: {message}"),
+                    None => "Disconnected".to_string(),
+                };
+                if self.retained.is_none() {
+                    self.state = DesktopAppState::Error(status.clone());
+                }
+                self.live_status = Some(status);
+            }
+            DesktopIntent::LiveSourceInvalid(message) => {
+                self.live_status = Some(message);
+            }
+            DesktopIntent::LiveDisconnectRequested => {
+                if self.live_generation.is_some() {
+                    self.stop_live();
+                    self.live_status = Some("Disconnected".to_string());
+                    if self.retained.is_none() {
+                        self.state = DesktopAppState::Idle;
+                    }
+                }
+            }
+            DesktopIntent::ControlRequestSent(request) => {
+                self.control_status = Some(format!("Sent {request}"));
+                self.control_requests.record(request);
+            }
+            DesktopIntent::ControlSendFailed(message) => {
+                self.control_status = Some(message);
+            }
+            DesktopIntent::RowIndexSelected(index) => {
+                if let Some(data) = self.retained.as_mut()
+                    && !data.select_row_index(index)
+                {
+                    self.control_status = Some(format!("Row {index} is hidden by the filter"));
+                }
+            }
+            DesktopIntent::GoToTimeRequested(clock, bound) => {
+                if let Some(data) = self.retained.as_mut()
+                    && !data.select_nearest_time(clock, bound)
+                {
+                    self.control_status = Some("No visible rows".to_string());
+                }
+            }
+            DesktopIntent::FollowToggled(follow) => {
+                self.follow = follow;
+                self.follow_error = None;
+            }
+            DesktopIntent::FollowRefreshRequested => {
+                if !self.follow {
+                    return;
+                }
+                if let Some(data) = self.retained.as_mut()
+                    && data.live_source.is_none()
+                    && let Err(err) = data.refresh_files()
+                {
+                    self.follow = false;
+                    self.follow_error = Some(format!("Follow stopped: {err:#}"));
+                }
+            }
+            DesktopIntent::PresetLibraryLoaded { path, library } => {
+                self.presets = library;
+                self.presets_path = path;
+            }
+            DesktopIntent::PresetFailed(message) => {
+                self.preset_status = Some(message);
+            }
+            DesktopIntent::PresetSelected(name) => {
+                let preset = match self.presets.find(&name) {
+                    Ok(preset) => preset.clone(),
+                    Err(err) => {
+                        self.preset_status = Some(err.to_string());
+                        return;
+                    }
+                };
+                if let Some(data) = self.retained.as_mut() {
+                    data.set_structured_filter(preset.filter.clone());
+                }
+                self.active_preset = Some(preset);
+                self.preset_status = None;
+            }
+            DesktopIntent::PresetSaved(name) => {
+                let name = name.trim();
+                if name.is_empty() {
+                    self.preset_status = Some("Enter a name for the preset".to_string());
+                    return;
+                }
+                let preset = FilterPreset {
+                    name: name.to_string(),
+                    filter: self
+                        .retained
+                        .as_ref()
+                        .map(|data| data.active_filter.clone())
+                        .unwrap_or_default(),
+                };
+                self.presets.insert(preset.clone());
+                self.active_preset = Some(preset);
+                self.preset_status = Some(self.save_presets(&format!("Saved preset \"{name}\"")));
+            }
+            DesktopIntent::PresetDeleted(name) => {
+                if !self.presets.remove(&name) {
+                    return;
+                }
+                if self.active_preset_name() == Some(name.as_str()) {
+                    self.active_preset = None;
+                }
+                self.preset_status = Some(self.save_presets(&format!("Deleted preset \"{name}\"")));
+            }
+            DesktopIntent::PresetsImported(path) => {
+                self.preset_status = Some(match PresetLibrary::load(&path) {
+                    Ok(imported) => {
+                        let count = self.presets.merge(imported);
+                        self.save_presets(&format!(
+                            "Imported {count} preset(s) from {}",
+                            path.display()
+                        ))
+                    }
+                    Err(err) => format!("Import failed: {err:#}"),
+                });
+            }
+            DesktopIntent::PresetsExported(path) => {
+                self.preset_status = Some(match self.presets.save(&path) {
+                    Ok(()) => format!(
+                        "Exported {} preset(s) to {}",
+                        self.presets.presets().len(),
+                        path.display()
+                    ),
+                    Err(err) => format!("Export failed: {err:#}"),
+                });
+            }
             DesktopIntent::RenderedSearchQueryUpdated(query) => {
                 if let Some(data) = self.retained.as_mut() {
                     data.set_rendered_search_query(query);
@@ -147,9 +578,33 @@ impl DesktopModel {
     pub(crate) fn reset_idle(&mut self) {
         self.retained = None;
         self.active_load_generation = None;
+        self.stop_live();
         self.state = DesktopAppState::Idle;
     }
 
+    fn apply_active_preset(&self, data: &mut RetainedDataSet) {
+        if let Some(preset) = &self.active_preset {
+            data.set_structured_filter(preset.filter.clone());
+        }
+    }
+
+    /// Write the preset library to its file; returns `done`, or why saving
+    /// failed.
+    fn save_presets(&self, done: &str) -> String {
+        let Some(path) = &self.presets_path else {
+            return format!("{done}; not saved, no user config directory");
+        };
+        match self.presets.save(path) {
+            Ok(()) => done.to_string(),
+            Err(err) => format!("{done}; saving failed: {err:#}"),
+        }
+    }
+
+    fn stop_live(&mut self) {
+        self.live_generation = None;
+        self.live_status = None;
+    }
+
     pub(crate) fn take_pending_scroll_to_selected(&mut self) -> bool {
         self.retained
             .as_mut()
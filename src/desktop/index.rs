@@ -1,10 +1,16 @@
-use crate::desktop::retained::{LogTableRow, RetainedDlt, StructuredFilter};
+use crate::desktop::query::SearchQuery;
+use crate::desktop::retained::{
+    LogTableRow, StructuredFilter, log_level_within, log_table_row, parse_message_id,
+};
+use crate::dlt::filter::FilterSet;
+use crate::dlt::store::DltStore;
 use std::ops::Range;
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct QueryPipeline<'a> {
     structured_filter: &'a StructuredFilter,
-    rendered_search_query: &'a str,
+    filter_set: Option<&'a FilterSet>,
+    rendered_search: Option<&'a SearchQuery>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -16,73 +22,154 @@ pub(crate) struct IndexLayer {
 impl<'a> QueryPipeline<'a> {
     pub(crate) fn new(
         structured_filter: &'a StructuredFilter,
-        rendered_search_query: &'a str,
+        filter_set: Option<&'a FilterSet>,
+        rendered_search: Option<&'a SearchQuery>,
     ) -> Self {
         Self {
             structured_filter,
-            rendered_search_query,
+            filter_set,
+            rendered_search,
         }
     }
 
-    pub(crate) fn build(self, dlt: &RetainedDlt) -> IndexLayer {
-        let structured_filtered_indices = self.apply_structured_filter(dlt);
+    pub(crate) fn build(self, dlt: &dyn DltStore) -> IndexLayer {
+        let mut index = IndexLayer::empty();
+        self.extend(dlt, &mut index, 0..dlt.len());
+        index
+    }
+
+    /// Run the pipeline over `rows` only, appending the matches to `index`.
+    ///
+    /// Used when rows are appended to a live store, so existing rows are not
+    /// filtered again.
+    pub(crate) fn extend(self, dlt: &dyn DltStore, index: &mut IndexLayer, rows: Range<usize>) {
+        let structured_filtered_indices = self.apply_structured_filter(dlt, rows);
         let visible_indices = self.apply_rendered_text_search(dlt, structured_filtered_indices);
-        let rendered_search_match_positions = if self.rendered_search_query.is_empty() {
-            Vec::new()
-        } else {
-            (0..visible_indices.len()).collect()
+        if self.rendered_search.is_some() {
+            let first = index.visible_indices.len();
+            index
+                .rendered_search_match_positions
+                .extend(first..first + visible_indices.len());
+        }
+        index.visible_indices.extend(visible_indices);
+    }
+
+    fn apply_structured_filter(self, dlt: &dyn DltStore, rows: Range<usize>) -> Vec<usize> {
+        rows.filter(|&index| self.structured_filter_matches(dlt, index))
+            .collect()
+    }
+
+    /// Structured Filter and Filter Set criteria for one row.
+    pub(crate) fn structured_filter_matches(self, dlt: &dyn DltStore, index: usize) -> bool {
+        let ecu_matches =
+            contains_ignore_case(dlt.ecu(index), self.structured_filter.ecu_contains.as_str());
+        let apid_matches = contains_ignore_case(
+            dlt.apid(index),
+            self.structured_filter.apid_contains.as_str(),
+        );
+        let ctid_matches = contains_ignore_case(
+            dlt.ctid(index),
+            self.structured_filter.ctid_contains.as_str(),
+        );
+        let kind_matches = self.structured_filter.kind_contains.is_empty() || {
+            let kind = super::retained::format_message_type(
+                dlt.message_type(index),
+                dlt.message_type_info(index),
+            );
+            contains_ignore_case(kind.as_str(), self.structured_filter.kind_contains.as_str())
         };
+        let level_matches = self
+            .structured_filter
+            .least_severe_level
+            .is_none_or(|least_severe| log_level_within(dlt, index, 1, least_severe));
+
+        ecu_matches
+            && apid_matches
+            && ctid_matches
+            && kind_matches
+            && level_matches
+            && self.v2_fields_match(dlt, index)
+            && self.message_id_matches(dlt, index)
+            && self
+                .structured_filter
+                .rules
+                .iter()
+                .all(|rule| rule.matches(dlt, index))
+            && self.filter_set_matches(dlt, index)
+    }
+
+    /// Source location, tag and privacy level criteria; v1 rows carry none
+    /// of these, so they only match while the criteria are empty.
+    fn v2_fields_match(self, dlt: &dyn DltStore, index: usize) -> bool {
+        let filter = self.structured_filter;
+        let file = filter.source_file_ends_with.trim();
+        let line = filter.source_line_equals.trim();
+        if !file.is_empty() || !line.is_empty() {
+            let Some((source_file, source_line)) = dlt.source_location(index) else {
+                return false;
+            };
+            if !source_file
+                .to_ascii_lowercase()
+                .ends_with(&file.to_ascii_lowercase())
+            {
+                return false;
+            }
+            if !line.is_empty() && line.parse() != Ok(source_line) {
+                return false;
+            }
+        }
+        let tag = filter.tag_equals.trim();
+        if !tag.is_empty()
+            && !dlt
+                .tags(index)
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(tag))
+        {
+            return false;
+        }
+        let privacy = filter.privacy_equals.trim();
+        if !privacy.is_empty()
+            && dlt
+                .privacy_level(index)
+                .map(|level| level.to_string())
+                .as_deref()
+                != Some(privacy)
+        {
+            return false;
+        }
+        true
+    }
 
-        IndexLayer {
-            visible_indices,
-            rendered_search_match_positions,
+    /// Rows without a message ID, and every row when the criterion does not
+    /// parse as an ID, are hidden while a message ID is entered.
+    fn message_id_matches(self, dlt: &dyn DltStore, index: usize) -> bool {
+        let text = self.structured_filter.message_id_equals.trim();
+        if text.is_empty() {
+            return true;
         }
+        parse_message_id(text).is_some_and(|id| dlt.message_id(index) == Some(id))
     }
 
-    fn apply_structured_filter(self, dlt: &RetainedDlt) -> Vec<usize> {
-        (0..dlt.len())
-            .filter(|&index| {
-                let ecu_matches = contains_ignore_case(
-                    dlt.ecu(index),
-                    self.structured_filter.ecu_contains.as_str(),
-                );
-                let apid_matches = contains_ignore_case(
-                    dlt.apid(index),
-                    self.structured_filter.apid_contains.as_str(),
-                );
-                let ctid_matches = contains_ignore_case(
-                    dlt.ctid(index),
-                    self.structured_filter.ctid_contains.as_str(),
-                );
-                let kind = super::retained::format_message_type(
-                    dlt.message_type(index),
-                    dlt.message_type_info(index),
-                );
-                let kind_matches = contains_ignore_case(
-                    kind.as_str(),
-                    self.structured_filter.kind_contains.as_str(),
-                );
-
-                ecu_matches && apid_matches && ctid_matches && kind_matches
-            })
-            .collect()
+    fn filter_set_matches(self, dlt: &dyn DltStore, index: usize) -> bool {
+        let Some(filter_set) = self.filter_set else {
+            return true;
+        };
+
+        filter_set.matches_row(dlt, index)
     }
 
     fn apply_rendered_text_search(
         self,
-        dlt: &RetainedDlt,
+        dlt: &dyn DltStore,
         structured_filtered_indices: Vec<usize>,
     ) -> Vec<usize> {
-        if self.rendered_search_query.is_empty() {
+        let Some(search) = self.rendered_search else {
             return structured_filtered_indices;
-        }
+        };
 
         structured_filtered_indices
             .into_iter()
-            .filter(|&index| {
-                let rendered = dlt.rendered_row_text(index);
-                contains_ignore_case(rendered.as_str(), self.rendered_search_query)
-            })
+            .filter(|&index| search.matches(dlt, index))
             .collect()
     }
 }
@@ -96,22 +183,19 @@ impl IndexLayer {
     }
 
     pub(crate) fn from_filter_and_search(
-        dlt: &RetainedDlt,
+        dlt: &dyn DltStore,
         filter: &StructuredFilter,
-        rendered_search_query: &str,
+        filter_set: Option<&FilterSet>,
+        rendered_search: Option<&SearchQuery>,
     ) -> Self {
-        QueryPipeline::new(filter, rendered_search_query).build(dlt)
+        QueryPipeline::new(filter, filter_set, rendered_search).build(dlt)
     }
 
     pub(crate) fn visible_count(&self) -> usize {
         self.visible_indices.len()
     }
 
-    pub(crate) fn visible_rows(
-        &self,
-        dlt: &RetainedDlt,
-        range: Range<usize>,
-    ) -> Vec<LogTableRow> {
+    pub(crate) fn visible_rows(&self, dlt: &dyn DltStore, range: Range<usize>) -> Vec<LogTableRow> {
         let total_rows = self.visible_count();
         let start = range.start.min(total_rows);
         let end = range.end.min(total_rows);
@@ -119,7 +203,7 @@ impl IndexLayer {
         self.visible_indices[start..end]
             .iter()
             .copied()
-            .map(|idx| dlt.row(idx))
+            .map(|idx| log_table_row(dlt, idx))
             .collect()
     }
 
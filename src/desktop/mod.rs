@@ -1,17 +1,34 @@
 mod application;
 mod index;
+mod preset;
+mod query;
 mod retained;
 mod ui;
 
 pub use application::DesktopBenchmarkHarness;
+pub(crate) use preset::{FilterPreset, PresetOptions};
 pub(crate) use ui::run_desktop_shell;
 
 #[cfg(test)]
 mod tests {
     use super::application::{DesktopAppState, DesktopIntent, DesktopModel};
-    use super::retained::{StructuredFilter, format_message_type, load_retained_dataset};
+    use super::preset::PresetLibrary;
+    use super::retained::{
+        LoadOptions, RuleCriterion, RuleField, StructuredFilter, StructuredRule,
+        format_message_type, load_retained_dataset, load_retained_dataset_until,
+        load_retained_dataset_with_options,
+    };
+    use crate::dlt::control::{ControlBody, ControlMessage, SERVICE_SET_LOG_LEVEL};
+    use crate::dlt::fibex::FibexDatabase;
+    use crate::dlt::filter::FilterSet;
+    use crate::dlt::merged::MergeMode;
+    use crate::dlt::stream::LiveSource;
+    use crate::dlt::time_window::{TimeBound, TimeClock, TimeWindow};
+    use crate::dlt::writer::MessageBuilder;
     use std::io::Write;
     use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
     fn write_v1_frame(
         file: &mut std::fs::File,
@@ -106,7 +123,10 @@ mod tests {
         let generation = model
             .active_load_generation()
             .expect("load generation should exist");
-        model.apply_intent(DesktopIntent::LoadSucceeded { generation, data });
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
         assert_eq!(model.state(), &DesktopAppState::Loaded);
 
         model.apply_intent(DesktopIntent::StructuredFilterUpdated(StructuredFilter {
@@ -145,7 +165,10 @@ mod tests {
             .expect("load generation should exist");
 
         let data = load_retained_dataset(vec![path]).expect("fixture should load");
-        model.apply_intent(DesktopIntent::LoadSucceeded { generation, data });
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
 
         assert_eq!(model.state(), &DesktopAppState::Loaded);
         assert!(model.loaded_data().is_some());
@@ -175,7 +198,7 @@ mod tests {
         let stale_data = load_retained_dataset(vec![path.clone()]).expect("fixture should load");
         model.apply_intent(DesktopIntent::LoadSucceeded {
             generation: stale_generation,
-            data: stale_data,
+            data: Box::new(stale_data),
         });
 
         assert_eq!(model.state(), &DesktopAppState::Loading);
@@ -184,7 +207,7 @@ mod tests {
         let active_data = load_retained_dataset(vec![path]).expect("fixture should load");
         model.apply_intent(DesktopIntent::LoadSucceeded {
             generation: active_generation,
-            data: active_data,
+            data: Box::new(active_data),
         });
 
         assert_eq!(model.state(), &DesktopAppState::Loaded);
@@ -211,7 +234,7 @@ mod tests {
         let active_data = load_retained_dataset(vec![path]).expect("fixture should load");
         model.apply_intent(DesktopIntent::LoadSucceeded {
             generation: active_generation,
-            data: active_data,
+            data: Box::new(active_data),
         });
 
         assert_eq!(model.state(), &DesktopAppState::Loaded);
@@ -254,7 +277,7 @@ mod tests {
         let active_data = load_retained_dataset(vec![path]).expect("fixture should load");
         model.apply_intent(DesktopIntent::LoadSucceeded {
             generation: active_generation,
-            data: active_data,
+            data: Box::new(active_data),
         });
 
         assert_eq!(model.state(), &DesktopAppState::Loaded);
@@ -305,6 +328,316 @@ mod tests {
         assert!(rows.is_empty());
     }
 
+    #[test]
+    fn desktop_model_applies_filter_file_to_loaded_data() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/testfile_single_payloads.dlt");
+        let filter_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/testfile_filter.dlf");
+        let filter_set = FilterSet::from_dlf_file(&filter_path).expect("filter should load");
+
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::FilterFileLoaded {
+            path: filter_path,
+            filter_set,
+        });
+
+        let data = load_retained_dataset(vec![path]).expect("fixture should load");
+        let total = data.message_count();
+        model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let generation = model
+            .active_load_generation()
+            .expect("load generation should exist");
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
+
+        let data = model.loaded_data().expect("data should be loaded");
+        assert_eq!(data.visible_message_count(), total - 1);
+        assert!(
+            data.visible_rows(0..total)
+                .iter()
+                .all(|row| row.payload != "105")
+        );
+
+        model.apply_intent(DesktopIntent::FilterFileCleared);
+        let data = model.loaded_data().expect("data should be loaded");
+        assert_eq!(data.visible_message_count(), total);
+        assert!(model.filter_set().is_none());
+    }
+
+    #[test]
+    fn desktop_model_exports_visible_rows() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/testfile_single_payloads.dlt");
+        let filter_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/testfile_filter.dlf");
+        let filter_set = FilterSet::from_dlf_file(&filter_path).expect("filter should load");
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let export_path = dir.path().join("visible.dlt");
+
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let generation = model
+            .active_load_generation()
+            .expect("load generation should exist");
+        let data = load_retained_dataset(vec![path]).expect("fixture should load");
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
+        model.apply_intent(DesktopIntent::FilterFileLoaded {
+            path: filter_path,
+            filter_set,
+        });
+        model.apply_intent(DesktopIntent::ExportVisibleRequested(export_path.clone()));
+
+        let visible = model
+            .loaded_data()
+            .expect("data should be loaded")
+            .visible_message_count();
+        assert!(
+            model
+                .export_status()
+                .is_some_and(|status| status.starts_with(&format!("Exported {visible} rows")))
+        );
+        let exported = load_retained_dataset(vec![export_path]).expect("export should reopen");
+        assert_eq!(exported.message_count(), visible);
+        assert_eq!(exported.parse_error_count(), 0);
+    }
+
+    #[test]
+    fn desktop_model_keeps_merge_mode_across_loads() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/testfile_single_payloads.dlt");
+
+        let mut model = DesktopModel::default();
+        assert_eq!(model.merge_mode(), None);
+
+        model.apply_intent(DesktopIntent::MergeModeChanged(Some(
+            MergeMode::StorageTimestamp,
+        )));
+        let data = load_retained_dataset_with_options(vec![path], &model.load_options())
+            .expect("fixture should load");
+        model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let generation = model
+            .active_load_generation()
+            .expect("load generation should exist");
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
+
+        assert_eq!(model.merge_mode(), Some(&MergeMode::StorageTimestamp));
+        let data = model.loaded_data().expect("data should be loaded");
+        assert_eq!(data.visible_message_count(), data.message_count());
+    }
+
+    #[test]
+    fn desktop_model_keeps_fibex_across_loads_until_cleared() {
+        let fibex_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/testfile_fibex.xml");
+        let database = FibexDatabase::from_file(&fibex_path).expect("FIBEX should load");
+
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::FibexFailed("bad file".to_string()));
+        assert_eq!(model.fibex_error(), Some("bad file"));
+
+        model.apply_intent(DesktopIntent::FibexLoaded {
+            paths: vec![fibex_path.clone()],
+            database: Arc::new(database),
+        });
+        assert_eq!(model.fibex_paths(), &[fibex_path]);
+        assert_eq!(model.fibex_error(), None);
+        assert!(model.load_options().fibex.is_some());
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/testfile_single_payloads.dlt");
+        let data = load_retained_dataset_with_options(vec![path], &model.load_options())
+            .expect("fixture should load");
+        assert!(data.message_count() > 0);
+
+        model.apply_intent(DesktopIntent::FibexCleared);
+        assert!(model.fibex_paths().is_empty());
+        assert!(model.load_options().fibex.is_none());
+    }
+
+    #[test]
+    fn desktop_model_appends_live_frames_through_active_filter() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/testfile_single_payloads.dlt");
+        let filter_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/testfile_filter.dlf");
+        let filter_set = FilterSet::from_dlf_file(&filter_path).expect("filter should load");
+        let bytes = std::fs::read(&path).expect("fixture should read");
+        let total = load_retained_dataset(vec![path])
+            .expect("fixture should load")
+            .message_count();
+
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::FilterFileLoaded {
+            path: filter_path,
+            filter_set,
+        });
+        model.apply_intent(DesktopIntent::LiveConnectRequested(LiveSource::Tcp(
+            "localhost".to_string(),
+        )));
+        let generation = model
+            .live_generation()
+            .expect("live generation should exist");
+        assert_eq!(model.state(), &DesktopAppState::Loading);
+
+        // Split on a frame boundary: the first stored frame is 16 + 19 bytes.
+        let (first, rest) = bytes.split_at(35);
+        for frames in [first, rest] {
+            model.apply_intent(DesktopIntent::LiveFramesReceived {
+                generation,
+                version: 1,
+                frames: frames.to_vec(),
+            });
+        }
+        model.apply_intent(DesktopIntent::LiveFramesReceived {
+            generation: generation + 1,
+            version: 1,
+            frames: bytes.clone(),
+        });
+
+        let data = model.loaded_data().expect("live data should exist");
+        assert_eq!(model.state(), &DesktopAppState::Loaded);
+        assert_eq!(data.live_source.as_deref(), Some("tcp localhost:3490"));
+        assert_eq!(data.message_count(), total);
+        assert_eq!(data.parse_error_count(), 0);
+        assert_eq!(data.visible_message_count(), total - 1);
+        assert_eq!(data.selected_visible_row(), Some(0));
+
+        model.apply_intent(DesktopIntent::LiveClosed {
+            generation,
+            error: None,
+        });
+        assert_eq!(model.live_generation(), None);
+        assert_eq!(model.live_status(), Some("Disconnected"));
+        assert!(model.loaded_data().is_some());
+    }
+
+    #[test]
+    fn desktop_model_matches_control_responses_to_sent_requests() {
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::LiveConnectRequested(LiveSource::Tcp(
+            "localhost".to_string(),
+        )));
+        let generation = model
+            .live_generation()
+            .expect("live generation should exist");
+        model.apply_intent(DesktopIntent::ControlRequestSent(
+            ControlMessage::set_log_level("APP", "CTX", 5),
+        ));
+        assert_eq!(model.control_requests().pending_count(), 1);
+
+        // set_log_level response with status "ok", sent back by the ECU.
+        let frames = MessageBuilder::new()
+            .with_ecu("ECU1")
+            .with_apid("APP")
+            .with_ctid("CTX")
+            .with_control(ControlMessage {
+                service_id: SERVICE_SET_LOG_LEVEL,
+                status: Some(0),
+                body: ControlBody::Empty,
+            })
+            .build(1)
+            .unwrap();
+        model.apply_intent(DesktopIntent::LiveFramesReceived {
+            generation,
+            version: 1,
+            frames,
+        });
+
+        let sent = &model.control_requests().sent()[0];
+        let (row, response) = sent.response.as_ref().expect("response should match");
+        assert_eq!(*row, 0);
+        assert_eq!(response.service_id, 0x01);
+        assert_eq!(model.control_requests().pending_count(), 0);
+
+        model.apply_intent(DesktopIntent::RowIndexSelected(0));
+        assert_eq!(model.loaded_data().unwrap().selected_visible_row(), Some(0));
+        model.apply_intent(DesktopIntent::RowIndexSelected(7));
+        assert_eq!(
+            model.control_status(),
+            Some("Row 7 is hidden by the filter")
+        );
+    }
+
+    #[test]
+    fn desktop_model_follows_appended_frames_through_active_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("followed.dlt");
+        let mut file = std::fs::File::create(&path).unwrap();
+        write_v1_frame(&mut file, 1, *b"ECU1", Some(*b"ECU1"));
+        file.flush().unwrap();
+
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::FollowToggled(true));
+        assert!(model.load_options().follow);
+        model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let generation = model
+            .active_load_generation()
+            .expect("load generation should exist");
+        let data = load_retained_dataset_with_options(vec![path], &model.load_options())
+            .expect("fixture should load");
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(data),
+        });
+        model.apply_intent(DesktopIntent::StructuredFilterUpdated(StructuredFilter {
+            ecu_contains: "ECU1".to_string(),
+            ..StructuredFilter::default()
+        }));
+
+        write_v1_frame(&mut file, 2, *b"ECU2", Some(*b"ECU2"));
+        write_v1_frame(&mut file, 3, *b"ECU1", Some(*b"ECU1"));
+        file.flush().unwrap();
+        model.apply_intent(DesktopIntent::FollowRefreshRequested);
+
+        let data = model.loaded_data().expect("data should be loaded");
+        assert_eq!(data.message_count(), 3);
+        assert_eq!(data.visible_message_count(), 2);
+        assert_eq!(data.parse_error_count(), 0);
+
+        model.apply_intent(DesktopIntent::FollowToggled(false));
+        write_v1_frame(&mut file, 4, *b"ECU1", Some(*b"ECU1"));
+        file.flush().unwrap();
+        model.apply_intent(DesktopIntent::FollowRefreshRequested);
+        assert_eq!(model.loaded_data().unwrap().message_count(), 3);
+        assert_eq!(model.follow_error(), None);
+    }
+
+    #[test]
+    fn following_an_empty_file_waits_until_stopped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.dlt");
+        std::fs::File::create(&path).unwrap();
+        let options = LoadOptions {
+            follow: true,
+            ..LoadOptions::default()
+        };
+        let stop = AtomicBool::new(false);
+        let mut waited_on = Vec::new();
+
+        let error = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                stop.store(true, Ordering::Relaxed);
+            });
+            load_retained_dataset_until(vec![path.clone()], &options, &stop, |path| {
+                waited_on.push(path.to_path_buf())
+            })
+            .unwrap_err()
+        });
+
+        assert!(error.to_string().contains("Stopped waiting"));
+        assert_eq!(waited_on, vec![path]);
+    }
+
     #[test]
     fn structured_filter_returns_no_rows_when_no_match() {
         let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(
@@ -349,6 +682,443 @@ mod tests {
         assert_eq!(data.visible_message_count(), total);
     }
 
+    #[test]
+    fn structured_filter_matches_v2_source_tags_and_privacy_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.dlt");
+        let frames = [
+            MessageBuilder::new()
+                .with_source_location("src/can_driver.c", 42)
+                .with_tag("Safety")
+                .with_privacy_level(1)
+                .with_verbose_string("bus off")
+                .build(2)
+                .unwrap(),
+            MessageBuilder::new()
+                .with_source_location("src/main.c", 7)
+                .with_tag("boot")
+                .with_verbose_string("started")
+                .build(2)
+                .unwrap(),
+            MessageBuilder::new()
+                .with_verbose_string("untagged")
+                .build(2)
+                .unwrap(),
+        ];
+        std::fs::write(&path, frames.concat()).unwrap();
+        let mut data = load_retained_dataset(vec![path]).expect("file should load");
+
+        let row = &data.visible_rows(0..1)[0];
+        assert_eq!(row.source, "src/can_driver.c:42");
+        assert_eq!(row.tags, "Safety");
+        assert_eq!(row.privacy, "1");
+        assert_eq!(data.visible_rows(2..3)[0].tags, "-");
+
+        let matching_rows = |data: &mut super::retained::RetainedDataSet, filter| {
+            data.set_structured_filter(filter);
+            data.visible_rows(0..data.visible_message_count())
+                .into_iter()
+                .map(|row| row.index)
+                .collect::<Vec<_>>()
+        };
+        let tag = StructuredFilter {
+            tag_equals: "safety".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert_eq!(matching_rows(&mut data, tag), vec![0]);
+        let source = StructuredFilter {
+            source_file_ends_with: "CAN_driver.c".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert_eq!(matching_rows(&mut data, source), vec![0]);
+        let source = StructuredFilter {
+            source_file_ends_with: "src/".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert!(matching_rows(&mut data, source).is_empty());
+        let source = StructuredFilter {
+            source_file_ends_with: ".c".to_string(),
+            source_line_equals: "7".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert_eq!(matching_rows(&mut data, source), vec![1]);
+        let source = StructuredFilter {
+            source_line_equals: "42:".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert!(matching_rows(&mut data, source).is_empty());
+        let privacy = StructuredFilter {
+            privacy_equals: "1".to_string(),
+            ..StructuredFilter::default()
+        };
+        assert_eq!(matching_rows(&mut data, privacy), vec![0]);
+    }
+
+    #[test]
+    fn message_id_filter_and_grouping_cover_both_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let v1_path = dir.path().join("v1.dlt");
+        let v2_path = dir.path().join("v2.dlt");
+        let non_verbose = |id, version| {
+            MessageBuilder::new()
+                .with_non_verbose(id, &[0x01])
+                .build(version)
+                .unwrap()
+        };
+        let v1_frames = [
+            non_verbose(0x10, 1),
+            non_verbose(0x20, 1),
+            MessageBuilder::new()
+                .with_verbose_string("verbose")
+                .build(1)
+                .unwrap(),
+        ];
+        std::fs::write(&v1_path, v1_frames.concat()).unwrap();
+        std::fs::write(&v2_path, non_verbose(0x10, 2)).unwrap();
+        let mut data = load_retained_dataset(vec![v1_path, v2_path]).expect("files should load");
+
+        let rows = data.visible_rows(0..data.visible_message_count());
+        let ids: Vec<_> = rows.iter().map(|row| row.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["0x00000010", "0x00000020", "-", "0x00000010"]);
+        assert_eq!(data.message_id_counts(), vec![(0x10, 2), (0x20, 1)]);
+
+        for text in ["0x10", "16", " 0X00000010 "] {
+            data.set_structured_filter(StructuredFilter {
+                message_id_equals: text.to_string(),
+                ..StructuredFilter::default()
+            });
+            let indices: Vec<_> = data
+                .visible_rows(0..data.visible_message_count())
+                .into_iter()
+                .map(|row| row.index)
+                .collect();
+            assert_eq!(indices, vec![0, 3], "filter {text:?}");
+        }
+        data.set_structured_filter(StructuredFilter {
+            message_id_equals: "not an id".to_string(),
+            ..StructuredFilter::default()
+        });
+        assert_eq!(data.visible_message_count(), 0);
+    }
+
+    #[test]
+    fn rows_after_message_counter_gaps_are_marked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gaps.dlt");
+        let frames: Vec<_> = [0, 1, 4, 5, 9]
+            .into_iter()
+            .map(|counter| {
+                MessageBuilder::new()
+                    .with_counter(counter)
+                    .with_verbose_string("text")
+                    .build(1)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let data = load_retained_dataset(vec![path]).expect("file should load");
+
+        let dropped: Vec<_> = data
+            .visible_rows(0..data.visible_message_count())
+            .into_iter()
+            .map(|row| row.dropped_before)
+            .collect();
+        assert_eq!(dropped, vec![None, None, Some(2), None, Some(3)]);
+        assert_eq!(data.dropped_message_count(), (5, 2));
+    }
+
+    #[test]
+    fn structured_filter_rules_combine_exact_sets_exclusions_levels_sessions_and_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.dlt");
+        let frames: Vec<_> = [
+            ("ECU1", "SYS", 1, 2, 100),
+            ("ECU1", "SYSM", 4, 2, 101),
+            ("ECU2", "SYS", 3, 7, 102),
+            ("ECU3", "SYS", 5, 7, 103),
+            ("ECU2", "NAVI", 2, 2, 104),
+        ]
+        .into_iter()
+        .map(|(ecu, apid, level, session, seconds)| {
+            MessageBuilder::new()
+                .with_storage_timestamp(seconds, 0)
+                .with_ecu(ecu)
+                .with_apid(apid)
+                .with_message_type_info(level)
+                .with_session_id(session)
+                .with_verbose_string("text")
+                .build(1)
+                .unwrap()
+        })
+        .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let mut data = load_retained_dataset(vec![path]).expect("file should load");
+        let mut visible_with = |rules: Vec<StructuredRule>| {
+            data.set_structured_filter(StructuredFilter {
+                rules,
+                ..StructuredFilter::default()
+            });
+            data.visible_rows(0..data.visible_message_count())
+                .into_iter()
+                .map(|row| row.index)
+                .collect::<Vec<_>>()
+        };
+        let apid_sys = RuleCriterion::OneOf {
+            field: RuleField::Apid,
+            values: vec!["sys".to_string()],
+        };
+        let ecus = RuleCriterion::OneOf {
+            field: RuleField::Ecu,
+            values: vec!["ECU1".to_string(), "ECU2".to_string()],
+        };
+
+        assert_eq!(
+            visible_with(vec![StructuredRule::new(apid_sys.clone())]),
+            vec![0, 2, 3]
+        );
+        assert_eq!(
+            visible_with(vec![StructuredRule::new(ecus.clone())]),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(
+            visible_with(vec![
+                StructuredRule::new(ecus.clone()),
+                StructuredRule::excluding(apid_sys.clone()),
+            ]),
+            vec![1, 4]
+        );
+        assert_eq!(
+            visible_with(vec![StructuredRule::new(RuleCriterion::LogLevel {
+                most_severe: 1,
+                least_severe: 3,
+            })]),
+            vec![0, 2, 4]
+        );
+        assert_eq!(
+            visible_with(vec![StructuredRule::new(RuleCriterion::SessionId(vec![7]))]),
+            vec![2, 3]
+        );
+        assert_eq!(
+            visible_with(vec![StructuredRule::new(RuleCriterion::TimeWindow(
+                TimeWindow {
+                    clock: TimeClock::Storage,
+                    from: Some(TimeBound::Absolute(101_000_000_000)),
+                    to: Some(TimeBound::Relative(3_000_000_000)),
+                }
+            ))]),
+            vec![1, 2]
+        );
+
+        let mut disabled = StructuredRule::new(apid_sys);
+        disabled.enabled = false;
+        assert_eq!(visible_with(vec![disabled]), vec![0, 1, 2, 3, 4]);
+
+        assert_eq!(
+            StructuredRule::excluding(ecus).to_string(),
+            "not ECU in {ECU1, ECU2}"
+        );
+    }
+
+    #[test]
+    fn go_to_time_selects_the_nearest_visible_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("times.dlt");
+        let frames: Vec<_> = [("APP1", 100), ("APP2", 104), ("APP1", 105), ("APP1", 110)]
+            .into_iter()
+            .map(|(apid, seconds)| {
+                MessageBuilder::new()
+                    .with_storage_timestamp(seconds, 0)
+                    .with_timestamp_ns(u64::from(seconds - 100) * 1_000_000_000)
+                    .with_apid(apid)
+                    .with_verbose_string("text")
+                    .build(2)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let generation = model
+            .active_load_generation()
+            .expect("load generation should exist");
+        model.apply_intent(DesktopIntent::LoadSucceeded {
+            generation,
+            data: Box::new(load_retained_dataset(vec![path]).expect("file should load")),
+        });
+        let selected = |model: &DesktopModel| model.loaded_data().unwrap().selected_row_index();
+
+        model.apply_intent(DesktopIntent::GoToTimeRequested(
+            TimeClock::Storage,
+            TimeBound::Absolute(103_500_000_000),
+        ));
+        assert_eq!(selected(&model), Some(1));
+        model.apply_intent(DesktopIntent::GoToTimeRequested(
+            TimeClock::Message,
+            TimeBound::Relative(8_000_000_000),
+        ));
+        assert_eq!(selected(&model), Some(3));
+
+        model.apply_intent(DesktopIntent::StructuredFilterUpdated(StructuredFilter {
+            apid_contains: "APP1".to_string(),
+            ..StructuredFilter::default()
+        }));
+        model.apply_intent(DesktopIntent::GoToTimeRequested(
+            TimeClock::Storage,
+            TimeBound::Relative(3_000_000_000),
+        ));
+        assert_eq!(selected(&model), Some(2));
+    }
+
+    #[test]
+    fn presets_are_saved_reapplied_on_load_and_shared_as_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("apps.dlt");
+        let frames: Vec<_> = ["APP1", "APP2", "APP1"]
+            .into_iter()
+            .map(|apid| {
+                MessageBuilder::new()
+                    .with_apid(apid)
+                    .with_verbose_string("text")
+                    .build(1)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let load = |model: &mut DesktopModel| {
+            model.apply_intent(DesktopIntent::OpenFilesRequested);
+            let generation = model
+                .active_load_generation()
+                .expect("load generation should exist");
+            model.apply_intent(DesktopIntent::LoadSucceeded {
+                generation,
+                data: Box::new(
+                    load_retained_dataset(vec![path.clone()]).expect("file should load"),
+                ),
+            });
+        };
+        let visible = |model: &DesktopModel| model.loaded_data().unwrap().visible_message_count();
+        let library_path = dir.path().join("config").join("filter-presets.toml");
+        let mut model = DesktopModel::default();
+        model.apply_intent(DesktopIntent::PresetLibraryLoaded {
+            path: Some(library_path.clone()),
+            library: PresetLibrary::default(),
+        });
+        load(&mut model);
+
+        model.apply_intent(DesktopIntent::StructuredFilterUpdated(StructuredFilter {
+            apid_contains: "APP1".to_string(),
+            ..StructuredFilter::default()
+        }));
+        model.apply_intent(DesktopIntent::PresetSaved(" App one ".to_string()));
+        assert_eq!(model.active_preset_name(), Some("App one"));
+        let saved = PresetLibrary::load(&library_path).unwrap();
+        assert_eq!(saved.find("App one").unwrap().filter.apid_contains, "APP1");
+
+        load(&mut model);
+        assert_eq!(visible(&model), 2);
+        model.apply_intent(DesktopIntent::StructuredFilterCleared);
+        assert_eq!(model.active_preset_name(), None);
+        assert_eq!(visible(&model), 3);
+        model.apply_intent(DesktopIntent::PresetSelected("App one".to_string()));
+        assert_eq!(visible(&model), 2);
+        model.apply_intent(DesktopIntent::PresetSelected("Missing".to_string()));
+        assert_eq!(
+            model.preset_status(),
+            Some("No filter preset named \"Missing\" (known presets: App one)")
+        );
+
+        let shared = dir.path().join("shared.toml");
+        model.apply_intent(DesktopIntent::PresetsExported(shared.clone()));
+        model.apply_intent(DesktopIntent::PresetDeleted("App one".to_string()));
+        assert!(model.presets().is_empty());
+        assert_eq!(model.active_preset_name(), None);
+        model.apply_intent(DesktopIntent::PresetsImported(shared));
+        assert_eq!(model.presets().len(), 1);
+        assert_eq!(
+            PresetLibrary::load(&library_path).unwrap().presets().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn least_severe_level_keeps_log_messages_at_or_above_the_level() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("levels.dlt");
+        // Log levels fatal to verbose, then a trace message whose MTIN is 1.
+        let frames: Vec<_> = [(0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (1, 1)]
+            .into_iter()
+            .map(|(message_type, info)| {
+                MessageBuilder::new()
+                    .with_message_type(message_type)
+                    .with_message_type_info(info)
+                    .with_verbose_string("text")
+                    .build(1)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let mut data = load_retained_dataset(vec![path]).expect("file should load");
+        let mut visible_with = |least_severe_level| {
+            data.set_structured_filter(StructuredFilter {
+                least_severe_level,
+                ..StructuredFilter::default()
+            });
+            data.visible_rows(0..data.visible_message_count())
+                .into_iter()
+                .map(|row| row.index)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(visible_with(None), vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(visible_with(Some(3)), vec![0, 1, 2]);
+        assert_eq!(visible_with(Some(1)), vec![0]);
+        assert_eq!(visible_with(Some(6)), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn rendered_search_runs_queries_and_falls_back_to_plain_text_on_syntax_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query.dlt");
+        let frames: Vec<_> = [
+            ("DIAG", "open (port 3"),
+            ("DIAG", "closed"),
+            ("NAVI", "open"),
+        ]
+        .into_iter()
+        .map(|(apid, text)| {
+            MessageBuilder::new()
+                .with_apid(apid)
+                .with_verbose_string(text)
+                .build(1)
+                .unwrap()
+        })
+        .collect();
+        std::fs::write(&path, frames.concat()).unwrap();
+        let mut data = load_retained_dataset(vec![path]).expect("file should load");
+        let matching_rows = |data: &super::retained::RetainedDataSet| {
+            data.visible_rows(0..data.visible_message_count())
+                .into_iter()
+                .map(|row| row.index)
+                .collect::<Vec<_>>()
+        };
+
+        data.set_rendered_search_query("apid:DIAG NOT payload:/^CLO/i".to_string());
+        assert!(data.rendered_search_syntax_error().is_none());
+        assert_eq!(matching_rows(&data), vec![0]);
+        assert_eq!(data.rendered_search_match_count(), 1);
+
+        data.set_rendered_search_query("(port".to_string());
+        let error = data
+            .rendered_search_syntax_error()
+            .expect("unclosed group is reported");
+        assert_eq!(error.position, 0);
+        assert_eq!(matching_rows(&data), vec![0]);
+
+        data.set_rendered_search_query("open OR closed".to_string());
+        assert!(data.rendered_search_syntax_error().is_none());
+        assert_eq!(matching_rows(&data), vec![0, 1, 2]);
+    }
+
     #[test]
     fn message_type_format_uses_family_and_info_when_present() {
         assert_eq!(format_message_type(0, 4), "log/info");
@@ -0,0 +1,286 @@
+use crate::desktop::index::QueryPipeline;
+use crate::desktop::retained::StructuredFilter;
+use crate::dlt::store::DltStore;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the preset library in the user config directory.
+const LIBRARY_FILE_NAME: &str = "filter-presets.toml";
+
+/// A Structured Filter saved under a name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FilterPreset {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) filter: StructuredFilter,
+}
+
+impl FilterPreset {
+    pub(crate) fn matches_row(&self, dlt: &dyn DltStore, row: usize) -> bool {
+        QueryPipeline::new(&self.filter, None, None).structured_filter_matches(dlt, row)
+    }
+}
+
+/// Named filter presets, stored as a TOML file of `[[preset]]` tables.
+///
+/// The same format is used for the user's library and for the files shared
+/// with import and export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PresetLibrary {
+    #[serde(default, rename = "preset")]
+    presets: Vec<FilterPreset>,
+}
+
+impl PresetLibrary {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Parsing {}", path.display()))
+    }
+
+    /// Like `load`, but a file that does not exist yet is an empty library.
+    pub(crate) fn load_or_empty(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(path)
+    }
+
+    /// Write the library, creating the parent directory if needed.
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Creating {}", parent.display()))?;
+        }
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Writing {}", path.display()))
+    }
+
+    pub(crate) fn presets(&self) -> &[FilterPreset] {
+        &self.presets
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&FilterPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// The preset called `name`, or an error listing the known names.
+    pub(crate) fn find(&self, name: &str) -> Result<&FilterPreset> {
+        self.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self
+                .presets
+                .iter()
+                .map(|preset| preset.name.as_str())
+                .collect();
+            anyhow!(
+                "No filter preset named \"{name}\" (known presets: {})",
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+    }
+
+    /// Add `preset`, replacing the preset of the same name in place.
+    pub(crate) fn insert(&mut self, preset: FilterPreset) {
+        match self
+            .presets
+            .iter_mut()
+            .find(|known| known.name == preset.name)
+        {
+            Some(known) => *known = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        let count = self.presets.len();
+        self.presets.retain(|preset| preset.name != name);
+        self.presets.len() != count
+    }
+
+    /// Insert every preset of `other`; returns how many were read.
+    pub(crate) fn merge(&mut self, other: PresetLibrary) -> usize {
+        let count = other.presets.len();
+        for preset in other.presets {
+            self.insert(preset);
+        }
+        count
+    }
+}
+
+/// The preset library in the user config directory, if one can be located.
+pub(crate) fn default_library_path() -> Option<PathBuf> {
+    user_config_dir().map(|dir| dir.join("dlt-explorer").join(LIBRARY_FILE_NAME))
+}
+
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS and
+/// `$XDG_CONFIG_HOME` or `~/.config` elsewhere.
+fn user_config_dir() -> Option<PathBuf> {
+    let from_env = |name: &str| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    if cfg!(windows) {
+        from_env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        from_env("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        from_env("XDG_CONFIG_HOME").or_else(|| from_env("HOME").map(|home| home.join(".config")))
+    }
+}
+
+/// Preset settings from the command line.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PresetOptions {
+    /// Preset to apply on start.
+    pub(crate) name: Option<String>,
+    /// Library file used instead of the one in the user config directory.
+    pub(crate) file: Option<PathBuf>,
+}
+
+impl PresetOptions {
+    pub(crate) fn library_path(&self) -> Option<PathBuf> {
+        self.file.clone().or_else(default_library_path)
+    }
+
+    /// The preset named on the command line, if any.
+    pub(crate) fn load_preset(&self) -> Result<Option<FilterPreset>> {
+        let Some(name) = &self.name else {
+            return Ok(None);
+        };
+        let path = self
+            .library_path()
+            .ok_or_else(|| anyhow!("No user config directory for filter presets"))?;
+        let library = PresetLibrary::load_or_empty(&path)?;
+        library.find(name).cloned().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop::retained::{RuleCriterion, RuleField, StructuredRule};
+    use crate::dlt::time_window::{TimeBound, TimeClock, TimeWindow};
+
+    fn preset(name: &str, apid: &str) -> FilterPreset {
+        FilterPreset {
+            name: name.to_string(),
+            filter: StructuredFilter {
+                apid_contains: apid.to_string(),
+                ..StructuredFilter::default()
+            },
+        }
+    }
+
+    #[test]
+    fn library_round_trips_rules_through_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(LIBRARY_FILE_NAME);
+        let mut library = PresetLibrary::default();
+        library.insert(FilterPreset {
+            name: "Errors".to_string(),
+            filter: StructuredFilter {
+                least_severe_level: Some(2),
+                rules: vec![
+                    StructuredRule::excluding(RuleCriterion::OneOf {
+                        field: RuleField::Ctid,
+                        values: vec!["NOIS".to_string(), "SPAM".to_string()],
+                    }),
+                    StructuredRule::new(RuleCriterion::SessionId(vec![7])),
+                    StructuredRule::new(RuleCriterion::TimeWindow(TimeWindow {
+                        clock: TimeClock::Message,
+                        from: Some(TimeBound::Relative(5_000_000_000)),
+                        to: None,
+                    })),
+                ],
+                ..StructuredFilter::default()
+            },
+        });
+        library.insert(preset("Navigation", "NAVI"));
+
+        library.save(&path).unwrap();
+        assert_eq!(PresetLibrary::load(&path).unwrap(), library);
+        let missing = dir.path().join("missing.toml");
+        assert!(PresetLibrary::load(&missing).is_err());
+        assert_eq!(
+            PresetLibrary::load_or_empty(&missing).unwrap(),
+            PresetLibrary::default()
+        );
+    }
+
+    #[test]
+    fn insert_and_merge_replace_presets_by_name() {
+        let mut library = PresetLibrary::default();
+        library.insert(preset("A", "ONE"));
+        library.insert(preset("B", "TWO"));
+        library.insert(preset("A", "THREE"));
+
+        let mut shared = PresetLibrary::default();
+        shared.insert(preset("B", "FOUR"));
+        shared.insert(preset("C", "FIVE"));
+        assert_eq!(library.merge(shared), 2);
+
+        let presets: Vec<_> = library
+            .presets()
+            .iter()
+            .map(|preset| (preset.name.as_str(), preset.filter.apid_contains.as_str()))
+            .collect();
+        assert_eq!(presets, vec![("A", "THREE"), ("B", "FOUR"), ("C", "FIVE")]);
+        assert!(library.remove("B"));
+        assert!(!library.remove("B"));
+        assert_eq!(
+            library.find("B").unwrap_err().to_string(),
+            "No filter preset named \"B\" (known presets: A, C)"
+        );
+    }
+
+    #[test]
+    fn partial_preset_files_load_with_defaults() {
+        let library: PresetLibrary = toml::from_str(
+            r#"
+            [[preset]]
+            name = "System"
+
+            [preset.filter]
+            ecu_contains = "ECU1"
+
+            [[preset.filter.rules]]
+            enabled = true
+            exclude = false
+            criterion = { one_of = { field = "apid", values = ["SYS"] } }
+            "#,
+        )
+        .unwrap();
+
+        let filter = &library.find("System").unwrap().filter;
+        assert_eq!(filter.ecu_contains, "ECU1");
+        assert_eq!(filter.rules[0].to_string(), "APID = SYS");
+        assert!(filter.kind_contains.is_empty());
+    }
+
+    #[test]
+    fn unknown_preset_names_report_the_name_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("presets").join("filter-presets.toml");
+        let mut library = PresetLibrary::default();
+        library.insert(preset("System", "SYS"));
+        library.save(&file).unwrap();
+
+        let options = PresetOptions {
+            name: Some("Missing".to_string()),
+            file: Some(file),
+        };
+        let error = options.load_preset().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("No filter preset named \"Missing\"")
+        );
+        assert_eq!(error.chain().count(), 1);
+    }
+}
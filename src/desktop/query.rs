@@ -0,0 +1,616 @@
+use crate::desktop::retained::{format_source_location, parse_message_id, rendered_row_text};
+use crate::dlt::filter::TextMatch;
+use crate::dlt::payload::{format_message_type, parse_log_level};
+use crate::dlt::store::DltStore;
+use crate::dlt::v1::protocol::MESSAGE_TYPE_LOG;
+use regex::RegexBuilder;
+use std::cell::OnceCell;
+use std::fmt;
+
+/// Most `(` and `NOT` a query may nest; the parser recurses on both, so a
+/// pasted query must not be able to exhaust the UI thread's stack.
+const MAX_NESTING: usize = 64;
+
+/// Search box query, parsed by `parse_query`.
+///
+/// Plain words and quoted phrases search the rendered row text ignoring
+/// case, `/regex/` searches it with a regular expression, and `field:value`
+/// terms look at one field. Terms next to each other must all match; `OR`,
+/// `NOT` and parentheses combine them further.
+#[derive(Debug, Clone)]
+pub(crate) enum SearchQuery {
+    Text {
+        field: TextField,
+        matcher: TextMatch,
+    },
+    Level {
+        comparison: Comparison,
+        level: u8,
+    },
+    MessageId(u32),
+    SessionId(u32),
+    Not(Box<SearchQuery>),
+    And(Vec<SearchQuery>),
+    Or(Vec<SearchQuery>),
+}
+
+/// Row fields searched as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextField {
+    /// The rendered row text, for terms without a field.
+    Row,
+    Ecu,
+    Apid,
+    Ctid,
+    Type,
+    Payload,
+    Source,
+    Tag,
+}
+
+/// How `level:` compares a row's log level, by severity: `level:>=warn`
+/// matches warn, error and fatal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Equal,
+    AtLeast,
+    MoreSevere,
+    AtMost,
+    LessSevere,
+}
+
+impl Comparison {
+    fn matches(self, mtin: u8, level: u8) -> bool {
+        // Lower MTIN values are more severe.
+        match self {
+            Self::Equal => mtin == level,
+            Self::AtLeast => mtin <= level,
+            Self::MoreSevere => mtin < level,
+            Self::AtMost => mtin >= level,
+            Self::LessSevere => mtin > level,
+        }
+    }
+}
+
+/// Where and why a query does not parse; `position` counts characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuerySyntaxError {
+    pub(crate) position: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for QuerySyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.position + 1, self.message)
+    }
+}
+
+enum Field {
+    Text(TextField),
+    Level,
+    MessageId,
+    SessionId,
+}
+
+fn field_named(name: &str) -> Option<Field> {
+    let field = match name.to_ascii_lowercase().as_str() {
+        "ecu" => Field::Text(TextField::Ecu),
+        "apid" => Field::Text(TextField::Apid),
+        "ctid" => Field::Text(TextField::Ctid),
+        "type" => Field::Text(TextField::Type),
+        "payload" => Field::Text(TextField::Payload),
+        "source" => Field::Text(TextField::Source),
+        "tag" => Field::Text(TextField::Tag),
+        "level" => Field::Level,
+        "msgid" => Field::MessageId,
+        "session" => Field::SessionId,
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Parse a search box query; `None` for a blank one.
+pub(crate) fn parse_query(text: &str) -> Result<Option<SearchQuery>, QuerySyntaxError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+        depth: 0,
+        nesting: 0,
+    };
+    parser.skip_whitespace();
+    if parser.peek().is_none() {
+        return Ok(None);
+    }
+    let query = parser.or()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("unmatched `)`"));
+    }
+    Ok(Some(query))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    /// Open parentheses; a word ends at `)` only inside one.
+    depth: usize,
+    /// Open parentheses and `NOT`s being parsed, up to `MAX_NESTING`.
+    nesting: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, message: impl Into<String>) -> QuerySyntaxError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Into<String>) -> QuerySyntaxError {
+        QuerySyntaxError {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// Enter a `(` or `NOT` at `position`, failing past `MAX_NESTING`.
+    fn nest(&mut self, position: usize) -> Result<(), QuerySyntaxError> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error_at(
+                position,
+                format!("more than {MAX_NESTING} nested `(` or `NOT`"),
+            ));
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Consume `keyword` if it stands alone as the next word.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let end = self.position + keyword.len();
+        let matches = end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .copied()
+                .eq(keyword.chars())
+            && self
+                .chars
+                .get(end)
+                .is_none_or(|&next| next.is_whitespace() || next == '(' || next == ')');
+        if matches {
+            self.position = end;
+        }
+        matches
+    }
+
+    fn or(&mut self) -> Result<SearchQuery, QuerySyntaxError> {
+        let mut queries = vec![self.and()?];
+        while self.keyword("OR") {
+            queries.push(self.and()?);
+        }
+        Ok(combine(queries, SearchQuery::Or))
+    }
+
+    fn and(&mut self) -> Result<SearchQuery, QuerySyntaxError> {
+        let mut queries = vec![self.unary()?];
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            if self.peek().is_none_or(|next| next == ')') || self.keyword("OR") {
+                self.position = start;
+                break;
+            }
+            self.keyword("AND");
+            queries.push(self.unary()?);
+        }
+        Ok(combine(queries, SearchQuery::And))
+    }
+
+    fn unary(&mut self) -> Result<SearchQuery, QuerySyntaxError> {
+        if self.keyword("NOT") {
+            self.nest(self.position - "NOT".len())?;
+            let query = self.unary()?;
+            self.nesting -= 1;
+            return Ok(SearchQuery::Not(Box::new(query)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<SearchQuery, QuerySyntaxError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(self.error("expected a search term")),
+            Some(')') => Err(self.error("unmatched `)`")),
+            Some('(') => {
+                let open = self.position;
+                self.nest(open)?;
+                self.position += 1;
+                self.depth += 1;
+                let query = self.or()?;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err(self.error_at(open, "unclosed `(`"));
+                }
+                self.position += 1;
+                self.depth -= 1;
+                self.nesting -= 1;
+                Ok(query)
+            }
+            Some(_) => self.term(),
+        }
+    }
+
+    fn term(&mut self) -> Result<SearchQuery, QuerySyntaxError> {
+        let start = self.position;
+        let field = self.field_prefix();
+        let value_start = self.position;
+        match field {
+            None => self.text_value(TextField::Row),
+            Some(Field::Text(field)) => self.text_value(field),
+            Some(Field::Level) => {
+                let word = self.word();
+                let (comparison, name) = split_comparison(&word);
+                let level = parse_log_level(name).ok_or_else(|| {
+                    self.error_at(value_start, format!("unknown log level `{name}`"))
+                })?;
+                Ok(SearchQuery::Level { comparison, level })
+            }
+            Some(Field::MessageId) => {
+                let word = self.word();
+                parse_message_id(&word)
+                    .map(SearchQuery::MessageId)
+                    .ok_or_else(|| {
+                        self.error_at(value_start, format!("invalid message ID `{word}`"))
+                    })
+            }
+            Some(Field::SessionId) => {
+                let word = self.word();
+                word.parse()
+                    .map(SearchQuery::SessionId)
+                    .map_err(|_| self.error_at(value_start, format!("invalid session ID `{word}`")))
+            }
+        }
+        .and_then(|query| {
+            if self.position == value_start {
+                Err(self.error_at(start, "expected a value after the field name"))
+            } else {
+                Ok(query)
+            }
+        })
+    }
+
+    /// Consume `name:` if `name` is a known field.
+    fn field_prefix(&mut self) -> Option<Field> {
+        let start = self.position;
+        let mut end = start;
+        while self.chars.get(end).is_some_and(char::is_ascii_alphabetic) {
+            end += 1;
+        }
+        if end == start || self.chars.get(end) != Some(&':') {
+            return None;
+        }
+        let name: String = self.chars[start..end].iter().collect();
+        let field = field_named(&name)?;
+        self.position = end + 1;
+        Some(field)
+    }
+
+    /// A phrase, regex or word matched against `field`.
+    fn text_value(&mut self, field: TextField) -> Result<SearchQuery, QuerySyntaxError> {
+        let exact = matches!(
+            field,
+            TextField::Ecu | TextField::Apid | TextField::Ctid | TextField::Tag
+        );
+        let matcher = match self.peek() {
+            Some('"') => text_match(self.phrase()?, exact),
+            Some('/') => self.regex()?,
+            _ => text_match(self.word(), exact),
+        };
+        Ok(SearchQuery::Text { field, matcher })
+    }
+
+    /// Characters up to whitespace, or up to `)` inside parentheses.
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(next) = self.peek() {
+            if next.is_whitespace() || (next == ')' && self.depth > 0) {
+                break;
+            }
+            word.push(next);
+            self.position += 1;
+        }
+        word
+    }
+
+    /// A `"quoted phrase"`; `\` escapes the next character.
+    fn phrase(&mut self) -> Result<String, QuerySyntaxError> {
+        let start = self.position;
+        self.position += 1;
+        let mut phrase = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(start, "unclosed `\"`")),
+                Some('"') => break,
+                Some('\\') if self.position + 1 < self.chars.len() => {
+                    phrase.push(self.chars[self.position + 1]);
+                    self.position += 2;
+                    continue;
+                }
+                Some(next) => phrase.push(next),
+            }
+            self.position += 1;
+        }
+        self.position += 1;
+        Ok(phrase)
+    }
+
+    /// A `/regex/`, optionally followed by the `i` flag to ignore case;
+    /// `\/` stands for a slash.
+    fn regex(&mut self) -> Result<TextMatch, QuerySyntaxError> {
+        let start = self.position;
+        self.position += 1;
+        let mut pattern = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(start, "unclosed `/`")),
+                Some('/') => break,
+                Some('\\') if self.chars.get(self.position + 1) == Some(&'/') => {
+                    pattern.push('/');
+                    self.position += 2;
+                    continue;
+                }
+                Some('\\') if self.position + 1 < self.chars.len() => {
+                    pattern.push('\\');
+                    pattern.push(self.chars[self.position + 1]);
+                    self.position += 2;
+                    continue;
+                }
+                Some(next) => pattern.push(next),
+            }
+            self.position += 1;
+        }
+        self.position += 1;
+        let mut ignore_case = false;
+        while let Some(flag) = self.peek().filter(char::is_ascii_alphabetic) {
+            if flag != 'i' {
+                return Err(self.error(format!("unknown regex flag `{flag}`")));
+            }
+            ignore_case = true;
+            self.position += 1;
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(TextMatch::Regex)
+            .map_err(|error| {
+                let reason = error.to_string();
+                let reason = reason.lines().last().unwrap_or_default().trim();
+                self.error_at(start, format!("invalid regex: {reason}"))
+            })
+    }
+}
+
+fn combine(
+    mut queries: Vec<SearchQuery>,
+    group: fn(Vec<SearchQuery>) -> SearchQuery,
+) -> SearchQuery {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        group(queries)
+    }
+}
+
+fn text_match(text: String, exact: bool) -> TextMatch {
+    if exact {
+        TextMatch::Exact {
+            text,
+            ignore_case: true,
+        }
+    } else {
+        TextMatch::Contains {
+            text,
+            ignore_case: true,
+        }
+    }
+}
+
+fn split_comparison(word: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        (">=", Comparison::AtLeast),
+        ("<=", Comparison::AtMost),
+        (">", Comparison::MoreSevere),
+        ("<", Comparison::LessSevere),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(name) = word.strip_prefix(prefix) {
+            return (comparison, name);
+        }
+    }
+    (Comparison::Equal, word)
+}
+
+/// Row values a query looks at; text that needs decoding is produced once,
+/// on first use.
+struct RowView<'a> {
+    dlt: &'a dyn DltStore,
+    index: usize,
+    rendered: OnceCell<String>,
+    payload: OnceCell<String>,
+}
+
+impl SearchQuery {
+    /// Case-insensitive search for `text` in the rendered row text, used
+    /// when the search box does not hold a valid query.
+    pub(crate) fn plain(text: &str) -> Self {
+        Self::Text {
+            field: TextField::Row,
+            matcher: text_match(text.to_string(), false),
+        }
+    }
+
+    pub(crate) fn matches(&self, dlt: &dyn DltStore, index: usize) -> bool {
+        self.matches_row(&RowView {
+            dlt,
+            index,
+            rendered: OnceCell::new(),
+            payload: OnceCell::new(),
+        })
+    }
+
+    fn matches_row(&self, row: &RowView<'_>) -> bool {
+        let (dlt, index) = (row.dlt, row.index);
+        match self {
+            Self::Text { field, matcher } => match field {
+                TextField::Row => {
+                    matcher.is_match(row.rendered.get_or_init(|| rendered_row_text(dlt, index)))
+                }
+                TextField::Ecu => matcher.is_match(dlt.ecu(index)),
+                TextField::Apid => matcher.is_match(dlt.apid(index)),
+                TextField::Ctid => matcher.is_match(dlt.ctid(index)),
+                TextField::Type => matcher.is_match(&format_message_type(
+                    dlt.message_type(index),
+                    dlt.message_type_info(index),
+                )),
+                TextField::Payload => {
+                    matcher.is_match(row.payload.get_or_init(|| dlt.payload_text(index)))
+                }
+                TextField::Source => {
+                    matcher.is_match(&format_source_location(dlt.source_location(index)))
+                }
+                TextField::Tag => dlt.tags(index).into_iter().any(|tag| matcher.is_match(tag)),
+            },
+            Self::Level { comparison, level } => {
+                let mtin = dlt.message_type_info(index);
+                dlt.message_type(index) == MESSAGE_TYPE_LOG
+                    && (1..=6).contains(&mtin)
+                    && comparison.matches(mtin, *level)
+            }
+            Self::MessageId(id) => dlt.message_id(index) == Some(*id),
+            Self::SessionId(id) => dlt.session_id(index) == *id,
+            Self::Not(query) => !query.matches_row(row),
+            Self::And(queries) => queries.iter().all(|query| query.matches_row(row)),
+            Self::Or(queries) => queries.iter().any(|query| query.matches_row(row)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::v1;
+    use crate::dlt::writer::MessageBuilder;
+
+    fn store() -> v1::Dlt {
+        let message = |apid: &str, level: u8, text: &str| {
+            MessageBuilder::new()
+                .with_ecu("ECU1")
+                .with_apid(apid)
+                .with_ctid("CTX1")
+                .with_message_type_info(level)
+                .with_verbose_string(text)
+                .build(1)
+                .unwrap()
+        };
+        let mut stream = v1::Dlt::stream();
+        let frames = [
+            message("DIAG", 2, "request timeout 250ms"),
+            message("DIAG", 4, "session started"),
+            message("NAVI", 3, "route lost"),
+            message("NAVI", 5, "Timeout (retry)"),
+        ];
+        assert!(stream.append(&frames.concat()).is_empty());
+        stream
+    }
+
+    fn matching_rows(query: &str) -> Vec<usize> {
+        let dlt = store();
+        let query = parse_query(query).unwrap().unwrap();
+        (0..dlt.len())
+            .filter(|&row| query.matches(&dlt, row))
+            .collect()
+    }
+
+    #[test]
+    fn words_phrases_and_regexes_search_the_rendered_row() {
+        assert_eq!(matching_rows("timeout"), vec![0, 3]);
+        assert_eq!(matching_rows("session started"), vec![1]);
+        assert_eq!(matching_rows("\"started session\""), Vec::<usize>::new());
+        assert_eq!(matching_rows(r"/timeout \d+ms/"), vec![0]);
+        assert_eq!(matching_rows("/^3 .*NAVI/"), vec![3]);
+        assert_eq!(matching_rows("/TIMEOUT/"), Vec::<usize>::new());
+        assert_eq!(matching_rows("/TIMEOUT/i"), vec![0, 3]);
+    }
+
+    #[test]
+    fn boolean_operators_and_parentheses() {
+        assert_eq!(matching_rows("timeout AND NOT retry"), vec![0]);
+        assert_eq!(matching_rows("started OR lost"), vec![1, 2]);
+        assert_eq!(matching_rows("NOT (timeout OR started)"), vec![2]);
+        assert_eq!(matching_rows("apid:NAVI (lost OR retry)"), vec![2, 3]);
+        assert_eq!(matching_rows("(Timeout (retry))"), vec![3]);
+    }
+
+    #[test]
+    fn field_terms_look_at_one_field() {
+        assert_eq!(matching_rows("apid:diag"), vec![0, 1]);
+        assert_eq!(matching_rows("apid:DIA"), Vec::<usize>::new());
+        assert_eq!(matching_rows("apid:/^NA/ payload:lost"), vec![2]);
+        assert_eq!(matching_rows(r"payload:/timeout \d+ms/"), vec![0]);
+        assert_eq!(matching_rows("type:log/warn"), vec![2]);
+        assert_eq!(matching_rows("ecu:ECU1 session:0"), vec![0, 1, 2, 3]);
+        assert_eq!(matching_rows("level:>=warn"), vec![0, 2]);
+        assert_eq!(matching_rows("level:<info"), vec![3]);
+        assert_eq!(matching_rows("level:error"), vec![0]);
+        assert_eq!(
+            matching_rows("apid:DIAG level:>=warn payload:/timeout \\d+ms/"),
+            vec![0]
+        );
+        assert_eq!(matching_rows("key:value"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_problem() {
+        let error = |query: &str| parse_query(query).unwrap_err();
+        assert_eq!(error("(timeout").position, 0);
+        assert_eq!(error("(timeout))").message, "unmatched `)`");
+        assert_eq!(error("(timeout))").position, 9);
+        assert_eq!(error("timeout AND").message, "expected a search term");
+        assert_eq!(error("a \"phrase").position, 2);
+        assert_eq!(error("/(/").message, "invalid regex: error: unclosed group");
+        assert_eq!(error("level:>=loud").message, "unknown log level `loud`");
+        assert_eq!(
+            error("apid: x").message,
+            "expected a value after the field name"
+        );
+        assert_eq!(
+            error("/x/g").to_string(),
+            "column 4: unknown regex flag `g`"
+        );
+        assert!(parse_query("   ").unwrap().is_none());
+    }
+
+    #[test]
+    fn deeply_nested_queries_are_syntax_errors() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_query(&nested(MAX_NESTING)).is_ok());
+        let error = parse_query(&nested(MAX_NESTING + 1)).unwrap_err();
+        assert_eq!(error.position, MAX_NESTING);
+        assert_eq!(error.message, "more than 64 nested `(` or `NOT`");
+        assert_eq!(
+            parse_query(&"NOT ".repeat(MAX_NESTING + 1))
+                .unwrap_err()
+                .position,
+            MAX_NESTING * 4
+        );
+        assert!(parse_query(&"(NOT ".repeat(10_000)).is_err());
+    }
+}
@@ -1,10 +1,28 @@
-use crate::desktop::index::IndexLayer;
+use crate::desktop::index::{IndexLayer, QueryPipeline};
+use crate::desktop::query::{QuerySyntaxError, SearchQuery, parse_query};
 use crate::dlt;
+use crate::dlt::control::{CONTROL_RESPONSE, ControlMessage};
+use crate::dlt::counter::CounterTracker;
 use crate::dlt::error::ParseError;
-use crate::dlt::payload::{MESSAGE_TYPE, decode_message_type_info};
+use crate::dlt::export::{ExportSummary, export_rows};
+use crate::dlt::fibex::{FibexDatabase, FibexDlt};
+use crate::dlt::filter::FilterSet;
+use crate::dlt::merged::MergeMode;
+use crate::dlt::payload::LOG_INFO;
+pub(crate) use crate::dlt::payload::format_message_type;
+use crate::dlt::storage::format_timestamp_ns;
+use crate::dlt::store::DltStore;
+use crate::dlt::time_window::{TimeBound, TimeClock, TimeWindow, nearest_row};
+use crate::dlt::v1::protocol::MESSAGE_TYPE_LOG;
 use anyhow::{Result, anyhow};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct LogTableRow {
@@ -13,29 +31,19 @@ pub(crate) struct LogTableRow {
     pub(crate) ecu: String,
     pub(crate) apid: String,
     pub(crate) ctid: String,
+    pub(crate) version: u8,
     pub(crate) kind: String,
+    /// `file:line` from a v2 source location.
+    pub(crate) source: String,
+    /// v2 tags, comma-separated.
+    pub(crate) tags: String,
+    pub(crate) privacy: String,
+    /// Non-verbose message ID as `0x` hex.
+    pub(crate) message_id: String,
     pub(crate) payload: String,
-}
-
-fn format_timestamp_ns(ns: u64) -> String {
-    let seconds = ns / 1_000_000_000;
-    let micros = (ns % 1_000_000_000) / 1_000;
-    format!("{}.{:06}", seconds, micros)
-}
-
-pub(crate) fn format_message_type(mstp: u8, mtin: u8) -> String {
-    let mstp_usize = mstp as usize;
-    let family = MESSAGE_TYPE.get(mstp_usize).copied().unwrap_or("");
-    let info = decode_message_type_info(mstp_usize, mtin as usize);
-
-    if family.is_empty() {
-        return format!("unknown({mstp},{mtin})");
-    }
-    if info.is_empty() {
-        return family.to_string();
-    }
-
-    format!("{family}/{info}")
+    /// Messages lost right before this row, going by the sender's message
+    /// counter.
+    pub(crate) dropped_before: Option<u8>,
 }
 
 fn display_field(value: &str) -> String {
@@ -54,12 +62,28 @@ fn display_payload(value: String) -> String {
     }
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct StructuredFilter {
     pub(crate) ecu_contains: String,
     pub(crate) apid_contains: String,
     pub(crate) ctid_contains: String,
     pub(crate) kind_contains: String,
+    /// Least severe log level shown, as an MTIN value (3 = warn and worse);
+    /// other message types are hidden while it is set.
+    pub(crate) least_severe_level: Option<u8>,
+    /// Suffix of the v2 source file name, ignoring case, e.g. `can_driver.c`.
+    pub(crate) source_file_ends_with: String,
+    /// v2 source line number; rows without a source location are hidden
+    /// while it is set.
+    pub(crate) source_line_equals: String,
+    /// Matches rows carrying this v2 tag, ignoring case.
+    pub(crate) tag_equals: String,
+    pub(crate) privacy_equals: String,
+    /// Non-verbose message ID, as `0x` hex or decimal.
+    pub(crate) message_id_equals: String,
+    /// Rules applied after the fields above; every enabled rule must hold.
+    pub(crate) rules: Vec<StructuredRule>,
 }
 
 impl StructuredFilter {
@@ -68,125 +92,251 @@ impl StructuredFilter {
     }
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
-pub(crate) struct RenderedTextSearch {
-    pub(crate) query: String,
-    pub(crate) active_match_position: Option<usize>,
+/// Header field compared by a `RuleCriterion`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RuleField {
+    Ecu,
+    Apid,
+    Ctid,
 }
 
-#[derive(Debug)]
-pub(crate) enum RetainedDlt {
-    V1(dlt::v1::Dlt),
-    V2(dlt::v2::Dlt),
-}
+impl RuleField {
+    pub(crate) const ALL: [RuleField; 3] = [RuleField::Ecu, RuleField::Apid, RuleField::Ctid];
 
-impl RetainedDlt {
-    pub(crate) fn len(&self) -> usize {
+    pub(crate) fn label(self) -> &'static str {
         match self {
-            Self::V1(dlt) => dlt.len(),
-            Self::V2(dlt) => dlt.len(),
+            RuleField::Ecu => "ECU",
+            RuleField::Apid => "APID",
+            RuleField::Ctid => "CTID",
         }
     }
 
-    pub(crate) fn unique_ecu_count(&self) -> usize {
+    pub(crate) fn value(self, dlt: &dyn DltStore, row: usize) -> &str {
         match self {
-            Self::V1(dlt) => dlt.unique_ecus().len(),
-            Self::V2(dlt) => dlt.unique_ecus().len(),
+            RuleField::Ecu => dlt.ecu(row),
+            RuleField::Apid => dlt.apid(row),
+            RuleField::Ctid => dlt.ctid(row),
         }
     }
+}
 
-    pub(crate) fn unique_apid_count(&self) -> usize {
+/// What a `StructuredRule` checks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RuleCriterion {
+    /// The field equals one of `values`, ignoring case.
+    OneOf {
+        field: RuleField,
+        values: Vec<String>,
+    },
+    /// The field contains `text`, ignoring case.
+    Contains { field: RuleField, text: String },
+    /// Log messages from level `most_severe` to `least_severe`, as MTIN
+    /// values (1 = fatal .. 6 = verbose).
+    LogLevel { most_severe: u8, least_severe: u8 },
+    /// The session ID is one of `ids`.
+    SessionId(Vec<u32>),
+    /// Storage or message timestamps within the window.
+    TimeWindow(TimeWindow),
+}
+
+/// One entry of the Structured Filter rule list; like dlt-viewer's filter
+/// list, each rule can be switched off without removing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct StructuredRule {
+    pub(crate) enabled: bool,
+    /// Hide the rows the criterion matches instead of keeping them.
+    pub(crate) exclude: bool,
+    pub(crate) criterion: RuleCriterion,
+}
+
+impl RuleCriterion {
+    pub(crate) fn matches(&self, dlt: &dyn DltStore, row: usize) -> bool {
         match self {
-            Self::V1(dlt) => dlt.unique_apids().len(),
-            Self::V2(dlt) => dlt.unique_apids().len(),
+            RuleCriterion::OneOf { field, values } => {
+                let value = field.value(dlt, row);
+                values
+                    .iter()
+                    .any(|candidate| candidate.eq_ignore_ascii_case(value))
+            }
+            RuleCriterion::Contains { field, text } => field
+                .value(dlt, row)
+                .to_ascii_lowercase()
+                .contains(&text.to_ascii_lowercase()),
+            RuleCriterion::LogLevel {
+                most_severe,
+                least_severe,
+            } => log_level_within(dlt, row, *most_severe, *least_severe),
+            RuleCriterion::SessionId(ids) => ids.contains(&dlt.session_id(row)),
+            RuleCriterion::TimeWindow(window) => window.matches(dlt, row),
         }
     }
+}
 
-    pub(crate) fn unique_ctid_count(&self) -> usize {
-        match self {
-            Self::V1(dlt) => dlt.unique_ctids().len(),
-            Self::V2(dlt) => dlt.unique_ctids().len(),
+impl StructuredRule {
+    pub(crate) fn new(criterion: RuleCriterion) -> Self {
+        Self {
+            enabled: true,
+            exclude: false,
+            criterion,
         }
     }
 
-    pub(crate) fn ecu(&self, index: usize) -> &str {
-        match self {
-            Self::V1(dlt) => dlt.ecu(index),
-            Self::V2(dlt) => dlt.ecu(index),
+    pub(crate) fn excluding(criterion: RuleCriterion) -> Self {
+        Self {
+            exclude: true,
+            ..Self::new(criterion)
         }
     }
 
-    pub(crate) fn apid(&self, index: usize) -> &str {
-        match self {
-            Self::V1(dlt) => dlt.apid(index),
-            Self::V2(dlt) => dlt.apid(index),
-        }
+    /// Disabled rules match every row.
+    pub(crate) fn matches(&self, dlt: &dyn DltStore, row: usize) -> bool {
+        !self.enabled || self.criterion.matches(dlt, row) != self.exclude
     }
+}
 
-    pub(crate) fn ctid(&self, index: usize) -> &str {
-        match self {
-            Self::V1(dlt) => dlt.ctid(index),
-            Self::V2(dlt) => dlt.ctid(index),
+impl fmt::Display for StructuredRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exclude {
+            write!(f, "not ")?;
+        }
+        match &self.criterion {
+            RuleCriterion::OneOf { field, values } => match values.as_slice() {
+                [value] => write!(f, "{} = {value}", field.label()),
+                values => write!(f, "{} in {{{}}}", field.label(), values.join(", ")),
+            },
+            RuleCriterion::Contains { field, text } => {
+                write!(f, "{} contains {text}", field.label())
+            }
+            RuleCriterion::LogLevel {
+                most_severe,
+                least_severe,
+            } => {
+                let name = |level: &u8| LOG_INFO.get(usize::from(*level)).copied().unwrap_or("?");
+                if most_severe == least_severe {
+                    write!(f, "level = {}", name(most_severe))
+                } else {
+                    write!(f, "level {} to {}", name(most_severe), name(least_severe))
+                }
+            }
+            RuleCriterion::SessionId(ids) => match ids.as_slice() {
+                [id] => write!(f, "session = {id}"),
+                ids => write!(f, "session in {{{}}}", ids.iter().join(", ")),
+            },
+            RuleCriterion::TimeWindow(window) => write!(f, "{window}"),
         }
     }
+}
 
-    pub(crate) fn message_type(&self, index: usize) -> u8 {
-        match self {
-            Self::V1(dlt) => dlt.message_type(index),
-            Self::V2(dlt) => dlt.message_type(index),
-        }
+/// Whether `row` is a log message with a level from `most_severe` to
+/// `least_severe`, compared as MTIN values.
+pub(crate) fn log_level_within(
+    dlt: &dyn DltStore,
+    row: usize,
+    most_severe: u8,
+    least_severe: u8,
+) -> bool {
+    dlt.message_type(row) == MESSAGE_TYPE_LOG
+        && (most_severe..=least_severe).contains(&dlt.message_type_info(row))
+}
+
+/// Comma-separated values of a rule, trimmed, without empty entries.
+pub(crate) fn parse_rule_values(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RenderedTextSearch {
+    pub(crate) query: String,
+    pub(crate) active_match_position: Option<usize>,
+    /// `query` parsed; a plain text search when it has a syntax error.
+    matcher: Option<SearchQuery>,
+    syntax_error: Option<QuerySyntaxError>,
+}
+
+impl RenderedTextSearch {
+    fn set_query(&mut self, query: String) {
+        (self.matcher, self.syntax_error) = match parse_query(&query) {
+            Ok(matcher) => (matcher, None),
+            Err(error) => (Some(SearchQuery::plain(&query)), Some(error)),
+        };
+        self.query = query;
     }
+}
 
-    pub(crate) fn message_type_info(&self, index: usize) -> u8 {
-        match self {
-            Self::V1(dlt) => dlt.message_type_info(index),
-            Self::V2(dlt) => dlt.message_type_info(index),
-        }
+pub(crate) fn format_source_location(location: Option<(&str, u32)>) -> String {
+    match location {
+        Some((file, line)) => format!("{file}:{line}"),
+        None => String::new(),
     }
+}
 
-    pub(crate) fn row(&self, index: usize) -> LogTableRow {
-        match self {
-            Self::V1(dlt) => LogTableRow {
-                index,
-                timestamp: format_timestamp_ns(dlt.storage_timestamp_ns(index)),
-                ecu: display_field(dlt.ecu(index)),
-                apid: display_field(dlt.apid(index)),
-                ctid: display_field(dlt.ctid(index)),
-                kind: format_message_type(dlt.message_type(index), dlt.message_type_info(index)),
-                payload: display_payload(dlt.payload_text(index)),
-            },
-            Self::V2(dlt) => LogTableRow {
-                index,
-                timestamp: format_timestamp_ns(dlt.storage_timestamp_ns(index)),
-                ecu: display_field(dlt.ecu(index)),
-                apid: display_field(dlt.apid(index)),
-                ctid: display_field(dlt.ctid(index)),
-                kind: format_message_type(dlt.message_type(index), dlt.message_type_info(index)),
-                payload: display_payload(dlt.payload_text(index)),
-            },
-        }
+pub(crate) fn format_message_id(message_id: Option<u32>) -> String {
+    match message_id {
+        Some(id) => format!("0x{id:08x}"),
+        None => String::new(),
     }
+}
 
-    pub(crate) fn rendered_row_text(&self, index: usize) -> String {
-        let row = self.row(index);
-        format!(
-            "{} {} {} {} {} {} {}",
-            row.index, row.timestamp, row.ecu, row.apid, row.ctid, row.kind, row.payload
-        )
+/// Parse a message ID typed as `0x` hex or decimal.
+pub(crate) fn parse_message_id(text: &str) -> Option<u32> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
     }
 }
 
+pub(crate) fn log_table_row(dlt: &dyn DltStore, index: usize) -> LogTableRow {
+    LogTableRow {
+        index,
+        timestamp: format_timestamp_ns(dlt.storage_timestamp_ns(index)),
+        ecu: display_field(dlt.ecu(index)),
+        apid: display_field(dlt.apid(index)),
+        ctid: display_field(dlt.ctid(index)),
+        version: dlt.protocol_version(index),
+        kind: format_message_type(dlt.message_type(index), dlt.message_type_info(index)),
+        source: display_field(&format_source_location(dlt.source_location(index))),
+        tags: display_field(&dlt.tags(index).join(", ")),
+        privacy: display_field(
+            &dlt.privacy_level(index)
+                .map(|level| level.to_string())
+                .unwrap_or_default(),
+        ),
+        message_id: display_field(&format_message_id(dlt.message_id(index))),
+        payload: display_payload(dlt.payload_text(index)),
+        dropped_before: None,
+    }
+}
+
+pub(crate) fn rendered_row_text(dlt: &dyn DltStore, index: usize) -> String {
+    let row = log_table_row(dlt, index);
+    format!(
+        "{} {} {} {} {} {} {}",
+        row.index, row.timestamp, row.ecu, row.apid, row.ctid, row.kind, row.payload
+    )
+}
+
 #[derive(Debug)]
 pub(crate) struct RetainedDataSet {
     pub(crate) paths: Vec<PathBuf>,
-    pub(crate) version: u8,
+    /// Description of the live source when rows arrive from one instead of files.
+    pub(crate) live_source: Option<String>,
+    pub(crate) versions: Vec<u8>,
     pub(crate) parse_errors: Vec<ParseError>,
-    dlt: RetainedDlt,
+    dlt: Box<dyn DltStore>,
     index: IndexLayer,
     pub(crate) active_filter: StructuredFilter,
+    filter_set: Option<FilterSet>,
     rendered_search: RenderedTextSearch,
     selected_visible_row: Option<usize>,
     pending_scroll_to_selected: bool,
+    counters: CounterTracker,
 }
 
 impl RetainedDataSet {
@@ -194,6 +344,15 @@ impl RetainedDataSet {
         self.dlt.len()
     }
 
+    /// Protocol versions present in the data set, e.g. `v1` or `v1, v2`.
+    pub(crate) fn version_label(&self) -> String {
+        self.versions
+            .iter()
+            .map(|version| format!("v{version}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub(crate) fn file_count(&self) -> usize {
         self.paths.len()
     }
@@ -202,16 +361,38 @@ impl RetainedDataSet {
         self.parse_errors.len()
     }
 
+    pub(crate) fn unique_ecus(&self) -> Vec<&str> {
+        self.dlt.unique_ecus()
+    }
+
     pub(crate) fn unique_ecu_count(&self) -> usize {
-        self.dlt.unique_ecu_count()
+        self.dlt.unique_ecus().len()
     }
 
     pub(crate) fn unique_apid_count(&self) -> usize {
-        self.dlt.unique_apid_count()
+        self.dlt.unique_apids().len()
     }
 
     pub(crate) fn unique_ctid_count(&self) -> usize {
-        self.dlt.unique_ctid_count()
+        self.dlt.unique_ctids().len()
+    }
+
+    /// Rows per non-verbose message ID, most frequent first.
+    pub(crate) fn message_id_counts(&self) -> Vec<(u32, usize)> {
+        let mut counts = BTreeMap::new();
+        for row in 0..self.dlt.len() {
+            if let Some(id) = self.dlt.message_id(row) {
+                *counts.entry(id).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Messages lost to message counter gaps, and the number of gaps.
+    pub(crate) fn dropped_message_count(&self) -> (u64, usize) {
+        (self.counters.dropped(), self.counters.gaps().len())
     }
 
     pub(crate) fn visible_message_count(&self) -> usize {
@@ -221,13 +402,59 @@ impl RetainedDataSet {
     pub(crate) fn rebuild_index(&mut self) {
         let previous_selected_index = self.selected_row_index();
         self.index = IndexLayer::from_filter_and_search(
-            &self.dlt,
+            self.dlt.as_ref(),
             &self.active_filter,
-            self.rendered_search.query.as_str(),
+            self.filter_set.as_ref(),
+            self.rendered_search.matcher.as_ref(),
         );
         self.rebuild_rendered_search(previous_selected_index);
     }
 
+    /// Append stored frames from a live stream and index only the new rows.
+    pub(crate) fn append_frames(&mut self, frames: &[u8]) -> Result<()> {
+        let first_row = self.dlt.len();
+        let errors = self.dlt.append(frames)?;
+        self.index_appended_rows(first_row, errors);
+        Ok(())
+    }
+
+    /// Parse frames appended to the files since the last refresh and index
+    /// only the new rows. Returns the number of new rows.
+    pub(crate) fn refresh_files(&mut self) -> Result<usize> {
+        let first_row = self.dlt.len();
+        let errors = self.dlt.refresh()?;
+        self.index_appended_rows(first_row, errors);
+        Ok(self.dlt.len() - first_row)
+    }
+
+    fn index_appended_rows(&mut self, first_row: usize, errors: Vec<ParseError>) {
+        self.parse_errors.extend(errors);
+        self.versions = self.dlt.protocol_versions();
+        self.counters
+            .scan(self.dlt.as_ref(), first_row..self.dlt.len());
+
+        QueryPipeline::new(
+            &self.active_filter,
+            self.filter_set.as_ref(),
+            self.rendered_search.matcher.as_ref(),
+        )
+        .extend(
+            self.dlt.as_ref(),
+            &mut self.index,
+            first_row..self.dlt.len(),
+        );
+        if self.selected_visible_row.is_none() && self.index.visible_count() > 0 {
+            self.rebuild_rendered_search(None);
+        }
+    }
+
+    /// Control responses among `rows`, for matching to sent requests.
+    pub(crate) fn control_responses(&self, rows: Range<usize>) -> Vec<(usize, ControlMessage)> {
+        rows.filter(|&row| self.dlt.message_type_info(row) == CONTROL_RESPONSE)
+            .filter_map(|row| Some((row, self.dlt.control_message(row)?)))
+            .collect()
+    }
+
     pub(crate) fn set_structured_filter(&mut self, filter: StructuredFilter) {
         self.active_filter = filter;
         self.rebuild_index();
@@ -238,12 +465,30 @@ impl RetainedDataSet {
         self.rebuild_index();
     }
 
+    pub(crate) fn set_filter_set(&mut self, filter_set: Option<FilterSet>) {
+        self.filter_set = filter_set;
+        self.rebuild_index();
+    }
+
+    /// Write the currently visible rows, in view order, to a new `.dlt` file.
+    pub(crate) fn export_visible(&self, path: &Path) -> Result<ExportSummary> {
+        export_rows(
+            self.dlt.as_ref(),
+            self.index.visible_indices.iter().copied(),
+            path,
+        )
+    }
+
     pub(crate) fn visible_rows(&self, range: Range<usize>) -> Vec<LogTableRow> {
-        self.index.visible_rows(&self.dlt, range)
+        let mut rows = self.index.visible_rows(self.dlt.as_ref(), range);
+        for row in &mut rows {
+            row.dropped_before = self.counters.gap_at(row.index).map(|gap| gap.dropped());
+        }
+        rows
     }
 
     pub(crate) fn set_rendered_search_query(&mut self, query: String) {
-        self.rendered_search.query = query;
+        self.rendered_search.set_query(query);
         self.rebuild_index();
     }
 
@@ -251,6 +496,11 @@ impl RetainedDataSet {
         self.rendered_search.query.as_str()
     }
 
+    /// Why the search query does not parse; it is then searched as plain text.
+    pub(crate) fn rendered_search_syntax_error(&self) -> Option<&QuerySyntaxError> {
+        self.rendered_search.syntax_error.as_ref()
+    }
+
     pub(crate) fn rendered_search_match_count(&self) -> usize {
         self.index.rendered_search_match_count()
     }
@@ -307,6 +557,32 @@ impl RetainedDataSet {
         pending
     }
 
+    /// Select the row with store index `index`; `false` when the current
+    /// filter or search hides it.
+    pub(crate) fn select_row_index(&mut self, index: usize) -> bool {
+        match self.index.visible_indices.binary_search(&index) {
+            Ok(position) => {
+                self.select_visible_row(position, true);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Select the visible row whose timestamp on `clock` is nearest to
+    /// `bound`; `false` when no row is visible.
+    pub(crate) fn select_nearest_time(&mut self, clock: TimeClock, bound: TimeBound) -> bool {
+        let ns = bound.resolve(self.dlt.as_ref(), clock);
+        let position = nearest_row(self.dlt.as_ref(), &self.index.visible_indices, clock, ns);
+        match position {
+            Some(position) => {
+                self.select_visible_row(position, true);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub(crate) fn select_visible_row(&mut self, position: usize, request_scroll: bool) {
         if self.index.visible_index_at(position).is_none() {
             return;
@@ -328,7 +604,7 @@ impl RetainedDataSet {
 
     #[cfg(test)]
     pub(crate) fn rendered_row_text_for_index(&self, index: usize) -> String {
-        self.dlt.rendered_row_text(index)
+        rendered_row_text(self.dlt.as_ref(), index)
     }
 
     fn rebuild_rendered_search(&mut self, previous_selected_index: Option<usize>) {
@@ -373,54 +649,90 @@ impl RetainedDataSet {
 }
 
 pub(crate) fn load_retained_dataset(paths: Vec<PathBuf>) -> Result<RetainedDataSet> {
+    load_retained_dataset_with_options(paths, &LoadOptions::default())
+}
+
+/// Settings applied when opening files; kept by the model across reloads.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LoadOptions {
+    pub(crate) merge: Option<MergeMode>,
+    /// Descriptions used to decode non-verbose messages.
+    pub(crate) fibex: Option<Arc<FibexDatabase>>,
+    /// Open files for following; path order is kept and `merge` is ignored.
+    pub(crate) follow: bool,
+}
+
+pub(crate) fn load_retained_dataset_with_options(
+    paths: Vec<PathBuf>,
+    options: &LoadOptions,
+) -> Result<RetainedDataSet> {
+    load_retained_dataset_until(paths, options, &AtomicBool::new(false), |_| {})
+}
+
+/// Like `load_retained_dataset_with_options`. When following, `waiting` is
+/// told about each file that has no first message yet, and setting `stop`
+/// gives up waiting for it.
+pub(crate) fn load_retained_dataset_until(
+    paths: Vec<PathBuf>,
+    options: &LoadOptions,
+    stop: &AtomicBool,
+    waiting: impl FnMut(&Path),
+) -> Result<RetainedDataSet> {
     if paths.is_empty() {
         return Err(anyhow!("No DLT paths selected"));
     }
 
-    let version = dlt::detect_version(&paths[0])?;
-    for path in &paths[1..] {
-        let candidate = dlt::detect_version(path)?;
-        if candidate != version {
-            return Err(anyhow!(
-                "Mixed DLT versions: first file is v{} but {:?} is v{}",
-                version,
-                path,
-                candidate
-            ));
-        }
-    }
-
-    if version == 1 {
-        let (dlt, parse_errors) = dlt::v1::Dlt::open(paths.clone())?;
-        let mut data = RetainedDataSet {
-            paths,
-            version,
-            parse_errors,
-            dlt: RetainedDlt::V1(dlt),
-            index: IndexLayer::empty(),
-            active_filter: StructuredFilter::default(),
-            rendered_search: RenderedTextSearch::default(),
-            selected_visible_row: None,
-            pending_scroll_to_selected: false,
-        };
-        data.rebuild_index();
-        Ok(data)
-    } else if version == 2 {
-        let (dlt, parse_errors) = dlt::v2::Dlt::open(paths.clone())?;
-        let mut data = RetainedDataSet {
-            paths,
-            version,
-            parse_errors,
-            dlt: RetainedDlt::V2(dlt),
-            index: IndexLayer::empty(),
-            active_filter: StructuredFilter::default(),
-            rendered_search: RenderedTextSearch::default(),
-            selected_visible_row: None,
-            pending_scroll_to_selected: false,
-        };
-        data.rebuild_index();
-        Ok(data)
+    let (mut dlt, parse_errors) = if options.follow {
+        let version = dlt::wait_for_follow_version(&paths, stop, waiting)?;
+        dlt::open_following(paths.clone(), version)?
     } else {
-        Err(anyhow!("Unsupported DLT version: {}", version))
-    }
-}
\ No newline at end of file
+        dlt::open_with_merge(paths.clone(), options.merge.as_ref())?
+    };
+    if let Some(database) = &options.fibex {
+        dlt = Box::new(FibexDlt::new(dlt, Arc::clone(database)));
+    }
+    let counters = CounterTracker::scan_all(dlt.as_ref());
+    let mut data = RetainedDataSet {
+        paths,
+        live_source: None,
+        versions: dlt.protocol_versions(),
+        parse_errors,
+        dlt,
+        index: IndexLayer::empty(),
+        active_filter: StructuredFilter::default(),
+        filter_set: None,
+        rendered_search: RenderedTextSearch::default(),
+        selected_visible_row: None,
+        pending_scroll_to_selected: false,
+        counters,
+    };
+    data.rebuild_index();
+    Ok(data)
+}
+
+/// An empty data set for a live stream of `version` messages from `source`;
+/// rows are added with `append_frames`.
+pub(crate) fn live_retained_dataset(
+    source: String,
+    version: u8,
+    options: &LoadOptions,
+) -> Result<RetainedDataSet> {
+    let mut dlt = dlt::open_stream(version)?;
+    if let Some(database) = &options.fibex {
+        dlt = Box::new(FibexDlt::new(dlt, Arc::clone(database)));
+    }
+    Ok(RetainedDataSet {
+        paths: Vec::new(),
+        live_source: Some(source),
+        versions: dlt.protocol_versions(),
+        parse_errors: Vec::new(),
+        dlt,
+        index: IndexLayer::empty(),
+        active_filter: StructuredFilter::default(),
+        filter_set: None,
+        rendered_search: RenderedTextSearch::default(),
+        selected_visible_row: None,
+        pending_scroll_to_selected: false,
+        counters: CounterTracker::new(),
+    })
+}
@@ -4,20 +4,61 @@ use crate::desktop::application::{
     DesktopModel,
     LoadGeneration,
 };
-use crate::desktop::retained::{RetainedDataSet, load_retained_dataset};
+use crate::desktop::preset::{PresetLibrary, PresetOptions};
+use crate::desktop::retained::{
+    RetainedDataSet, RuleCriterion, RuleField, StructuredFilter, StructuredRule, format_message_id,
+    load_retained_dataset_until, parse_rule_values,
+};
+use crate::dlt::control::{
+    ControlMessage, LOG_INFO_WITH_DESCRIPTIONS, RequestLog, log_level_name, trace_status_name,
+};
+use crate::dlt::fibex::FibexDatabase;
+use crate::dlt::filter::FilterSet;
+use crate::dlt::merged::{MergeMode, parse_ecu_offset};
+use crate::dlt::payload::LOG_INFO;
+use crate::dlt::stream::{ControlSender, DEFAULT_BAUD_RATE, LiveSource};
+use crate::dlt::time_window::{TimeClock, TimeWindow, parse_time_bound};
 use anyhow::{Result, anyhow};
 use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
 
+const TABLE_COL_VERSION: f32 = 30.0;
 const TABLE_COL_TIMESTAMP: f32 = 140.0;
 const TABLE_COL_ECU: f32 = 70.0;
 const TABLE_COL_APID: f32 = 70.0;
 const TABLE_COL_CTID: f32 = 70.0;
 const TABLE_COL_TYPE: f32 = 140.0;
+const TABLE_COL_SOURCE: f32 = 160.0;
+const TABLE_COL_TAGS: f32 = 120.0;
+const TABLE_COL_PRIVACY: f32 = 50.0;
+const TABLE_COL_MESSAGE_ID: f32 = 90.0;
+const SYNTAX_ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 60, 60);
+const SEARCH_HELP: &str = "Words and \"quoted phrases\" search the row text, ignoring case.\n\
+/regex/ searches it with a regular expression; /regex/i ignores case.\n\
+field:value searches one field: ecu, apid, ctid, tag (whole value), type, payload, \
+source (substring), msgid, session, and level with =, >=, >, <=, < (>=warn is warn and worse).\n\
+Terms must all match; combine them with OR, NOT and parentheses.";
+const TIME_HELP: &str = "Seconds as shown in the Timestamp column, +seconds after the first row, \
+or a UTC time YYYY-MM-DD HH:MM:SS[.fraction].";
+/// Marks rows that follow a message counter gap, and the dropped count.
+const DROPPED_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 60, 60);
+/// Message IDs listed in the grouping panel.
+const MESSAGE_ID_GROUP_LIMIT: usize = 50;
 const TABLE_ROW_HEIGHT: f32 = 20.0;
+/// How often followed files are checked for appended messages.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
 
-fn render_structured_filter_controls(ui: &mut egui::Ui, data: &RetainedDataSet) -> Vec<DesktopIntent> {
+fn render_structured_filter_controls(
+    ui: &mut egui::Ui,
+    data: &RetainedDataSet,
+    rule_form: &mut RuleForm,
+) -> Vec<DesktopIntent> {
     ui.separator();
     ui.label("Structured Filter");
 
@@ -50,9 +91,67 @@ fn render_structured_filter_controls(ui: &mut egui::Ui, data: &RetainedDataSet)
                     .hint_text("Type contains"),
             )
             .changed();
+        egui::ComboBox::from_id_salt("least_severe_level")
+            .selected_text(level_selector_label(next_filter.least_severe_level))
+            .show_ui(ui, |ui| {
+                for level in [None, Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)] {
+                    changed |= ui
+                        .selectable_value(
+                            &mut next_filter.least_severe_level,
+                            level,
+                            level_selector_label(level),
+                        )
+                        .changed();
+                }
+            });
 
         clear_clicked = ui.button("Clear").clicked();
     });
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut next_filter.source_file_ends_with)
+                    .hint_text("Source file ends with"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut next_filter.source_line_equals).hint_text("Line ="),
+            )
+            .changed();
+        changed |= ui
+            .add(egui::TextEdit::singleline(&mut next_filter.tag_equals).hint_text("Tag ="))
+            .changed();
+        changed |= ui
+            .add(egui::TextEdit::singleline(&mut next_filter.privacy_equals).hint_text("Privacy ="))
+            .changed();
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut next_filter.message_id_equals)
+                    .hint_text("Msg ID ="),
+            )
+            .changed();
+    });
+    egui::CollapsingHeader::new("Message IDs")
+        .id_salt("message_id_groups")
+        .show(ui, |ui| {
+            let counts = data.message_id_counts();
+            if counts.is_empty() {
+                ui.label("No non-verbose messages.");
+            }
+            for (id, count) in counts.into_iter().take(MESSAGE_ID_GROUP_LIMIT) {
+                let label = format!("{} ({count})", format_message_id(Some(id)));
+                if ui.selectable_label(false, label).clicked() {
+                    next_filter.message_id_equals = format_message_id(Some(id));
+                    changed = true;
+                }
+            }
+        });
+    egui::CollapsingHeader::new(format!("Filter rules ({})", next_filter.rules.len()))
+        .id_salt("structured_filter_rules")
+        .show(ui, |ui| {
+            changed |= render_filter_rules(ui, rule_form, &mut next_filter);
+        });
 
     if clear_clicked {
         intents.push(DesktopIntent::StructuredFilterCleared);
@@ -69,9 +168,638 @@ fn render_structured_filter_controls(ui: &mut egui::Ui, data: &RetainedDataSet)
     intents
 }
 
+fn render_clock_selector(ui: &mut egui::Ui, salt: &str, clock: &mut TimeClock) {
+    egui::ComboBox::from_id_salt(salt)
+        .selected_text(clock.label())
+        .show_ui(ui, |ui| {
+            for candidate in [TimeClock::Storage, TimeClock::Message] {
+                ui.selectable_value(clock, candidate, candidate.label());
+            }
+        });
+}
+
+/// Inputs of the "Go to time" control.
+#[derive(Debug, Clone)]
+struct GoToTimeForm {
+    clock: TimeClock,
+    text: String,
+    error: Option<String>,
+}
+
+impl Default for GoToTimeForm {
+    fn default() -> Self {
+        Self {
+            clock: TimeClock::Storage,
+            text: String::new(),
+            error: None,
+        }
+    }
+}
+
+/// Selects the visible row nearest to the entered time.
+fn render_go_to_time(ui: &mut egui::Ui, form: &mut GoToTimeForm) -> Option<DesktopIntent> {
+    let mut intent = None;
+    ui.horizontal(|ui| {
+        ui.label("Go to time");
+        render_clock_selector(ui, "go_to_time_clock", &mut form.clock);
+        let response = ui
+            .add(egui::TextEdit::singleline(&mut form.text).hint_text("seconds, +seconds or date"))
+            .on_hover_text(TIME_HELP);
+        let entered =
+            response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+        if ui.button("Go").clicked() || entered {
+            match parse_time_bound(&form.text) {
+                Ok(bound) => {
+                    form.error = None;
+                    intent = Some(DesktopIntent::GoToTimeRequested(form.clock, bound));
+                }
+                Err(err) => form.error = Some(err.to_string()),
+            }
+        }
+    });
+    if let Some(error) = &form.error {
+        ui.colored_label(SYNTAX_ERROR_COLOR, error);
+    }
+    intent
+}
+
+fn level_selector_label(least_severe: Option<u8>) -> String {
+    match least_severe {
+        None => "All levels".to_string(),
+        Some(1) => "fatal".to_string(),
+        Some(level) => format!("{} and worse", LOG_INFO[usize::from(level)]),
+    }
+}
+
+/// Rule list with an enable checkbox, include/exclude toggle and remove
+/// button per rule, followed by the form adding a rule. Returns whether
+/// `filter` changed.
+fn render_filter_rules(
+    ui: &mut egui::Ui,
+    form: &mut RuleForm,
+    filter: &mut StructuredFilter,
+) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    for (position, rule) in filter.rules.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let text = rule.to_string();
+            changed |= ui.checkbox(&mut rule.enabled, text).changed();
+            let toggle = if rule.exclude { "Exclude" } else { "Include" };
+            if ui
+                .small_button(toggle)
+                .on_hover_text("Keep or hide the matching rows")
+                .clicked()
+            {
+                rule.exclude = !rule.exclude;
+                changed = true;
+            }
+            if ui.small_button("Remove").clicked() {
+                removed = Some(position);
+            }
+        });
+    }
+    if let Some(position) = removed {
+        filter.rules.remove(position);
+        changed = true;
+    }
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("rule_kind")
+            .selected_text(form.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in RuleKind::ALL {
+                    ui.selectable_value(&mut form.kind, kind, kind.label());
+                }
+            });
+        match form.kind {
+            RuleKind::Equals | RuleKind::Contains => {
+                egui::ComboBox::from_id_salt("rule_field")
+                    .selected_text(form.field.label())
+                    .show_ui(ui, |ui| {
+                        for field in RuleField::ALL {
+                            ui.selectable_value(&mut form.field, field, field.label());
+                        }
+                    });
+                let hint = if form.kind == RuleKind::Equals {
+                    "values, comma separated"
+                } else {
+                    "text"
+                };
+                ui.add(egui::TextEdit::singleline(&mut form.values).hint_text(hint));
+            }
+            RuleKind::LogLevel => {
+                for (salt, level) in [
+                    ("rule_most_severe", &mut form.most_severe),
+                    ("rule_least_severe", &mut form.least_severe),
+                ] {
+                    egui::ComboBox::from_id_salt(salt)
+                        .selected_text(LOG_INFO[usize::from(*level)])
+                        .show_ui(ui, |ui| {
+                            for candidate in 1..=6 {
+                                ui.selectable_value(
+                                    level,
+                                    candidate,
+                                    LOG_INFO[usize::from(candidate)],
+                                );
+                            }
+                        });
+                }
+            }
+            RuleKind::SessionId => {
+                ui.add(
+                    egui::TextEdit::singleline(&mut form.values)
+                        .hint_text("session IDs, comma separated"),
+                );
+            }
+            RuleKind::TimeWindow => {
+                render_clock_selector(ui, "rule_clock", &mut form.clock);
+                ui.add(egui::TextEdit::singleline(&mut form.from).hint_text("from"))
+                    .on_hover_text(TIME_HELP);
+                ui.add(egui::TextEdit::singleline(&mut form.to).hint_text("to"))
+                    .on_hover_text(TIME_HELP);
+            }
+        }
+        ui.checkbox(&mut form.exclude, "Exclude");
+        if ui.button("Add rule").clicked() {
+            match form.rule() {
+                Ok(rule) => {
+                    filter.rules.push(rule);
+                    form.error = None;
+                    changed = true;
+                }
+                Err(message) => form.error = Some(message),
+            }
+        }
+    });
+    if let Some(error) = &form.error {
+        ui.colored_label(SYNTAX_ERROR_COLOR, error);
+    }
+
+    changed
+}
+
+/// Preset dropdown, saving the current Structured Filter under a name, and
+/// import and export of preset files.
+fn render_preset_controls(
+    ui: &mut egui::Ui,
+    model: &DesktopModel,
+    name_text: &mut String,
+) -> Vec<DesktopIntent> {
+    let mut intents = Vec::new();
+    let active = model.active_preset_name();
+    egui::ComboBox::from_label("Preset")
+        .selected_text(active.unwrap_or("None"))
+        .show_ui(ui, |ui| {
+            if model.presets().is_empty() {
+                ui.weak("No saved presets");
+            }
+            for preset in model.presets() {
+                let is_active = active == Some(preset.name.as_str());
+                if ui.selectable_label(is_active, &preset.name).clicked() {
+                    intents.push(DesktopIntent::PresetSelected(preset.name.clone()));
+                }
+            }
+        });
+    if let Some(name) = active
+        && ui.button("Delete").clicked()
+    {
+        intents.push(DesktopIntent::PresetDeleted(name.to_string()));
+    }
+
+    ui.add(
+        egui::TextEdit::singleline(name_text)
+            .hint_text("Preset name")
+            .desired_width(120.0),
+    );
+    if ui
+        .add_enabled(
+            !name_text.trim().is_empty(),
+            egui::Button::new("Save preset"),
+        )
+        .on_hover_text("Save the Structured Filter; an existing preset of that name is replaced")
+        .clicked()
+    {
+        intents.push(DesktopIntent::PresetSaved(std::mem::take(name_text)));
+    }
+
+    if ui.button("Import presets").clicked()
+        && let Some(path) = rfd::FileDialog::new()
+            .add_filter("Filter presets", &["toml"])
+            .pick_file()
+    {
+        intents.push(DesktopIntent::PresetsImported(path));
+    }
+    if !model.presets().is_empty()
+        && ui.button("Export presets").clicked()
+        && let Some(path) = rfd::FileDialog::new()
+            .add_filter("Filter presets", &["toml"])
+            .set_file_name("filter-presets.toml")
+            .save_file()
+    {
+        intents.push(DesktopIntent::PresetsExported(path));
+    }
+    if let Some(status) = model.preset_status() {
+        ui.label(status);
+    }
+
+    intents
+}
+
+fn merge_mode_label(mode: Option<&MergeMode>) -> &'static str {
+    match mode {
+        None => "Auto",
+        Some(MergeMode::Concatenate) => "Path order",
+        Some(MergeMode::StorageTimestamp) => "Storage time",
+        Some(MergeMode::MessageTimestamp { .. }) => "Message time",
+    }
+}
+
+fn parse_ecu_offsets(text: &str) -> Result<HashMap<String, i64>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_ecu_offset)
+        .collect()
+}
+
+fn render_merge_controls(
+    ui: &mut egui::Ui,
+    current: Option<&MergeMode>,
+    ecu_offsets_text: &mut String,
+) -> Option<DesktopIntent> {
+    let offsets = parse_ecu_offsets(ecu_offsets_text);
+    let message_mode = MergeMode::MessageTimestamp {
+        ecu_offsets_ns: offsets.clone().unwrap_or_default(),
+    };
+    let options = [
+        None,
+        Some(MergeMode::Concatenate),
+        Some(MergeMode::StorageTimestamp),
+        Some(message_mode),
+    ];
+
+    let mut selected = current.cloned();
+    egui::ComboBox::from_label("Merge")
+        .selected_text(merge_mode_label(current))
+        .show_ui(ui, |ui| {
+            for option in options {
+                let label = merge_mode_label(option.as_ref());
+                let is_current = merge_mode_label(current) == label;
+                if ui.selectable_label(is_current, label).clicked() {
+                    selected = option;
+                }
+            }
+        });
+
+    if let Some(MergeMode::MessageTimestamp { .. }) = current {
+        ui.add(
+            egui::TextEdit::singleline(ecu_offsets_text)
+                .hint_text("ECU offsets, e.g. ECU1=0.5, ECU2=-1")
+                .desired_width(200.0),
+        );
+        match &offsets {
+            Ok(offsets) => {
+                let changed = !matches!(
+                    current,
+                    Some(MergeMode::MessageTimestamp { ecu_offsets_ns }) if ecu_offsets_ns == offsets
+                );
+                if ui
+                    .add_enabled(changed, egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    selected = Some(MergeMode::MessageTimestamp {
+                        ecu_offsets_ns: offsets.clone(),
+                    });
+                }
+            }
+            Err(message) => {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+        }
+    }
+
+    (selected.as_ref() != current).then_some(DesktopIntent::MergeModeChanged(selected))
+}
+
+/// Control requests offered in the control panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlService {
+    SetLogLevel,
+    SetDefaultLogLevel,
+    SetTraceStatus,
+    GetLogInfo,
+    StoreConfig,
+    GetSoftwareVersion,
+}
+
+impl ControlService {
+    const ALL: [ControlService; 6] = [
+        ControlService::SetLogLevel,
+        ControlService::SetDefaultLogLevel,
+        ControlService::SetTraceStatus,
+        ControlService::GetLogInfo,
+        ControlService::StoreConfig,
+        ControlService::GetSoftwareVersion,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ControlService::SetLogLevel => "set_log_level",
+            ControlService::SetDefaultLogLevel => "set_default_log_level",
+            ControlService::SetTraceStatus => "set_trace_status",
+            ControlService::GetLogInfo => "get_log_info",
+            ControlService::StoreConfig => "store_config",
+            ControlService::GetSoftwareVersion => "get_software_version",
+        }
+    }
+
+    /// Whether the request addresses an application and context.
+    fn takes_context(self) -> bool {
+        matches!(
+            self,
+            ControlService::SetLogLevel
+                | ControlService::SetTraceStatus
+                | ControlService::GetLogInfo
+        )
+    }
+
+    fn request(self, form: &ControlForm) -> ControlMessage {
+        let apid = form.apid.trim();
+        let ctid = form.ctid.trim();
+        match self {
+            ControlService::SetLogLevel => {
+                ControlMessage::set_log_level(apid, ctid, form.log_level)
+            }
+            ControlService::SetDefaultLogLevel => {
+                ControlMessage::set_default_log_level(form.log_level)
+            }
+            ControlService::SetTraceStatus => {
+                ControlMessage::set_trace_status(apid, ctid, form.trace_status)
+            }
+            ControlService::GetLogInfo => {
+                ControlMessage::get_log_info(LOG_INFO_WITH_DESCRIPTIONS, apid, ctid)
+            }
+            ControlService::StoreConfig => ControlMessage::store_config(),
+            ControlService::GetSoftwareVersion => ControlMessage::get_software_version(),
+        }
+    }
+}
+
+/// Inputs of the control panel.
+#[derive(Debug, Clone)]
+struct ControlForm {
+    service: ControlService,
+    ecu: String,
+    apid: String,
+    ctid: String,
+    log_level: i8,
+    trace_status: i8,
+}
+
+impl Default for ControlForm {
+    fn default() -> Self {
+        Self {
+            service: ControlService::SetLogLevel,
+            ecu: String::new(),
+            apid: String::new(),
+            ctid: String::new(),
+            log_level: 4,
+            trace_status: 0,
+        }
+    }
+}
+
+/// Kinds of rule offered by the rule form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RuleKind {
+    Equals,
+    Contains,
+    LogLevel,
+    SessionId,
+    TimeWindow,
+}
+
+impl RuleKind {
+    const ALL: [RuleKind; 5] = [
+        RuleKind::Equals,
+        RuleKind::Contains,
+        RuleKind::LogLevel,
+        RuleKind::SessionId,
+        RuleKind::TimeWindow,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            RuleKind::Equals => "equals",
+            RuleKind::Contains => "contains",
+            RuleKind::LogLevel => "log level",
+            RuleKind::SessionId => "session",
+            RuleKind::TimeWindow => "time window",
+        }
+    }
+}
+
+/// Inputs of the form adding a Structured Filter rule.
+#[derive(Debug, Clone)]
+struct RuleForm {
+    kind: RuleKind,
+    field: RuleField,
+    values: String,
+    most_severe: u8,
+    least_severe: u8,
+    clock: TimeClock,
+    from: String,
+    to: String,
+    exclude: bool,
+    error: Option<String>,
+}
+
+impl Default for RuleForm {
+    fn default() -> Self {
+        Self {
+            kind: RuleKind::Equals,
+            field: RuleField::Apid,
+            values: String::new(),
+            most_severe: 1,
+            least_severe: 3,
+            clock: TimeClock::Storage,
+            from: String::new(),
+            to: String::new(),
+            exclude: false,
+            error: None,
+        }
+    }
+}
+
+impl RuleForm {
+    fn rule(&self) -> Result<StructuredRule, String> {
+        let criterion = match self.kind {
+            RuleKind::Equals => {
+                let values = parse_rule_values(&self.values);
+                if values.is_empty() {
+                    return Err("Enter at least one value".to_string());
+                }
+                RuleCriterion::OneOf {
+                    field: self.field,
+                    values,
+                }
+            }
+            RuleKind::Contains => {
+                let text = self.values.trim();
+                if text.is_empty() {
+                    return Err("Enter the text to look for".to_string());
+                }
+                RuleCriterion::Contains {
+                    field: self.field,
+                    text: text.to_string(),
+                }
+            }
+            RuleKind::LogLevel => RuleCriterion::LogLevel {
+                most_severe: self.most_severe.min(self.least_severe),
+                least_severe: self.most_severe.max(self.least_severe),
+            },
+            RuleKind::SessionId => {
+                let ids = parse_rule_values(&self.values)
+                    .iter()
+                    .map(|value| value.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "Session IDs must be numbers".to_string())?;
+                if ids.is_empty() {
+                    return Err("Enter at least one session ID".to_string());
+                }
+                RuleCriterion::SessionId(ids)
+            }
+            RuleKind::TimeWindow => {
+                let bound = |text: &str| match text.trim() {
+                    "" => Ok(None),
+                    text => parse_time_bound(text)
+                        .map(Some)
+                        .map_err(|err| err.to_string()),
+                };
+                let from = bound(&self.from)?;
+                let to = bound(&self.to)?;
+                if from.is_none() && to.is_none() {
+                    return Err("Enter a start or end time".to_string());
+                }
+                RuleCriterion::TimeWindow(TimeWindow {
+                    clock: self.clock,
+                    from,
+                    to,
+                })
+            }
+        };
+        Ok(if self.exclude {
+            StructuredRule::excluding(criterion)
+        } else {
+            StructuredRule::new(criterion)
+        })
+    }
+}
+
+/// Returns whether "Send" was clicked, plus intents from the request list.
+fn render_control_panel(
+    ui: &mut egui::Ui,
+    form: &mut ControlForm,
+    default_ecu: &str,
+    requests: &RequestLog,
+    status: Option<&str>,
+    can_send: bool,
+) -> (bool, Vec<DesktopIntent>) {
+    ui.heading("Control");
+    let mut intents = Vec::new();
+
+    egui::Grid::new("control_form")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("ECU");
+            ui.add(egui::TextEdit::singleline(&mut form.ecu).hint_text(default_ecu));
+            ui.end_row();
+
+            ui.label("Service");
+            egui::ComboBox::from_id_salt("control_service")
+                .selected_text(form.service.label())
+                .show_ui(ui, |ui| {
+                    for service in ControlService::ALL {
+                        ui.selectable_value(&mut form.service, service, service.label());
+                    }
+                });
+            ui.end_row();
+
+            if form.service.takes_context() {
+                ui.label("APID");
+                ui.add(egui::TextEdit::singleline(&mut form.apid).hint_text("all"));
+                ui.end_row();
+                ui.label("CTID");
+                ui.add(egui::TextEdit::singleline(&mut form.ctid).hint_text("all"));
+                ui.end_row();
+            }
+            if matches!(
+                form.service,
+                ControlService::SetLogLevel | ControlService::SetDefaultLogLevel
+            ) {
+                ui.label("Log level");
+                egui::ComboBox::from_id_salt("control_log_level")
+                    .selected_text(log_level_name(form.log_level))
+                    .show_ui(ui, |ui| {
+                        for level in -1..=6 {
+                            ui.selectable_value(&mut form.log_level, level, log_level_name(level));
+                        }
+                    });
+                ui.end_row();
+            }
+            if form.service == ControlService::SetTraceStatus {
+                ui.label("Trace status");
+                egui::ComboBox::from_id_salt("control_trace_status")
+                    .selected_text(trace_status_name(form.trace_status))
+                    .show_ui(ui, |ui| {
+                        for status in -1..=1 {
+                            ui.selectable_value(
+                                &mut form.trace_status,
+                                status,
+                                trace_status_name(status),
+                            );
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+
+    let send_clicked = ui
+        .add_enabled(can_send, egui::Button::new("Send"))
+        .on_disabled_hover_text("Connect over TCP or serial to send control requests")
+        .clicked();
+    if let Some(status) = status {
+        ui.label(status);
+    }
+
+    ui.separator();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for sent in requests.sent().iter().rev() {
+            ui.label(sent.request.to_string());
+            match &sent.response {
+                Some((row, response)) => {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("→ {response}"));
+                        if ui.small_button(format!("row {row}")).clicked() {
+                            intents.push(DesktopIntent::RowIndexSelected(*row));
+                        }
+                    });
+                }
+                None => {
+                    ui.weak("→ waiting for response");
+                }
+            }
+            ui.add_space(4.0);
+        }
+    });
+
+    (send_clicked, intents)
+}
+
 fn render_rendered_search_controls(ui: &mut egui::Ui, data: &RetainedDataSet) -> Vec<DesktopIntent> {
     ui.separator();
-    ui.label("Rendered Text Search");
+    ui.label("Rendered Text Search").on_hover_text(SEARCH_HELP);
 
     let mut intents = Vec::new();
     let mut query = data.rendered_search_query().to_string();
@@ -84,14 +812,25 @@ fn render_rendered_search_controls(ui: &mut egui::Ui, data: &RetainedDataSet) ->
         query_changed = ui
             .add(
                 egui::TextEdit::singleline(&mut query)
-                    .hint_text("Search rendered message text"),
+                    .hint_text("text \"phrase\" /regex/ apid:X level:>=warn AND OR NOT")
+                    .text_color_opt(
+                        data.rendered_search_syntax_error()
+                            .map(|_| SYNTAX_ERROR_COLOR),
+                    ),
             )
+            .on_hover_text(SEARCH_HELP)
             .changed();
 
         clear_clicked = ui.button("Clear").clicked();
         prev_clicked = ui.button("Prev").clicked();
         next_clicked = ui.button("Next").clicked();
     });
+    if let Some(error) = data.rendered_search_syntax_error() {
+        ui.colored_label(
+            SYNTAX_ERROR_COLOR,
+            format!("Syntax error at {error}; searching as plain text"),
+        );
+    }
 
     if query_changed {
         intents.push(DesktopIntent::RenderedSearchQueryUpdated(query));
@@ -123,13 +862,32 @@ fn render_rendered_search_controls(ui: &mut egui::Ui, data: &RetainedDataSet) ->
     intents
 }
 
+/// Table columns that are hidden unless switched on; they hold fields that
+/// most verbose traces never fill.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct OptionalColumns {
+    source: bool,
+    tags: bool,
+    privacy: bool,
+    message_id: bool,
+}
+
 fn render_log_table_with_navigation(
     ui: &mut egui::Ui,
     data: &RetainedDataSet,
+    columns: &mut OptionalColumns,
     should_scroll_to_selection: bool,
 ) -> Vec<DesktopIntent> {
     ui.separator();
-    ui.label("Log Table");
+    ui.horizontal(|ui| {
+        ui.label("Log Table");
+        ui.separator();
+        ui.checkbox(&mut columns.source, "Source");
+        ui.checkbox(&mut columns.tags, "Tags");
+        ui.checkbox(&mut columns.privacy, "Privacy");
+        ui.checkbox(&mut columns.message_id, "Msg ID");
+    });
+    let columns = *columns;
 
     let mut intents = Vec::new();
 
@@ -138,6 +896,10 @@ fn render_log_table_with_navigation(
             [50.0, TABLE_ROW_HEIGHT],
             egui::Label::new(egui::RichText::new("#").strong()),
         );
+        ui.add_sized(
+            [TABLE_COL_VERSION, TABLE_ROW_HEIGHT],
+            egui::Label::new(egui::RichText::new("Ver").strong()),
+        );
         ui.add_sized(
             [TABLE_COL_TIMESTAMP, TABLE_ROW_HEIGHT],
             egui::Label::new(egui::RichText::new("Timestamp").strong()),
@@ -158,6 +920,30 @@ fn render_log_table_with_navigation(
             [TABLE_COL_TYPE, TABLE_ROW_HEIGHT],
             egui::Label::new(egui::RichText::new("Type").strong()),
         );
+        if columns.source {
+            ui.add_sized(
+                [TABLE_COL_SOURCE, TABLE_ROW_HEIGHT],
+                egui::Label::new(egui::RichText::new("Source").strong()),
+            );
+        }
+        if columns.tags {
+            ui.add_sized(
+                [TABLE_COL_TAGS, TABLE_ROW_HEIGHT],
+                egui::Label::new(egui::RichText::new("Tags").strong()),
+            );
+        }
+        if columns.privacy {
+            ui.add_sized(
+                [TABLE_COL_PRIVACY, TABLE_ROW_HEIGHT],
+                egui::Label::new(egui::RichText::new("Priv").strong()),
+            );
+        }
+        if columns.message_id {
+            ui.add_sized(
+                [TABLE_COL_MESSAGE_ID, TABLE_ROW_HEIGHT],
+                egui::Label::new(egui::RichText::new("Msg ID").strong()),
+            );
+        }
         ui.label(egui::RichText::new("Payload").strong());
     });
     ui.separator();
@@ -179,17 +965,26 @@ fn render_log_table_with_navigation(
                 let is_selected = selected_visible_row == Some(visible_position);
 
                 let response = ui.horizontal(|ui| {
+                    let mut index_text = egui::RichText::new(row.index.to_string())
+                        .strong()
+                        .background_color(if is_selected {
+                            egui::Color32::from_rgb(34, 74, 125)
+                        } else {
+                            egui::Color32::TRANSPARENT
+                        });
+                    if row.dropped_before.is_some() {
+                        index_text = index_text.color(DROPPED_COLOR).underline();
+                    }
+                    let index_label =
+                        ui.add_sized([50.0, TABLE_ROW_HEIGHT], egui::Label::new(index_text));
+                    if let Some(dropped) = row.dropped_before {
+                        index_label.on_hover_text(format!(
+                            "{dropped} message(s) lost before this row (message counter gap)"
+                        ));
+                    }
                     ui.add_sized(
-                        [50.0, TABLE_ROW_HEIGHT],
-                        egui::Label::new(
-                            egui::RichText::new(row.index.to_string())
-                                .strong()
-                                .background_color(if is_selected {
-                                    egui::Color32::from_rgb(34, 74, 125)
-                                } else {
-                                    egui::Color32::TRANSPARENT
-                                }),
-                        ),
+                        [TABLE_COL_VERSION, TABLE_ROW_HEIGHT],
+                        egui::Label::new(format!("v{}", row.version)),
                     );
                     ui.add_sized(
                         [TABLE_COL_TIMESTAMP, TABLE_ROW_HEIGHT],
@@ -211,6 +1006,30 @@ fn render_log_table_with_navigation(
                         [TABLE_COL_TYPE, TABLE_ROW_HEIGHT],
                         egui::Label::new(row.kind),
                     );
+                    if columns.source {
+                        ui.add_sized(
+                            [TABLE_COL_SOURCE, TABLE_ROW_HEIGHT],
+                            egui::Label::new(row.source),
+                        );
+                    }
+                    if columns.tags {
+                        ui.add_sized(
+                            [TABLE_COL_TAGS, TABLE_ROW_HEIGHT],
+                            egui::Label::new(row.tags),
+                        );
+                    }
+                    if columns.privacy {
+                        ui.add_sized(
+                            [TABLE_COL_PRIVACY, TABLE_ROW_HEIGHT],
+                            egui::Label::new(row.privacy),
+                        );
+                    }
+                    if columns.message_id {
+                        ui.add_sized(
+                            [TABLE_COL_MESSAGE_ID, TABLE_ROW_HEIGHT],
+                            egui::Label::new(row.message_id),
+                        );
+                    }
                     ui.label(row.payload);
                 });
 
@@ -232,15 +1051,117 @@ fn render_log_table_with_navigation(
 
 struct DesktopShell {
     model: DesktopModel,
+    ecu_offsets_text: String,
+    live_kind: LiveKind,
+    live_address_text: String,
     load_event_tx: Sender<LoadWorkerEvent>,
     load_event_rx: Receiver<LoadWorkerEvent>,
+    live_event_tx: Sender<LiveWorkerEvent>,
+    live_event_rx: Receiver<LiveWorkerEvent>,
+    /// Set to make the live worker stop reading.
+    live_stop: Option<Arc<AtomicBool>>,
+    /// Set to make a following load stop waiting for a first message.
+    load_stop: Option<Arc<AtomicBool>>,
+    /// File the running load waits on for a first message.
+    load_waiting: Option<PathBuf>,
+    last_follow_refresh: Instant,
+    /// Writes control requests to the connected live source.
+    control_sender: Option<ControlSender>,
+    control_form: ControlForm,
+    rule_form: RuleForm,
+    go_to_time: GoToTimeForm,
+    preset_name_text: String,
+    optional_columns: OptionalColumns,
+}
+
+/// Live input kinds selectable in the toolbar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LiveKind {
+    Tcp,
+    Udp,
+    Serial,
+}
+
+impl LiveKind {
+    const ALL: [LiveKind; 3] = [LiveKind::Tcp, LiveKind::Udp, LiveKind::Serial];
+
+    fn label(self) -> &'static str {
+        match self {
+            LiveKind::Tcp => "TCP",
+            LiveKind::Udp => "UDP",
+            LiveKind::Serial => "Serial",
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            LiveKind::Tcp => "dlt-daemon host[:port]",
+            LiveKind::Udp => "[addr:]port",
+            LiveKind::Serial => "device[@baud]",
+        }
+    }
+
+    fn source(self, input: &str) -> Result<LiveSource, String> {
+        let input = input.trim().to_string();
+        match self {
+            LiveKind::Tcp => Ok(LiveSource::Tcp(input)),
+            LiveKind::Udp => Ok(LiveSource::Udp(input)),
+            LiveKind::Serial => {
+                let (path, baud_rate) = match input.rsplit_once('@') {
+                    Some((path, baud)) => (
+                        path.to_string(),
+                        baud.parse()
+                            .map_err(|_| format!("invalid baud rate {baud:?}"))?,
+                    ),
+                    None => (input, DEFAULT_BAUD_RATE),
+                };
+                Ok(LiveSource::Serial {
+                    path: PathBuf::from(path),
+                    baud_rate,
+                })
+            }
+        }
+    }
+
+    fn of(source: &LiveSource) -> (Self, String) {
+        match source {
+            LiveSource::Tcp(address) => (LiveKind::Tcp, address.clone()),
+            LiveSource::Udp(address) => (LiveKind::Udp, address.clone()),
+            LiveSource::Serial { path, baud_rate } => {
+                (LiveKind::Serial, format!("{}@{baud_rate}", path.display()))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum LiveWorkerEvent {
+    Frames {
+        generation: LoadGeneration,
+        version: u8,
+        frames: Vec<u8>,
+    },
+    /// The source is open; `sender` writes control requests to it, if the
+    /// source kind supports that.
+    Connected {
+        generation: LoadGeneration,
+        sender: Option<ControlSender>,
+    },
+    Closed {
+        generation: LoadGeneration,
+        error: Option<String>,
+    },
 }
 
 #[derive(Debug)]
 enum LoadWorkerEvent {
+    Waiting {
+        generation: LoadGeneration,
+        path: PathBuf,
+    },
     Succeeded {
         generation: LoadGeneration,
-        data: RetainedDataSet,
+        data: Box<RetainedDataSet>,
     },
     Failed {
         generation: LoadGeneration,
@@ -248,42 +1169,216 @@ enum LoadWorkerEvent {
     },
 }
 
-impl LoadWorkerEvent {
-    fn into_intent(self) -> DesktopIntent {
-        match self {
-            Self::Succeeded { generation, data } => {
-                DesktopIntent::LoadSucceeded { generation, data }
-            }
-            Self::Failed {
-                generation,
-                message,
-            } => DesktopIntent::LoadFailed {
-                generation,
-                message,
-            },
-        }
-    }
-}
-
 impl Default for DesktopShell {
     fn default() -> Self {
         let (load_event_tx, load_event_rx) = mpsc::channel();
+        let (live_event_tx, live_event_rx) = mpsc::channel();
         Self {
             model: DesktopModel::default(),
+            ecu_offsets_text: String::new(),
+            live_kind: LiveKind::Tcp,
+            live_address_text: String::new(),
             load_event_tx,
             load_event_rx,
+            live_event_tx,
+            live_event_rx,
+            live_stop: None,
+            load_stop: None,
+            load_waiting: None,
+            last_follow_refresh: Instant::now(),
+            control_sender: None,
+            control_form: ControlForm::default(),
+            rule_form: RuleForm::default(),
+            go_to_time: GoToTimeForm::default(),
+            preset_name_text: String::new(),
+            optional_columns: OptionalColumns::default(),
         }
     }
 }
 
-impl eframe::App for DesktopShell {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+impl DesktopShell {
+    fn start_load(&mut self, paths: Vec<PathBuf>) {
+        self.model.apply_intent(DesktopIntent::OpenFilesRequested);
+        let Some(generation) = self.model.active_load_generation() else {
+            return;
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.load_stop.replace(Arc::clone(&stop)) {
+            previous.store(true, Ordering::Relaxed);
+        }
+        self.load_waiting = None;
+        let options = self.model.load_options();
+        let load_event_tx = self.load_event_tx.clone();
+        thread::spawn(move || {
+            let waiting = |path: &Path| {
+                let path = path.to_path_buf();
+                let _ = load_event_tx.send(LoadWorkerEvent::Waiting { generation, path });
+            };
+            let event = match load_retained_dataset_until(paths, &options, &stop, waiting) {
+                Ok(data) => LoadWorkerEvent::Succeeded {
+                    generation,
+                    data: Box::new(data),
+                },
+                Err(err) => LoadWorkerEvent::Failed {
+                    generation,
+                    message: err.to_string(),
+                },
+            };
+
+            let _ = load_event_tx.send(event);
+        });
+    }
+
+    fn start_live(&mut self, source: LiveSource) {
+        self.model
+            .apply_intent(DesktopIntent::LiveConnectRequested(source.clone()));
+        let Some(generation) = self.model.live_generation() else {
+            return;
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        self.live_stop = Some(Arc::clone(&stop));
+        let live_event_tx = self.live_event_tx.clone();
+        thread::spawn(move || {
+            let result = source.open().and_then(|reader| {
+                let sender = reader.control_sender().ok();
+                let _ = live_event_tx.send(LiveWorkerEvent::Connected { generation, sender });
+                reader.read_frames(&stop, |version, frames| {
+                    live_event_tx
+                        .send(LiveWorkerEvent::Frames {
+                            generation,
+                            version,
+                            frames,
+                        })
+                        .is_ok()
+                })
+            });
+            let error = result.err().map(|err| format!("{err:#}"));
+
+            let _ = live_event_tx.send(LiveWorkerEvent::Closed { generation, error });
+        });
+    }
+
+    fn drain_load_events(&mut self) {
         while let Ok(event) = self.load_event_rx.try_recv() {
-            self.model.apply_intent(event.into_intent());
+            let intent = match event {
+                LoadWorkerEvent::Waiting { generation, path } => {
+                    if self.model.active_load_generation() == Some(generation) {
+                        self.load_waiting = Some(path);
+                    }
+                    continue;
+                }
+                LoadWorkerEvent::Succeeded { generation, data } => {
+                    DesktopIntent::LoadSucceeded { generation, data }
+                }
+                LoadWorkerEvent::Failed {
+                    generation,
+                    message,
+                } => DesktopIntent::LoadFailed {
+                    generation,
+                    message,
+                },
+            };
+            self.model.apply_intent(intent);
         }
 
+        // A load that was replaced or finished must not keep waiting.
+        if self.model.active_load_generation().is_none() {
+            self.load_waiting = None;
+            if let Some(stop) = self.load_stop.take() {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn drain_live_events(&mut self) {
+        while let Ok(event) = self.live_event_rx.try_recv() {
+            let intent = match event {
+                LiveWorkerEvent::Connected { generation, sender } => {
+                    if self.model.live_generation() == Some(generation) {
+                        self.control_sender = sender;
+                    }
+                    continue;
+                }
+                LiveWorkerEvent::Frames {
+                    generation,
+                    version,
+                    frames,
+                } => DesktopIntent::LiveFramesReceived {
+                    generation,
+                    version,
+                    frames,
+                },
+                LiveWorkerEvent::Closed { generation, error } => {
+                    DesktopIntent::LiveClosed { generation, error }
+                }
+            };
+            self.model.apply_intent(intent);
+        }
+
+        // The model drops the stream on disconnect, reset or a new load.
+        if self.model.live_generation().is_none() {
+            self.control_sender = None;
+            if let Some(stop) = self.live_stop.take() {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Send the request described by the control panel form.
+    fn send_control_request(&mut self) {
+        let Some(sender) = self.control_sender.as_mut() else {
+            return;
+        };
+        let data = self.model.loaded_data();
+        let version = data
+            .and_then(|data| data.versions.first().copied())
+            .unwrap_or(1);
+        let ecu = match self.control_form.ecu.trim() {
+            "" => default_control_ecu(data),
+            ecu => ecu.to_string(),
+        };
+        let request = self.control_form.service.request(&self.control_form);
+        let intent = match sender.send(&request, version, &ecu) {
+            Ok(()) => DesktopIntent::ControlRequestSent(request),
+            Err(err) => DesktopIntent::ControlSendFailed(format!("Send failed: {err:#}")),
+        };
+        self.model.apply_intent(intent);
+    }
+
+    /// Paths to reload after a load option changed; live streams are not reloaded.
+    fn reload_paths(&self) -> Option<Vec<PathBuf>> {
+        self.model
+            .loaded_data()
+            .filter(|data| data.live_source.is_none())
+            .map(|data| data.paths.clone())
+    }
+}
+
+/// ECU addressed when the form leaves it empty: the first ECU seen, or
+/// dlt-daemon's default ID.
+fn default_control_ecu(data: Option<&RetainedDataSet>) -> String {
+    data.and_then(|data| data.unique_ecus().first().map(|ecu| ecu.to_string()))
+        .unwrap_or_else(|| "ECU1".to_string())
+}
+
+impl eframe::App for DesktopShell {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.drain_load_events();
+        self.drain_live_events();
+
         if self.model.active_load_generation().is_some() {
             ctx.request_repaint();
+        } else if self.model.live_generation().is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        } else if self.model.follow() && self.model.loaded_data().is_some() {
+            if self.last_follow_refresh.elapsed() >= FOLLOW_INTERVAL {
+                self.last_follow_refresh = Instant::now();
+                self.model
+                    .apply_intent(DesktopIntent::FollowRefreshRequested);
+            }
+            ctx.request_repaint_after(FOLLOW_INTERVAL);
         }
 
         egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
@@ -297,31 +1392,174 @@ impl eframe::App for DesktopShell {
                         return;
                     };
 
-                    self.model.apply_intent(DesktopIntent::OpenFilesRequested);
-                    let Some(generation) = self.model.active_load_generation() else {
-                        return;
+                    self.start_load(paths);
+                }
+
+                if ui.button("Load filter").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("DLT filter files", &["dlf"])
+                        .pick_file()
+                {
+                    let intent = match FilterSet::from_dlf_file(&path) {
+                        Ok(filter_set) => DesktopIntent::FilterFileLoaded { path, filter_set },
+                        Err(err) => DesktopIntent::FilterFileFailed(format!("{err:#}")),
                     };
+                    self.model.apply_intent(intent);
+                }
 
-                    let load_event_tx = self.load_event_tx.clone();
-                    thread::spawn(move || {
-                        let event = match load_retained_dataset(paths) {
-                            Ok(data) => LoadWorkerEvent::Succeeded { generation, data },
-                            Err(err) => LoadWorkerEvent::Failed {
-                                generation,
-                                message: err.to_string(),
-                            },
-                        };
+                if self.model.filter_set().is_some() && ui.button("Clear filter").clicked() {
+                    self.model.apply_intent(DesktopIntent::FilterFileCleared);
+                }
 
-                        let _ = load_event_tx.send(event);
-                    });
+                if self.model.loaded_data().is_some()
+                    && ui.button("Export visible").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("DLT files", &["dlt"])
+                        .set_file_name("export.dlt")
+                        .save_file()
+                {
+                    self.model
+                        .apply_intent(DesktopIntent::ExportVisibleRequested(path));
+                }
+
+                let mut fibex_changed = false;
+                if ui.button("Load FIBEX").clicked()
+                    && let Some(paths) = rfd::FileDialog::new()
+                        .add_filter("FIBEX files", &["xml"])
+                        .pick_files()
+                {
+                    let intent = match FibexDatabase::from_files(&paths) {
+                        Ok(database) => {
+                            fibex_changed = true;
+                            DesktopIntent::FibexLoaded {
+                                paths,
+                                database: Arc::new(database),
+                            }
+                        }
+                        Err(err) => DesktopIntent::FibexFailed(format!("{err:#}")),
+                    };
+                    self.model.apply_intent(intent);
+                }
+
+                if !self.model.fibex_paths().is_empty() && ui.button("Clear FIBEX").clicked() {
+                    self.model.apply_intent(DesktopIntent::FibexCleared);
+                    fibex_changed = true;
+                }
+
+                if fibex_changed && let Some(paths) = self.reload_paths() {
+                    self.start_load(paths);
                 }
 
                 if ui.button("Reset").clicked() {
                     self.model.apply_intent(DesktopIntent::ResetRequested);
                 }
+
+                let mut follow = self.model.follow();
+                if ui
+                    .checkbox(&mut follow, "Follow")
+                    .on_hover_text("Keep reading messages appended to the loaded files")
+                    .changed()
+                {
+                    self.model
+                        .apply_intent(DesktopIntent::FollowToggled(follow));
+                }
+                if let Some(message) = self.model.follow_error() {
+                    ui.colored_label(egui::Color32::RED, message);
+                }
+
+                ui.separator();
+                egui::ComboBox::from_id_salt("live_kind")
+                    .selected_text(self.live_kind.label())
+                    .show_ui(ui, |ui| {
+                        for kind in LiveKind::ALL {
+                            ui.selectable_value(&mut self.live_kind, kind, kind.label());
+                        }
+                    });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.live_address_text)
+                        .hint_text(self.live_kind.hint())
+                        .desired_width(160.0),
+                );
+                if self.model.live_generation().is_some() {
+                    if ui.button("Disconnect").clicked() {
+                        self.model
+                            .apply_intent(DesktopIntent::LiveDisconnectRequested);
+                    }
+                } else if ui
+                    .add_enabled(
+                        !self.live_address_text.trim().is_empty(),
+                        egui::Button::new("Connect"),
+                    )
+                    .clicked()
+                {
+                    match self.live_kind.source(&self.live_address_text) {
+                        Ok(source) => self.start_live(source),
+                        Err(message) => self
+                            .model
+                            .apply_intent(DesktopIntent::LiveSourceInvalid(message)),
+                    }
+                }
+                if let Some(status) = self.model.live_status() {
+                    ui.label(status);
+                }
+
+                ui.separator();
+                if let Some(intent) =
+                    render_merge_controls(ui, self.model.merge_mode(), &mut self.ecu_offsets_text)
+                {
+                    self.model.apply_intent(intent);
+                    if let Some(paths) = self.reload_paths() {
+                        self.start_load(paths);
+                    }
+                }
+
+                if let Some(path) = self.model.filter_path() {
+                    ui.label(format!("Filter: {}", path.display()));
+                }
+                if let Some(message) = self.model.filter_error() {
+                    ui.colored_label(egui::Color32::RED, message);
+                }
+                if !self.model.fibex_paths().is_empty() {
+                    ui.label(format!("FIBEX: {} file(s)", self.model.fibex_paths().len()));
+                }
+                if let Some(message) = self.model.fibex_error() {
+                    ui.colored_label(egui::Color32::RED, message);
+                }
+                if let Some(status) = self.model.export_status() {
+                    ui.label(status);
+                }
+            });
+            ui.horizontal(|ui| {
+                for intent in render_preset_controls(ui, &self.model, &mut self.preset_name_text) {
+                    self.model.apply_intent(intent);
+                }
             });
         });
 
+        if self.model.live_generation().is_some()
+            || !self.model.control_requests().sent().is_empty()
+        {
+            let default_ecu = default_control_ecu(self.model.loaded_data());
+            let (send_clicked, intents) = egui::SidePanel::right("control_panel")
+                .show(ctx, |ui| {
+                    render_control_panel(
+                        ui,
+                        &mut self.control_form,
+                        &default_ecu,
+                        self.model.control_requests(),
+                        self.model.control_status(),
+                        self.control_sender.is_some(),
+                    )
+                })
+                .inner;
+            if send_clicked {
+                self.send_control_request();
+            }
+            for intent in intents {
+                self.model.apply_intent(intent);
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("DLT Explorer");
             ui.separator();
@@ -334,7 +1572,22 @@ impl eframe::App for DesktopShell {
                 DesktopAppState::Loading => {
                     ui.label("State: loading");
                     ui.spinner();
-                    ui.label("Loading DLT data...");
+                    match &self.load_waiting {
+                        Some(path) => {
+                            ui.label(format!(
+                                "Waiting for a first message in {}...",
+                                path.display()
+                            ));
+                            if ui.button("Cancel").clicked()
+                                && let Some(stop) = &self.load_stop
+                            {
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                        None => {
+                            ui.label("Loading DLT data...");
+                        }
+                    }
                 }
                 DesktopAppState::Loaded => {
                     ui.label("State: loaded");
@@ -343,18 +1596,33 @@ impl eframe::App for DesktopShell {
                     let mut pending_intents = Vec::new();
 
                     if let Some(data) = self.model.loaded_data() {
-                        ui.label(format!(
-                            "Loaded {} message(s) from {} file(s) (DLT v{}).",
-                            data.message_count(),
-                            data.file_count(),
-                            data.version
-                        ));
+                        match &data.live_source {
+                            Some(address) => ui.label(format!(
+                                "Received {} message(s) from {} (DLT {}).",
+                                data.message_count(),
+                                address,
+                                data.version_label()
+                            )),
+                            None => ui.label(format!(
+                                "Loaded {} message(s) from {} file(s) (DLT {}).",
+                                data.message_count(),
+                                data.file_count(),
+                                data.version_label()
+                            )),
+                        };
                         ui.label(format!(
                             "Metadata: {} ECU(s), {} APID(s), {} CTID(s).",
                             data.unique_ecu_count(),
                             data.unique_apid_count(),
                             data.unique_ctid_count()
                         ));
+                        let (dropped, gaps) = data.dropped_message_count();
+                        if gaps > 0 {
+                            ui.colored_label(
+                                DROPPED_COLOR,
+                                format!("Dropped messages: {dropped} in {gaps} counter gap(s)"),
+                            );
+                        }
 
                         let parse_error_count = data.parse_error_count();
                         if parse_error_count > 0 {
@@ -376,11 +1644,17 @@ impl eframe::App for DesktopShell {
                             }
                         }
 
-                        pending_intents.extend(render_structured_filter_controls(ui, data));
+                        pending_intents.extend(render_structured_filter_controls(
+                            ui,
+                            data,
+                            &mut self.rule_form,
+                        ));
                         pending_intents.extend(render_rendered_search_controls(ui, data));
+                        pending_intents.extend(render_go_to_time(ui, &mut self.go_to_time));
                         pending_intents.extend(render_log_table_with_navigation(
                             ui,
                             data,
+                            &mut self.optional_columns,
                             should_scroll_to_selection,
                         ));
                     }
@@ -398,13 +1672,78 @@ impl eframe::App for DesktopShell {
     }
 }
 
-pub(crate) fn run_desktop_shell() -> Result<()> {
+pub(crate) fn run_desktop_shell(
+    filter_path: Option<PathBuf>,
+    merge: Option<MergeMode>,
+    fibex_paths: Vec<PathBuf>,
+    live_source: Option<LiveSource>,
+    follow: bool,
+    presets: PresetOptions,
+) -> Result<()> {
     let options = eframe::NativeOptions::default();
-    eframe::run_native(
-        "Dlt Explorer",
-        options,
-        Box::new(|_cc| Ok(Box::new(DesktopShell::default()))),
-    )
-    .map_err(|e| anyhow!(e.to_string()))?;
+    let mut shell = DesktopShell::default();
+    shell
+        .model
+        .apply_intent(DesktopIntent::FollowToggled(follow));
+    let path = presets.library_path();
+    match path
+        .as_deref()
+        .map(PresetLibrary::load_or_empty)
+        .transpose()
+    {
+        Ok(library) => {
+            shell
+                .model
+                .apply_intent(DesktopIntent::PresetLibraryLoaded {
+                    path,
+                    library: library.unwrap_or_default(),
+                });
+        }
+        // Keep the broken file rather than overwrite it with new presets.
+        Err(err) if presets.name.is_none() => {
+            shell
+                .model
+                .apply_intent(DesktopIntent::PresetFailed(format!("{err:#}")));
+        }
+        Err(err) => return Err(err),
+    }
+    if let Some(name) = presets.name {
+        shell
+            .model
+            .apply_intent(DesktopIntent::PresetSelected(name));
+        if let Some(message) = shell.model.preset_status() {
+            return Err(anyhow!("{message}"));
+        }
+    }
+    if let Some(MergeMode::MessageTimestamp { ecu_offsets_ns }) = &merge {
+        shell.ecu_offsets_text = ecu_offsets_ns
+            .iter()
+            .map(|(ecu, ns)| format!("{ecu}={}", *ns as f64 / 1e9))
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    shell
+        .model
+        .apply_intent(DesktopIntent::MergeModeChanged(merge));
+    if !fibex_paths.is_empty() {
+        let database = FibexDatabase::from_files(&fibex_paths)?;
+        shell.model.apply_intent(DesktopIntent::FibexLoaded {
+            paths: fibex_paths,
+            database: Arc::new(database),
+        });
+    }
+    if let Some(path) = filter_path {
+        let filter_set = FilterSet::from_dlf_file(&path)?;
+        shell
+            .model
+            .apply_intent(DesktopIntent::FilterFileLoaded { path, filter_set });
+    }
+    if let Some(source) = live_source {
+        (shell.live_kind, shell.live_address_text) = LiveKind::of(&source);
+        shell.start_live(source);
+    }
+
+    eframe::run_native("Dlt Explorer", options, Box::new(|_cc| Ok(Box::new(shell))))
+        .map_err(|e| anyhow!(e.to_string()))?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,1163 @@
+use anyhow::{Result, anyhow, bail};
+use std::fmt;
+
+use crate::dlt::payload::{LOG_INFO, RETURN_TYPE, SERVICE_ID_NAME};
+use crate::dlt::verbose::Reader;
+use crate::dlt::writer::MessageBuilder;
+
+/// MTIN of a control request.
+pub const CONTROL_REQUEST: u8 = 0x01;
+/// MTIN of a control response.
+pub const CONTROL_RESPONSE: u8 = 0x02;
+
+pub const SERVICE_SET_LOG_LEVEL: u32 = 0x01;
+pub const SERVICE_SET_TRACE_STATUS: u32 = 0x02;
+pub const SERVICE_GET_LOG_INFO: u32 = 0x03;
+pub const SERVICE_GET_DEFAULT_LOG_LEVEL: u32 = 0x04;
+pub const SERVICE_STORE_CONFIG: u32 = 0x05;
+pub const SERVICE_RESET_TO_FACTORY_DEFAULT: u32 = 0x06;
+pub const SERVICE_SET_COM_INTERFACE_STATUS: u32 = 0x07;
+pub const SERVICE_SET_COM_INTERFACE_MAX_BANDWIDTH: u32 = 0x08;
+pub const SERVICE_SET_VERBOSE_MODE: u32 = 0x09;
+pub const SERVICE_SET_MESSAGE_FILTERING: u32 = 0x0A;
+pub const SERVICE_SET_TIMING_PACKETS: u32 = 0x0B;
+pub const SERVICE_GET_LOCAL_TIME: u32 = 0x0C;
+pub const SERVICE_USE_ECU_ID: u32 = 0x0D;
+pub const SERVICE_USE_SESSION_ID: u32 = 0x0E;
+pub const SERVICE_USE_TIMESTAMP: u32 = 0x0F;
+pub const SERVICE_USE_EXTENDED_HEADER: u32 = 0x10;
+pub const SERVICE_SET_DEFAULT_LOG_LEVEL: u32 = 0x11;
+pub const SERVICE_SET_DEFAULT_TRACE_STATUS: u32 = 0x12;
+pub const SERVICE_GET_SOFTWARE_VERSION: u32 = 0x13;
+pub const SERVICE_MESSAGE_BUFFER_OVERFLOW: u32 = 0x14;
+pub const SERVICE_UNREGISTER_CONTEXT: u32 = 0xF01;
+pub const SERVICE_CONNECTION_INFO: u32 = 0xF02;
+pub const SERVICE_TIMEZONE: u32 = 0xF03;
+pub const SERVICE_MARKER: u32 = 0xF04;
+pub const SERVICE_OFFLINE_LOGSTORAGE: u32 = 0xF05;
+
+/// dlt-daemon specific services, starting at 0xF01.
+const DAEMON_SERVICE_NAME: [&str; 9] = [
+    "unregister_context",
+    "connection_info",
+    "timezone",
+    "marker",
+    "offline_logstorage",
+    "passive_node_connect",
+    "passive_node_connection_status",
+    "set_all_log_level",
+    "set_all_trace_status",
+];
+
+/// `get_log_info` status values 3-7 echo the request options on success.
+pub const LOG_INFO_WITH_LOG_LEVEL: u8 = 4;
+pub const LOG_INFO_WITH_TRACE_STATUS: u8 = 5;
+pub const LOG_INFO_WITH_BOTH: u8 = 6;
+pub const LOG_INFO_WITH_DESCRIPTIONS: u8 = 7;
+
+/// Communication interface named in requests; dlt-daemon's client connection.
+pub const DEFAULT_COM_INTERFACE: &str = "remo";
+/// APID and CTID of the requests this tool sends.
+pub const REQUEST_APID: &str = "DLTX";
+pub const REQUEST_CTID: &str = "CTRL";
+
+/// Name of a control service, e.g. `get_log_info`; `None` for unknown IDs.
+pub fn service_name(service_id: u32) -> Option<&'static str> {
+    let name = match service_id {
+        0xF01..=0xF09 => DAEMON_SERVICE_NAME[(service_id - 0xF01) as usize],
+        id => SERVICE_ID_NAME.get(id as usize).copied().unwrap_or(""),
+    };
+    (!name.is_empty()).then_some(name)
+}
+
+/// Name of a log level as used in control messages (-1 = default, 0 = off).
+pub fn log_level_name(level: i8) -> String {
+    match level {
+        -1 => "default".to_string(),
+        0 => "off".to_string(),
+        1..=6 => LOG_INFO[level as usize].to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Name of a trace status as used in control messages (-1 = default).
+pub fn trace_status_name(status: i8) -> String {
+    match status {
+        -1 => "default".to_string(),
+        0 => "off".to_string(),
+        1 => "on".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A decoded control message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlMessage {
+    pub service_id: u32,
+    /// Response status (see `RETURN_TYPE`); `None` for requests.
+    pub status: Option<u8>,
+    pub body: ControlBody,
+}
+
+impl ControlMessage {
+    fn request(service_id: u32, body: ControlBody) -> Self {
+        Self {
+            service_id,
+            status: None,
+            body,
+        }
+    }
+
+    /// Request to set the log level of one context; an empty `ctid` (or
+    /// `apid`) addresses all contexts (or applications).
+    pub fn set_log_level(apid: &str, ctid: &str, level: i8) -> Self {
+        Self::request(
+            SERVICE_SET_LOG_LEVEL,
+            ControlBody::SetLogLevel {
+                apid: apid.to_string(),
+                ctid: ctid.to_string(),
+                level,
+                com: DEFAULT_COM_INTERFACE.to_string(),
+            },
+        )
+    }
+
+    /// Request to set the log level of contexts without an explicit one.
+    pub fn set_default_log_level(level: i8) -> Self {
+        Self::request(
+            SERVICE_SET_DEFAULT_LOG_LEVEL,
+            ControlBody::DefaultLogLevel {
+                level,
+                com: DEFAULT_COM_INTERFACE.to_string(),
+            },
+        )
+    }
+
+    /// Request to switch tracing of one context on or off.
+    pub fn set_trace_status(apid: &str, ctid: &str, status: i8) -> Self {
+        Self::request(
+            SERVICE_SET_TRACE_STATUS,
+            ControlBody::SetTraceStatus {
+                apid: apid.to_string(),
+                ctid: ctid.to_string(),
+                status,
+                com: DEFAULT_COM_INTERFACE.to_string(),
+            },
+        )
+    }
+
+    /// Request for the registered applications and contexts; `options` is
+    /// one of the `LOG_INFO_WITH_*` values.
+    pub fn get_log_info(options: u8, apid: &str, ctid: &str) -> Self {
+        Self::request(
+            SERVICE_GET_LOG_INFO,
+            ControlBody::GetLogInfoRequest {
+                options,
+                apid: apid.to_string(),
+                ctid: ctid.to_string(),
+                com: DEFAULT_COM_INTERFACE.to_string(),
+            },
+        )
+    }
+
+    /// Request to persist the current log levels and trace states.
+    pub fn store_config() -> Self {
+        Self::request(SERVICE_STORE_CONFIG, ControlBody::Empty)
+    }
+
+    pub fn get_software_version() -> Self {
+        Self::request(SERVICE_GET_SOFTWARE_VERSION, ControlBody::Empty)
+    }
+
+    /// Encode the payload, starting at the service ID; the inverse of
+    /// `decode`. Responses carry their status after the service ID.
+    pub fn encode(&self, big_endian: bool) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        put_u32(&mut data, self.service_id, big_endian);
+        if let Some(status) = self.status {
+            data.push(status);
+        }
+        let response = self.status.is_some();
+        match &self.body {
+            ControlBody::Empty => {}
+            ControlBody::SetLogLevel {
+                apid,
+                ctid,
+                level,
+                com,
+            } => {
+                put_id(&mut data, apid)?;
+                put_id(&mut data, ctid)?;
+                data.push(*level as u8);
+                put_id(&mut data, com)?;
+            }
+            ControlBody::SetTraceStatus {
+                apid,
+                ctid,
+                status,
+                com,
+            } => {
+                put_id(&mut data, apid)?;
+                put_id(&mut data, ctid)?;
+                data.push(*status as u8);
+                put_id(&mut data, com)?;
+            }
+            ControlBody::GetLogInfoRequest {
+                options,
+                apid,
+                ctid,
+                com,
+            } => {
+                data.push(*options);
+                put_id(&mut data, apid)?;
+                put_id(&mut data, ctid)?;
+                put_id(&mut data, com)?;
+            }
+            ControlBody::LogInfo { applications, com } => {
+                encode_log_info(
+                    &mut data,
+                    applications,
+                    self.status.unwrap_or_default(),
+                    big_endian,
+                )?;
+                if !com.is_empty() {
+                    put_id(&mut data, com)?;
+                }
+            }
+            // A `get_default_log_level` response carries no interface.
+            ControlBody::DefaultLogLevel { level, com } => {
+                data.push(*level as u8);
+                if !response {
+                    put_id(&mut data, com)?;
+                }
+            }
+            ControlBody::DefaultTraceStatus { status, com } => {
+                data.push(*status as u8);
+                put_id(&mut data, com)?;
+            }
+            ControlBody::ComInterfaceStatus { com, status } => {
+                put_id(&mut data, com)?;
+                data.push(*status);
+            }
+            ControlBody::ComInterfaceMaxBandwidth { com, max_bandwidth } => {
+                put_id(&mut data, com)?;
+                put_u32(&mut data, *max_bandwidth, big_endian);
+            }
+            ControlBody::Switch(value) => data.push(*value),
+            ControlBody::SoftwareVersion(version) => {
+                put_u32(&mut data, version.len() as u32, big_endian);
+                data.extend_from_slice(version.as_bytes());
+            }
+            ControlBody::BufferOverflow { overflow, counter } => {
+                data.push(*overflow);
+                put_u32(&mut data, *counter, big_endian);
+            }
+            ControlBody::UnregisterContext { apid, ctid, com } => {
+                put_id(&mut data, apid)?;
+                put_id(&mut data, ctid)?;
+                put_id(&mut data, com)?;
+            }
+            ControlBody::ConnectionInfo { state, com } => {
+                data.push(*state);
+                put_id(&mut data, com)?;
+            }
+            ControlBody::Timezone { offset_s, is_dst } => {
+                put_u32(&mut data, *offset_s as u32, big_endian);
+                data.push(u8::from(*is_dst));
+            }
+            ControlBody::OfflineLogstorage {
+                mount_point,
+                connection_type,
+                com,
+            } => {
+                data.extend_from_slice(mount_point.as_bytes());
+                data.push(0);
+                data.push(*connection_type);
+                put_id(&mut data, com)?;
+            }
+            ControlBody::Raw(bytes) => data.extend_from_slice(bytes),
+        }
+        Ok(data)
+    }
+
+    /// A complete request message in the given protocol version, without a
+    /// storage header, as sent to dlt-daemon. `counter` is the MCNT.
+    pub fn request_message(&self, version: u8, ecu: &str, counter: u8) -> Result<Vec<u8>> {
+        if self.status.is_some() {
+            bail!("control responses cannot be sent as requests");
+        }
+        id_bytes(ecu)?;
+        MessageBuilder::new()
+            .with_ecu(ecu)
+            .with_apid(REQUEST_APID)
+            .with_ctid(REQUEST_CTID)
+            .with_counter(counter)
+            .with_control(self.clone())
+            .build_message(version)
+    }
+}
+
+/// The application/context table of a `get_log_info` response; `options`
+/// (the response status) selects the per-context fields, as in decoding.
+fn encode_log_info(
+    data: &mut Vec<u8>,
+    applications: &[ApplicationLogInfo],
+    options: u8,
+    big_endian: bool,
+) -> Result<()> {
+    let with_level = matches!(
+        options,
+        LOG_INFO_WITH_LOG_LEVEL | LOG_INFO_WITH_BOTH | LOG_INFO_WITH_DESCRIPTIONS
+    );
+    let with_trace = matches!(
+        options,
+        LOG_INFO_WITH_TRACE_STATUS | LOG_INFO_WITH_BOTH | LOG_INFO_WITH_DESCRIPTIONS
+    );
+    let with_descriptions = options == LOG_INFO_WITH_DESCRIPTIONS;
+    put_u16(data, count_u16(applications.len())?, big_endian);
+    for application in applications {
+        put_id(data, &application.apid)?;
+        put_u16(data, count_u16(application.contexts.len())?, big_endian);
+        for context in &application.contexts {
+            put_id(data, &context.ctid)?;
+            if with_level {
+                data.push(context.log_level.unwrap_or(-1) as u8);
+            }
+            if with_trace {
+                data.push(context.trace_status.unwrap_or(-1) as u8);
+            }
+            if with_descriptions {
+                put_description(data, context.description.as_deref(), big_endian)?;
+            }
+        }
+        if with_descriptions {
+            put_description(data, application.description.as_deref(), big_endian)?;
+        }
+    }
+    Ok(())
+}
+
+fn put_description(data: &mut Vec<u8>, text: Option<&str>, big_endian: bool) -> Result<()> {
+    let text = text.unwrap_or_default();
+    put_u16(data, count_u16(text.len())?, big_endian);
+    data.extend_from_slice(text.as_bytes());
+    Ok(())
+}
+
+fn count_u16(count: usize) -> Result<u16> {
+    u16::try_from(count).map_err(|_| anyhow!("{count} entries do not fit a 16-bit count"))
+}
+
+fn put_u16(data: &mut Vec<u8>, value: u16, big_endian: bool) {
+    if big_endian {
+        data.extend_from_slice(&value.to_be_bytes());
+    } else {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn put_u32(data: &mut Vec<u8>, value: u32, big_endian: bool) {
+    if big_endian {
+        data.extend_from_slice(&value.to_be_bytes());
+    } else {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn put_id(data: &mut Vec<u8>, id: &str) -> Result<()> {
+    data.extend_from_slice(&id_bytes(id)?);
+    Ok(())
+}
+
+/// A NUL-padded 4-byte identifier.
+fn id_bytes(id: &str) -> Result<[u8; 4]> {
+    if id.len() > 4 || !id.is_ascii() {
+        bail!("{id:?} is not an ID of up to 4 ASCII characters");
+    }
+    let mut bytes = [0u8; 4];
+    bytes[..id.len()].copy_from_slice(id.as_bytes());
+    Ok(bytes)
+}
+
+/// A control request sent on a connection, with its response once matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentRequest {
+    pub request: ControlMessage,
+    /// Row of the response in the live store, and the decoded response.
+    pub response: Option<(usize, ControlMessage)>,
+}
+
+/// Control requests sent on a connection, matched to their responses.
+///
+/// dlt-daemon answers requests in order, so a response belongs to the
+/// oldest unanswered request for the same service.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestLog {
+    sent: Vec<SentRequest>,
+}
+
+impl RequestLog {
+    pub fn record(&mut self, request: ControlMessage) {
+        self.sent.push(SentRequest {
+            request,
+            response: None,
+        });
+    }
+
+    /// Attach the response in `row` to its request; returns the request's
+    /// position, or `None` when no request of that service is waiting.
+    pub fn match_response(&mut self, row: usize, response: ControlMessage) -> Option<usize> {
+        let position = self.sent.iter().position(|sent| {
+            sent.response.is_none() && sent.request.service_id == response.service_id
+        })?;
+        self.sent[position].response = Some((row, response));
+        Some(position)
+    }
+
+    pub fn sent(&self) -> &[SentRequest] {
+        &self.sent
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.sent
+            .iter()
+            .filter(|sent| sent.response.is_none())
+            .count()
+    }
+}
+
+/// Service-specific fields of a control message.
+///
+/// Field layouts follow dlt-daemon's `DltService*` request and response
+/// structs. `com` is the communication interface name, e.g. `remo`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlBody {
+    /// The service carries nothing beyond its ID (and status).
+    Empty,
+    SetLogLevel {
+        apid: String,
+        ctid: String,
+        level: i8,
+        com: String,
+    },
+    SetTraceStatus {
+        apid: String,
+        ctid: String,
+        status: i8,
+        com: String,
+    },
+    GetLogInfoRequest {
+        options: u8,
+        apid: String,
+        ctid: String,
+        com: String,
+    },
+    LogInfo {
+        applications: Vec<ApplicationLogInfo>,
+        com: String,
+    },
+    /// Log level in a `set_default_log_level` request or a
+    /// `get_default_log_level` response.
+    DefaultLogLevel {
+        level: i8,
+        com: String,
+    },
+    DefaultTraceStatus {
+        status: i8,
+        com: String,
+    },
+    ComInterfaceStatus {
+        com: String,
+        status: u8,
+    },
+    ComInterfaceMaxBandwidth {
+        com: String,
+        max_bandwidth: u32,
+    },
+    /// On/off switch of the `set_verbose_mode`, `set_timing_packets` and
+    /// `use_*` services.
+    Switch(u8),
+    SoftwareVersion(String),
+    BufferOverflow {
+        overflow: u8,
+        counter: u32,
+    },
+    UnregisterContext {
+        apid: String,
+        ctid: String,
+        com: String,
+    },
+    ConnectionInfo {
+        state: u8,
+        com: String,
+    },
+    Timezone {
+        offset_s: i32,
+        is_dst: bool,
+    },
+    OfflineLogstorage {
+        mount_point: String,
+        connection_type: u8,
+        com: String,
+    },
+    /// Bytes of a service or layout this decoder does not know.
+    Raw(Vec<u8>),
+}
+
+/// One application in a `get_log_info` response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplicationLogInfo {
+    pub apid: String,
+    pub contexts: Vec<ContextLogInfo>,
+    pub description: Option<String>,
+}
+
+/// One context in a `get_log_info` response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextLogInfo {
+    pub ctid: String,
+    pub log_level: Option<i8>,
+    pub trace_status: Option<i8>,
+    pub description: Option<String>,
+}
+
+/// Decode a control payload, starting at the service ID.
+///
+/// `mtin` tells requests (`CONTROL_REQUEST`) from responses; any other value
+/// is decoded as a response. Returns `None` when the payload is too short to
+/// hold a service ID. A body that does not match its service's layout is
+/// kept as `ControlBody::Raw`.
+pub fn decode(data: &[u8], big_endian: bool, mtin: u8) -> Option<ControlMessage> {
+    let mut reader = Reader::new(data, big_endian);
+    let service_id = reader.u32()?;
+    let response = mtin != CONTROL_REQUEST;
+    let status = if response {
+        reader.take(1).map(|b| b[0])
+    } else {
+        None
+    };
+    let rest = reader.clone();
+    let body = if response {
+        decode_response(&mut reader, service_id, status)
+    } else {
+        decode_request(&mut reader, service_id)
+    };
+    let body = match body {
+        Some(body) => body,
+        None => {
+            let mut rest = rest;
+            let remaining = rest.take(rest.remaining()).unwrap_or_default();
+            ControlBody::Raw(remaining.to_vec())
+        }
+    };
+    Some(ControlMessage {
+        service_id,
+        status,
+        body,
+    })
+}
+
+fn decode_request(reader: &mut Reader, service_id: u32) -> Option<ControlBody> {
+    let body = match service_id {
+        SERVICE_SET_LOG_LEVEL => ControlBody::SetLogLevel {
+            apid: id(reader)?,
+            ctid: id(reader)?,
+            level: i8_value(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_SET_TRACE_STATUS => ControlBody::SetTraceStatus {
+            apid: id(reader)?,
+            ctid: id(reader)?,
+            status: i8_value(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_GET_LOG_INFO => ControlBody::GetLogInfoRequest {
+            options: reader.take(1)?[0],
+            apid: id(reader)?,
+            ctid: id(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_SET_COM_INTERFACE_STATUS => ControlBody::ComInterfaceStatus {
+            com: id(reader)?,
+            status: reader.take(1)?[0],
+        },
+        SERVICE_SET_COM_INTERFACE_MAX_BANDWIDTH => ControlBody::ComInterfaceMaxBandwidth {
+            com: id(reader)?,
+            max_bandwidth: reader.u32()?,
+        },
+        SERVICE_SET_VERBOSE_MODE
+        | SERVICE_SET_MESSAGE_FILTERING
+        | SERVICE_SET_TIMING_PACKETS
+        | SERVICE_USE_ECU_ID
+        | SERVICE_USE_SESSION_ID
+        | SERVICE_USE_TIMESTAMP
+        | SERVICE_USE_EXTENDED_HEADER => ControlBody::Switch(reader.take(1)?[0]),
+        SERVICE_SET_DEFAULT_LOG_LEVEL => ControlBody::DefaultLogLevel {
+            level: i8_value(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_SET_DEFAULT_TRACE_STATUS => ControlBody::DefaultTraceStatus {
+            status: i8_value(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_OFFLINE_LOGSTORAGE => {
+            // Fixed-size, NUL-padded mount point, then type and interface.
+            let len = reader.remaining().checked_sub(5)?;
+            ControlBody::OfflineLogstorage {
+                mount_point: reader.text(len)?.trim_end_matches('\0').to_string(),
+                connection_type: reader.take(1)?[0],
+                com: id(reader)?,
+            }
+        }
+        _ if reader.remaining() == 0 => ControlBody::Empty,
+        _ => return None,
+    };
+    Some(body)
+}
+
+fn decode_response(
+    reader: &mut Reader,
+    service_id: u32,
+    status: Option<u8>,
+) -> Option<ControlBody> {
+    let body = match service_id {
+        SERVICE_GET_LOG_INFO if matches!(status, Some(3..=7)) => {
+            decode_log_info(reader, status.unwrap_or_default())?
+        }
+        SERVICE_GET_DEFAULT_LOG_LEVEL if reader.remaining() > 0 => ControlBody::DefaultLogLevel {
+            level: i8_value(reader)?,
+            com: String::new(),
+        },
+        SERVICE_GET_SOFTWARE_VERSION if reader.remaining() > 0 => {
+            let len = reader.u32()? as usize;
+            ControlBody::SoftwareVersion(reader.text(len)?)
+        }
+        SERVICE_MESSAGE_BUFFER_OVERFLOW if reader.remaining() > 0 => ControlBody::BufferOverflow {
+            overflow: reader.take(1)?[0],
+            counter: reader.u32()?,
+        },
+        SERVICE_UNREGISTER_CONTEXT => ControlBody::UnregisterContext {
+            apid: id(reader)?,
+            ctid: id(reader)?,
+            com: id(reader)?,
+        },
+        SERVICE_CONNECTION_INFO => ControlBody::ConnectionInfo {
+            state: reader.take(1)?[0],
+            com: id(reader)?,
+        },
+        SERVICE_TIMEZONE => ControlBody::Timezone {
+            offset_s: reader.u32()? as i32,
+            is_dst: reader.take(1)?[0] != 0,
+        },
+        _ if reader.remaining() == 0 => ControlBody::Empty,
+        _ => return None,
+    };
+    Some(body)
+}
+
+/// The application/context table of a successful `get_log_info` response.
+fn decode_log_info(reader: &mut Reader, options: u8) -> Option<ControlBody> {
+    let with_level = matches!(
+        options,
+        LOG_INFO_WITH_LOG_LEVEL | LOG_INFO_WITH_BOTH | LOG_INFO_WITH_DESCRIPTIONS
+    );
+    let with_trace = matches!(
+        options,
+        LOG_INFO_WITH_TRACE_STATUS | LOG_INFO_WITH_BOTH | LOG_INFO_WITH_DESCRIPTIONS
+    );
+    let with_descriptions = options == LOG_INFO_WITH_DESCRIPTIONS;
+
+    let app_count = reader.u16()?;
+    let mut applications = Vec::with_capacity(app_count.into());
+    for _ in 0..app_count {
+        let apid = id(reader)?;
+        let context_count = reader.u16()?;
+        let mut contexts = Vec::with_capacity(context_count.into());
+        for _ in 0..context_count {
+            contexts.push(ContextLogInfo {
+                ctid: id(reader)?,
+                log_level: if with_level {
+                    Some(i8_value(reader)?)
+                } else {
+                    None
+                },
+                trace_status: if with_trace {
+                    Some(i8_value(reader)?)
+                } else {
+                    None
+                },
+                description: if with_descriptions {
+                    Some(description(reader)?)
+                } else {
+                    None
+                },
+            });
+        }
+        let description = if with_descriptions {
+            Some(description(reader)?)
+        } else {
+            None
+        };
+        applications.push(ApplicationLogInfo {
+            apid,
+            contexts,
+            description,
+        });
+    }
+    let com = if reader.remaining() >= 4 {
+        id(reader)?
+    } else {
+        String::new()
+    };
+    Some(ControlBody::LogInfo { applications, com })
+}
+
+/// A 4-byte, NUL-padded identifier such as an APID or interface name.
+fn id(reader: &mut Reader) -> Option<String> {
+    let bytes = reader.take(4)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+fn i8_value(reader: &mut Reader) -> Option<i8> {
+    reader.take(1).map(|b| b[0] as i8)
+}
+
+/// Description text preceded by its 16-bit length.
+fn description(reader: &mut Reader) -> Option<String> {
+    let len = reader.u16()? as usize;
+    reader.text(len)
+}
+
+fn status_name(service_id: u32, status: u8) -> String {
+    if service_id == SERVICE_GET_LOG_INFO {
+        match status {
+            3..=7 => return "ok".to_string(),
+            9 => return "response_data_overflow".to_string(),
+            _ => {}
+        }
+    }
+    match RETURN_TYPE.get(status as usize) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("status({status})"),
+    }
+}
+
+impl fmt::Display for ControlMessage {
+    /// `[control <service>] <status> <body>`, e.g.
+    /// `[control set_log_level] apid=APP ctid=CTX log_level=debug com=remo`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match service_name(self.service_id) {
+            Some(name) => write!(f, "[control {name}]")?,
+            None => write!(f, "[control 0x{:04x}]", self.service_id)?,
+        }
+        if let Some(status) = self.status {
+            write!(f, " {}", status_name(self.service_id, status))?;
+        }
+        match &self.body {
+            ControlBody::Empty => Ok(()),
+            body => write!(f, " {body}"),
+        }
+    }
+}
+
+impl fmt::Display for ControlBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => Ok(()),
+            Self::SetLogLevel {
+                apid,
+                ctid,
+                level,
+                com,
+            } => write!(
+                f,
+                "apid={apid} ctid={ctid} log_level={} com={com}",
+                log_level_name(*level)
+            ),
+            Self::SetTraceStatus {
+                apid,
+                ctid,
+                status,
+                com,
+            } => write!(
+                f,
+                "apid={apid} ctid={ctid} trace_status={} com={com}",
+                trace_status_name(*status)
+            ),
+            Self::GetLogInfoRequest {
+                options,
+                apid,
+                ctid,
+                com,
+            } => write!(f, "options={options} apid={apid} ctid={ctid} com={com}"),
+            Self::LogInfo { applications, com } => {
+                for (i, app) in applications.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{app}")?;
+                }
+                if !com.is_empty() {
+                    write!(f, " com={com}")?;
+                }
+                Ok(())
+            }
+            Self::DefaultLogLevel { level, com } => {
+                write!(f, "log_level={}", log_level_name(*level))?;
+                if !com.is_empty() {
+                    write!(f, " com={com}")?;
+                }
+                Ok(())
+            }
+            Self::DefaultTraceStatus { status, com } => {
+                write!(f, "trace_status={} com={com}", trace_status_name(*status))
+            }
+            Self::ComInterfaceStatus { com, status } => write!(f, "com={com} status={status}"),
+            Self::ComInterfaceMaxBandwidth { com, max_bandwidth } => {
+                write!(f, "com={com} max_bandwidth={max_bandwidth}")
+            }
+            Self::Switch(value) => write!(f, "{}", if *value == 0 { "off" } else { "on" }),
+            Self::SoftwareVersion(version) => f.write_str(version),
+            Self::BufferOverflow { overflow, counter } => {
+                write!(f, "overflow={overflow} counter={counter}")
+            }
+            Self::UnregisterContext { apid, ctid, com } => {
+                write!(f, "apid={apid} ctid={ctid} com={com}")
+            }
+            Self::ConnectionInfo { state, com } => {
+                let state = match state {
+                    1 => "disconnected".to_string(),
+                    2 => "connected".to_string(),
+                    other => other.to_string(),
+                };
+                write!(f, "state={state} com={com}")
+            }
+            Self::Timezone { offset_s, is_dst } => {
+                let sign = if *offset_s < 0 { '-' } else { '+' };
+                let abs = offset_s.unsigned_abs();
+                write!(f, "utc{sign}{:02}:{:02}", abs / 3600, (abs % 3600) / 60)?;
+                if *is_dst {
+                    f.write_str(" dst")?;
+                }
+                Ok(())
+            }
+            Self::OfflineLogstorage {
+                mount_point,
+                connection_type,
+                com,
+            } => write!(
+                f,
+                "mount_point={mount_point} connection_type={connection_type} com={com}"
+            ),
+            Self::Raw(bytes) => f.write_str(&crate::dlt::payload::hex_dump(bytes)),
+        }
+    }
+}
+
+impl fmt::Display for ApplicationLogInfo {
+    /// `APID[CTID level trace "description", ...] "description"`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[", self.apid)?;
+        for (i, context) in self.contexts.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(&context.ctid)?;
+            if let Some(level) = context.log_level {
+                write!(f, " {}", log_level_name(level))?;
+            }
+            if let Some(status) = context.trace_status {
+                write!(f, " {}", trace_status_name(status))?;
+            }
+            if let Some(description) = &context.description {
+                write!(f, " {description:?}")?;
+            }
+        }
+        f.write_str("]")?;
+        if let Some(description) = &self.description {
+            write!(f, " {description:?}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(service_id: u32, status: u8, body: &[u8]) -> Vec<u8> {
+        let mut data = service_id.to_le_bytes().to_vec();
+        data.push(status);
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn request(service_id: u32, body: &[u8]) -> Vec<u8> {
+        let mut data = service_id.to_le_bytes().to_vec();
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn text(data: &[u8], mtin: u8) -> String {
+        decode(data, false, mtin).unwrap().to_string()
+    }
+
+    #[test]
+    fn names_standard_and_daemon_services() {
+        assert_eq!(service_name(0x03), Some("get_log_info"));
+        assert_eq!(service_name(0x14), Some("message_buffer_overflow"));
+        assert_eq!(service_name(0xF02), Some("connection_info"));
+        assert_eq!(service_name(0xF04), Some("marker"));
+        assert_eq!(service_name(0x00), None);
+        assert_eq!(service_name(0x99), None);
+    }
+
+    #[test]
+    fn decodes_set_log_level_request() {
+        let data = request(SERVICE_SET_LOG_LEVEL, b"APP1CTX1\x05remo");
+
+        assert_eq!(
+            decode(&data, false, CONTROL_REQUEST).unwrap().body,
+            ControlBody::SetLogLevel {
+                apid: "APP1".into(),
+                ctid: "CTX1".into(),
+                level: 5,
+                com: "remo".into()
+            }
+        );
+        assert_eq!(
+            text(&data, CONTROL_REQUEST),
+            "[control set_log_level] apid=APP1 ctid=CTX1 log_level=debug com=remo"
+        );
+    }
+
+    #[test]
+    fn decodes_get_log_info_table_with_descriptions() {
+        let mut body = 2u16.to_le_bytes().to_vec();
+        body.extend_from_slice(b"APP1");
+        body.extend_from_slice(&2u16.to_le_bytes());
+        body.extend_from_slice(b"CTX1\x04\x01");
+        body.extend_from_slice(&3u16.to_le_bytes());
+        body.extend_from_slice(b"one");
+        body.extend_from_slice(b"CTX2\xff\x00");
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&4u16.to_le_bytes());
+        body.extend_from_slice(b"app1");
+        body.extend_from_slice(b"AP2\0");
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(b"remo");
+        let data = response(SERVICE_GET_LOG_INFO, LOG_INFO_WITH_DESCRIPTIONS, &body);
+
+        let message = decode(&data, false, CONTROL_RESPONSE).unwrap();
+
+        let ControlBody::LogInfo { applications, com } = &message.body else {
+            panic!("expected log info, got {:?}", message.body);
+        };
+        assert_eq!(com, "remo");
+        assert_eq!(applications.len(), 2);
+        assert_eq!(applications[0].contexts[1].log_level, Some(-1));
+        assert_eq!(applications[1].apid, "AP2");
+        assert_eq!(
+            message.to_string(),
+            "[control get_log_info] ok APP1[CTX1 info on \"one\", CTX2 default off \"\"] \"app1\" \
+             AP2[] \"\" com=remo"
+        );
+    }
+
+    #[test]
+    fn decodes_get_log_info_levels_only() {
+        let mut body = 1u16.to_le_bytes().to_vec();
+        body.extend_from_slice(b"APP1");
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(b"CTX1\x03");
+        let data = response(SERVICE_GET_LOG_INFO, LOG_INFO_WITH_LOG_LEVEL, &body);
+
+        assert_eq!(
+            text(&data, CONTROL_RESPONSE),
+            "[control get_log_info] ok APP1[CTX1 warn]"
+        );
+        assert_eq!(
+            text(&response(SERVICE_GET_LOG_INFO, 8, &[]), CONTROL_RESPONSE),
+            "[control get_log_info] no_matching_context_id"
+        );
+    }
+
+    #[test]
+    fn decodes_responses_with_bodies() {
+        let mut version = 5u32.to_le_bytes().to_vec();
+        version.extend_from_slice(b"2.18\0");
+        assert_eq!(
+            text(
+                &response(SERVICE_GET_SOFTWARE_VERSION, 0, &version),
+                CONTROL_RESPONSE
+            ),
+            "[control get_software_version] ok 2.18"
+        );
+        assert_eq!(
+            text(
+                &response(SERVICE_GET_DEFAULT_LOG_LEVEL, 0, &[4]),
+                CONTROL_RESPONSE
+            ),
+            "[control get_default_log_level] ok log_level=info"
+        );
+        assert_eq!(
+            text(&response(SERVICE_GET_LOCAL_TIME, 0, &[]), CONTROL_RESPONSE),
+            "[control get_local_time] ok"
+        );
+        let mut overflow = vec![1];
+        overflow.extend_from_slice(&42u32.to_le_bytes());
+        assert_eq!(
+            text(
+                &response(SERVICE_MESSAGE_BUFFER_OVERFLOW, 0, &overflow),
+                CONTROL_RESPONSE
+            ),
+            "[control message_buffer_overflow] ok overflow=1 counter=42"
+        );
+    }
+
+    #[test]
+    fn decodes_daemon_services() {
+        assert_eq!(
+            text(
+                &response(SERVICE_UNREGISTER_CONTEXT, 0, b"APP1CTX1remo"),
+                CONTROL_RESPONSE
+            ),
+            "[control unregister_context] ok apid=APP1 ctid=CTX1 com=remo"
+        );
+        assert_eq!(
+            text(
+                &response(SERVICE_CONNECTION_INFO, 0, b"\x02remo"),
+                CONTROL_RESPONSE
+            ),
+            "[control connection_info] ok state=connected com=remo"
+        );
+        let mut timezone = (-5400i32).to_le_bytes().to_vec();
+        timezone.push(1);
+        assert_eq!(
+            text(&response(SERVICE_TIMEZONE, 0, &timezone), CONTROL_RESPONSE),
+            "[control timezone] ok utc-01:30 dst"
+        );
+        assert_eq!(
+            text(&response(SERVICE_MARKER, 0, &[]), CONTROL_RESPONSE),
+            "[control marker] ok"
+        );
+        let mut storage = b"/mnt/usb".to_vec();
+        storage.resize(32, 0);
+        storage.push(1);
+        storage.extend_from_slice(b"remo");
+        assert_eq!(
+            text(
+                &request(SERVICE_OFFLINE_LOGSTORAGE, &storage),
+                CONTROL_REQUEST
+            ),
+            "[control offline_logstorage] mount_point=/mnt/usb connection_type=1 com=remo"
+        );
+    }
+
+    #[test]
+    fn big_endian_bodies_and_unknown_layouts() {
+        let mut data = SERVICE_SET_COM_INTERFACE_MAX_BANDWIDTH
+            .to_be_bytes()
+            .to_vec();
+        data.extend_from_slice(b"remo");
+        data.extend_from_slice(&1000u32.to_be_bytes());
+        assert_eq!(
+            decode(&data, true, CONTROL_REQUEST).unwrap().to_string(),
+            "[control set_com_interface_max_bandwidth] com=remo max_bandwidth=1000"
+        );
+
+        // A truncated body is kept as raw bytes.
+        assert_eq!(
+            text(&request(SERVICE_SET_LOG_LEVEL, b"APP"), CONTROL_REQUEST),
+            "[control set_log_level] 41 50 50"
+        );
+        assert_eq!(
+            text(&response(0x1234, 0, &[0xab]), CONTROL_RESPONSE),
+            "[control 0x1234] ok ab"
+        );
+        assert!(decode(&[1, 0], false, CONTROL_REQUEST).is_none());
+    }
+
+    fn requests() -> Vec<ControlMessage> {
+        vec![
+            ControlMessage::set_log_level("APP", "CTX", 5),
+            ControlMessage::set_default_log_level(-1),
+            ControlMessage::set_trace_status("APP", "", 1),
+            ControlMessage::get_log_info(LOG_INFO_WITH_DESCRIPTIONS, "", ""),
+            ControlMessage::store_config(),
+            ControlMessage::get_software_version(),
+        ]
+    }
+
+    #[test]
+    fn encoded_requests_decode_to_themselves() {
+        for request in requests() {
+            for big_endian in [false, true] {
+                let data = request.encode(big_endian).unwrap();
+                assert_eq!(
+                    decode(&data, big_endian, CONTROL_REQUEST).as_ref(),
+                    Some(&request)
+                );
+            }
+        }
+        assert_eq!(
+            ControlMessage::set_log_level("APP", "CTX", 5).to_string(),
+            "[control set_log_level] apid=APP ctid=CTX log_level=debug com=remo"
+        );
+        assert!(
+            ControlMessage::set_log_level("TOOLONG", "", 1)
+                .encode(false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn request_messages_parse_in_both_versions() {
+        for version in [1, 2] {
+            let mut frames = Vec::new();
+            for request in requests() {
+                frames.extend_from_slice(b"DLT\x01");
+                frames.extend_from_slice(&[0; 8]);
+                frames.extend_from_slice(b"ECU1");
+                frames.extend_from_slice(&request.request_message(version, "ECU1", 7).unwrap());
+            }
+            let mut store = crate::dlt::open_stream(version).unwrap();
+            assert!(store.append(&frames).unwrap().is_empty());
+
+            assert_eq!(store.len(), requests().len());
+            for (row, request) in requests().iter().enumerate() {
+                assert_eq!(store.control_message(row).as_ref(), Some(request));
+                assert_eq!(store.message_type_info(row), CONTROL_REQUEST);
+                assert_eq!(store.ecu(row), "ECU1");
+                assert_eq!(store.apid(row), REQUEST_APID);
+                assert_eq!(store.ctid(row), REQUEST_CTID);
+                assert_eq!(store.message_counter(row), 7);
+            }
+        }
+    }
+
+    #[test]
+    fn responses_match_oldest_pending_request_of_their_service() {
+        let mut log = RequestLog::default();
+        log.record(ControlMessage::get_software_version());
+        log.record(ControlMessage::set_log_level("APP", "CTX", 4));
+        log.record(ControlMessage::set_log_level("APP", "CTX", 5));
+        let ok = |service_id| ControlMessage {
+            service_id,
+            status: Some(0),
+            body: ControlBody::Empty,
+        };
+
+        assert_eq!(log.match_response(10, ok(SERVICE_SET_LOG_LEVEL)), Some(1));
+        assert_eq!(log.match_response(11, ok(SERVICE_SET_LOG_LEVEL)), Some(2));
+        assert_eq!(log.match_response(12, ok(SERVICE_SET_LOG_LEVEL)), None);
+        assert_eq!(log.pending_count(), 1);
+        assert_eq!(
+            log.sent()[1].response.as_ref().map(|(row, _)| *row),
+            Some(10)
+        );
+        assert!(log.sent()[0].response.is_none());
+    }
+}
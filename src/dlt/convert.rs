@@ -0,0 +1,553 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::dlt::control;
+use crate::dlt::storage::STORAGE_HEADER_SIZE;
+use crate::dlt::store::DltStore;
+use crate::dlt::v1::header::parse_v1_header;
+use crate::dlt::v1::protocol::{
+    MESSAGE_TYPE_CONTROL, htyp_has_msbf, htyp_has_ueh, htyp_has_wtms, msin_is_verb, msin_mstp,
+    msin_mtin,
+};
+use crate::dlt::v2::header::parse_v2_header;
+use crate::dlt::v2::protocol::{
+    CNTI_CONTROL, CNTI_NON_VERBOSE, CNTI_VERBOSE, htyp2_cnti, htyp2_has_wpvl, htyp2_has_wsfln,
+    htyp2_has_wsgm, htyp2_has_wtgs,
+};
+use crate::dlt::verbose::decode_arguments_prefix;
+use crate::dlt::writer::MessageBuilder;
+
+/// Largest message timestamp v1 can hold: `u32::MAX` ticks of 0.1 ms.
+const V1_MAX_TIMESTAMP_NS: u64 = u32::MAX as u64 * 100_000;
+
+/// Information a converted message could not carry over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Loss {
+    /// v2 source file name and line number (WSFLN).
+    SourceLocation,
+    /// v2 tags (WTGS).
+    Tags,
+    /// v2 privacy level (WPVL).
+    PrivacyLevel,
+    /// A v2 segment whose message is not complete, such as a frame
+    /// converted on its own; it is not written.
+    Segmented,
+    /// A v2 timestamp finer than v1's 0.1 ms ticks.
+    TimestampPrecision,
+    /// A v2 timestamp past v1's range, clamped to its maximum.
+    TimestampRange,
+    /// Message type and info of a v1 non-verbose message; v2 non-verbose
+    /// messages have no MSIN.
+    NonVerboseMessageType,
+    /// Non-verbose argument bytes copied in their little-endian order,
+    /// which v2 does not allow; only a FIBEX description could swap them.
+    NonVerboseByteOrder,
+    /// A control body this tool cannot decode, copied in its original byte
+    /// order.
+    ControlByteOrder,
+    /// Verbose payload bytes after the last argument that could be decoded.
+    UndecodedArguments,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Loss::SourceLocation => "source file name and line number dropped",
+            Loss::Tags => "tags dropped",
+            Loss::PrivacyLevel => "privacy level dropped",
+            Loss::Segmented => "incomplete segmented message skipped",
+            Loss::TimestampPrecision => "timestamp truncated to 0.1 ms",
+            Loss::TimestampRange => "timestamp clamped to the v1 range",
+            Loss::NonVerboseMessageType => "non-verbose message type and level dropped",
+            Loss::NonVerboseByteOrder => "non-verbose arguments left little-endian",
+            Loss::ControlByteOrder => "undecoded control body left in its byte order",
+            Loss::UndecodedArguments => "undecodable verbose arguments dropped",
+        })
+    }
+}
+
+/// Counts from converting a set of rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConvertSummary {
+    /// Rows written, converted or copied.
+    pub rows: usize,
+    pub bytes: u64,
+    /// Rows already in the target version, copied byte-exact.
+    pub copied: usize,
+    /// Rows that could not be written at all.
+    pub skipped: usize,
+    /// Number of rows affected by each kind of loss.
+    pub losses: BTreeMap<Loss, usize>,
+}
+
+/// One frame in the target version, with what was lost on the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedFrame {
+    /// Storage header and message; `None` when the message was skipped.
+    pub frame: Option<Vec<u8>>,
+    pub losses: Vec<Loss>,
+}
+
+/// Convert a stored frame (storage header and message) of protocol version
+/// `from` into version `to`.
+///
+/// Frames already in the target version are returned unchanged. Otherwise
+/// the headers are read with the source version's parser and the message is
+/// rebuilt with `MessageBuilder`; verbose arguments and control bodies are
+/// decoded and re-encoded in the target byte order.
+///
+/// A v2 segment (WSGM) is not a complete message on its own; it is skipped
+/// as `Loss::Segmented`. `convert_rows` converts reassembled rows instead.
+pub fn convert_frame(frame: &[u8], from: u8, to: u8) -> Result<ConvertedFrame> {
+    convert_message(frame, None, from, to)
+}
+
+/// `convert_frame`, where `joined_payload`, if given, is the payload of all
+/// segments of a reassembled v2 message that starts with `frame`.
+fn convert_message(
+    frame: &[u8],
+    joined_payload: Option<&[u8]>,
+    from: u8,
+    to: u8,
+) -> Result<ConvertedFrame> {
+    if from == to {
+        return Ok(ConvertedFrame {
+            frame: Some(frame.to_vec()),
+            losses: Vec::new(),
+        });
+    }
+    if frame.len() < STORAGE_HEADER_SIZE {
+        bail!("frame of {} bytes has no storage header", frame.len());
+    }
+    let (builder, losses) = match (from, to) {
+        (1, 2) => from_v1(frame)?,
+        (2, 1) => from_v2(frame, joined_payload)?,
+        _ => bail!("cannot convert DLT version {from} to {to}"),
+    };
+    let frame = match builder {
+        Some(builder) => Some(builder.build(to)?),
+        None => None,
+    };
+    Ok(ConvertedFrame { frame, losses })
+}
+
+/// Convert `rows` to version `to` and write them as a `.dlt` stream.
+pub fn convert_rows<W: Write>(
+    dlt: &dyn DltStore,
+    rows: impl IntoIterator<Item = usize>,
+    to: u8,
+    writer: &mut W,
+) -> Result<ConvertSummary> {
+    let mut summary = ConvertSummary::default();
+    for row in rows {
+        let from = dlt.protocol_version(row);
+        if from == to {
+            // Copied byte-exact, with every segment of a reassembled row.
+            for frame in dlt.frames_raw(row) {
+                writer.write_all(frame)?;
+                summary.bytes += frame.len() as u64;
+            }
+            summary.rows += 1;
+            summary.copied += 1;
+            continue;
+        }
+        let converted = convert_message(dlt.frame_raw(row), Some(dlt.payload_raw(row)), from, to)
+            .with_context(|| format!("failed to convert row {row}"))?;
+        for loss in converted.losses {
+            *summary.losses.entry(loss).or_default() += 1;
+        }
+        let Some(frame) = converted.frame else {
+            summary.skipped += 1;
+            continue;
+        };
+        writer.write_all(&frame)?;
+        summary.rows += 1;
+        summary.bytes += frame.len() as u64;
+    }
+    Ok(summary)
+}
+
+/// Convert `rows` to version `to` into a new `.dlt` file at `path`,
+/// replacing any existing file.
+pub fn convert_to_file(
+    dlt: &dyn DltStore,
+    rows: impl IntoIterator<Item = usize>,
+    to: u8,
+    path: &Path,
+) -> Result<ConvertSummary> {
+    let file = File::create(path)
+        .with_context(|| format!("failed to create output file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let summary = convert_rows(dlt, rows, to, &mut writer)?;
+    writer
+        .flush()
+        .with_context(|| format!("failed to write output file {}", path.display()))?;
+    Ok(summary)
+}
+
+/// A builder carrying the storage header of `frame`.
+fn with_storage_header(frame: &[u8]) -> MessageBuilder {
+    let le_u32 = |at: usize| u32::from_le_bytes(frame[at..at + 4].try_into().unwrap());
+    MessageBuilder::new()
+        .with_storage_timestamp(le_u32(4), le_u32(8))
+        .with_storage_ecu(&frame[12..16].try_into().unwrap())
+}
+
+fn id(bytes: [u8; 4]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn from_v1(frame: &[u8]) -> Result<(Option<MessageBuilder>, Vec<Loss>)> {
+    let msg = &frame[STORAGE_HEADER_SIZE..];
+    let header = parse_v1_header(msg).map_err(|kind| anyhow!("invalid v1 message: {kind:?}"))?;
+    let payload = &msg[header.payload_offset..header.payload_offset + header.payload_len];
+    let big_endian = htyp_has_msbf(header.htyp);
+    let ueh = htyp_has_ueh(header.htyp);
+    let mut losses = Vec::new();
+
+    let mut builder = with_storage_header(frame)
+        .with_counter(msg[1])
+        .with_message_type(msin_mstp(header.msin))
+        .with_message_type_info(msin_mtin(header.msin));
+    if let Some(ecu) = header.ecu {
+        builder = builder.with_ecu(&id(ecu));
+    }
+    if let (Some(apid), Some(ctid)) = (header.apid, header.ctid) {
+        builder = builder.with_apid(&id(apid)).with_ctid(&id(ctid));
+    }
+    if let Some(session_id) = header.session_id {
+        builder = builder.with_session_id(session_id);
+    }
+    if htyp_has_wtms(header.htyp) {
+        builder = builder.with_timestamp_ns(header.message_timestamp_ns);
+    }
+
+    if ueh && msin_is_verb(header.msin) {
+        let (arguments, consumed) = decode_arguments_prefix(payload, big_endian);
+        if consumed < payload.len() {
+            losses.push(Loss::UndecodedArguments);
+        }
+        for argument in arguments {
+            builder = builder.with_argument(argument);
+        }
+    } else if ueh && msin_mstp(header.msin) == MESSAGE_TYPE_CONTROL {
+        let Some(message) = control::decode(payload, big_endian, msin_mtin(header.msin)) else {
+            bail!("control message without a service ID");
+        };
+        if matches!(message.body, control::ControlBody::Raw(_)) && !big_endian {
+            losses.push(Loss::ControlByteOrder);
+        }
+        builder = builder.with_control(message);
+    } else {
+        // Non-verbose: the payload starts with the message ID.
+        let Some((id_bytes, arguments)) = payload.split_first_chunk::<4>() else {
+            bail!("non-verbose message without a message ID");
+        };
+        let message_id = if big_endian {
+            u32::from_be_bytes(*id_bytes)
+        } else {
+            u32::from_le_bytes(*id_bytes)
+        };
+        if ueh {
+            losses.push(Loss::NonVerboseMessageType);
+        }
+        if !big_endian && !arguments.is_empty() {
+            losses.push(Loss::NonVerboseByteOrder);
+        }
+        builder = builder.with_non_verbose(message_id, arguments);
+    }
+    Ok((Some(builder), losses))
+}
+
+fn from_v2(
+    frame: &[u8],
+    joined_payload: Option<&[u8]>,
+) -> Result<(Option<MessageBuilder>, Vec<Loss>)> {
+    let msg = &frame[STORAGE_HEADER_SIZE..];
+    let header = parse_v2_header(msg).map_err(|kind| anyhow!("invalid v2 message: {kind:?}"))?;
+    let mut payload = &msg[header.payload_offset..header.payload_offset + header.payload_len];
+    let cnti = htyp2_cnti(header.htyp2);
+    let mut losses = Vec::new();
+
+    if htyp2_has_wsgm(header.htyp2) {
+        // The first segment's header with the payload of all segments.
+        let Some(joined_payload) = joined_payload else {
+            return Ok((None, vec![Loss::Segmented]));
+        };
+        payload = joined_payload;
+    }
+    for (present, loss) in [
+        (htyp2_has_wsfln(header.htyp2), Loss::SourceLocation),
+        (htyp2_has_wtgs(header.htyp2), Loss::Tags),
+        (htyp2_has_wpvl(header.htyp2), Loss::PrivacyLevel),
+    ] {
+        if present {
+            losses.push(loss);
+        }
+    }
+
+    // v1 can keep v2's big-endian payloads as they are by setting MSBF.
+    let mut builder = with_storage_header(frame)
+        .with_counter(msg[4])
+        .with_big_endian(true)
+        .with_message_type(header.message_type)
+        .with_message_type_info(header.message_type_info);
+    if let Some(ecu) = header.ecu {
+        builder = builder.with_ecu(&id(ecu));
+    }
+    if header.apid.is_some() || header.ctid.is_some() {
+        builder = builder
+            .with_apid(&id(header.apid.unwrap_or_default()))
+            .with_ctid(&id(header.ctid.unwrap_or_default()));
+    }
+    if let Some(session_id) = header.session_id {
+        builder = builder.with_session_id(session_id);
+    }
+    if cnti != CNTI_CONTROL {
+        let mut ns = header.message_timestamp_ns;
+        if ns % 100_000 != 0 {
+            losses.push(Loss::TimestampPrecision);
+        }
+        if ns > V1_MAX_TIMESTAMP_NS {
+            losses.push(Loss::TimestampRange);
+            ns = V1_MAX_TIMESTAMP_NS;
+        }
+        builder = builder.with_timestamp_ns(ns);
+    }
+
+    match cnti {
+        CNTI_VERBOSE => {
+            let (arguments, consumed) = decode_arguments_prefix(payload, true);
+            if consumed < payload.len() {
+                losses.push(Loss::UndecodedArguments);
+            }
+            for argument in arguments {
+                builder = builder.with_argument(argument);
+            }
+        }
+        CNTI_NON_VERBOSE => {
+            let message_id = header.message_id.unwrap_or_default();
+            builder = builder.with_non_verbose(message_id, payload);
+        }
+        CNTI_CONTROL => {
+            let Some(message) = control::decode(payload, true, header.message_type_info) else {
+                bail!("control message without a service ID");
+            };
+            builder = builder.with_control(message);
+        }
+        other => bail!("unknown v2 content type {other}"),
+    }
+    Ok((Some(builder), losses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::control::{ControlMessage, SERVICE_GET_SOFTWARE_VERSION};
+    use crate::dlt::verbose::{Argument, Value};
+
+    fn open(frames: &[Vec<u8>], version: u8) -> Box<dyn DltStore> {
+        let mut store = crate::dlt::open_stream(version).unwrap();
+        assert!(store.append(&frames.concat()).unwrap().is_empty());
+        store
+    }
+
+    fn converted(frame: &[u8], from: u8, to: u8) -> (Box<dyn DltStore>, Vec<Loss>) {
+        let converted = convert_frame(frame, from, to).unwrap();
+        let frame = converted.frame.expect("frame should be written");
+        (open(&[frame], to), converted.losses)
+    }
+
+    fn log_message() -> MessageBuilder {
+        MessageBuilder::new()
+            .with_storage_timestamp(77, 123_456)
+            .with_storage_ecu(b"STOR")
+            .with_ecu("ECU1")
+            .with_apid("APP1")
+            .with_ctid("CTX1")
+            .with_session_id(9)
+            .with_counter(42)
+            .with_timestamp_ns(5_000_000_000)
+            .with_message_type_info(0x03)
+            .with_argument(Argument {
+                name: Some("speed".into()),
+                unit: Some("km/h".into()),
+                value: Value::U32(88),
+            })
+            .with_verbose_string("warning")
+    }
+
+    fn assert_same_rows(left: &dyn DltStore, right: &dyn DltStore) {
+        assert_eq!(left.storage_timestamp_ns(0), right.storage_timestamp_ns(0));
+        assert_eq!(left.ecu(0), right.ecu(0));
+        assert_eq!(left.apid(0), right.apid(0));
+        assert_eq!(left.ctid(0), right.ctid(0));
+        assert_eq!(left.session_id(0), right.session_id(0));
+        assert_eq!(left.message_counter(0), right.message_counter(0));
+        assert_eq!(left.message_timestamp_ns(0), right.message_timestamp_ns(0));
+        assert_eq!(left.message_type(0), right.message_type(0));
+        assert_eq!(left.message_type_info(0), right.message_type_info(0));
+        assert_eq!(left.payload_arguments(0), right.payload_arguments(0));
+        assert_eq!(left.payload_text(0), right.payload_text(0));
+        assert_eq!(&left.frame_raw(0)[12..16], &right.frame_raw(0)[12..16]);
+    }
+
+    #[test]
+    fn verbose_messages_convert_both_ways_without_loss() {
+        for (from, to) in [(1, 2), (2, 1)] {
+            let frame = log_message().build(from).unwrap();
+            let source = open(std::slice::from_ref(&frame), from);
+
+            let (target, losses) = converted(&frame, from, to);
+
+            assert!(losses.is_empty(), "{losses:?}");
+            assert_eq!(target.protocol_version(0), to);
+            assert_same_rows(source.as_ref(), target.as_ref());
+        }
+    }
+
+    #[test]
+    fn control_and_non_verbose_messages_convert() {
+        let response = ControlMessage {
+            service_id: SERVICE_GET_SOFTWARE_VERSION,
+            status: Some(0),
+            body: control::ControlBody::SoftwareVersion("2.18".into()),
+        };
+        let control = MessageBuilder::new()
+            .with_ecu("ECU1")
+            .with_apid("DA1")
+            .with_ctid("DC1")
+            .with_control(response.clone())
+            .build(1)
+            .unwrap();
+        let (target, losses) = converted(&control, 1, 2);
+        assert!(losses.is_empty());
+        assert_eq!(target.control_message(0), Some(response));
+
+        let non_verbose = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_non_verbose(0x1234, &[1, 0])
+            .build(1)
+            .unwrap();
+        let (target, losses) = converted(&non_verbose, 1, 2);
+        assert_eq!(
+            losses,
+            vec![Loss::NonVerboseMessageType, Loss::NonVerboseByteOrder]
+        );
+        let payload = target.non_verbose_payload(0).unwrap();
+        assert_eq!(payload.message_id, 0x1234);
+        assert_eq!(payload.arguments, &[1, 0]);
+
+        // v2 payloads are big-endian, which v1 keeps by setting MSBF.
+        let non_verbose = MessageBuilder::new()
+            .with_non_verbose(0x1234, &[0, 1])
+            .build(2)
+            .unwrap();
+        let (target, losses) = converted(&non_verbose, 2, 1);
+        assert!(losses.is_empty());
+        let payload = target.non_verbose_payload(0).unwrap();
+        assert_eq!(payload.message_id, 0x1234);
+        assert!(payload.big_endian);
+    }
+
+    #[test]
+    fn v2_only_fields_are_reported_as_loss() {
+        let frame = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_source_location("main.c", 12)
+            .with_tag("boot")
+            .with_privacy_level(1)
+            .with_timestamp_ns(1_000_000_001)
+            .with_verbose_string("text")
+            .build(2)
+            .unwrap();
+
+        let (target, losses) = converted(&frame, 2, 1);
+
+        assert_eq!(
+            losses,
+            vec![
+                Loss::SourceLocation,
+                Loss::Tags,
+                Loss::PrivacyLevel,
+                Loss::TimestampPrecision,
+            ]
+        );
+        assert_eq!(target.payload_text(0), "text");
+        assert_eq!(target.message_timestamp_ns(0), 1_000_000_000);
+    }
+
+    #[test]
+    fn convert_rows_counts_copies_and_losses() {
+        let frames = [
+            log_message().build(1).unwrap(),
+            log_message().build(2).unwrap(),
+            MessageBuilder::new()
+                .with_tag("x")
+                .with_verbose_string("tagged")
+                .build(2)
+                .unwrap(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            let path = dir.path().join(format!("{i}.dlt"));
+            std::fs::write(&path, frame).unwrap();
+            paths.push(path);
+        }
+        let (dlt, _) = crate::dlt::open_with_merge(paths, None).unwrap();
+
+        let mut out = Vec::new();
+        let summary = convert_rows(dlt.as_ref(), 0..dlt.len(), 1, &mut out).unwrap();
+
+        assert_eq!(summary.rows, 3);
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(summary.bytes, out.len() as u64);
+        assert_eq!(summary.losses, BTreeMap::from([(Loss::Tags, 1)]));
+        let converted = open(&[out], 1);
+        assert_eq!(converted.len(), 3);
+        assert!(convert_frame(&frames[0], 1, 3).is_err());
+    }
+
+    #[test]
+    fn segmented_rows_are_copied_with_every_segment() {
+        let segments = log_message().build_segments(8).unwrap();
+        let dlt = open(&segments, 2);
+        assert_eq!(dlt.len(), 1);
+
+        let mut out = Vec::new();
+        let summary = convert_rows(dlt.as_ref(), 0..dlt.len(), 2, &mut out).unwrap();
+
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.bytes, out.len() as u64);
+        assert_eq!(out, segments.concat());
+    }
+
+    #[test]
+    fn reassembled_rows_convert_to_v1_from_the_joined_payload() {
+        let segments = log_message().build_segments(8).unwrap();
+        let dlt = open(&segments, 2);
+        let source = open(&[log_message().build(2).unwrap()], 2);
+
+        let mut out = Vec::new();
+        let summary = convert_rows(dlt.as_ref(), 0..dlt.len(), 1, &mut out).unwrap();
+
+        assert_eq!(summary.rows, 1);
+        assert_eq!(summary.skipped, 0);
+        assert!(summary.losses.is_empty(), "{:?}", summary.losses);
+        let converted = open(&[out], 1);
+        assert_eq!(converted.len(), 1);
+        assert_same_rows(source.as_ref(), converted.as_ref());
+
+        // A segment on its own is still not a message v1 can hold.
+        let lone = convert_frame(&segments[0], 2, 1).unwrap();
+        assert_eq!(lone.frame, None);
+        assert_eq!(lone.losses, vec![Loss::Segmented]);
+    }
+}
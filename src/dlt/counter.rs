@@ -0,0 +1,209 @@
+use crate::dlt::intern::InternTable;
+use crate::dlt::store::DltStore;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// ECU, session ID, APID and CTID (interned) of one message counter sequence.
+type SenderKey = (u16, u32, u16, u16);
+
+/// A row whose message counter (MCNT) does not follow the previous row of
+/// the same sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterGap {
+    pub row: usize,
+    pub ecu: String,
+    pub session_id: u32,
+    pub apid: String,
+    pub ctid: String,
+    /// Counter that should have come next.
+    pub expected: u8,
+    /// Counter the row carries.
+    pub found: u8,
+}
+
+impl CounterGap {
+    /// Messages lost between the previous row of the sender and this one,
+    /// counting the wrap from 255 to 0.
+    pub fn dropped(&self) -> u8 {
+        self.found.wrapping_sub(self.expected)
+    }
+}
+
+/// Checks message counter continuity per sender, row by row.
+///
+/// Libdlt counts messages per context, so a sender is an ECU, session ID,
+/// APID and CTID. A repeated counter is taken as a duplicate, and a counter
+/// back at 0 as a restarted sender, rather than as lost messages. Rows can be
+/// scanned in chunks, so live stores only need to scan the rows they append.
+#[derive(Default)]
+pub struct CounterTracker {
+    intern: InternTable,
+    last_counter: HashMap<SenderKey, u8>,
+    gaps: Vec<CounterGap>,
+}
+
+impl CounterTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracker that has scanned every row of `dlt`.
+    pub fn scan_all(dlt: &dyn DltStore) -> Self {
+        let mut tracker = Self::new();
+        tracker.scan(dlt, 0..dlt.len());
+        tracker
+    }
+
+    /// Check `rows`, which must follow the rows scanned before.
+    pub fn scan(&mut self, dlt: &dyn DltStore, rows: Range<usize>) {
+        for row in rows {
+            let key = (
+                self.intern.insert(dlt.ecu(row)),
+                dlt.session_id(row),
+                self.intern.insert(dlt.apid(row)),
+                self.intern.insert(dlt.ctid(row)),
+            );
+            let found = dlt.message_counter(row);
+            let previous = self.last_counter.insert(key, dlt.last_message_counter(row));
+            let Some(previous) = previous else {
+                continue;
+            };
+            let expected = previous.wrapping_add(1);
+            if found != expected && found != previous && found != 0 {
+                self.gaps.push(CounterGap {
+                    row,
+                    ecu: dlt.ecu(row).to_string(),
+                    session_id: dlt.session_id(row),
+                    apid: dlt.apid(row).to_string(),
+                    ctid: dlt.ctid(row).to_string(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    /// Gaps in row order.
+    pub fn gaps(&self) -> &[CounterGap] {
+        &self.gaps
+    }
+
+    /// The gap right before `row`, if its counter does not follow on.
+    pub fn gap_at(&self, row: usize) -> Option<&CounterGap> {
+        self.gaps
+            .binary_search_by_key(&row, |gap| gap.row)
+            .ok()
+            .map(|position| &self.gaps[position])
+    }
+
+    /// Total number of lost messages over all gaps.
+    pub fn dropped(&self) -> u64 {
+        self.gaps.iter().map(|gap| u64::from(gap.dropped())).sum()
+    }
+}
+
+impl std::fmt::Debug for CounterTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CounterTracker")
+            .field("senders", &self.last_counter.len())
+            .field("gaps", &self.gaps.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::v1;
+    use crate::dlt::v2;
+    use crate::dlt::writer::MessageBuilder;
+
+    fn message(apid: &str, counter: u8) -> Vec<u8> {
+        MessageBuilder::new()
+            .with_apid(apid)
+            .with_ctid("CTX1")
+            .with_counter(counter)
+            .with_verbose_string("text")
+            .build(1)
+            .unwrap()
+    }
+
+    fn stream(frames: &[Vec<u8>]) -> v1::Dlt {
+        let mut stream = v1::Dlt::stream();
+        assert!(stream.append(&frames.concat()).is_empty());
+        stream
+    }
+
+    #[test]
+    fn continuous_counters_including_wrap_have_no_gaps() {
+        let dlt = stream(&[
+            message("APP1", 254),
+            message("APP1", 255),
+            message("APP1", 0),
+        ]);
+        let tracker = CounterTracker::scan_all(&dlt);
+        assert!(tracker.gaps().is_empty());
+        assert_eq!(tracker.dropped(), 0);
+    }
+
+    #[test]
+    fn gaps_are_tracked_per_sender_across_the_wrap() {
+        let dlt = stream(&[
+            message("APP1", 10),
+            message("APP2", 200),
+            message("APP1", 11),
+            message("APP2", 2),
+            message("APP1", 14),
+            message("APP1", 14),
+            message("APP1", 0),
+        ]);
+        let tracker = CounterTracker::scan_all(&dlt);
+
+        let gaps: Vec<_> = tracker
+            .gaps()
+            .iter()
+            .map(|gap| (gap.row, gap.apid.as_str(), gap.dropped()))
+            .collect();
+        assert_eq!(gaps, vec![(3, "APP2", 57), (4, "APP1", 2)]);
+        assert_eq!(tracker.dropped(), 59);
+        assert_eq!(tracker.gap_at(4).unwrap().expected, 12);
+        assert!(tracker.gap_at(5).is_none());
+        assert!(tracker.gap_at(6).is_none());
+    }
+
+    #[test]
+    fn scanning_in_chunks_matches_one_scan() {
+        let dlt = stream(&[message("APP1", 1), message("APP1", 5), message("APP1", 9)]);
+        let mut tracker = CounterTracker::new();
+        tracker.scan(&dlt, 0..1);
+        tracker.scan(&dlt, 1..3);
+        assert_eq!(tracker.gaps(), CounterTracker::scan_all(&dlt).gaps());
+        assert_eq!(tracker.dropped(), 6);
+    }
+
+    #[test]
+    fn reassembled_segments_continue_from_their_last_counter() {
+        let mut frames: Vec<Vec<u8>> = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_ctid("CTX1")
+            .with_counter(3)
+            .with_verbose_string("a long text in segments")
+            .build_segments(10)
+            .unwrap();
+        let segments = frames.len() as u8;
+        frames.push(
+            MessageBuilder::new()
+                .with_apid("APP1")
+                .with_ctid("CTX1")
+                .with_counter(3 + segments)
+                .with_verbose_string("next")
+                .build(2)
+                .unwrap(),
+        );
+        let mut dlt = v2::Dlt::stream();
+        assert!(dlt.append(&frames.concat()).is_empty());
+
+        assert_eq!(dlt.len(), 2);
+        assert!(CounterTracker::scan_all(&dlt).gaps().is_empty());
+    }
+}
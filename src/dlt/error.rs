@@ -24,6 +24,14 @@ pub enum ParseErrorKind {
     InvalidExtensionField,
     /// Payload offset or length exceeds the message bounds.
     PayloadOutOfBounds,
+    /// A v2 segment that continues no sequence in progress.
+    UnexpectedSegment,
+    /// A v2 segmented message that was interrupted, ended out of sequence,
+    /// or whose segments do not add up to its total length; reported at its
+    /// first segment.
+    IncompleteSegmentedMessage,
+    /// A v2 segmented message aborted by the sender for `reason`.
+    SegmentationAborted { reason: u8 },
 }
 
 impl std::fmt::Display for ParseError {
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::dlt::store::DltStore;
+
+/// Row and byte counts written by an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub rows: usize,
+    /// Bytes of all frames written, including every segment of a
+    /// reassembled v2 message.
+    pub bytes: u64,
+}
+
+/// Write the given rows as a `.dlt` stream.
+///
+/// Each row is written as its original stored frames — storage header and
+/// message — so every frame is byte-exact with the source files and the
+/// output can be opened by any DLT tool. A reassembled v2 message is written
+/// as all of its segments, one after the other, even if other messages were
+/// interleaved with them in the source. Rows are written in the order given.
+pub fn write_rows<W: Write>(
+    dlt: &dyn DltStore,
+    rows: impl IntoIterator<Item = usize>,
+    writer: &mut W,
+) -> std::io::Result<ExportSummary> {
+    let mut summary = ExportSummary { rows: 0, bytes: 0 };
+    for row in rows {
+        for frame in dlt.frames_raw(row) {
+            writer.write_all(frame)?;
+            summary.bytes += frame.len() as u64;
+        }
+        summary.rows += 1;
+    }
+    Ok(summary)
+}
+
+/// Write the given rows to a new `.dlt` file at `path`, replacing any existing file.
+pub fn export_rows(
+    dlt: &dyn DltStore,
+    rows: impl IntoIterator<Item = usize>,
+    path: &Path,
+) -> Result<ExportSummary> {
+    let file = File::create(path)
+        .with_context(|| format!("failed to create export file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let summary = write_rows(dlt, rows, &mut writer)
+        .with_context(|| format!("failed to write export file {}", path.display()))?;
+    writer
+        .flush()
+        .with_context(|| format!("failed to write export file {}", path.display()))?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::writer::MessageBuilder;
+    use std::path::PathBuf;
+
+    fn test_data_path(filename: &str) -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/data");
+        path.push(filename);
+        path
+    }
+
+    #[test]
+    fn exporting_all_rows_reproduces_the_source_file() {
+        let path = test_data_path("testfile_single_payloads.dlt");
+        let (dlt, errors) = crate::dlt::open(vec![path.clone()]).unwrap();
+        assert!(errors.is_empty());
+
+        let mut out = Vec::new();
+        let summary = write_rows(dlt.as_ref(), 0..dlt.len(), &mut out).unwrap();
+
+        assert_eq!(summary.rows, dlt.len());
+        assert_eq!(summary.bytes, out.len() as u64);
+        assert_eq!(out, std::fs::read(path).unwrap());
+    }
+
+    #[test]
+    fn exported_subset_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.dlt");
+        let frames: Vec<Vec<u8>> = ["keep 1", "drop", "keep 2"]
+            .iter()
+            .map(|text| {
+                MessageBuilder::new()
+                    .with_apid("APP1")
+                    .with_ctid("CTX1")
+                    .with_verbose_string(text)
+                    .build(2)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(&source, frames.concat()).unwrap();
+
+        let (dlt, _) = crate::dlt::open(vec![source]).unwrap();
+        let target = dir.path().join("trimmed.dlt");
+        let summary = export_rows(dlt.as_ref(), [0, 2], &target).unwrap();
+        assert_eq!(summary.rows, 2);
+
+        let written = std::fs::read(&target).unwrap();
+        assert_eq!(written, [frames[0].clone(), frames[2].clone()].concat());
+
+        let (reopened, errors) = crate::dlt::open(vec![target]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.payload_text(0), "keep 1");
+        assert_eq!(reopened.payload_text(1), "keep 2");
+    }
+
+    #[test]
+    fn export_summary_counts_a_segmented_row_once_with_all_its_bytes() {
+        let segments = MessageBuilder::new()
+            .with_verbose_string("a payload that is sent in several segments")
+            .build_segments(8)
+            .unwrap();
+        let plain = MessageBuilder::new()
+            .with_verbose_string("plain")
+            .build(2)
+            .unwrap();
+        let mut dlt = crate::dlt::open_stream(2).unwrap();
+        let frames = [segments.concat(), plain.clone()].concat();
+        assert!(dlt.append(&frames).unwrap().is_empty());
+
+        let mut out = Vec::new();
+        let summary = write_rows(dlt.as_ref(), 0..dlt.len(), &mut out).unwrap();
+
+        assert!(segments.len() > 2);
+        assert_eq!(summary.rows, 2);
+        assert_eq!(summary.bytes, frames.len() as u64);
+        assert_eq!(out, frames);
+    }
+
+    #[test]
+    fn exported_segmented_rows_reopen_whole() {
+        let text = "a payload that is sent in several segments";
+        let segments = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_verbose_string(text)
+            .build_segments(8)
+            .unwrap();
+        let other = MessageBuilder::new()
+            .with_apid("APP2")
+            .with_verbose_string("in between")
+            .build(2)
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("segments.dlt");
+        // Another message arrives while the segments are sent.
+        std::fs::write(
+            &source,
+            [&segments[..1], std::slice::from_ref(&other), &segments[1..]]
+                .concat()
+                .concat(),
+        )
+        .unwrap();
+        let (dlt, errors) = crate::dlt::open(vec![source]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(dlt.payload_text(1), text);
+
+        let target = dir.path().join("exported.dlt");
+        export_rows(dlt.as_ref(), [1], &target).unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), segments.concat());
+        let (reopened, errors) = crate::dlt::open(vec![target]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.payload_text(0), text);
+    }
+}
@@ -0,0 +1,810 @@
+use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::dlt::control::ControlMessage;
+use crate::dlt::error::ParseError;
+use crate::dlt::payload::{DLT_TYLE_8BIT, DLT_TYLE_16BIT, DLT_TYLE_32BIT, DLT_TYLE_64BIT};
+use crate::dlt::store::{DltStore, NonVerbosePayload};
+use crate::dlt::verbose::{self, Argument, Value};
+
+/// Wire type of one FIBEX signal inside a non-verbose payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalType {
+    Bool,
+    /// Signed integer; the value is the TYLE code (1 = 8 bit .. 5 = 128 bit).
+    Signed(u32),
+    /// Unsigned integer; the value is the TYLE code.
+    Unsigned(u32),
+    /// IEEE 754 float; the value is the TYLE code.
+    Float(u32),
+    /// Text preceded by a 16-bit length.
+    String,
+    /// Raw bytes preceded by a 16-bit length.
+    Raw,
+}
+
+impl SignalType {
+    /// Signal types predefined by dlt-viewer's FIBEX files, e.g. `S_UINT16`.
+    fn from_signal_id(id: &str) -> Option<Self> {
+        Some(match id {
+            "S_BOOL" => Self::Bool,
+            "S_SINT8" => Self::Signed(DLT_TYLE_8BIT),
+            "S_SINT16" => Self::Signed(DLT_TYLE_16BIT),
+            "S_SINT32" => Self::Signed(DLT_TYLE_32BIT),
+            "S_SINT64" => Self::Signed(DLT_TYLE_64BIT),
+            "S_UINT8" => Self::Unsigned(DLT_TYLE_8BIT),
+            "S_UINT16" => Self::Unsigned(DLT_TYLE_16BIT),
+            "S_UINT32" => Self::Unsigned(DLT_TYLE_32BIT),
+            "S_UINT64" => Self::Unsigned(DLT_TYLE_64BIT),
+            "S_FLOA16" => Self::Float(DLT_TYLE_16BIT),
+            "S_FLOA32" => Self::Float(DLT_TYLE_32BIT),
+            "S_FLOA64" => Self::Float(DLT_TYLE_64BIT),
+            "S_STRG_ASCII" | "S_STRG_UTF8" => Self::String,
+            "S_RAWD" | "S_RAW" => Self::Raw,
+            _ => return None,
+        })
+    }
+
+    /// ASAM base data types used by `<CODED-TYPE BASE-DATA-TYPE=...>`.
+    fn from_base_data_type(base: &str) -> Option<Self> {
+        Some(match base {
+            "A_UINT8" => Self::Unsigned(DLT_TYLE_8BIT),
+            "A_UINT16" => Self::Unsigned(DLT_TYLE_16BIT),
+            "A_UINT32" => Self::Unsigned(DLT_TYLE_32BIT),
+            "A_UINT64" => Self::Unsigned(DLT_TYLE_64BIT),
+            "A_INT8" => Self::Signed(DLT_TYLE_8BIT),
+            "A_INT16" => Self::Signed(DLT_TYLE_16BIT),
+            "A_INT32" => Self::Signed(DLT_TYLE_32BIT),
+            "A_INT64" => Self::Signed(DLT_TYLE_64BIT),
+            "A_FLOAT32" => Self::Float(DLT_TYLE_32BIT),
+            "A_FLOAT64" => Self::Float(DLT_TYLE_64BIT),
+            "A_ASCIISTRING" | "A_UNICODE2STRING" => Self::String,
+            "A_BYTEFIELD" => Self::Raw,
+            _ => return None,
+        })
+    }
+
+    fn decode(self, reader: &mut verbose::Reader) -> Option<Value> {
+        match self {
+            Self::Bool => Some(Value::Bool(reader.take(1)?[0] != 0)),
+            Self::Signed(tyle) => reader.signed(tyle),
+            Self::Unsigned(tyle) => reader.unsigned(tyle),
+            Self::Float(tyle) => reader.float(tyle),
+            Self::String => {
+                let len = reader.u16()? as usize;
+                reader.text(len).map(Value::String)
+            }
+            Self::Raw => {
+                let len = reader.u16()? as usize;
+                reader.take(len).map(|bytes| Value::Raw(bytes.to_vec()))
+            }
+        }
+    }
+}
+
+/// One element of a frame's payload, in PDU sequence order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameItem {
+    /// Constant text from the PDU description; not transmitted.
+    Text(String),
+    /// A value read from the payload.
+    Signal {
+        name: Option<String>,
+        kind: SignalType,
+    },
+}
+
+/// Description of one non-verbose message, keyed by its message ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FibexFrame {
+    pub apid: String,
+    pub ctid: String,
+    pub message_type: u8,
+    pub message_type_info: u8,
+    pub items: Vec<FrameItem>,
+}
+
+impl FibexFrame {
+    /// Decode non-verbose argument bytes into arguments, static text included.
+    ///
+    /// Decoding stops at the first signal that does not fit in the payload.
+    pub fn decode(&self, data: &[u8], big_endian: bool) -> Vec<Argument> {
+        let mut reader = verbose::Reader::new(data, big_endian);
+        let mut arguments = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let (name, value) = match item {
+                FrameItem::Text(text) => (None, Value::String(text.clone())),
+                FrameItem::Signal { name, kind } => match kind.decode(&mut reader) {
+                    Some(value) => (name.clone(), value),
+                    None => break,
+                },
+            };
+            arguments.push(Argument {
+                name,
+                unit: None,
+                value,
+            });
+        }
+        arguments
+    }
+}
+
+/// Non-verbose message descriptions loaded from one or more FIBEX files.
+///
+/// Only the subset of FIBEX written by dlt-viewer and the GENIVI tooling is
+/// understood: frames with a `MANUFACTURER-EXTENSION` carrying APID, CTID and
+/// message type, PDUs carrying either a static `DESC` text or signals.
+#[derive(Debug, Clone, Default)]
+pub struct FibexDatabase {
+    frames: HashMap<u32, FibexFrame>,
+}
+
+impl FibexDatabase {
+    /// Load a single FIBEX file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read FIBEX file {}", path.display()))?;
+        Self::from_str(&text)
+            .with_context(|| format!("failed to parse FIBEX file {}", path.display()))
+    }
+
+    /// Load several FIBEX files; later files win on duplicate message IDs.
+    pub fn from_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut database = Self::default();
+        for path in paths {
+            database.frames.extend(Self::from_file(path)?.frames);
+        }
+        Ok(database)
+    }
+
+    /// Parse the XML contents of a FIBEX file.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(xml: &str) -> Result<Self> {
+        FibexParser::default().parse(xml)
+    }
+
+    pub fn frame(&self, message_id: u32) -> Option<&FibexFrame> {
+        self.frames.get(&message_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct RawFrame {
+    apid: String,
+    ctid: String,
+    message_type: String,
+    message_info: String,
+    /// (sequence number, PDU ID)
+    pdus: Vec<(u32, String)>,
+}
+
+#[derive(Default)]
+struct RawPdu {
+    desc: Option<String>,
+    /// (sequence number, signal ID)
+    signals: Vec<(u32, String)>,
+}
+
+#[derive(Default)]
+struct RawSignal {
+    name: Option<String>,
+    coding: String,
+}
+
+/// Collects the FIBEX elements by ID, then resolves the references between them.
+#[derive(Default)]
+struct FibexParser {
+    frames: Vec<(String, RawFrame)>,
+    pdus: HashMap<String, RawPdu>,
+    signals: HashMap<String, RawSignal>,
+    /// Coding ID to `BASE-DATA-TYPE`.
+    codings: HashMap<String, String>,
+    frame: Option<(String, RawFrame)>,
+    pdu: Option<(String, RawPdu)>,
+    signal: Option<(String, RawSignal)>,
+    coding: Option<String>,
+    /// (sequence number, referenced ID) of the open PDU or signal instance.
+    instance: (u32, String),
+}
+
+impl FibexParser {
+    fn parse(mut self, xml: &str) -> Result<FibexDatabase> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+        let mut path: Vec<String> = Vec::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => {
+                    let name = local_name(&start);
+                    self.open(&name, &start)?;
+                    path.push(name);
+                }
+                Event::Empty(start) => {
+                    let name = local_name(&start);
+                    self.open(&name, &start)?;
+                    self.close(&name);
+                }
+                Event::Text(text) => {
+                    let text = text.unescape()?;
+                    self.text(&path, text.trim());
+                }
+                Event::End(_) => {
+                    if let Some(name) = path.pop() {
+                        self.close(&name);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        self.resolve()
+    }
+
+    fn open(&mut self, name: &str, start: &BytesStart) -> Result<()> {
+        match name {
+            "FRAME" => self.frame = Some((attribute(start, "ID")?, RawFrame::default())),
+            "PDU" => self.pdu = Some((attribute(start, "ID")?, RawPdu::default())),
+            "SIGNAL" => self.signal = Some((attribute(start, "ID")?, RawSignal::default())),
+            "CODING" => self.coding = Some(attribute(start, "ID")?),
+            "PDU-INSTANCE" | "SIGNAL-INSTANCE" => self.instance = (0, String::new()),
+            "PDU-REF" | "SIGNAL-REF" => self.instance.1 = attribute(start, "ID-REF")?,
+            "CODING-REF" => {
+                if let Some((_, signal)) = self.signal.as_mut() {
+                    signal.coding = attribute(start, "ID-REF")?;
+                }
+            }
+            "CODED-TYPE" => {
+                if let Some(coding) = &self.coding {
+                    let base = attribute(start, "BASE-DATA-TYPE")?;
+                    self.codings.insert(coding.clone(), base);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "FRAME" => self.frames.extend(self.frame.take()),
+            "PDU" => {
+                if let Some((id, mut pdu)) = self.pdu.take() {
+                    pdu.signals.sort_by_key(|(sequence, _)| *sequence);
+                    self.pdus.insert(id, pdu);
+                }
+            }
+            "SIGNAL" => {
+                if let Some((id, signal)) = self.signal.take() {
+                    self.signals.insert(id, signal);
+                }
+            }
+            "CODING" => self.coding = None,
+            "PDU-INSTANCE" => {
+                if let Some((_, frame)) = self.frame.as_mut() {
+                    frame.pdus.push(std::mem::take(&mut self.instance));
+                }
+            }
+            "SIGNAL-INSTANCE" => {
+                if let Some((_, pdu)) = self.pdu.as_mut() {
+                    pdu.signals.push(std::mem::take(&mut self.instance));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, path: &[String], text: &str) {
+        let Some((element, parents)) = path.split_last() else {
+            return;
+        };
+        let parent = parents.last().map(String::as_str);
+        match (parent, element.as_str()) {
+            (Some("PDU-INSTANCE" | "SIGNAL-INSTANCE"), "SEQUENCE-NUMBER") => {
+                self.instance.0 = text.parse().unwrap_or(0);
+            }
+            (Some("PDU"), "DESC") => {
+                if let Some((_, pdu)) = self.pdu.as_mut() {
+                    pdu.desc = Some(text.to_string());
+                }
+            }
+            (Some("SIGNAL"), "SHORT-NAME") => {
+                if let Some((_, signal)) = self.signal.as_mut() {
+                    signal.name = Some(text.to_string());
+                }
+            }
+            (Some("MANUFACTURER-EXTENSION"), field) => {
+                if let Some((_, frame)) = self.frame.as_mut() {
+                    let target = match field {
+                        "APPLICATION_ID" => &mut frame.apid,
+                        "CONTEXT_ID" => &mut frame.ctid,
+                        "MESSAGE_TYPE" => &mut frame.message_type,
+                        "MESSAGE_INFO" => &mut frame.message_info,
+                        _ => return,
+                    };
+                    *target = text.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve(mut self) -> Result<FibexDatabase> {
+        let mut frames = HashMap::with_capacity(self.frames.len());
+        for (id, mut raw) in std::mem::take(&mut self.frames) {
+            let message_id = parse_message_id(&id)?;
+            raw.pdus.sort_by_key(|(sequence, _)| *sequence);
+
+            let mut items = Vec::new();
+            for (_, pdu_id) in &raw.pdus {
+                let pdu = self
+                    .pdus
+                    .get(pdu_id)
+                    .ok_or_else(|| anyhow!("frame {id} references unknown PDU {pdu_id}"))?;
+                if let Some(desc) = &pdu.desc {
+                    items.push(FrameItem::Text(desc.clone()));
+                }
+                for (_, signal_id) in &pdu.signals {
+                    items.push(self.signal_item(signal_id)?);
+                }
+            }
+
+            let message_type = parse_message_type(&raw.message_type);
+            frames.insert(
+                message_id,
+                FibexFrame {
+                    apid: raw.apid,
+                    ctid: raw.ctid,
+                    message_type,
+                    message_type_info: parse_message_info(&raw.message_info),
+                    items,
+                },
+            );
+        }
+        Ok(FibexDatabase { frames })
+    }
+
+    fn signal_item(&self, signal_id: &str) -> Result<FrameItem> {
+        if let Some(signal) = self.signals.get(signal_id) {
+            let kind = self
+                .codings
+                .get(&signal.coding)
+                .and_then(|base| SignalType::from_base_data_type(base))
+                .ok_or_else(|| anyhow!("signal {signal_id} has unsupported coding"))?;
+            return Ok(FrameItem::Signal {
+                name: signal.name.clone(),
+                kind,
+            });
+        }
+        let kind = SignalType::from_signal_id(signal_id)
+            .ok_or_else(|| anyhow!("unknown signal {signal_id}"))?;
+        Ok(FrameItem::Signal { name: None, kind })
+    }
+}
+
+fn local_name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).into_owned()
+}
+
+/// Attribute value by local name, ignoring the namespace prefix.
+fn attribute(start: &BytesStart, name: &str) -> Result<String> {
+    for attr in start.attributes() {
+        let attr = attr?;
+        if attr.key.local_name().as_ref() == name.as_bytes() {
+            return Ok(attr.unescape_value()?.into_owned());
+        }
+    }
+    Err(anyhow!(
+        "<{}> is missing attribute {name}",
+        local_name(start)
+    ))
+}
+
+/// Message ID from a frame ID such as `ID_1234`.
+fn parse_message_id(id: &str) -> Result<u32> {
+    id.strip_prefix("ID_")
+        .unwrap_or(id)
+        .parse()
+        .map_err(|_| anyhow!("frame ID {id:?} does not name a message ID"))
+}
+
+fn parse_message_type(value: &str) -> u8 {
+    match value {
+        "DLT_TYPE_APP_TRACE" => 1,
+        "DLT_TYPE_NW_TRACE" => 2,
+        "DLT_TYPE_CONTROL" => 3,
+        _ => 0,
+    }
+}
+
+fn parse_message_info(value: &str) -> u8 {
+    match value {
+        "DLT_LOG_FATAL" | "DLT_TRACE_VARIABLE" | "DLT_NW_TRACE_IPC" | "DLT_CONTROL_REQUEST" => 1,
+        "DLT_LOG_ERROR" | "DLT_TRACE_FUNCTION_IN" | "DLT_NW_TRACE_CAN" | "DLT_CONTROL_RESPONSE" => {
+            2
+        }
+        "DLT_LOG_WARN" | "DLT_TRACE_FUNCTION_OUT" | "DLT_NW_TRACE_FLEXRAY" => 3,
+        "DLT_LOG_INFO" | "DLT_TRACE_STATE" | "DLT_NW_TRACE_MOST" => 4,
+        "DLT_LOG_DEBUG" | "DLT_TRACE_VFB" | "DLT_NW_TRACE_ETHERNET" => 5,
+        "DLT_LOG_VERBOSE" | "DLT_NW_TRACE_SOMEIP" => 6,
+        _ => 0,
+    }
+}
+
+/// A store whose non-verbose messages are decoded with a `FibexDatabase`.
+///
+/// Rows whose message ID has a frame description get their payload, and any
+/// header fields the message itself does not carry (APID, CTID, message type
+/// and info), from the description. All other rows pass through unchanged.
+pub struct FibexDlt {
+    inner: Box<dyn DltStore>,
+    database: Arc<FibexDatabase>,
+    apids: BTreeSet<String>,
+    ctids: BTreeSet<String>,
+}
+
+impl FibexDlt {
+    pub fn new(inner: Box<dyn DltStore>, database: Arc<FibexDatabase>) -> Self {
+        let mut store = Self {
+            inner,
+            database,
+            apids: BTreeSet::new(),
+            ctids: BTreeSet::new(),
+        };
+        store.collect_ids(0);
+        store
+    }
+
+    /// Add the APIDs and CTIDs of rows from `first_row` on to the unique sets.
+    fn collect_ids(&mut self, first_row: usize) {
+        for row in first_row..self.len() {
+            let apid = self.apid(row).to_string();
+            let ctid = self.ctid(row).to_string();
+            if !apid.is_empty() {
+                self.apids.insert(apid);
+            }
+            if !ctid.is_empty() {
+                self.ctids.insert(ctid);
+            }
+        }
+    }
+
+    fn frame(&self, row: usize) -> Option<(&FibexFrame, NonVerbosePayload<'_>)> {
+        let payload = self.inner.non_verbose_payload(row)?;
+        let frame = self.database.frame(payload.message_id)?;
+        Some((frame, payload))
+    }
+}
+
+impl DltStore for FibexDlt {
+    fn protocol_versions(&self) -> Vec<u8> {
+        self.inner.protocol_versions()
+    }
+
+    fn protocol_version(&self, row: usize) -> u8 {
+        self.inner.protocol_version(row)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn ecu(&self, row: usize) -> &str {
+        self.inner.ecu(row)
+    }
+
+    fn apid(&self, row: usize) -> &str {
+        let apid = self.inner.apid(row);
+        match self.frame(row) {
+            Some((frame, _)) if apid.is_empty() => &frame.apid,
+            _ => apid,
+        }
+    }
+
+    fn ctid(&self, row: usize) -> &str {
+        let ctid = self.inner.ctid(row);
+        match self.frame(row) {
+            Some((frame, _)) if ctid.is_empty() => &frame.ctid,
+            _ => ctid,
+        }
+    }
+
+    fn session_id(&self, row: usize) -> u32 {
+        self.inner.session_id(row)
+    }
+
+    fn message_counter(&self, row: usize) -> u8 {
+        self.inner.message_counter(row)
+    }
+
+    fn last_message_counter(&self, row: usize) -> u8 {
+        self.inner.last_message_counter(row)
+    }
+
+    fn is_verbose(&self, row: usize) -> bool {
+        self.inner.is_verbose(row)
+    }
+
+    fn argument_count(&self, row: usize) -> u8 {
+        self.inner.argument_count(row)
+    }
+
+    fn file_index(&self, row: usize) -> u16 {
+        self.inner.file_index(row)
+    }
+
+    fn byte_offset(&self, row: usize) -> u64 {
+        self.inner.byte_offset(row)
+    }
+
+    fn storage_timestamp_ns(&self, row: usize) -> u64 {
+        self.inner.storage_timestamp_ns(row)
+    }
+
+    fn message_timestamp_ns(&self, row: usize) -> u64 {
+        self.inner.message_timestamp_ns(row)
+    }
+
+    // Messages without message info (MTIN 0) have no type in their header.
+    fn message_type(&self, row: usize) -> u8 {
+        match self.frame(row) {
+            Some((frame, _)) if self.inner.message_type_info(row) == 0 => frame.message_type,
+            _ => self.inner.message_type(row),
+        }
+    }
+
+    fn message_type_info(&self, row: usize) -> u8 {
+        let mtin = self.inner.message_type_info(row);
+        match self.frame(row) {
+            Some((frame, _)) if mtin == 0 => frame.message_type_info,
+            _ => mtin,
+        }
+    }
+
+    fn payload_raw(&self, row: usize) -> &[u8] {
+        self.inner.payload_raw(row)
+    }
+
+    fn frame_raw(&self, row: usize) -> &[u8] {
+        self.inner.frame_raw(row)
+    }
+
+    fn frames_raw(&self, row: usize) -> Vec<&[u8]> {
+        self.inner.frames_raw(row)
+    }
+
+    fn payload_text(&self, row: usize) -> String {
+        match self.frame(row) {
+            Some(_) => self
+                .payload_arguments(row)
+                .iter()
+                .map(|argument| argument.value.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => self.inner.payload_text(row),
+        }
+    }
+
+    fn payload_arguments(&self, row: usize) -> Vec<Argument> {
+        match self.frame(row) {
+            Some((frame, payload)) => frame.decode(payload.arguments, payload.big_endian),
+            None => self.inner.payload_arguments(row),
+        }
+    }
+
+    fn message_id(&self, row: usize) -> Option<u32> {
+        self.inner.message_id(row)
+    }
+
+    fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>> {
+        self.inner.non_verbose_payload(row)
+    }
+
+    fn control_message(&self, row: usize) -> Option<ControlMessage> {
+        self.inner.control_message(row)
+    }
+
+    fn source_location(&self, row: usize) -> Option<(&str, u32)> {
+        self.inner.source_location(row)
+    }
+
+    fn tags(&self, row: usize) -> Vec<&str> {
+        self.inner.tags(row)
+    }
+
+    fn privacy_level(&self, row: usize) -> Option<u8> {
+        self.inner.privacy_level(row)
+    }
+
+    fn unique_ecus(&self) -> Vec<&str> {
+        self.inner.unique_ecus()
+    }
+
+    fn unique_apids(&self) -> Vec<&str> {
+        self.apids.iter().map(String::as_str).collect()
+    }
+
+    fn unique_ctids(&self) -> Vec<&str> {
+        self.ctids.iter().map(String::as_str).collect()
+    }
+
+    fn append(&mut self, frames: &[u8]) -> Result<Vec<ParseError>> {
+        let first_row = self.len();
+        let errors = self.inner.append(frames)?;
+        self.collect_ids(first_row);
+        Ok(errors)
+    }
+
+    fn refresh(&mut self) -> Result<Vec<ParseError>> {
+        let first_row = self.len();
+        let errors = self.inner.refresh()?;
+        self.collect_ids(first_row);
+        Ok(errors)
+    }
+}
+
+impl fmt::Debug for FibexDlt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FibexDlt")
+            .field("frames", &self.database.len())
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::storage::STORAGE_HEADER_PATTERN;
+    use crate::dlt::writer::MessageBuilder;
+
+    fn fixture() -> FibexDatabase {
+        FibexDatabase::from_file(Path::new("tests/data/testfile_fibex.xml")).unwrap()
+    }
+
+    fn open(frames: &[Vec<u8>]) -> (tempfile::TempDir, FibexDlt) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("non_verbose.dlt");
+        std::fs::write(&path, frames.concat()).unwrap();
+        let (dlt, errors) = crate::dlt::open(vec![path]).unwrap();
+        assert!(errors.is_empty());
+        (dir, FibexDlt::new(dlt, Arc::new(fixture())))
+    }
+
+    /// v1 non-verbose frame without extended header: storage header, standard
+    /// header with ECU, then message ID and arguments.
+    fn v1_non_verbose(message_id: u32, arguments: &[u8], big_endian: bool) -> Vec<u8> {
+        let mut payload = if big_endian {
+            message_id.to_be_bytes().to_vec()
+        } else {
+            message_id.to_le_bytes().to_vec()
+        };
+        payload.extend_from_slice(arguments);
+
+        let mut frame = STORAGE_HEADER_PATTERN.to_vec();
+        frame.extend_from_slice(&1u32.to_le_bytes());
+        frame.extend_from_slice(&0i32.to_le_bytes());
+        frame.extend_from_slice(b"ECU1");
+        let htyp = 0x20 | 0x04 | if big_endian { 0x02 } else { 0x00 }; // v1 | WEID | MSBF
+        frame.push(htyp);
+        frame.push(7); // MCNT
+        frame.extend_from_slice(&((4 + 4 + payload.len()) as u16).to_be_bytes());
+        frame.extend_from_slice(b"ECU1");
+        frame.extend_from_slice(&payload);
+        frame
+    }
+
+    #[test]
+    fn parses_frames_in_sequence_order() {
+        let database = fixture();
+
+        assert_eq!(database.len(), 2);
+        let frame = database.frame(10).unwrap();
+        assert_eq!(frame.apid, "TEMP");
+        assert_eq!(frame.ctid, "SENS");
+        assert_eq!((frame.message_type, frame.message_type_info), (0, 3));
+        assert_eq!(
+            frame.items,
+            vec![
+                FrameItem::Text("Temperature:".into()),
+                FrameItem::Signal {
+                    name: None,
+                    kind: SignalType::Signed(DLT_TYLE_16BIT)
+                },
+                FrameItem::Text("state".into()),
+                FrameItem::Signal {
+                    name: None,
+                    kind: SignalType::String
+                },
+            ]
+        );
+        assert_eq!(
+            database.frame(11).unwrap().items[0],
+            FrameItem::Signal {
+                name: Some("speed".into()),
+                kind: SignalType::Unsigned(DLT_TYLE_32BIT)
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_references_to_unknown_signals() {
+        let xml = r#"<FIBEX><PDU ID="P"><SIGNAL-INSTANCE><SIGNAL-REF ID-REF="S_NOPE"/>
+            </SIGNAL-INSTANCE></PDU><FRAME ID="ID_1"><PDU-INSTANCE><PDU-REF ID-REF="P"/>
+            </PDU-INSTANCE></FRAME></FIBEX>"#;
+
+        let error = FibexDatabase::from_str(xml).unwrap_err();
+
+        assert!(error.to_string().contains("S_NOPE"));
+    }
+
+    #[test]
+    fn decodes_v1_non_verbose_messages() {
+        let mut arguments = (-215i16).to_le_bytes().to_vec();
+        arguments.extend_from_slice(&3u16.to_le_bytes());
+        arguments.extend_from_slice(b"ok\0");
+        let (_dir, dlt) = open(&[
+            v1_non_verbose(10, &arguments, false),
+            v1_non_verbose(99, &[1, 2], false),
+        ]);
+
+        assert_eq!(dlt.payload_text(0), "Temperature: -215 state ok");
+        assert_eq!(dlt.apid(0), "TEMP");
+        assert_eq!(dlt.ctid(0), "SENS");
+        assert_eq!(dlt.message_type_info(0), 3);
+        assert_eq!(dlt.unique_apids(), vec!["TEMP"]);
+
+        // Unknown message IDs keep the undecoded payload.
+        assert_eq!(dlt.apid(1), "");
+        assert_eq!(dlt.payload_text(1), dlt.inner.payload_text(1));
+    }
+
+    #[test]
+    fn decodes_big_endian_v1_and_stops_at_truncated_signal() {
+        let mut arguments = 120u32.to_be_bytes().to_vec();
+        arguments.push(1);
+        let (_dir, dlt) = open(&[
+            v1_non_verbose(11, &arguments, true),
+            v1_non_verbose(11, &[0, 0], true),
+        ]);
+
+        let decoded = dlt.payload_arguments(0);
+        assert_eq!(decoded[0].name.as_deref(), Some("speed"));
+        assert_eq!(decoded[0].value, Value::U32(120));
+        assert_eq!(decoded[1].value, Value::Bool(true));
+        assert_eq!(dlt.message_type(0), 1);
+        assert_eq!(dlt.message_type_info(0), 4);
+
+        assert!(dlt.payload_arguments(1).is_empty());
+    }
+
+    #[test]
+    fn decodes_v2_non_verbose_messages_by_msid() {
+        let mut arguments = 50u32.to_be_bytes().to_vec();
+        arguments.push(0);
+        let (_dir, dlt) = open(&[MessageBuilder::new()
+            .with_ecu("ECU2")
+            .with_non_verbose(11, &arguments)
+            .build(2)
+            .unwrap()]);
+
+        assert_eq!(dlt.payload_text(0), "50 false");
+        assert_eq!(dlt.apid(0), "CAR");
+        assert_eq!(dlt.ctid(0), "DRV");
+        assert_eq!(dlt.message_type(0), 1);
+    }
+}
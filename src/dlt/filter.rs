@@ -0,0 +1,545 @@
+use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use regex::{Regex, RegexBuilder};
+use std::cell::OnceCell;
+use std::path::Path;
+
+use crate::dlt::payload::{MESSAGE_TYPE, decode_message_type_info};
+use crate::dlt::store::DltStore;
+use crate::dlt::v1::protocol::{MESSAGE_TYPE_CONTROL, MESSAGE_TYPE_LOG};
+
+/// How a filter rule contributes to the outcome of a `FilterSet`.
+///
+/// Values follow the `<type>` element of dlt-viewer `.dlf` files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    /// A message must match at least one enabled positive rule.
+    Positive,
+    /// A message matching any enabled negative rule is dropped.
+    Negative,
+    /// Highlights messages in dlt-viewer; does not affect visibility.
+    Marker,
+}
+
+impl FilterKind {
+    fn from_dlf(value: u32) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Positive),
+            1 => Ok(Self::Negative),
+            2 => Ok(Self::Marker),
+            other => Err(anyhow!("unsupported filter type {other}")),
+        }
+    }
+}
+
+/// Text comparison used by a single filter field.
+#[derive(Debug, Clone)]
+pub enum TextMatch {
+    /// Whole-value comparison (ECU, APID, CTID without regex).
+    Exact { text: String, ignore_case: bool },
+    /// Substring search (header and payload text without regex).
+    Contains { text: String, ignore_case: bool },
+    /// Regular expression search.
+    Regex(Regex),
+}
+
+impl TextMatch {
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Self::Exact { text, ignore_case } => {
+                if *ignore_case {
+                    value.eq_ignore_ascii_case(text)
+                } else {
+                    value == text
+                }
+            }
+            Self::Contains { text, ignore_case } => {
+                if *ignore_case {
+                    value.to_lowercase().contains(&text.to_lowercase())
+                } else {
+                    value.contains(text.as_str())
+                }
+            }
+            Self::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// One filter entry, as defined by a `<filter>` element in a `.dlf` file.
+///
+/// Each populated field narrows the rule; a message matches the rule only when
+/// every populated field matches.
+#[derive(Debug, Clone)]
+pub struct FilterRule {
+    pub name: String,
+    pub kind: FilterKind,
+    pub enabled: bool,
+    pub ecu: Option<TextMatch>,
+    pub apid: Option<TextMatch>,
+    pub ctid: Option<TextMatch>,
+    pub header_text: Option<TextMatch>,
+    pub payload_text: Option<TextMatch>,
+    /// Only match control messages.
+    pub control_only: bool,
+    /// Only match log messages with MTIN <= this level (1=fatal .. 6=verbose).
+    pub log_level_max: Option<u8>,
+    /// Only match log messages with MTIN >= this level (1=fatal .. 6=verbose).
+    pub log_level_min: Option<u8>,
+}
+
+impl FilterRule {
+    fn matches(&self, message: &FilterMessage<'_>, payload: &OnceCell<String>) -> bool {
+        if let Some(ecu) = &self.ecu
+            && !ecu.is_match(message.ecu)
+        {
+            return false;
+        }
+        if let Some(apid) = &self.apid
+            && !apid.is_match(message.apid)
+        {
+            return false;
+        }
+        if let Some(ctid) = &self.ctid
+            && !ctid.is_match(message.ctid)
+        {
+            return false;
+        }
+        if self.control_only && message.message_type != MESSAGE_TYPE_CONTROL {
+            return false;
+        }
+        if let Some(max) = self.log_level_max
+            && (message.message_type != MESSAGE_TYPE_LOG || message.message_type_info > max)
+        {
+            return false;
+        }
+        if let Some(min) = self.log_level_min
+            && (message.message_type != MESSAGE_TYPE_LOG || message.message_type_info < min)
+        {
+            return false;
+        }
+        if let Some(header_text) = &self.header_text
+            && !header_text.is_match(&message.header_text())
+        {
+            return false;
+        }
+        if let Some(payload_text) = &self.payload_text
+            && !payload_text.is_match(payload.get_or_init(message.payload_text))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// The message fields a `FilterSet` is evaluated against.
+///
+/// The payload is produced on demand so rules that never look at it do not pay
+/// for decoding.
+pub struct FilterMessage<'a> {
+    pub ecu: &'a str,
+    pub apid: &'a str,
+    pub ctid: &'a str,
+    pub message_type: u8,
+    pub message_type_info: u8,
+    pub payload_text: &'a dyn Fn() -> String,
+}
+
+impl FilterMessage<'_> {
+    /// Header text searched by `<headertext>`: ECU, APID, CTID, type and type info.
+    fn header_text(&self) -> String {
+        let mstp = self.message_type as usize;
+        let msg_type = MESSAGE_TYPE.get(mstp).copied().unwrap_or("");
+        let type_info = decode_message_type_info(mstp, self.message_type_info as usize);
+        format!(
+            "{} {} {} {} {}",
+            self.ecu, self.apid, self.ctid, msg_type, type_info
+        )
+    }
+}
+
+/// An ordered list of filter rules with dlt-viewer semantics.
+///
+/// A message passes when it matches at least one enabled positive rule (or no
+/// positive rule is enabled) and matches no enabled negative rule. Marker rules
+/// and disabled rules are kept so they round-trip but never affect the result.
+#[derive(Debug, Clone, Default)]
+pub struct FilterSet {
+    rules: Vec<FilterRule>,
+}
+
+impl FilterSet {
+    pub fn new(rules: Vec<FilterRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Load a dlt-viewer `.dlf` filter file.
+    pub fn from_dlf_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read filter file {}", path.display()))?;
+        Self::from_dlf_str(&text)
+            .with_context(|| format!("failed to parse filter file {}", path.display()))
+    }
+
+    /// Parse the XML contents of a dlt-viewer `.dlf` filter file.
+    pub fn from_dlf_str(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut rules = Vec::new();
+        let mut current: Option<DlfFilterFields> = None;
+        let mut element = String::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => {
+                    let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                    if name == "filter" {
+                        current = Some(DlfFilterFields::default());
+                    }
+                    element = name;
+                }
+                Event::Text(text) => {
+                    if let Some(fields) = current.as_mut() {
+                        fields.set(&element, text.unescape()?.into_owned());
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(fields) = current.as_mut() {
+                        let value = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                        fields.set(&element, value);
+                    }
+                }
+                Event::End(end) => {
+                    if end.local_name().as_ref() == b"filter"
+                        && let Some(fields) = current.take()
+                    {
+                        rules.push(fields.into_rule()?);
+                    }
+                    element.clear();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate the filter set against one row of a message store.
+    pub fn matches_row(&self, dlt: &dyn DltStore, row: usize) -> bool {
+        self.matches(&FilterMessage {
+            ecu: dlt.ecu(row),
+            apid: dlt.apid(row),
+            ctid: dlt.ctid(row),
+            message_type: dlt.message_type(row),
+            message_type_info: dlt.message_type_info(row),
+            payload_text: &|| dlt.payload_text(row),
+        })
+    }
+
+    /// Evaluate the filter set against one message.
+    pub fn matches(&self, message: &FilterMessage<'_>) -> bool {
+        let payload = OnceCell::new();
+        let mut has_positive = false;
+        let mut positive_matched = false;
+
+        for rule in self.rules.iter().filter(|rule| rule.enabled) {
+            match rule.kind {
+                FilterKind::Positive => {
+                    has_positive = true;
+                    if !positive_matched && rule.matches(message, &payload) {
+                        positive_matched = true;
+                    }
+                }
+                FilterKind::Negative => {
+                    if rule.matches(message, &payload) {
+                        return false;
+                    }
+                }
+                FilterKind::Marker => {}
+            }
+        }
+
+        !has_positive || positive_matched
+    }
+}
+
+/// Raw `<filter>` element values as found in a `.dlf` file.
+#[derive(Default)]
+struct DlfFilterFields {
+    kind: Option<String>,
+    name: String,
+    ecu: String,
+    apid: String,
+    ctid: String,
+    header_text: String,
+    payload_text: String,
+    enable_filter: bool,
+    enable_regex: bool,
+    ignore_case: bool,
+    enable_ecu: bool,
+    enable_apid: bool,
+    enable_ctid: bool,
+    enable_header_text: bool,
+    enable_payload_text: bool,
+    enable_control_messages: bool,
+    enable_log_level_max: bool,
+    enable_log_level_min: bool,
+    log_level_max: Option<String>,
+    log_level_min: Option<String>,
+}
+
+impl DlfFilterFields {
+    fn set(&mut self, element: &str, value: String) {
+        let flag = value.trim() == "1";
+        match element {
+            "type" => self.kind = Some(value),
+            "name" => self.name = value,
+            "ecuid" => self.ecu = value,
+            "applicationid" => self.apid = value,
+            "contextid" => self.ctid = value,
+            "headertext" => self.header_text = value,
+            "payloadtext" => self.payload_text = value,
+            "enablefilter" => self.enable_filter = flag,
+            "enableregexp" | "enableregex" => self.enable_regex = flag,
+            "ignoreCase" | "enableignorecase" => self.ignore_case = flag,
+            "enableecuid" => self.enable_ecu = flag,
+            "enableapplicationid" => self.enable_apid = flag,
+            "enablecontextid" => self.enable_ctid = flag,
+            "enableheadertext" => self.enable_header_text = flag,
+            "enablepayloadtext" => self.enable_payload_text = flag,
+            "enablectrlmsgs" => self.enable_control_messages = flag,
+            "enableLogLevelMax" => self.enable_log_level_max = flag,
+            "enableLogLevelMin" => self.enable_log_level_min = flag,
+            "logLevelMax" => self.log_level_max = Some(value),
+            "logLevelMin" => self.log_level_min = Some(value),
+            _ => {}
+        }
+    }
+
+    fn into_rule(self) -> Result<FilterRule> {
+        let kind = match &self.kind {
+            Some(value) => FilterKind::from_dlf(
+                value
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid filter type {value:?}"))?,
+            )?,
+            None => FilterKind::Positive,
+        };
+
+        let ecu = self.text_match(self.enable_ecu, &self.ecu, true)?;
+        let apid = self.text_match(self.enable_apid, &self.apid, true)?;
+        let ctid = self.text_match(self.enable_ctid, &self.ctid, true)?;
+        let header_text = self.text_match(self.enable_header_text, &self.header_text, false)?;
+        let payload_text = self.text_match(self.enable_payload_text, &self.payload_text, false)?;
+        let log_level_max = parse_log_level(self.enable_log_level_max, &self.log_level_max)?;
+        let log_level_min = parse_log_level(self.enable_log_level_min, &self.log_level_min)?;
+
+        Ok(FilterRule {
+            name: self.name,
+            kind,
+            enabled: self.enable_filter,
+            ecu,
+            apid,
+            ctid,
+            header_text,
+            payload_text,
+            control_only: self.enable_control_messages,
+            log_level_max,
+            log_level_min,
+        })
+    }
+
+    fn text_match(&self, enabled: bool, text: &str, exact: bool) -> Result<Option<TextMatch>> {
+        if !enabled {
+            return Ok(None);
+        }
+
+        if self.enable_regex {
+            let regex = RegexBuilder::new(text)
+                .case_insensitive(self.ignore_case)
+                .build()
+                .with_context(|| format!("invalid regular expression {text:?}"))?;
+            return Ok(Some(TextMatch::Regex(regex)));
+        }
+
+        let text = text.to_string();
+        let ignore_case = self.ignore_case;
+        Ok(Some(if exact {
+            TextMatch::Exact { text, ignore_case }
+        } else {
+            TextMatch::Contains { text, ignore_case }
+        }))
+    }
+}
+
+fn parse_log_level(enabled: bool, value: &Option<String>) -> Result<Option<u8>> {
+    if !enabled {
+        return Ok(None);
+    }
+    let Some(value) = value else {
+        return Err(anyhow!("log level filter enabled without a level"));
+    };
+    let level = value
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid log level {value:?}"))?;
+    Ok(Some(level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dlf(filters: &[&str]) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<dltfilter>\n");
+        for filter in filters {
+            xml.push_str("<filter>");
+            xml.push_str(filter);
+            xml.push_str("</filter>\n");
+        }
+        xml.push_str("</dltfilter>\n");
+        xml
+    }
+
+    fn message<'a>(
+        ecu: &'a str,
+        apid: &'a str,
+        ctid: &'a str,
+        mstp: u8,
+        mtin: u8,
+        payload: &'a dyn Fn() -> String,
+    ) -> FilterMessage<'a> {
+        FilterMessage {
+            ecu,
+            apid,
+            ctid,
+            message_type: mstp,
+            message_type_info: mtin,
+            payload_text: payload,
+        }
+    }
+
+    #[test]
+    fn parses_dlt_viewer_filter_fields() {
+        let xml = dlf(&["<type>1</type><name>noise</name><ecuid>ECU1</ecuid>\
+             <applicationid>APP</applicationid><contextid>CON</contextid>\
+             <payloadtext>a &amp; b</payloadtext><enablefilter>1</enablefilter>\
+             <enableecuid>1</enableecuid><enableapplicationid>0</enableapplicationid>\
+             <enablecontextid>1</enablecontextid><enablepayloadtext>1</enablepayloadtext>\
+             <enableLogLevelMax>1</enableLogLevelMax><logLevelMax>3</logLevelMax>"]);
+
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+        assert_eq!(set.rules().len(), 1);
+
+        let rule = &set.rules()[0];
+        assert_eq!(rule.name, "noise");
+        assert_eq!(rule.kind, FilterKind::Negative);
+        assert!(rule.enabled);
+        assert!(rule.ecu.is_some());
+        assert!(rule.apid.is_none());
+        assert!(rule.ctid.is_some());
+        assert!(matches!(
+            &rule.payload_text,
+            Some(TextMatch::Contains { text, .. }) if text == "a & b"
+        ));
+        assert_eq!(rule.log_level_max, Some(3));
+        assert_eq!(rule.log_level_min, None);
+    }
+
+    #[test]
+    fn positive_rules_are_alternatives_and_negative_rules_exclude() {
+        let xml = dlf(&[
+            "<type>0</type><applicationid>APP1</applicationid>\
+             <enablefilter>1</enablefilter><enableapplicationid>1</enableapplicationid>",
+            "<type>0</type><applicationid>APP2</applicationid>\
+             <enablefilter>1</enablefilter><enableapplicationid>1</enableapplicationid>",
+            "<type>1</type><payloadtext>heartbeat</payloadtext>\
+             <enablefilter>1</enablefilter><enablepayloadtext>1</enablepayloadtext>",
+        ]);
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+
+        let hello = || "hello".to_string();
+        let heartbeat = || "heartbeat ok".to_string();
+        assert!(set.matches(&message("ECU", "APP1", "CTX", 0, 4, &hello)));
+        assert!(set.matches(&message("ECU", "APP2", "CTX", 0, 4, &hello)));
+        assert!(!set.matches(&message("ECU", "APP3", "CTX", 0, 4, &hello)));
+        assert!(!set.matches(&message("ECU", "APP1", "CTX", 0, 4, &heartbeat)));
+    }
+
+    #[test]
+    fn exact_id_match_does_not_accept_prefixes() {
+        let xml = dlf(&["<applicationid>SYS</applicationid>\
+             <enablefilter>1</enablefilter><enableapplicationid>1</enableapplicationid>"]);
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+
+        let payload = String::new;
+        assert!(set.matches(&message("ECU", "SYS", "CTX", 0, 4, &payload)));
+        assert!(!set.matches(&message("ECU", "SYSM", "CTX", 0, 4, &payload)));
+    }
+
+    #[test]
+    fn regex_and_ignore_case_apply_to_payload() {
+        let xml = dlf(&[
+            "<payloadtext>timeout \\d+ms</payloadtext><enablefilter>1</enablefilter>\
+             <enablepayloadtext>1</enablepayloadtext><enableregexp>1</enableregexp>\
+             <ignoreCase>1</ignoreCase>",
+        ]);
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+
+        let hit = || "TIMEOUT 250ms on bus".to_string();
+        let miss = || "timeout soon".to_string();
+        assert!(set.matches(&message("ECU", "APP", "CTX", 0, 4, &hit)));
+        assert!(!set.matches(&message("ECU", "APP", "CTX", 0, 4, &miss)));
+    }
+
+    #[test]
+    fn log_level_bounds_only_match_log_messages() {
+        let xml = dlf(&["<enablefilter>1</enablefilter>\
+             <enableLogLevelMax>1</enableLogLevelMax><logLevelMax>3</logLevelMax>\
+             <enableLogLevelMin>1</enableLogLevelMin><logLevelMin>2</logLevelMin>"]);
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+
+        let payload = String::new;
+        assert!(!set.matches(&message("ECU", "APP", "CTX", 0, 1, &payload)));
+        assert!(set.matches(&message("ECU", "APP", "CTX", 0, 2, &payload)));
+        assert!(set.matches(&message("ECU", "APP", "CTX", 0, 3, &payload)));
+        assert!(!set.matches(&message("ECU", "APP", "CTX", 0, 4, &payload)));
+        assert!(!set.matches(&message("ECU", "APP", "CTX", 3, 2, &payload)));
+    }
+
+    #[test]
+    fn disabled_and_marker_rules_do_not_filter() {
+        let xml = dlf(&[
+            "<type>0</type><applicationid>APP1</applicationid>\
+             <enablefilter>0</enablefilter><enableapplicationid>1</enableapplicationid>",
+            "<type>2</type><applicationid>APP1</applicationid>\
+             <enablefilter>1</enablefilter><enableapplicationid>1</enableapplicationid>",
+        ]);
+        let set = FilterSet::from_dlf_str(&xml).unwrap();
+
+        let payload = String::new;
+        assert_eq!(set.rules().len(), 2);
+        assert!(set.matches(&message("ECU", "APP9", "CTX", 0, 4, &payload)));
+    }
+
+    #[test]
+    fn invalid_regex_is_reported() {
+        let xml = dlf(&[
+            "<payloadtext>(unclosed</payloadtext><enablefilter>1</enablefilter>\
+             <enablepayloadtext>1</enablepayloadtext><enableregexp>1</enableregexp>",
+        ]);
+        assert!(FilterSet::from_dlf_str(&xml).is_err());
+    }
+}
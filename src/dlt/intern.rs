@@ -2,37 +2,70 @@ use std::collections::HashMap;
 
 /// Intern table for deduplicating short strings (APID, CTID, ECU).
 ///
-/// ID 0 is reserved for "absent" (empty string).
-pub struct InternTable {
-    map: HashMap<String, u16>,
+/// ID 0 is reserved for "absent" (empty string). Free-form strings such as
+/// v2 source file names and tags use a table with `u32` IDs, because their
+/// number is not bounded by the ID format.
+pub struct InternTable<Id = u16> {
+    map: HashMap<String, Id>,
     strings: Vec<String>,
 }
 
-impl InternTable {
-    pub fn new() -> Self {
+/// ID type of an `InternTable`.
+pub trait InternId: Copy + TryFrom<usize> {
+    fn index(self) -> usize;
+}
+
+impl InternId for u16 {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl InternId for u32 {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl<Id: InternId> Default for InternTable<Id> {
+    fn default() -> Self {
         let mut table = Self {
             map: HashMap::new(),
-            strings: vec![String::new()], // ID 0 = absent
+            strings: Vec::new(),
         };
-        table.map.insert(String::new(), 0);
+        table.insert(""); // ID 0 = absent
         table
     }
+}
 
+impl InternTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<Id: InternId> InternTable<Id> {
     /// Insert a string and return its interned ID.
     /// Returns the existing ID if the string was already interned.
-    pub fn insert(&mut self, s: &str) -> u16 {
+    ///
+    /// # Panics
+    ///
+    /// If the table already holds as many strings as `Id` can number.
+    pub fn insert(&mut self, s: &str) -> Id {
         if let Some(&id) = self.map.get(s) {
             return id;
         }
-        let id = self.strings.len() as u16;
+        let Ok(id) = Id::try_from(self.strings.len()) else {
+            panic!("intern table is full at {} strings", self.strings.len());
+        };
         self.strings.push(s.to_string());
         self.map.insert(s.to_string(), id);
         id
     }
 
     /// Resolve an interned ID back to its string.
-    pub fn resolve(&self, id: u16) -> &str {
-        &self.strings[id as usize]
+    pub fn resolve(&self, id: Id) -> &str {
+        &self.strings[id.index()]
     }
 }
 
@@ -82,4 +115,25 @@ mod tests {
         let id = table.insert("ECU1");
         assert_eq!(id, 1);
     }
+
+    #[test]
+    fn wide_ids_continue_past_the_u16_range() {
+        let mut table = InternTable::<u32>::default();
+        let names: Vec<String> = (0..70_000).map(|n| format!("src/file_{n}.c")).collect();
+        let ids: Vec<u32> = names.iter().map(|name| table.insert(name)).collect();
+
+        assert_eq!(ids[69_999], 70_000);
+        for (name, id) in names.iter().zip(ids) {
+            assert_eq!(table.resolve(id), name);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "intern table is full")]
+    fn short_ids_refuse_to_wrap() {
+        let mut table = InternTable::new();
+        for n in 0..=u16::MAX as u32 {
+            table.insert(&n.to_string());
+        }
+    }
 }
@@ -0,0 +1,513 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use crate::dlt::control::ControlMessage;
+use crate::dlt::store::{DltStore, NonVerbosePayload};
+use crate::dlt::verbose::Argument;
+
+/// Row order used when combining several files into one view.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Rows in path order, then on-disk order within each file.
+    #[default]
+    Concatenate,
+    /// k-way merge of the files by storage header timestamp.
+    StorageTimestamp,
+    /// k-way merge of the files by message timestamp, shifted per ECU.
+    ///
+    /// Message timestamps count from each ECU's own start-up, so the offset
+    /// (in nanoseconds, keyed by ECU ID) aligns the ECU clocks with each
+    /// other. ECUs without an entry use an offset of zero.
+    MessageTimestamp {
+        ecu_offsets_ns: HashMap<String, i64>,
+    },
+}
+
+/// Parse an `ECU=SECONDS` clock offset into an ECU ID and a nanosecond offset.
+pub fn parse_ecu_offset(value: &str) -> Result<(String, i64), String> {
+    let (ecu, seconds) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ECU=SECONDS, got {value:?}"))?;
+    let seconds: f64 = seconds
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid offset {seconds:?} for ECU {ecu:?}"))?;
+    Ok((ecu.trim().to_string(), (seconds * 1e9).round() as i64))
+}
+
+/// Rows from one or more stores presented as a single store in `MergeMode` order.
+///
+/// Each source file is treated as one run that keeps its on-disk order, also
+/// when the file switches protocol version mid-stream. Timestamp modes merge
+/// the runs by their sort key, falling back to file index and byte offset so
+/// rows with equal keys keep a deterministic order.
+pub struct MergedDlt {
+    parts: Vec<Box<dyn DltStore>>,
+    rows: Vec<(u16, u32)>, // (part_index, row within part)
+}
+
+impl MergedDlt {
+    pub fn new(parts: Vec<Box<dyn DltStore>>, mode: &MergeMode) -> Self {
+        let mut runs: Vec<Vec<(u16, u32)>> = Vec::new();
+        for (part_idx, part) in parts.iter().enumerate() {
+            for row in 0..part.len() {
+                let file_idx = part.file_index(row) as usize;
+                if runs.len() <= file_idx {
+                    runs.resize_with(file_idx + 1, Vec::new);
+                }
+                runs[file_idx].push((part_idx as u16, row as u32));
+            }
+        }
+
+        // Rows of a file parsed by different version stores interleave by offset.
+        if parts.len() > 1 {
+            for run in &mut runs {
+                run.sort_by_key(|&(part_idx, row)| {
+                    parts[part_idx as usize].byte_offset(row as usize)
+                });
+            }
+        }
+
+        let rows = match mode {
+            MergeMode::Concatenate => runs.into_iter().flatten().collect(),
+            MergeMode::StorageTimestamp => merge_runs(&parts, runs, |part, row| {
+                part.storage_timestamp_ns(row) as i128
+            }),
+            MergeMode::MessageTimestamp { ecu_offsets_ns } => {
+                merge_runs(&parts, runs, |part, row| {
+                    let offset = ecu_offsets_ns.get(part.ecu(row)).copied().unwrap_or(0);
+                    part.message_timestamp_ns(row) as i128 + offset as i128
+                })
+            }
+        };
+
+        Self { parts, rows }
+    }
+
+    fn locate(&self, row: usize) -> (&dyn DltStore, usize) {
+        let (part_idx, part_row) = self.rows[row];
+        (self.parts[part_idx as usize].as_ref(), part_row as usize)
+    }
+
+    fn unique_strings<'a>(
+        &'a self,
+        column: impl Fn(&'a dyn DltStore) -> Vec<&'a str>,
+    ) -> Vec<&'a str> {
+        let mut result: Vec<&str> = self
+            .parts
+            .iter()
+            .flat_map(|part| column(part.as_ref()))
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+}
+
+/// k-way merge of per-file runs, each already in on-disk order.
+fn merge_runs(
+    parts: &[Box<dyn DltStore>],
+    runs: Vec<Vec<(u16, u32)>>,
+    key: impl Fn(&dyn DltStore, usize) -> i128,
+) -> Vec<(u16, u32)> {
+    let sort_key = |(part_idx, row): (u16, u32)| {
+        let part = parts[part_idx as usize].as_ref();
+        let row = row as usize;
+        (key(part, row), part.file_index(row), part.byte_offset(row))
+    };
+
+    let total = runs.iter().map(Vec::len).sum();
+    let mut merged = Vec::with_capacity(total);
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for (run_idx, run) in runs.iter().enumerate() {
+        if let Some(&first) = run.first() {
+            heap.push(Reverse((sort_key(first), run_idx, 0usize)));
+        }
+    }
+
+    while let Some(Reverse((_, run_idx, pos))) = heap.pop() {
+        let run = &runs[run_idx];
+        merged.push(run[pos]);
+        if let Some(&next) = run.get(pos + 1) {
+            heap.push(Reverse((sort_key(next), run_idx, pos + 1)));
+        }
+    }
+
+    merged
+}
+
+impl DltStore for MergedDlt {
+    fn protocol_versions(&self) -> Vec<u8> {
+        let mut versions: Vec<u8> = self
+            .parts
+            .iter()
+            .flat_map(|part| part.protocol_versions())
+            .collect();
+        versions.sort_unstable();
+        versions.dedup();
+        versions
+    }
+
+    fn protocol_version(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.protocol_version(row)
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn ecu(&self, row: usize) -> &str {
+        let (part, row) = self.locate(row);
+        part.ecu(row)
+    }
+
+    fn apid(&self, row: usize) -> &str {
+        let (part, row) = self.locate(row);
+        part.apid(row)
+    }
+
+    fn ctid(&self, row: usize) -> &str {
+        let (part, row) = self.locate(row);
+        part.ctid(row)
+    }
+
+    fn session_id(&self, row: usize) -> u32 {
+        let (part, row) = self.locate(row);
+        part.session_id(row)
+    }
+
+    fn file_index(&self, row: usize) -> u16 {
+        let (part, row) = self.locate(row);
+        part.file_index(row)
+    }
+
+    fn byte_offset(&self, row: usize) -> u64 {
+        let (part, row) = self.locate(row);
+        part.byte_offset(row)
+    }
+
+    fn storage_timestamp_ns(&self, row: usize) -> u64 {
+        let (part, row) = self.locate(row);
+        part.storage_timestamp_ns(row)
+    }
+
+    fn message_timestamp_ns(&self, row: usize) -> u64 {
+        let (part, row) = self.locate(row);
+        part.message_timestamp_ns(row)
+    }
+
+    fn message_type(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.message_type(row)
+    }
+
+    fn message_type_info(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.message_type_info(row)
+    }
+
+    fn payload_raw(&self, row: usize) -> &[u8] {
+        let (part, row) = self.locate(row);
+        part.payload_raw(row)
+    }
+
+    fn frame_raw(&self, row: usize) -> &[u8] {
+        let (part, row) = self.locate(row);
+        part.frame_raw(row)
+    }
+
+    fn frames_raw(&self, row: usize) -> Vec<&[u8]> {
+        let (part, row) = self.locate(row);
+        part.frames_raw(row)
+    }
+
+    fn payload_arguments(&self, row: usize) -> Vec<Argument> {
+        let (part, row) = self.locate(row);
+        part.payload_arguments(row)
+    }
+
+    fn message_id(&self, row: usize) -> Option<u32> {
+        let (part, row) = self.locate(row);
+        part.message_id(row)
+    }
+
+    fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>> {
+        let (part, row) = self.locate(row);
+        part.non_verbose_payload(row)
+    }
+
+    fn control_message(&self, row: usize) -> Option<ControlMessage> {
+        let (part, row) = self.locate(row);
+        part.control_message(row)
+    }
+
+    fn source_location(&self, row: usize) -> Option<(&str, u32)> {
+        let (part, row) = self.locate(row);
+        part.source_location(row)
+    }
+
+    fn tags(&self, row: usize) -> Vec<&str> {
+        let (part, row) = self.locate(row);
+        part.tags(row)
+    }
+
+    fn privacy_level(&self, row: usize) -> Option<u8> {
+        let (part, row) = self.locate(row);
+        part.privacy_level(row)
+    }
+
+    fn last_message_counter(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.last_message_counter(row)
+    }
+
+    fn message_counter(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.message_counter(row)
+    }
+
+    fn is_verbose(&self, row: usize) -> bool {
+        let (part, row) = self.locate(row);
+        part.is_verbose(row)
+    }
+
+    fn argument_count(&self, row: usize) -> u8 {
+        let (part, row) = self.locate(row);
+        part.argument_count(row)
+    }
+
+    fn payload_text(&self, row: usize) -> String {
+        let (part, row) = self.locate(row);
+        part.payload_text(row)
+    }
+
+    fn unique_ecus(&self) -> Vec<&str> {
+        self.unique_strings(|part| part.unique_ecus())
+    }
+
+    fn unique_apids(&self) -> Vec<&str> {
+        self.unique_strings(|part| part.unique_apids())
+    }
+
+    fn unique_ctids(&self) -> Vec<&str> {
+        self.unique_strings(|part| part.unique_ctids())
+    }
+}
+
+impl fmt::Debug for MergedDlt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MergedDlt")
+            .field("messages", &self.len())
+            .field("parts", &self.parts)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::error::ParseErrorKind;
+    use crate::dlt::writer::MessageBuilder;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    /// Minimal v1 frame: storage header + standard header with ECU, no payload.
+    fn v1_frame(seconds: u32, ecu: &[u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(b"DLT\x01");
+        frame.extend_from_slice(&seconds.to_le_bytes());
+        frame.extend_from_slice(&0u32.to_le_bytes());
+        frame.extend_from_slice(ecu);
+        frame.push((1 << 5) | 0x04); // VERS=1, WEID
+        frame.push(0);
+        frame.extend_from_slice(&8u16.to_be_bytes());
+        frame.extend_from_slice(ecu);
+        frame
+    }
+
+    fn v2_frame(seconds: u32, payload: &str) -> Vec<u8> {
+        MessageBuilder::new()
+            .with_storage_timestamp(seconds, 0)
+            .with_ecu("ECU2")
+            .with_apid("APP2")
+            .with_ctid("CTX2")
+            .with_verbose_string(payload)
+            .build(2)
+            .unwrap()
+    }
+
+    fn write_file(dir: &tempfile::TempDir, name: &str, frames: &[Vec<u8>]) -> PathBuf {
+        let path = dir.path().join(name);
+        let mut f = std::fs::File::create(&path).unwrap();
+        for frame in frames {
+            f.write_all(frame).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn open_mixed_version_files_interleaves_by_storage_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let v1_path = write_file(
+            &dir,
+            "v1.dlt",
+            &[v1_frame(10, b"ECU1"), v1_frame(30, b"ECU1")],
+        );
+        let v2_path = write_file(
+            &dir,
+            "v2.dlt",
+            &[v2_frame(20, "middle"), v2_frame(40, "last")],
+        );
+
+        let (dlt, errors) = crate::dlt::open(vec![v1_path, v2_path]).unwrap();
+
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        assert_eq!(dlt.protocol_versions(), vec![1, 2]);
+        assert_eq!(dlt.len(), 4);
+
+        let versions: Vec<u8> = (0..dlt.len())
+            .map(|row| dlt.protocol_version(row))
+            .collect();
+        assert_eq!(versions, vec![1, 2, 1, 2]);
+        assert_eq!(dlt.file_index(1), 1);
+        assert_eq!(dlt.payload_text(1), "middle");
+        assert_eq!(dlt.ecu(2), "ECU1");
+        assert_eq!(dlt.unique_ecus(), vec!["ECU1", "ECU2"]);
+    }
+
+    #[test]
+    fn open_file_switching_version_mid_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = v1_frame(1, b"ECU1");
+        let second = v2_frame(2, "switched");
+        let third = v1_frame(3, b"ECU1");
+        let second_offset = first.len() as u64;
+        let path = write_file(&dir, "switch.dlt", &[first, second, third]);
+
+        let (dlt, errors) = crate::dlt::open(vec![path]).unwrap();
+
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        assert_eq!(dlt.len(), 3);
+        assert_eq!(dlt.protocol_version(0), 1);
+        assert_eq!(dlt.protocol_version(1), 2);
+        assert_eq!(dlt.protocol_version(2), 1);
+        assert_eq!(dlt.byte_offset(0), 0);
+        assert_eq!(dlt.byte_offset(1), second_offset);
+        assert_eq!(dlt.payload_text(1), "switched");
+    }
+
+    #[test]
+    fn merged_store_keeps_unrelated_parse_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut truncated = v1_frame(5, b"ECU1");
+        truncated.truncate(truncated.len() - 2);
+        let path = write_file(
+            &dir,
+            "mixed.dlt",
+            &[v1_frame(1, b"ECU1"), v2_frame(2, "x"), truncated],
+        );
+
+        let (dlt, errors) = crate::dlt::open(vec![path]).unwrap();
+
+        assert_eq!(dlt.len(), 2);
+        assert!(!errors.is_empty());
+        assert!(
+            errors
+                .iter()
+                .all(|error| !matches!(error.kind, ParseErrorKind::InvalidVersion { .. }))
+        );
+    }
+
+    fn v2_timed_frame(seconds: u32, ecu: &str, timestamp_ns: u64, payload: &str) -> Vec<u8> {
+        MessageBuilder::new()
+            .with_storage_timestamp(seconds, 0)
+            .with_ecu(ecu)
+            .with_apid("APP2")
+            .with_ctid("CTX2")
+            .with_timestamp_ns(timestamp_ns)
+            .with_verbose_string(payload)
+            .build(2)
+            .unwrap()
+    }
+
+    fn payloads(dlt: &dyn DltStore) -> Vec<String> {
+        (0..dlt.len()).map(|row| dlt.payload_text(row)).collect()
+    }
+
+    #[test]
+    fn storage_merge_interleaves_files_and_keeps_file_order() {
+        let dir = tempfile::tempdir().unwrap();
+        // b2 is stamped earlier than b1 but must stay after it within its file.
+        let a = write_file(&dir, "a.dlt", &[v2_frame(10, "a1"), v2_frame(30, "a2")]);
+        let b = write_file(
+            &dir,
+            "b.dlt",
+            &[v2_frame(20, "b1"), v2_frame(15, "b2"), v2_frame(40, "b3")],
+        );
+
+        let (concat, _) =
+            crate::dlt::open_with_merge(vec![a.clone(), b.clone()], Some(&MergeMode::Concatenate))
+                .unwrap();
+        assert_eq!(payloads(concat.as_ref()), ["a1", "a2", "b1", "b2", "b3"]);
+
+        let (merged, errors) =
+            crate::dlt::open_with_merge(vec![a, b], Some(&MergeMode::StorageTimestamp)).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(payloads(merged.as_ref()), ["a1", "b1", "b2", "a2", "b3"]);
+    }
+
+    #[test]
+    fn message_merge_applies_per_ecu_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_file(
+            &dir,
+            "a.dlt",
+            &[
+                v2_timed_frame(1, "ECUA", 1_000, "a1"),
+                v2_timed_frame(1, "ECUA", 3_000, "a2"),
+            ],
+        );
+        let b = write_file(
+            &dir,
+            "b.dlt",
+            &[
+                v2_timed_frame(1, "ECUB", 500, "b1"),
+                v2_timed_frame(1, "ECUB", 1_500, "b2"),
+            ],
+        );
+
+        let (merged, _) = crate::dlt::open_with_merge(
+            vec![a.clone(), b.clone()],
+            Some(&MergeMode::MessageTimestamp {
+                ecu_offsets_ns: HashMap::new(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(payloads(merged.as_ref()), ["b1", "a1", "b2", "a2"]);
+
+        let (shifted, _) = crate::dlt::open_with_merge(
+            vec![a, b],
+            Some(&MergeMode::MessageTimestamp {
+                ecu_offsets_ns: HashMap::from([("ECUB".to_string(), 2_000)]),
+            }),
+        )
+        .unwrap();
+        assert_eq!(payloads(shifted.as_ref()), ["a1", "b1", "a2", "b2"]);
+    }
+
+    #[test]
+    fn parse_ecu_offset_accepts_signed_seconds() {
+        assert_eq!(
+            parse_ecu_offset("ECU1=1.5"),
+            Ok(("ECU1".to_string(), 1_500_000_000))
+        );
+        assert_eq!(
+            parse_ecu_offset("ECU2=-0.25"),
+            Ok(("ECU2".to_string(), -250_000_000))
+        );
+        assert!(parse_ecu_offset("ECU1").is_err());
+        assert!(parse_ecu_offset("ECU1=soon").is_err());
+    }
+}
@@ -1,16 +1,42 @@
+pub mod control;
+pub mod convert;
+pub mod counter;
 pub mod error;
+pub mod export;
+pub mod fibex;
+pub mod filter;
 pub mod intern;
+pub mod merged;
 pub mod payload;
+mod source;
 pub mod storage;
+pub mod store;
+pub mod stream;
+pub mod time_window;
 pub mod v1;
 pub mod v2;
+pub mod verbose;
+pub mod writer;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
+use crate::dlt::error::{ParseError, ParseErrorKind};
+use crate::dlt::merged::{MergeMode, MergedDlt};
 use crate::dlt::storage::{STORAGE_HEADER_PATTERN, STORAGE_HEADER_SIZE};
+use crate::dlt::store::DltStore;
+
+/// Protocol versions `open` can parse.
+const SUPPORTED_VERSIONS: [u8; 2] = [1, 2];
+
+/// How often `wait_for_follow_version` looks at a file that is too short.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 /// Detect the DLT protocol version from the first message in a file.
 ///
@@ -18,14 +44,194 @@ use crate::dlt::storage::{STORAGE_HEADER_PATTERN, STORAGE_HEADER_SIZE};
 /// and extracts the version from bits 5-7.  Returns 1 for v1 files and
 /// 2 for v2 files.
 pub fn detect_version(path: &PathBuf) -> Result<u8> {
-    let mut file = File::open(path)?;
+    peek_version(path)?.ok_or_else(|| anyhow!("{} is too short for a DLT message", path.display()))
+}
+
+/// Like `detect_version`, but `None` while the file is too short to tell.
+fn peek_version(path: &PathBuf) -> Result<Option<u8>> {
     // Read storage header plus the first message header byte (HTYP).
-    let mut buf = [0u8; STORAGE_HEADER_SIZE + 1];
-    file.read_exact(&mut buf)?;
-    if &buf[0..4] != STORAGE_HEADER_PATTERN {
+    let mut buf = Vec::with_capacity(STORAGE_HEADER_SIZE + 1);
+    File::open(path)?
+        .take((STORAGE_HEADER_SIZE + 1) as u64)
+        .read_to_end(&mut buf)?;
+    let marker = &buf[..buf.len().min(STORAGE_HEADER_PATTERN.len())];
+    if !STORAGE_HEADER_PATTERN.starts_with(marker) {
         anyhow::bail!("Not a DLT file: missing DLT\\x01 marker");
     }
-    let htyp = buf[STORAGE_HEADER_SIZE];
+    let Some(&htyp) = buf.get(STORAGE_HEADER_SIZE) else {
+        return Ok(None);
+    };
     let version = (htyp >> 5) & 0x07;
-    Ok(version)
+    Ok(Some(version))
+}
+
+/// The protocol version of files to follow with `open_following`.
+///
+/// Files that are too short to tell, such as one `dlt-receive` has just
+/// created, are polled until their first storage header and HTYP byte are
+/// written. `waiting` is called once for each file that has to be waited
+/// for, and setting `stop` ends the wait with an error. All files must hold
+/// the same version.
+pub fn wait_for_follow_version(
+    paths: &[PathBuf],
+    stop: &AtomicBool,
+    mut waiting: impl FnMut(&Path),
+) -> Result<u8> {
+    let mut version = None;
+    for path in paths {
+        let mut waited = false;
+        let found = loop {
+            if let Some(found) = peek_version(path)? {
+                break found;
+            }
+            if stop.load(Ordering::Relaxed) {
+                return Err(anyhow!(
+                    "Stopped waiting for a first message in {}",
+                    path.display()
+                ));
+            }
+            if !waited {
+                waiting(path);
+                waited = true;
+            }
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+        };
+        match version {
+            None => version = Some(found),
+            Some(version) if version != found => {
+                return Err(anyhow!(
+                    "Cannot follow files of different DLT versions: {} is not v{}",
+                    path.display(),
+                    version
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    version.ok_or_else(|| anyhow!("No DLT paths"))
+}
+
+/// Open one or more DLT files as a version-agnostic store.
+///
+/// Files may mix protocol versions, including files that switch version
+/// mid-stream. Single-version sets keep path order; mixed sets are merged by
+/// storage timestamp. Use `open_with_merge` to choose the row order.
+pub fn open(paths: Vec<PathBuf>) -> Result<(Box<dyn DltStore>, Vec<ParseError>)> {
+    open_with_merge(paths, None)
+}
+
+/// Open one or more DLT files, ordering rows according to `merge`.
+///
+/// Each version present is parsed by its own store and combined into a
+/// `MergedDlt` when needed. `None` keeps path order for single-version sets
+/// and merges mixed-version sets by storage timestamp.
+pub fn open_with_merge(
+    paths: Vec<PathBuf>,
+    merge: Option<&MergeMode>,
+) -> Result<(Box<dyn DltStore>, Vec<ParseError>)> {
+    if paths.is_empty() {
+        return Err(anyhow!("No DLT paths"));
+    }
+
+    let mut pending = Vec::new();
+    for path in &paths {
+        let version = detect_version(path)?;
+        if !pending.contains(&version) {
+            pending.push(version);
+        }
+    }
+
+    let mut parts: Vec<(u8, Box<dyn DltStore>, Vec<ParseError>)> = Vec::new();
+    while let Some(version) = pending.pop() {
+        let (store, errors) = open_version(version, paths.clone())?;
+        for error in &errors {
+            if let ParseErrorKind::InvalidVersion { found } = error.kind
+                && SUPPORTED_VERSIONS.contains(&found)
+                && !pending.contains(&found)
+                && !parts.iter().any(|(opened, _, _)| *opened == found)
+            {
+                pending.push(found);
+            }
+        }
+        parts.push((version, store, errors));
+    }
+
+    if parts.len() == 1 {
+        let (_, store, errors) = parts.remove(0);
+        return match merge {
+            None | Some(MergeMode::Concatenate) => Ok((store, errors)),
+            Some(mode) => Ok((Box::new(MergedDlt::new(vec![store], mode)), errors)),
+        };
+    }
+
+    // A frame rejected by one version's scanner is expected when another
+    // version's scanner parsed it successfully.
+    let parsed: HashSet<(u16, u64)> = parts
+        .iter()
+        .flat_map(|(_, store, _)| {
+            (0..store.len()).map(|row| (store.file_index(row), store.byte_offset(row)))
+        })
+        .collect();
+
+    let mut stores = Vec::with_capacity(parts.len());
+    let mut errors = Vec::new();
+    for (_, store, part_errors) in parts {
+        stores.push(store);
+        errors.extend(part_errors.into_iter().filter(|error| {
+            !(matches!(error.kind, ParseErrorKind::InvalidVersion { .. })
+                && parsed.contains(&(error.file_index, error.byte_offset)))
+        }));
+    }
+    errors.sort_by_key(|error| (error.file_index, error.byte_offset));
+    errors.dedup();
+
+    let mode = merge.cloned().unwrap_or(MergeMode::StorageTimestamp);
+    Ok((Box::new(MergedDlt::new(stores, &mode)), errors))
+}
+
+/// Open DLT files that are still being written, e.g. by `dlt-receive`.
+///
+/// All files hold protocol `version`, as found by `wait_for_follow_version`;
+/// rows are kept in path order and `DltStore::refresh` picks up bytes
+/// appended later.
+pub fn open_following(
+    paths: Vec<PathBuf>,
+    version: u8,
+) -> Result<(Box<dyn DltStore>, Vec<ParseError>)> {
+    match version {
+        1 => {
+            let (dlt, errors) = v1::Dlt::open_following(paths)?;
+            Ok((Box::new(dlt), errors))
+        }
+        2 => {
+            let (dlt, errors) = v2::Dlt::open_following(paths)?;
+            Ok((Box::new(dlt), errors))
+        }
+        other => Err(anyhow!("Unsupported DLT version: {}", other)),
+    }
+}
+
+/// An empty store for a live stream of `version` messages.
+///
+/// Rows are added with `DltStore::append` as stored frames arrive.
+pub fn open_stream(version: u8) -> Result<Box<dyn DltStore>> {
+    match version {
+        1 => Ok(Box::new(v1::Dlt::stream())),
+        2 => Ok(Box::new(v2::Dlt::stream())),
+        other => Err(anyhow!("Unsupported DLT version: {}", other)),
+    }
+}
+
+fn open_version(version: u8, paths: Vec<PathBuf>) -> Result<(Box<dyn DltStore>, Vec<ParseError>)> {
+    match version {
+        1 => {
+            let (dlt, errors) = v1::Dlt::open(paths)?;
+            Ok((Box::new(dlt), errors))
+        }
+        2 => {
+            let (dlt, errors) = v2::Dlt::open(paths)?;
+            Ok((Box::new(dlt), errors))
+        }
+        other => Err(anyhow!("Unsupported DLT version: {}", other)),
+    }
 }
@@ -0,0 +1,166 @@
+use anyhow::{Result, bail};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Bytes backing one source of a columnar store.
+///
+/// Files are memory-mapped, or read into memory while they are followed;
+/// live streams accumulate their frames in an owned buffer that grows as new
+/// data arrives.
+pub(crate) enum Source {
+    Mapped(MappedFile),
+    Buffer(Vec<u8>),
+}
+
+impl Deref for Source {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Source::Mapped(mapped) => mapped.data(),
+            Source::Buffer(buffer) => buffer,
+        }
+    }
+}
+
+/// A file whose bytes can be refreshed when it grows.
+pub(crate) struct MappedFile {
+    path: PathBuf,
+    file: File,
+    bytes: FileBytes,
+    /// Offset where scanning resumes once more bytes are appended.
+    pub(crate) resume_offset: usize,
+}
+
+enum FileBytes {
+    Mapped(Mmap),
+    /// A followed file may be truncated under a live mapping, for example
+    /// by logrotate's `copytruncate`, and the next row access would then
+    /// fault. Followed files are read instead, so truncation is only an
+    /// error on the next `remap`.
+    Read(Vec<u8>),
+}
+
+impl MappedFile {
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the file is read-only and the Mmap is kept alive for the
+        // lifetime of the store.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            bytes: FileBytes::Mapped(mmap),
+            resume_offset: 0,
+        })
+    }
+
+    /// Open a file that is still being written, reading its bytes into
+    /// memory instead of mapping them.
+    pub(crate) fn open_following(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            bytes: FileBytes::Read(buffer),
+            resume_offset: 0,
+        })
+    }
+
+    /// Remap the file, or read its new bytes, if bytes were appended since
+    /// it was last mapped or read.
+    ///
+    /// Returns whether the data grew. The open handle is kept, so a file
+    /// that is renamed by log rotation keeps being followed.
+    pub(crate) fn remap(&mut self) -> Result<bool> {
+        let len = self.file.metadata()?.len() as usize;
+        let current = self.data().len();
+        if len < current {
+            bail!("{} was truncated while being followed", self.path.display());
+        }
+        if len == current {
+            return Ok(false);
+        }
+        match &mut self.bytes {
+            // SAFETY: as in `open`; rows only hold offsets, never references
+            // into the previous mapping.
+            FileBytes::Mapped(mmap) => *mmap = unsafe { Mmap::map(&self.file)? },
+            FileBytes::Read(buffer) => {
+                self.file.seek(SeekFrom::Start(current as u64))?;
+                self.file.read_to_end(buffer)?;
+            }
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        match &self.bytes {
+            FileBytes::Mapped(mmap) => mmap,
+            FileBytes::Read(buffer) => buffer,
+        }
+    }
+}
+
+/// Largest live-stream buffer. Rows keep byte offsets as `u32`, so a stream
+/// that grows past this continues in a new buffer source.
+const MAX_BUFFER_LEN: usize = u32::MAX as usize;
+
+/// Index of the buffer source that takes the next `additional` appended
+/// bytes, pushing a new buffer when the last source is a file or is full.
+pub(crate) fn live_buffer_index(sources: &mut Vec<Source>, additional: usize) -> usize {
+    live_buffer_index_within(sources, additional, MAX_BUFFER_LEN)
+}
+
+fn live_buffer_index_within(sources: &mut Vec<Source>, additional: usize, max_len: usize) -> usize {
+    let has_room = match sources.last() {
+        Some(Source::Buffer(buffer)) => buffer.is_empty() || buffer.len() + additional <= max_len,
+        _ => false,
+    };
+    if !has_room {
+        sources.push(Source::Buffer(Vec::new()));
+    }
+    sources.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn followed_files_report_truncation_and_keep_their_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("followed.dlt");
+        std::fs::write(&path, b"abcd").unwrap();
+        let mut followed = MappedFile::open_following(&path).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+
+        file.write_all(b"ef").unwrap();
+        assert!(followed.remap().unwrap());
+        assert_eq!(followed.data(), b"abcdef");
+        assert!(!followed.remap().unwrap());
+
+        file.set_len(2).unwrap();
+        assert!(followed.remap().is_err());
+        assert_eq!(followed.data(), b"abcdef");
+    }
+
+    #[test]
+    fn live_buffer_rotates_before_offsets_overflow() {
+        let mut sources = vec![Source::Buffer(vec![0; 6])];
+
+        assert_eq!(live_buffer_index_within(&mut sources, 4, 10), 0);
+        assert_eq!(live_buffer_index_within(&mut sources, 5, 10), 1);
+        assert_eq!(sources.len(), 2);
+        // An empty buffer takes any append rather than rotating forever.
+        assert_eq!(live_buffer_index_within(&mut sources, 20, 10), 1);
+    }
+}
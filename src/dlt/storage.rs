@@ -13,3 +13,60 @@ pub const STORAGE_HEADER_SIZE: usize = STORAGE_HEADER_PATTERN_SIZE
     + STORAGE_HEADER_MICROSECONDS_SIZE
     + STORAGE_HEADER_ECU_SIZE;
 
+/// Length of the longest suffix of `data` that is a proper prefix of
+/// `pattern`, i.e. the start of a marker split across two reads.
+pub fn partial_marker_len(data: &[u8], pattern: &[u8]) -> usize {
+    (1..pattern.len())
+        .rev()
+        .find(|&n| data.ends_with(&pattern[..n]))
+        .unwrap_or(0)
+}
+
+/// Format a storage timestamp as `seconds.microseconds`.
+pub fn format_timestamp_ns(ns: u64) -> String {
+    let seconds = ns / 1_000_000_000;
+    let micros = (ns % 1_000_000_000) / 1_000;
+    format!("{}.{:06}", seconds, micros)
+}
+
+/// Parse `seconds[.fraction]`, as written by `format_timestamp_ns`, into
+/// nanoseconds; digits past nanoseconds are dropped.
+pub fn parse_timestamp_ns(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (seconds, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    if (seconds.is_empty() && fraction.is_empty()) || !all_digits(seconds) || !all_digits(fraction)
+    {
+        return None;
+    }
+    let seconds: u64 = if seconds.is_empty() {
+        0
+    } else {
+        seconds.parse().ok()?
+    };
+    let nanos = fraction
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(9)
+        .fold(0, |nanos, digit| nanos * 10 + u64::from(digit - b'0'));
+    seconds.checked_mul(1_000_000_000)?.checked_add(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_ns_reads_formatted_timestamps() {
+        let ns = 1_305_029_684_265_792_000;
+        assert_eq!(parse_timestamp_ns(&format_timestamp_ns(ns)), Some(ns));
+        assert_eq!(parse_timestamp_ns("12"), Some(12_000_000_000));
+        assert_eq!(parse_timestamp_ns(" 1.5 "), Some(1_500_000_000));
+        assert_eq!(parse_timestamp_ns(".000000001999"), Some(1));
+        assert_eq!(parse_timestamp_ns(""), None);
+        assert_eq!(parse_timestamp_ns("."), None);
+        assert_eq!(parse_timestamp_ns("-1"), None);
+        assert_eq!(parse_timestamp_ns("1e3"), None);
+        assert_eq!(parse_timestamp_ns("99999999999999999999"), None);
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{Result, bail};
+use std::fmt;
+
+use crate::dlt::control::ControlMessage;
+use crate::dlt::error::ParseError;
+use crate::dlt::verbose::Argument;
+use crate::dlt::{v1, v2};
+
+/// Message ID and argument bytes of a non-verbose log or trace message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonVerbosePayload<'a> {
+    pub message_id: u32,
+    /// Argument data following the message ID.
+    pub arguments: &'a [u8],
+    pub big_endian: bool,
+}
+
+/// Version-agnostic, row-indexed access to a parsed DLT message store.
+///
+/// Implemented by the columnar `v1::Dlt` and `v2::Dlt` stores so callers can
+/// read messages through one code path regardless of protocol version.
+pub trait DltStore: fmt::Debug + Send + Sync {
+    /// Sorted, deduplicated DLT protocol versions present in this store.
+    fn protocol_versions(&self) -> Vec<u8>;
+
+    /// DLT protocol version of a single row.
+    fn protocol_version(&self, row: usize) -> u8;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn ecu(&self, row: usize) -> &str;
+
+    fn apid(&self, row: usize) -> &str;
+
+    fn ctid(&self, row: usize) -> &str;
+
+    fn session_id(&self, row: usize) -> u32;
+
+    /// Message counter (MCNT), wrapping at 255 per sending context.
+    fn message_counter(&self, row: usize) -> u8;
+
+    /// Counter of the last message that makes up the row; differs from
+    /// `message_counter` only for reassembled v2 segments.
+    fn last_message_counter(&self, row: usize) -> u8 {
+        self.message_counter(row)
+    }
+
+    /// Whether the payload uses verbose (self-describing) encoding.
+    fn is_verbose(&self, row: usize) -> bool;
+
+    /// Number of payload arguments (NOAR) declared in the header.
+    fn argument_count(&self, row: usize) -> u8;
+
+    /// Index of the source file in the paths list.
+    fn file_index(&self, row: usize) -> u16;
+
+    /// Byte offset of the message's storage header within its source file.
+    fn byte_offset(&self, row: usize) -> u64;
+
+    fn storage_timestamp_ns(&self, row: usize) -> u64;
+
+    fn message_timestamp_ns(&self, row: usize) -> u64;
+
+    /// Message type (MSTP), e.g. 0 = log, 3 = control.
+    fn message_type(&self, row: usize) -> u8;
+
+    /// Message type info (MTIN), e.g. the log level for log messages.
+    fn message_type_info(&self, row: usize) -> u8;
+
+    /// Undecoded payload bytes.
+    fn payload_raw(&self, row: usize) -> &[u8];
+
+    /// The complete stored frame (storage header and message), byte-exact;
+    /// for a reassembled v2 message, the frame of its first segment.
+    fn frame_raw(&self, row: usize) -> &[u8];
+
+    /// Every stored frame that makes up the row, byte-exact and in file
+    /// order; more than one only for reassembled v2 segments.
+    fn frames_raw(&self, row: usize) -> Vec<&[u8]> {
+        vec![self.frame_raw(row)]
+    }
+
+    /// Payload decoded to display text.
+    fn payload_text(&self, row: usize) -> String;
+
+    /// Typed verbose arguments; empty for non-verbose and control messages.
+    fn payload_arguments(&self, row: usize) -> Vec<Argument>;
+
+    /// Message ID of a non-verbose message; `None` for verbose and control
+    /// messages.
+    fn message_id(&self, row: usize) -> Option<u32>;
+
+    /// Message ID and argument bytes; `None` for verbose and control messages.
+    fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>>;
+
+    /// Decoded control message; `None` for log, trace and network messages.
+    fn control_message(&self, row: usize) -> Option<ControlMessage>;
+
+    /// Source file name and line number; only v2 messages carry one (WSFLN).
+    fn source_location(&self, _row: usize) -> Option<(&str, u32)> {
+        None
+    }
+
+    /// Tags in header order; only v2 messages carry them (WTGS).
+    fn tags(&self, _row: usize) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Privacy level; only v2 messages carry one (WPVL).
+    fn privacy_level(&self, _row: usize) -> Option<u8> {
+        None
+    }
+
+    /// Sorted, deduplicated list of all ECU strings seen.
+    fn unique_ecus(&self) -> Vec<&str>;
+
+    /// Sorted, deduplicated list of all APID strings seen.
+    fn unique_apids(&self) -> Vec<&str>;
+
+    /// Sorted, deduplicated list of all CTID strings seen.
+    fn unique_ctids(&self) -> Vec<&str>;
+
+    /// Append stored frames received from a live stream as new rows.
+    ///
+    /// Only stores that own a stream buffer support this; merged stores are
+    /// built once from files.
+    fn append(&mut self, _frames: &[u8]) -> Result<Vec<ParseError>> {
+        bail!("store does not support appending")
+    }
+
+    /// Parse frames appended to the underlying files since they were opened
+    /// or last refreshed, adding them as new rows.
+    ///
+    /// Only single-version file stores support this; merged stores would
+    /// have to reorder rows that are already shown.
+    fn refresh(&mut self) -> Result<Vec<ParseError>> {
+        bail!("store does not support following files")
+    }
+}
+
+macro_rules! impl_dlt_store {
+    ($store:ty, $version:expr $(, $extra:item)*) => {
+        impl DltStore for $store {
+            $($extra)*
+
+            fn protocol_versions(&self) -> Vec<u8> {
+                vec![$version]
+            }
+
+            fn protocol_version(&self, _row: usize) -> u8 {
+                $version
+            }
+
+            fn len(&self) -> usize {
+                <$store>::len(self)
+            }
+
+            fn ecu(&self, row: usize) -> &str {
+                <$store>::ecu(self, row)
+            }
+
+            fn apid(&self, row: usize) -> &str {
+                <$store>::apid(self, row)
+            }
+
+            fn ctid(&self, row: usize) -> &str {
+                <$store>::ctid(self, row)
+            }
+
+            fn session_id(&self, row: usize) -> u32 {
+                <$store>::session_id(self, row)
+            }
+
+            fn message_counter(&self, row: usize) -> u8 {
+                <$store>::message_counter(self, row)
+            }
+
+            fn is_verbose(&self, row: usize) -> bool {
+                <$store>::is_verbose(self, row)
+            }
+
+            fn argument_count(&self, row: usize) -> u8 {
+                <$store>::argument_count(self, row)
+            }
+
+            fn file_index(&self, row: usize) -> u16 {
+                <$store>::file_index(self, row)
+            }
+
+            fn byte_offset(&self, row: usize) -> u64 {
+                <$store>::byte_offset(self, row)
+            }
+
+            fn storage_timestamp_ns(&self, row: usize) -> u64 {
+                <$store>::storage_timestamp_ns(self, row)
+            }
+
+            fn message_timestamp_ns(&self, row: usize) -> u64 {
+                <$store>::message_timestamp_ns(self, row)
+            }
+
+            fn message_type(&self, row: usize) -> u8 {
+                <$store>::message_type(self, row)
+            }
+
+            fn message_type_info(&self, row: usize) -> u8 {
+                <$store>::message_type_info(self, row)
+            }
+
+            fn payload_raw(&self, row: usize) -> &[u8] {
+                <$store>::payload_raw(self, row)
+            }
+
+            fn frame_raw(&self, row: usize) -> &[u8] {
+                <$store>::frame_raw(self, row)
+            }
+
+            fn payload_text(&self, row: usize) -> String {
+                <$store>::payload_text(self, row)
+            }
+
+            fn payload_arguments(&self, row: usize) -> Vec<Argument> {
+                <$store>::payload_arguments(self, row)
+            }
+
+            fn message_id(&self, row: usize) -> Option<u32> {
+                <$store>::message_id(self, row)
+            }
+
+            fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>> {
+                <$store>::non_verbose_payload(self, row)
+            }
+
+            fn control_message(&self, row: usize) -> Option<ControlMessage> {
+                <$store>::control_message(self, row)
+            }
+
+            fn unique_ecus(&self) -> Vec<&str> {
+                <$store>::unique_ecus(self)
+            }
+
+            fn unique_apids(&self) -> Vec<&str> {
+                <$store>::unique_apids(self)
+            }
+
+            fn unique_ctids(&self) -> Vec<&str> {
+                <$store>::unique_ctids(self)
+            }
+
+            fn append(&mut self, frames: &[u8]) -> Result<Vec<ParseError>> {
+                Ok(<$store>::append(self, frames))
+            }
+
+            fn refresh(&mut self) -> Result<Vec<ParseError>> {
+                <$store>::refresh(self)
+            }
+        }
+    };
+}
+
+impl_dlt_store!(v1::Dlt, 1);
+impl_dlt_store!(
+    v2::Dlt,
+    2,
+    fn source_location(&self, row: usize) -> Option<(&str, u32)> {
+        v2::Dlt::source_location(self, row)
+    },
+    fn tags(&self, row: usize) -> Vec<&str> {
+        v2::Dlt::tags(self, row)
+    },
+    fn privacy_level(&self, row: usize) -> Option<u8> {
+        v2::Dlt::privacy_level(self, row)
+    },
+    fn last_message_counter(&self, row: usize) -> u8 {
+        v2::Dlt::last_message_counter(self, row)
+    },
+    fn frames_raw(&self, row: usize) -> Vec<&[u8]> {
+        v2::Dlt::frames_raw(self, row)
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::writer::MessageBuilder;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn test_data_path(filename: &str) -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/data");
+        path.push(filename);
+        path
+    }
+
+    #[test]
+    fn open_v1_through_trait_matches_inherent_accessors() {
+        let path = test_data_path("testfile_single_payloads.dlt");
+        let (inherent, _) = v1::Dlt::open(vec![path.clone()]).unwrap();
+        let (store, errors) = crate::dlt::open(vec![path]).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(store.protocol_versions(), vec![1]);
+        assert_eq!(store.len(), inherent.len());
+        for row in 0..store.len() {
+            assert_eq!(store.ecu(row), inherent.ecu(row));
+            assert_eq!(store.apid(row), inherent.apid(row));
+            assert_eq!(store.ctid(row), inherent.ctid(row));
+            assert_eq!(store.payload_raw(row), inherent.payload_raw(row));
+            assert_eq!(store.payload_text(row), inherent.payload_text(row));
+            assert_eq!(store.source_location(row), None);
+            assert!(store.tags(row).is_empty());
+        }
+    }
+
+    #[test]
+    fn open_v2_through_trait() {
+        let msg_bytes = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_ctid("CTX1")
+            .with_ecu("ECU9")
+            .with_session_id(7)
+            .with_verbose_string("hello")
+            .build(2)
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store_v2.dlt");
+        {
+            let mut f = std::fs::File::create(&path).unwrap();
+            f.write_all(&msg_bytes).unwrap();
+        }
+
+        let (store, errors) = crate::dlt::open(vec![path]).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(store.protocol_version(0), 2);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.ecu(0), "ECU9");
+        assert_eq!(store.apid(0), "APP1");
+        assert_eq!(store.ctid(0), "CTX1");
+        assert_eq!(store.session_id(0), 7);
+        assert_eq!(store.payload_text(0), "hello");
+        assert_eq!(
+            store.payload_arguments(0),
+            vec![Argument {
+                name: None,
+                unit: None,
+                value: crate::dlt::verbose::Value::String("hello".into()),
+            }]
+        );
+        assert_eq!(store.unique_apids(), vec!["APP1"]);
+    }
+
+    #[test]
+    fn header_fields_for_text_export() {
+        let path = test_data_path("testfile_single_payloads.dlt");
+        let (v1_store, _) = crate::dlt::open(vec![path]).unwrap();
+        assert_eq!(v1_store.message_counter(0), 0);
+        assert_eq!(v1_store.message_counter(2), 2);
+        assert!(v1_store.is_verbose(0));
+        assert_eq!(v1_store.argument_count(0), 1);
+
+        let msg_bytes = MessageBuilder::new()
+            .with_verbose_string("a")
+            .with_verbose_string("b")
+            .build(2)
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store_v2_args.dlt");
+        std::fs::write(&path, msg_bytes).unwrap();
+        let (v2_store, _) = crate::dlt::open(vec![path]).unwrap();
+        assert!(v2_store.is_verbose(0));
+        assert_eq!(v2_store.argument_count(0), 2);
+        assert_eq!(v2_store.message_counter(0), 0);
+    }
+
+    #[test]
+    fn open_rejects_empty_path_list() {
+        assert!(crate::dlt::open(Vec::new()).is_err());
+    }
+}
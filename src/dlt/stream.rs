@@ -0,0 +1,718 @@
+use anyhow::{Context, Result, anyhow, bail};
+use memchr::memmem::Finder;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::dlt::control::ControlMessage;
+use crate::dlt::storage::{STORAGE_HEADER_PATTERN, STORAGE_HEADER_SIZE, partial_marker_len};
+
+/// TCP port dlt-daemon listens on for clients.
+pub const DEFAULT_DAEMON_PORT: u16 = 3490;
+
+/// Baud rate used for serial devices unless one is given.
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+/// Serial header dlt-daemon puts in front of each message on a UART.
+pub const SERIAL_HEADER_PATTERN: &[u8] = b"DLS\x01";
+
+/// Minimum v1 standard header: HTYP, MCNT, LEN.
+const V1_HEADER_MIN: usize = 4;
+/// Minimum v2 base header: HTYP2 (4 bytes), MCNT, LEN.
+const V2_HEADER_MIN: usize = 7;
+/// v1 HTYP flag: ECU ID follows LEN.
+const V1_HTYP_WEID: u8 = 0x04;
+
+/// How long a blocking read waits before the stop flag is checked again.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How messages are delimited in a live byte stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// Messages back to back, as dlt-daemon sends them over TCP and UDP.
+    #[default]
+    Bare,
+    /// Each message preceded by the `DLS\x01` serial header.
+    Serial,
+}
+
+/// Splits a live byte stream into messages.
+///
+/// Live sources send messages without a storage header, so each complete
+/// message is prefixed with a synthesized one carrying the receive time. The
+/// result can be appended to a store like frames read from a file.
+///
+/// On garbage, bare streams skip one byte at a time until the bytes can start
+/// a v1 or v2 message; serial streams skip to the next `DLS\x01` header, the
+/// same way `scan_frames` resyncs on `DLT\x01` in files.
+#[derive(Debug, Default)]
+pub struct StreamFramer {
+    framing: Framing,
+    pending: Vec<u8>,
+    skipped_bytes: usize,
+}
+
+impl StreamFramer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_framing(framing: Framing) -> Self {
+        Self {
+            framing,
+            ..Self::default()
+        }
+    }
+
+    /// Feed received bytes and return the stored frames of every message
+    /// completed by them, grouped into runs of the same protocol version.
+    pub fn push(&mut self, data: &[u8], received_ns: u64) -> Vec<(u8, Vec<u8>)> {
+        self.pending.extend_from_slice(data);
+
+        let finder = Finder::new(SERIAL_HEADER_PATTERN);
+        let header_len = match self.framing {
+            Framing::Bare => 0,
+            Framing::Serial => SERIAL_HEADER_PATTERN.len(),
+        };
+        let mut batches: Vec<(u8, Vec<u8>)> = Vec::new();
+        let mut pos = 0;
+        while pos < self.pending.len() {
+            let rest = &self.pending[pos..];
+            if self.framing == Framing::Serial && !rest.starts_with(SERIAL_HEADER_PATTERN) {
+                // Keep a tail that may be the start of a split serial header.
+                let skip = finder.find(rest).unwrap_or_else(|| {
+                    rest.len() - partial_marker_len(rest, SERIAL_HEADER_PATTERN)
+                });
+                if skip == 0 {
+                    break;
+                }
+                self.skipped_bytes += skip;
+                pos += skip;
+                continue;
+            }
+
+            let Some(&htyp) = rest.get(header_len) else {
+                break;
+            };
+            let version = (htyp >> 5) & 0x07;
+            let body = &rest[header_len..];
+            if body.len() < header_min(version) {
+                // Too short to read LEN yet.
+                break;
+            }
+            let Some(len) = message_len(version, body) else {
+                self.skipped_bytes += 1;
+                pos += 1;
+                continue;
+            };
+            if body.len() < len {
+                break;
+            }
+
+            let msg = &body[..len];
+            let frames = match batches.last_mut() {
+                Some((batch_version, frames)) if *batch_version == version => frames,
+                _ => {
+                    batches.push((version, Vec::new()));
+                    &mut batches.last_mut().expect("batch just pushed").1
+                }
+            };
+            frames.extend_from_slice(&storage_header(received_ns, message_ecu(version, msg)));
+            frames.extend_from_slice(msg);
+            pos += header_len + len;
+        }
+
+        self.pending.drain(..pos);
+        batches
+    }
+
+    /// Drop an incomplete message, e.g. at the end of a datagram.
+    pub fn discard_pending(&mut self) {
+        self.skipped_bytes += self.pending.len();
+        self.pending.clear();
+    }
+
+    /// Bytes dropped while resynchronising to a message start.
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes
+    }
+
+    /// Bytes of an incomplete message waiting for more data.
+    pub fn pending_bytes(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Header bytes needed to read LEN; 0 for versions that are never framed.
+fn header_min(version: u8) -> usize {
+    match version {
+        1 => V1_HEADER_MIN,
+        2 => V2_HEADER_MIN,
+        _ => 0,
+    }
+}
+
+/// Declared length of the message at the start of `data`, or `None` when the
+/// bytes cannot start a message of `version`.
+fn message_len(version: u8, data: &[u8]) -> Option<usize> {
+    let len_at = match version {
+        1 => 2,
+        2 => 5,
+        _ => return None,
+    };
+    let len = u16::from_be_bytes(data.get(len_at..len_at + 2)?.try_into().ok()?) as usize;
+    (len >= header_min(version)).then_some(len)
+}
+
+/// ECU ID carried in a v1 standard header, used as the storage header ECU.
+fn message_ecu(version: u8, msg: &[u8]) -> [u8; 4] {
+    if version == 1
+        && msg[0] & V1_HTYP_WEID != 0
+        && let Some(ecu) = msg.get(V1_HEADER_MIN..V1_HEADER_MIN + 4)
+    {
+        return ecu.try_into().expect("slice of four bytes");
+    }
+    [0; 4]
+}
+
+/// Storage header as dlt-daemon writes it: marker, seconds and microseconds
+/// (little-endian), ECU ID.
+fn storage_header(received_ns: u64, ecu: [u8; 4]) -> [u8; STORAGE_HEADER_SIZE] {
+    let mut header = [0u8; STORAGE_HEADER_SIZE];
+    header[..4].copy_from_slice(STORAGE_HEADER_PATTERN);
+    header[4..8].copy_from_slice(&((received_ns / 1_000_000_000) as u32).to_le_bytes());
+    header[8..12].copy_from_slice(&((received_ns % 1_000_000_000 / 1_000) as u32).to_le_bytes());
+    header[12..16].copy_from_slice(&ecu);
+    header
+}
+
+/// Current wall-clock time in nanoseconds since the Unix epoch.
+pub fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// `HOST[:PORT]` with the dlt-daemon port filled in when none is given.
+pub fn daemon_address(address: &str) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        return address.to_string();
+    }
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => {
+            address.to_string()
+        }
+        _ if address.contains(':') && !address.starts_with('[') => {
+            format!("[{address}]:{DEFAULT_DAEMON_PORT}")
+        }
+        _ => format!("{address}:{DEFAULT_DAEMON_PORT}"),
+    }
+}
+
+/// Local address to bind for `[ADDR:]PORT` (or a bare `ADDR`), plus the
+/// multicast group to join when ADDR is one.
+pub fn udp_endpoint(address: &str) -> Result<(SocketAddr, Option<IpAddr>)> {
+    let target: SocketAddr = match address.parse::<u16>() {
+        Ok(port) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port),
+        Err(_) => daemon_address(address)
+            .parse()
+            .map_err(|_| anyhow!("invalid UDP address {address:?}, expected [ADDR:]PORT"))?,
+    };
+    if !target.ip().is_multicast() {
+        return Ok((target, None));
+    }
+    let any: IpAddr = match target.ip() {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    Ok((SocketAddr::new(any, target.port()), Some(target.ip())))
+}
+
+/// Where live messages come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiveSource {
+    /// dlt-daemon client connection to `HOST[:PORT]`.
+    Tcp(String),
+    /// Datagrams received on `[ADDR:]PORT`; a multicast ADDR joins that group.
+    Udp(String),
+    /// UART or pty sending messages behind `DLS\x01` serial headers.
+    Serial { path: PathBuf, baud_rate: u32 },
+}
+
+impl fmt::Display for LiveSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiveSource::Tcp(address) => write!(f, "tcp {}", daemon_address(address)),
+            LiveSource::Udp(address) => write!(f, "udp {address}"),
+            LiveSource::Serial { path, baud_rate } => {
+                write!(f, "serial {} @ {baud_rate}", path.display())
+            }
+        }
+    }
+}
+
+impl LiveSource {
+    /// Connect, bind or open the source.
+    pub fn open(&self) -> Result<LiveReader> {
+        match self {
+            LiveSource::Tcp(address) => {
+                let address = daemon_address(address);
+                let stream = TcpStream::connect(&address)
+                    .with_context(|| format!("Failed to connect to {address}"))?;
+                stream.set_read_timeout(Some(POLL_INTERVAL))?;
+                Ok(LiveReader::Tcp(stream))
+            }
+            LiveSource::Udp(address) => {
+                let (bind, group) = udp_endpoint(address)?;
+                let socket =
+                    UdpSocket::bind(bind).with_context(|| format!("Failed to bind {bind}"))?;
+                match group {
+                    Some(IpAddr::V4(group)) => {
+                        socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)
+                    }
+                    Some(IpAddr::V6(group)) => socket.join_multicast_v6(&group, 0),
+                    None => Ok(()),
+                }
+                .with_context(|| format!("Failed to join multicast group {address}"))?;
+                socket.set_read_timeout(Some(POLL_INTERVAL))?;
+                Ok(LiveReader::Udp(socket))
+            }
+            LiveSource::Serial { path, baud_rate } => {
+                let port = serialport::new(path.to_string_lossy(), *baud_rate)
+                    .timeout(POLL_INTERVAL)
+                    .open()
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                Ok(LiveReader::Serial(port))
+            }
+        }
+    }
+}
+
+/// An opened live source.
+pub enum LiveReader {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+    Serial(Box<dyn serialport::SerialPort>),
+}
+
+impl LiveReader {
+    /// A handle that writes control requests to the same target.
+    ///
+    /// dlt-daemon takes requests on its client TCP connection; serial
+    /// targets read them behind a `DLS\x01` header. UDP sources only listen.
+    pub fn control_sender(&self) -> Result<ControlSender> {
+        let (writer, framing): (Box<dyn Write + Send>, Framing) = match self {
+            LiveReader::Tcp(stream) => (Box::new(stream.try_clone()?), Framing::Bare),
+            LiveReader::Serial(port) => (Box::new(port.try_clone()?), Framing::Serial),
+            LiveReader::Udp(_) => bail!("UDP sources cannot send control requests"),
+        };
+        Ok(ControlSender {
+            writer,
+            framing,
+            counter: 0,
+        })
+    }
+
+    /// Read until the source ends or `stop` is set, passing each batch of
+    /// stored frames and its protocol version to `sink`. Stops early when
+    /// `sink` returns `false`.
+    pub fn read_frames<F>(self, stop: &AtomicBool, mut sink: F) -> Result<StreamFramer>
+    where
+        F: FnMut(u8, Vec<u8>) -> bool,
+    {
+        match self {
+            LiveReader::Tcp(stream) => pump(stream, Framing::Bare, stop, sink),
+            LiveReader::Serial(port) => pump(port, Framing::Serial, stop, sink),
+            LiveReader::Udp(socket) => {
+                // Messages never span datagrams, so each datagram is framed on its own.
+                let mut framer = StreamFramer::new();
+                let mut buf = vec![0u8; 64 * 1024];
+                while !stop.load(Ordering::Relaxed) {
+                    let read = match socket.recv(&mut buf) {
+                        Ok(read) => read,
+                        Err(err) if is_retryable(&err) => continue,
+                        Err(err) => return Err(err.into()),
+                    };
+                    let batches = framer.push(&buf[..read], now_ns());
+                    framer.discard_pending();
+                    for (version, frames) in batches {
+                        if !sink(version, frames) {
+                            return Ok(framer);
+                        }
+                    }
+                }
+                Ok(framer)
+            }
+        }
+    }
+}
+
+/// Writes control requests to a connected target.
+pub struct ControlSender {
+    writer: Box<dyn Write + Send>,
+    framing: Framing,
+    counter: u8,
+}
+
+impl ControlSender {
+    /// Encode `request` as a `version` message addressed to `ecu` and send it.
+    pub fn send(&mut self, request: &ControlMessage, version: u8, ecu: &str) -> Result<()> {
+        let mut data = Vec::new();
+        if self.framing == Framing::Serial {
+            data.extend_from_slice(SERIAL_HEADER_PATTERN);
+        }
+        data.extend_from_slice(&request.request_message(version, ecu, self.counter)?);
+        self.writer.write_all(&data)?;
+        self.writer.flush()?;
+        self.counter = self.counter.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ControlSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ControlSender")
+            .field("framing", &self.framing)
+            .field("counter", &self.counter)
+            .finish()
+    }
+}
+
+fn pump<R, F>(
+    mut reader: R,
+    framing: Framing,
+    stop: &AtomicBool,
+    mut sink: F,
+) -> Result<StreamFramer>
+where
+    R: Read,
+    F: FnMut(u8, Vec<u8>) -> bool,
+{
+    let mut framer = StreamFramer::with_framing(framing);
+    let mut buf = vec![0u8; 64 * 1024];
+    while !stop.load(Ordering::Relaxed) {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if is_retryable(&err) => continue,
+            // A serial device reports a hang-up when it goes away.
+            Err(err) if framing == Framing::Serial && err.kind() == ErrorKind::BrokenPipe => break,
+            Err(err) => return Err(err.into()),
+        };
+        for (version, frames) in framer.push(&buf[..read], now_ns()) {
+            if !sink(version, frames) {
+                return Ok(framer);
+            }
+        }
+    }
+    Ok(framer)
+}
+
+/// Read errors that only mean "no data yet".
+fn is_retryable(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::writer::MessageBuilder;
+
+    /// v1 log message with ECU ID and a one-byte payload.
+    fn v1_message(mcnt: u8) -> Vec<u8> {
+        let mut msg = vec![0x20 | V1_HTYP_WEID, mcnt, 0, 9];
+        msg.extend_from_slice(b"ECU1");
+        msg.push(0xAA);
+        msg
+    }
+
+    #[test]
+    fn frames_complete_messages_with_storage_header() {
+        let mut framer = StreamFramer::new();
+        let mut data = v1_message(0);
+        data.extend(v1_message(1));
+
+        let batches = framer.push(&data, 1_500_000_000);
+
+        assert_eq!(batches.len(), 1);
+        let (version, frames) = &batches[0];
+        assert_eq!(*version, 1);
+        assert_eq!(frames.len(), 2 * (STORAGE_HEADER_SIZE + 9));
+        assert_eq!(&frames[..4], STORAGE_HEADER_PATTERN);
+        assert_eq!(&frames[4..8], &1u32.to_le_bytes());
+        assert_eq!(&frames[8..12], &500_000u32.to_le_bytes());
+        assert_eq!(&frames[12..16], b"ECU1");
+        assert_eq!(&frames[16..25], v1_message(0).as_slice());
+    }
+
+    #[test]
+    fn keeps_partial_messages_until_complete() {
+        let mut framer = StreamFramer::new();
+        let msg = v1_message(0);
+
+        assert!(framer.push(&msg[..2], 0).is_empty());
+        assert!(framer.push(&msg[2..6], 0).is_empty());
+        assert_eq!(framer.pending_bytes(), 6);
+        let batches = framer.push(&msg[6..], 0);
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(&batches[0].1[STORAGE_HEADER_SIZE..], msg.as_slice());
+        assert_eq!(framer.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn resyncs_after_garbage() {
+        let mut framer = StreamFramer::new();
+        let mut data = vec![0x00, 0xFF, 0x03];
+        data.extend(v1_message(7));
+
+        let batches = framer.push(&data, 0);
+
+        assert_eq!(framer.skipped_bytes(), 3);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].1[STORAGE_HEADER_SIZE + 1], 7);
+    }
+
+    #[test]
+    fn groups_messages_by_version() {
+        let v2 = MessageBuilder::new()
+            .with_verbose_string("hi")
+            .build(2)
+            .unwrap();
+        let mut data = v1_message(0);
+        data.extend_from_slice(&v2[STORAGE_HEADER_SIZE..]);
+        data.extend(v1_message(1));
+
+        let versions: Vec<u8> = StreamFramer::new()
+            .push(&data, 0)
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect();
+
+        assert_eq!(versions, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn daemon_address_defaults_port() {
+        assert_eq!(daemon_address("localhost"), "localhost:3490");
+        assert_eq!(daemon_address("10.0.0.1:4000"), "10.0.0.1:4000");
+        assert_eq!(daemon_address("::1"), "[::1]:3490");
+        assert_eq!(daemon_address("[::1]:4000"), "[::1]:4000");
+    }
+
+    fn serial_message(mcnt: u8) -> Vec<u8> {
+        let mut data = SERIAL_HEADER_PATTERN.to_vec();
+        data.extend(v1_message(mcnt));
+        data
+    }
+
+    fn counters(batches: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        batches
+            .iter()
+            .flat_map(|(_, frames)| frames.chunks(STORAGE_HEADER_SIZE + 9))
+            .map(|frame| frame[STORAGE_HEADER_SIZE + 1])
+            .collect()
+    }
+
+    #[test]
+    fn serial_framing_strips_sync_header() {
+        let mut framer = StreamFramer::with_framing(Framing::Serial);
+        let mut data = serial_message(0);
+        data.extend(serial_message(1));
+
+        let batches = framer.push(&data, 0);
+
+        assert_eq!(counters(&batches), vec![0, 1]);
+        assert_eq!(
+            &batches[0].1[STORAGE_HEADER_SIZE..][..9],
+            v1_message(0).as_slice()
+        );
+        assert_eq!(framer.skipped_bytes(), 0);
+    }
+
+    #[test]
+    fn serial_framing_resyncs_to_next_sync_header() {
+        let mut framer = StreamFramer::with_framing(Framing::Serial);
+        // Garbage, a sync header followed by an invalid message, then a good one.
+        let mut data = b"noise".to_vec();
+        data.extend_from_slice(SERIAL_HEADER_PATTERN);
+        data.extend_from_slice(&[0xE0, 0, 0, 0]);
+        data.extend(serial_message(5));
+
+        let batches = framer.push(&data, 0);
+
+        assert_eq!(counters(&batches), vec![5]);
+        assert_eq!(framer.skipped_bytes(), 5 + 8);
+    }
+
+    #[test]
+    fn serial_framing_keeps_split_sync_header() {
+        let mut framer = StreamFramer::with_framing(Framing::Serial);
+        let mut data = b"xx".to_vec();
+        data.extend(serial_message(3));
+
+        assert!(framer.push(&data[..4], 0).is_empty());
+        assert_eq!(framer.skipped_bytes(), 2);
+        let batches = framer.push(&data[4..], 0);
+
+        assert_eq!(counters(&batches), vec![3]);
+    }
+
+    #[test]
+    fn discard_pending_drops_partial_datagram() {
+        let mut framer = StreamFramer::new();
+        let msg = v1_message(0);
+        assert!(framer.push(&msg[..5], 0).is_empty());
+
+        framer.discard_pending();
+
+        assert_eq!(framer.pending_bytes(), 0);
+        assert_eq!(framer.skipped_bytes(), 5);
+        assert_eq!(counters(&framer.push(&v1_message(1), 0)), vec![1]);
+    }
+
+    #[test]
+    fn udp_endpoint_joins_multicast_groups() {
+        assert_eq!(
+            udp_endpoint("3490").unwrap(),
+            ("0.0.0.0:3490".parse().unwrap(), None)
+        );
+        assert_eq!(
+            udp_endpoint("127.0.0.1:4000").unwrap(),
+            ("127.0.0.1:4000".parse().unwrap(), None)
+        );
+        assert_eq!(
+            udp_endpoint("239.255.42.99").unwrap(),
+            (
+                "0.0.0.0:3490".parse().unwrap(),
+                Some("239.255.42.99".parse().unwrap())
+            )
+        );
+        assert!(udp_endpoint("not an address:x").is_err());
+    }
+
+    #[test]
+    fn udp_source_frames_each_datagram() {
+        let source = LiveSource::Udp("127.0.0.1:0".to_string());
+        let LiveReader::Udp(socket) = source.open().unwrap() else {
+            panic!("expected a UDP reader");
+        };
+        let target = socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut datagram = v1_message(0);
+        datagram.extend(v1_message(1));
+        sender.send_to(&datagram, target).unwrap();
+        // A truncated message must not leak into the next datagram.
+        sender.send_to(&v1_message(2)[..6], target).unwrap();
+        sender.send_to(&v1_message(3), target).unwrap();
+
+        let mut received = Vec::new();
+        let framer = LiveReader::Udp(socket)
+            .read_frames(&AtomicBool::new(false), |version, frames| {
+                received.push((version, frames));
+                counters(&received).len() < 3
+            })
+            .unwrap();
+
+        assert_eq!(counters(&received), vec![0, 1, 3]);
+        assert_eq!(framer.skipped_bytes(), 6);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn serial_source_reads_from_pty() {
+        use serialport::SerialPort;
+        use std::io::Write;
+
+        let (mut master, slave) = serialport::TTYPort::pair().unwrap();
+        let source = LiveSource::Serial {
+            path: PathBuf::from(slave.name().unwrap()),
+            baud_rate: DEFAULT_BAUD_RATE,
+        };
+        let reader = source.open().unwrap();
+
+        let mut data = b"\x00garbage".to_vec();
+        data.extend(serial_message(0));
+        data.extend(serial_message(1));
+        master.write_all(&data).unwrap();
+
+        let mut received = Vec::new();
+        reader
+            .read_frames(&AtomicBool::new(false), |version, frames| {
+                received.push((version, frames));
+                counters(&received).len() < 2
+            })
+            .unwrap();
+
+        assert_eq!(counters(&received), vec![0, 1]);
+        assert_eq!(&received[0].1[12..16], b"ECU1");
+    }
+
+    #[test]
+    fn stop_flag_ends_reading() {
+        let source = LiveSource::Udp("127.0.0.1:0".to_string());
+        let reader = source.open().unwrap();
+
+        let framer = reader
+            .read_frames(&AtomicBool::new(true), |_, _| true)
+            .unwrap();
+
+        assert_eq!(framer.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn control_sender_writes_requests_on_the_tcp_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let source = LiveSource::Tcp(listener.local_addr().unwrap().to_string());
+        let reader = source.open().unwrap();
+        let (mut daemon, _) = listener.accept().unwrap();
+
+        let mut sender = reader.control_sender().unwrap();
+        let request = ControlMessage::set_log_level("APP", "CTX", 6);
+        sender.send(&request, 1, "ECU1").unwrap();
+        sender.send(&request, 2, "ECU1").unwrap();
+
+        let mut framer = StreamFramer::new();
+        let mut batches = Vec::new();
+        let mut buf = [0u8; 256];
+        while batches.len() < 2 {
+            let read = daemon.read(&mut buf).unwrap();
+            assert!(read > 0, "connection closed early");
+            batches.extend(framer.push(&buf[..read], 0));
+        }
+        for (expected_version, (version, frames)) in [1, 2].into_iter().zip(batches) {
+            assert_eq!(version, expected_version);
+            let mut store = crate::dlt::open_stream(version).unwrap();
+            assert!(store.append(&frames).unwrap().is_empty());
+            assert_eq!(store.control_message(0), Some(request.clone()));
+            assert_eq!(store.message_counter(0), version - 1);
+        }
+    }
+
+    #[test]
+    fn udp_sources_cannot_send_control_requests() {
+        let source = LiveSource::Udp("127.0.0.1:0".to_string());
+        assert!(source.open().unwrap().control_sender().is_err());
+    }
+
+    #[test]
+    fn live_source_display() {
+        assert_eq!(LiveSource::Tcp("ecu".into()).to_string(), "tcp ecu:3490");
+        assert_eq!(LiveSource::Udp("3490".into()).to_string(), "udp 3490");
+        assert_eq!(
+            LiveSource::Serial {
+                path: PathBuf::from("/dev/ttyUSB0"),
+                baud_rate: 921_600,
+            }
+            .to_string(),
+            "serial /dev/ttyUSB0 @ 921600"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::dlt::storage::{format_timestamp_ns, parse_timestamp_ns};
+use crate::dlt::store::DltStore;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Timestamp a time window or a "go to time" applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeClock {
+    /// Receive time from the storage header, wall time since the Unix epoch.
+    Storage,
+    /// Sender time from the message header, usually time since ECU start.
+    Message,
+}
+
+impl TimeClock {
+    pub fn timestamp_ns(self, dlt: &dyn DltStore, row: usize) -> u64 {
+        match self {
+            TimeClock::Storage => dlt.storage_timestamp_ns(row),
+            TimeClock::Message => dlt.message_timestamp_ns(row),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeClock::Storage => "storage time",
+            TimeClock::Message => "message time",
+        }
+    }
+}
+
+/// One end of a time window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeBound {
+    /// Nanoseconds on the clock itself.
+    Absolute(u64),
+    /// Nanoseconds after the first row's timestamp on the same clock.
+    Relative(u64),
+}
+
+impl TimeBound {
+    /// Nanoseconds on the clock, resolving relative bounds against row 0.
+    pub fn resolve(self, dlt: &dyn DltStore, clock: TimeClock) -> u64 {
+        match self {
+            TimeBound::Absolute(ns) => ns,
+            TimeBound::Relative(offset) => {
+                let first = if dlt.is_empty() {
+                    0
+                } else {
+                    clock.timestamp_ns(dlt, 0)
+                };
+                first.saturating_add(offset)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimeBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeBound::Absolute(ns) => write!(f, "{}", format_timestamp_ns(*ns)),
+            TimeBound::Relative(ns) => write!(f, "+{}", format_timestamp_ns(*ns)),
+        }
+    }
+}
+
+/// Parse a time bound: `+SECONDS[.FRACTION]` relative to the first row,
+/// `SECONDS[.FRACTION]` as shown in the timestamp column, or a UTC wall time
+/// `YYYY-MM-DD HH:MM:SS[.FRACTION]` (a `T` may separate date and time).
+pub fn parse_time_bound(text: &str) -> Result<TimeBound> {
+    let text = text.trim();
+    if let Some(offset) = text.strip_prefix('+') {
+        return parse_timestamp_ns(offset)
+            .map(TimeBound::Relative)
+            .ok_or_else(|| anyhow!("\"{text}\" is not a number of seconds"));
+    }
+    parse_timestamp_ns(text)
+        .or_else(|| parse_wall_time_ns(text))
+        .map(TimeBound::Absolute)
+        .ok_or_else(|| anyhow!("\"{text}\" is not seconds, +seconds or YYYY-MM-DD HH:MM:SS"))
+}
+
+/// Parse `YYYY-MM-DD HH:MM:SS[.FRACTION]`, in UTC, into nanoseconds since
+/// the Unix epoch. Years before 1970 or after 9999 are rejected.
+fn parse_wall_time_ns(text: &str) -> Option<u64> {
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = text.split_once(['T', ' '])?;
+    let mut date = date.split('-').map(|part| part.parse::<u64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if date.next().is_some()
+        || time.next().is_some()
+        || !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let seconds = days_since_epoch(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second;
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        parse_timestamp_ns(&format!("0.{fraction}"))?
+    };
+    seconds.checked_mul(NANOS_PER_SECOND)?.checked_add(nanos)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Counts years from March, so the leap day ends the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Rows whose timestamp on `clock` is from `from` up to, not including, `to`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub clock: TimeClock,
+    pub from: Option<TimeBound>,
+    pub to: Option<TimeBound>,
+}
+
+impl TimeWindow {
+    pub fn matches(&self, dlt: &dyn DltStore, row: usize) -> bool {
+        let timestamp = self.clock.timestamp_ns(dlt, row);
+        self.from
+            .is_none_or(|from| timestamp >= from.resolve(dlt, self.clock))
+            && self
+                .to
+                .is_none_or(|to| timestamp < to.resolve(dlt, self.clock))
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.clock.label())?;
+        if let Some(from) = self.from {
+            write!(f, " from {from}")?;
+        }
+        if let Some(to) = self.to {
+            write!(f, " until {to}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Position in `rows` of the row whose timestamp on `clock` is nearest to
+/// `ns`, the earlier row on a tie.
+///
+/// Binary search, so `rows` should be in timestamp order on `clock`; in a
+/// file whose clock jumps back, one of the rows around `ns` is found.
+pub fn nearest_row(dlt: &dyn DltStore, rows: &[usize], clock: TimeClock, ns: u64) -> Option<usize> {
+    let after = rows.partition_point(|&row| clock.timestamp_ns(dlt, row) < ns);
+    let distance = |position: usize| clock.timestamp_ns(dlt, rows[position]).abs_diff(ns);
+    match (after.checked_sub(1), (after < rows.len()).then_some(after)) {
+        (Some(before), Some(after)) if distance(after) < distance(before) => Some(after),
+        (Some(before), _) => Some(before),
+        (None, after) => after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::v1;
+    use crate::dlt::writer::MessageBuilder;
+
+    fn stream(seconds: &[u32]) -> v1::Dlt {
+        let frames: Vec<_> = seconds
+            .iter()
+            .map(|&seconds| {
+                MessageBuilder::new()
+                    .with_storage_timestamp(seconds, 0)
+                    .with_verbose_string("text")
+                    .build(1)
+                    .unwrap()
+            })
+            .collect();
+        let mut dlt = v1::Dlt::stream();
+        assert!(dlt.append(&frames.concat()).is_empty());
+        dlt
+    }
+
+    #[test]
+    fn parse_time_bound_accepts_seconds_offsets_and_wall_time() {
+        assert_eq!(
+            parse_time_bound("1305029684.5").unwrap(),
+            TimeBound::Absolute(1_305_029_684_500_000_000)
+        );
+        assert_eq!(
+            parse_time_bound(" +2.25").unwrap(),
+            TimeBound::Relative(2_250_000_000)
+        );
+        assert_eq!(
+            parse_time_bound("2011-05-10 12:14:44.5").unwrap(),
+            TimeBound::Absolute(1_305_029_684_500_000_000)
+        );
+        assert_eq!(
+            parse_time_bound("2024-02-29T00:00:00Z").unwrap(),
+            TimeBound::Absolute(1_709_164_800 * NANOS_PER_SECOND)
+        );
+        assert_eq!(
+            parse_time_bound("1970-01-01 00:00:00").unwrap(),
+            TimeBound::Absolute(0)
+        );
+        for invalid in [
+            "",
+            "+",
+            "+-1",
+            "yesterday",
+            "2024-13-01 00:00:00",
+            "2024-01-01 24:00:00",
+            "2024-02-31 00:00:00",
+            "2023-02-29 00:00:00",
+            "100000000000000-01-01 00:00:00",
+        ] {
+            assert!(parse_time_bound(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn windows_resolve_relative_bounds_against_the_first_row() {
+        let dlt = stream(&[100, 101, 102, 103]);
+        let window = |from, to| TimeWindow {
+            clock: TimeClock::Storage,
+            from,
+            to,
+        };
+        let matching = |window: TimeWindow| {
+            (0..dlt.len())
+                .filter(|&row| window.matches(&dlt, row))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching(window(
+                Some(TimeBound::Absolute(101 * NANOS_PER_SECOND)),
+                None
+            )),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            matching(window(
+                Some(TimeBound::Relative(NANOS_PER_SECOND)),
+                Some(TimeBound::Relative(3 * NANOS_PER_SECOND)),
+            )),
+            vec![1, 2]
+        );
+        assert_eq!(
+            window(None, Some(TimeBound::Relative(NANOS_PER_SECOND / 2))).to_string(),
+            "storage time until +0.500000"
+        );
+    }
+
+    #[test]
+    fn nearest_row_picks_the_closer_neighbour() {
+        let dlt = stream(&[100, 102, 106, 110]);
+        let rows = [0, 1, 2, 3];
+        let at =
+            |seconds: u64| nearest_row(&dlt, &rows, TimeClock::Storage, seconds * NANOS_PER_SECOND);
+
+        assert_eq!(at(0), Some(0));
+        assert_eq!(at(101), Some(0));
+        assert_eq!(at(103), Some(1));
+        assert_eq!(at(105), Some(2));
+        assert_eq!(at(106), Some(2));
+        assert_eq!(at(999), Some(3));
+        assert_eq!(
+            nearest_row(&dlt, &rows[2..], TimeClock::Storage, 0),
+            Some(0)
+        );
+        assert_eq!(nearest_row(&dlt, &[], TimeClock::Storage, 0), None);
+    }
+}
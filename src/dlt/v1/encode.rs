@@ -0,0 +1,82 @@
+use anyhow::{Result, anyhow, bail};
+
+use super::protocol::*;
+use crate::dlt::writer::{Content, MessageBuilder, message_len};
+
+/// Encode a v1 message: standard header, extended header and payload. No
+/// storage header is added.
+///
+/// The extended header is written for verbose and control messages, and for
+/// non-verbose messages with an APID or CTID. Non-verbose payloads start
+/// with the message ID, in the payload byte order.
+pub(crate) fn message(message: &MessageBuilder) -> Result<Vec<u8>> {
+    if message.source_location.is_some()
+        || !message.tags.is_empty()
+        || message.privacy_level.is_some()
+        || message.segment.is_some()
+    {
+        bail!("v1 messages cannot carry a source location, tags, privacy level or segment");
+    }
+
+    let big_endian = message.big_endian;
+    let verbose = matches!(message.content, Content::Verbose(_));
+    let payload = match &message.content {
+        Content::NonVerbose {
+            message_id,
+            arguments,
+        } => {
+            let mut payload = if big_endian {
+                message_id.to_be_bytes().to_vec()
+            } else {
+                message_id.to_le_bytes().to_vec()
+            };
+            payload.extend_from_slice(arguments);
+            payload
+        }
+        _ => message.payload(big_endian)?,
+    };
+    let ueh = !matches!(message.content, Content::NonVerbose { .. })
+        || message.apid.is_some()
+        || message.ctid.is_some();
+
+    let mut htyp = HTYP_VERSION_1;
+    let mut len = STD_HEADER_MIN + payload.len();
+    for (present, flag, size) in [
+        (ueh, HTYP_UEH, EXT_HEADER_SIZE),
+        (big_endian, HTYP_MSBF, 0),
+        (message.ecu.is_some(), HTYP_WEID, SIZE_WEID),
+        (message.session_id.is_some(), HTYP_WSID, SIZE_WSID),
+        (message.timestamp_ns.is_some(), HTYP_WTMS, SIZE_WTMS),
+    ] {
+        if present {
+            htyp |= flag;
+            len += size;
+        }
+    }
+
+    let mut msg = Vec::with_capacity(len);
+    msg.push(htyp);
+    msg.push(message.counter);
+    msg.extend_from_slice(&message_len(len)?.to_be_bytes());
+    if let Some(ecu) = message.ecu {
+        msg.extend_from_slice(&ecu);
+    }
+    if let Some(session_id) = message.session_id {
+        msg.extend_from_slice(&session_id.to_be_bytes());
+    }
+    if let Some(ns) = message.timestamp_ns {
+        // 0.1 ms ticks.
+        let ticks = u32::try_from(ns / 100_000)
+            .map_err(|_| anyhow!("timestamp {ns} ns exceeds the v1 range"))?;
+        msg.extend_from_slice(&ticks.to_be_bytes());
+    }
+    if ueh {
+        let (mstp, mtin) = message.message_info();
+        msg.push((mstp << 1) | (mtin << 4) | u8::from(verbose));
+        msg.push(message.argument_count()?);
+        msg.extend_from_slice(&message.apid.unwrap_or_default());
+        msg.extend_from_slice(&message.ctid.unwrap_or_default());
+    }
+    msg.extend_from_slice(&payload);
+    Ok(msg)
+}
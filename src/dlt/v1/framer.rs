@@ -21,6 +21,9 @@ pub struct ScanOutput {
     pub default_storage_ecu: Option<[u8; 4]>,
     /// Sparse per-frame storage ECU overrides: (frame_index, ecu).
     pub storage_ecu_overrides: Vec<(usize, [u8; 4])>,
+    /// Offset where a later scan of the same data, grown by appended bytes,
+    /// should resume. `data.len()` unless scanning with `scan_frames_following`.
+    pub resume_offset: usize,
 }
 
 /// Scan `data` for DLT v1 frames, returning one `Frame` per valid v1 message
@@ -32,12 +35,26 @@ pub struct ScanOutput {
 ///
 /// On error the scanner resyncs to the next `DLT\x01` marker.
 pub fn scan_frames(data: &[u8], file_index: u16) -> ScanOutput {
+    scan(data, file_index, false)
+}
+
+/// Like `scan_frames`, for data that is still being written.
+///
+/// Scanning stops at a trailing frame that runs past the end of `data`, and
+/// `resume_offset` points at it (or at a split `DLT\x01` marker) instead of
+/// reporting it as an error.
+pub fn scan_frames_following(data: &[u8], file_index: u16) -> ScanOutput {
+    scan(data, file_index, true)
+}
+
+fn scan(data: &[u8], file_index: u16, following: bool) -> ScanOutput {
     let finder = Finder::new(STORAGE_HEADER_PATTERN);
     let mut frames = Vec::new();
     let mut errors = Vec::new();
     let mut default_storage_ecu = None;
     let mut storage_ecu_overrides = Vec::new();
     let mut search_start = 0;
+    let mut incomplete_at = None;
 
     while let Some(rel_pos) = finder.find(&data[search_start..]) {
         let pos = search_start + rel_pos;
@@ -45,6 +62,10 @@ pub fn scan_frames(data: &[u8], file_index: u16) -> ScanOutput {
 
         // Need at least the storage header + v1 standard header minimum
         if storage_end + STD_HEADER_MIN > data.len() {
+            if following {
+                incomplete_at = Some(pos);
+                break;
+            }
             errors.push(ParseError {
                 file_index,
                 byte_offset: pos as u64,
@@ -94,6 +115,10 @@ pub fn scan_frames(data: &[u8], file_index: u16) -> ScanOutput {
         }
 
         if msg_start + len as usize > data.len() {
+            if following {
+                incomplete_at = Some(pos);
+                break;
+            }
             errors.push(ParseError {
                 file_index,
                 byte_offset: pos as u64,
@@ -123,11 +148,20 @@ pub fn scan_frames(data: &[u8], file_index: u16) -> ScanOutput {
         search_start = msg_start + len as usize;
     }
 
+    let resume_offset = match incomplete_at {
+        Some(pos) => pos,
+        None if following => {
+            (data.len() - partial_marker_len(data, STORAGE_HEADER_PATTERN)).max(search_start)
+        }
+        None => data.len(),
+    };
+
     ScanOutput {
         frames,
         errors,
         default_storage_ecu,
         storage_ecu_overrides,
+        resume_offset,
     }
 }
 
@@ -307,6 +341,24 @@ mod tests {
         assert_eq!(out.errors[0].kind, ParseErrorKind::Truncated);
     }
 
+    #[test]
+    fn following_leaves_incomplete_tail_for_resume() {
+        let frame = minimal_v1_frame();
+        let mut data = frame.clone();
+        data.extend_from_slice(&frame[..frame.len() - 1]);
+
+        let out = scan_frames_following(&data, 0);
+        assert_eq!(out.frames.len(), 1);
+        assert!(out.errors.is_empty());
+        assert_eq!(out.resume_offset, frame.len());
+
+        data.truncate(frame.len() + 3);
+        let out = scan_frames_following(&data, 0);
+        assert_eq!(out.frames.len(), 1);
+        assert_eq!(out.resume_offset, frame.len(), "split marker is kept");
+        assert_eq!(scan_frames(&data, 0).resume_offset, data.len());
+    }
+
     #[test]
     fn mixed_storage_ecu_uses_sparse_overrides() {
         let mut data = minimal_v1_frame();
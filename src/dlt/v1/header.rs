@@ -5,6 +5,8 @@ use crate::dlt::error::ParseErrorKind;
 pub struct ParsedHeader {
     pub htyp: u8,
     pub msin: u8,
+    /// Message counter (MCNT).
+    pub message_counter: u8,
     pub ecu: Option<[u8; 4]>,
     pub apid: Option<[u8; 4]>,
     pub ctid: Option<[u8; 4]>,
@@ -20,7 +22,7 @@ pub struct ParsedHeader {
 ///
 /// `msg` starts at the standard header (HTYP byte) and has the full message length.
 /// Returns a specific `ParseErrorKind` when the message is malformed.
-pub(super) fn parse_v1_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind> {
+pub(crate) fn parse_v1_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind> {
     debug_assert!(msg.len() >= STD_HEADER_MIN);
 
     let htyp = msg[0];
@@ -89,6 +91,7 @@ pub(super) fn parse_v1_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
     Ok(ParsedHeader {
         htyp,
         msin: msin_byte,
+        message_counter: msg[1],
         ecu,
         apid,
         ctid,
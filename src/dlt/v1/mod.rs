@@ -1,35 +1,47 @@
+pub(crate) mod encode;
 mod framer;
-mod header;
+pub(crate) mod header;
 mod payload;
-mod protocol;
+pub(crate) mod protocol;
 
 use anyhow::Result;
-use memmap2::Mmap;
 use std::fmt;
-use std::fs::File;
 use std::path::PathBuf;
 
+use crate::dlt::control::{self, ControlMessage};
 use crate::dlt::error::{ParseError, ParseErrorKind};
 use crate::dlt::intern::InternTable;
-use framer::scan_frames;
+use crate::dlt::source::{MappedFile, Source, live_buffer_index};
+use crate::dlt::storage::STORAGE_HEADER_SIZE;
+use crate::dlt::store::NonVerbosePayload;
+use crate::dlt::verbose::Argument;
+use framer::{scan_frames, scan_frames_following};
 use header::parse_v1_header;
-use protocol::{msin_mstp, msin_mtin};
+use protocol::{
+    EXT_HEADER_SIZE, MESSAGE_TYPE_CONTROL, htyp_has_msbf, htyp_has_ueh, msin_is_verb, msin_mstp,
+    msin_mtin,
+};
 
 /// DLT v1 parsed data in columnar (struct-of-arrays) layout.
 ///
-/// Payloads are stored lazily as mmap-backed byte ranges, decoded on demand.
+/// Payloads are stored lazily as byte ranges into the mapped files (or the
+/// live-stream buffer), decoded on demand.
 pub struct Dlt {
-    mmaps: Vec<Mmap>,
+    sources: Vec<Source>,
     intern: InternTable,
     htyp: Vec<u8>,
     msin: Vec<u8>,
+    message_counter: Vec<u8>,
     storage_timestamp_ns: Vec<u64>,
     message_timestamp_ns: Vec<u64>,
     ecu: Vec<u16>,
     apid: Vec<u16>,
     ctid: Vec<u16>,
     session_id: Vec<u32>,
-    payload_loc: Vec<(u16, u32, u32)>, // (mmap_index, offset, len)
+    /// Message ID of non-verbose messages, read from the payload.
+    message_id: Vec<Option<u32>>,
+    payload_loc: Vec<(u16, u32, u32)>, // (source_index, offset, len)
+    frame_loc: Vec<(u32, u32)>,        // (storage header offset, frame len incl. storage header)
 }
 
 impl Dlt {
@@ -38,124 +50,225 @@ impl Dlt {
     /// Returns successfully parsed messages alongside any errors encountered.
     /// Non-v1 messages and malformed frames are recorded as errors and skipped.
     pub fn open(paths: Vec<PathBuf>) -> Result<(Self, Vec<ParseError>)> {
-        let mut mmaps = Vec::with_capacity(paths.len());
-        let mut intern = InternTable::new();
-        let mut htyp = Vec::new();
-        let mut msin = Vec::new();
-        let mut storage_timestamp_ns = Vec::new();
-        let mut message_timestamp_ns = Vec::new();
-        let mut ecu = Vec::new();
-        let mut apid = Vec::new();
-        let mut ctid = Vec::new();
-        let mut session_id = Vec::new();
-        let mut payload_loc = Vec::new();
+        Self::open_files(paths, false)
+    }
+
+    /// Open DLT v1 files that are still being written, for use with `refresh`.
+    ///
+    /// A trailing frame that is not complete yet is left for a later
+    /// `refresh` instead of being reported as an error. The files are read
+    /// into memory rather than mapped, so one truncated while it is followed
+    /// makes `refresh` fail instead of faulting on row access.
+    pub fn open_following(paths: Vec<PathBuf>) -> Result<(Self, Vec<ParseError>)> {
+        Self::open_files(paths, true)
+    }
+
+    fn open_files(paths: Vec<PathBuf>, following: bool) -> Result<(Self, Vec<ParseError>)> {
+        let mut dlt = Self::empty();
         let mut all_errors = Vec::new();
 
         for (file_idx, path) in paths.iter().enumerate() {
-            let file = File::open(path)?;
-            // SAFETY: the file is read-only and the Mmap is kept alive for the
-            // lifetime of the Dlt struct.
-            let mmap = unsafe { Mmap::map(&file)? };
-
-            let scan = scan_frames(&mmap, file_idx as u16);
-            all_errors.extend(scan.errors);
-
-            let mut next_override = 0usize;
-            for (frame_idx, frame) in scan.frames.into_iter().enumerate() {
-                let msg = &mmap[frame.msg_start..frame.msg_start + frame.msg_len];
-
-                // Resolve this frame's storage ECU first so override cursor stays
-                // aligned even when message ECU is present and takes precedence.
-                let mut storage_ecu = scan.default_storage_ecu;
-                if next_override < scan.storage_ecu_overrides.len()
-                    && scan.storage_ecu_overrides[next_override].0 == frame_idx
-                {
-                    storage_ecu = Some(scan.storage_ecu_overrides[next_override].1);
-                    next_override += 1;
+            let mut mapped = if following {
+                MappedFile::open_following(path)?
+            } else {
+                MappedFile::open(path)?
+            };
+            let (errors, resume_offset) = dlt.ingest(file_idx as u16, mapped.data(), 0, following);
+            mapped.resume_offset = resume_offset;
+            all_errors.extend(errors);
+            dlt.sources.push(Source::Mapped(mapped));
+        }
+
+        Ok((dlt, all_errors))
+    }
+
+    /// Parse frames appended to the files since they were opened or last
+    /// refreshed, resuming after the last complete frame of each file.
+    ///
+    /// New rows are appended in file order; existing rows are untouched.
+    pub fn refresh(&mut self) -> Result<Vec<ParseError>> {
+        let mut all_errors = Vec::new();
+        for source_idx in 0..self.sources.len() {
+            let Source::Mapped(mapped) = &mut self.sources[source_idx] else {
+                continue;
+            };
+            if !mapped.remap()? {
+                continue;
+            }
+            let Source::Mapped(mut mapped) =
+                std::mem::replace(&mut self.sources[source_idx], Source::Buffer(Vec::new()))
+            else {
+                unreachable!("source was just matched as mapped");
+            };
+            let (errors, resume_offset) =
+                self.ingest(source_idx as u16, mapped.data(), mapped.resume_offset, true);
+            mapped.resume_offset = resume_offset;
+            all_errors.extend(errors);
+            self.sources[source_idx] = Source::Mapped(mapped);
+        }
+        Ok(all_errors)
+    }
+
+    /// An empty store fed with stored frames through `append`.
+    pub fn stream() -> Self {
+        let mut dlt = Self::empty();
+        dlt.sources.push(Source::Buffer(Vec::new()));
+        dlt
+    }
+
+    /// Append stored frames (storage header and message) to the live-stream
+    /// buffer and parse them into new rows.
+    ///
+    /// `frames` must hold whole frames; byte offsets of the new rows continue
+    /// from the end of the previously appended data. Once the buffer would
+    /// outgrow 32-bit offsets, new rows go to a fresh buffer source with the
+    /// next file index.
+    pub fn append(&mut self, frames: &[u8]) -> Vec<ParseError> {
+        let source_idx = live_buffer_index(&mut self.sources, frames.len());
+        let Some(Source::Buffer(buffer)) = self.sources.last_mut() else {
+            unreachable!("a buffer source was just ensured");
+        };
+        let mut buffer = std::mem::take(buffer);
+        let start = buffer.len();
+        buffer.extend_from_slice(frames);
+
+        let (errors, _) = self.ingest(source_idx as u16, &buffer, start, false);
+        self.sources[source_idx] = Source::Buffer(buffer);
+        errors
+    }
+
+    fn empty() -> Self {
+        Dlt {
+            sources: Vec::new(),
+            intern: InternTable::new(),
+            htyp: Vec::new(),
+            msin: Vec::new(),
+            storage_timestamp_ns: Vec::new(),
+            message_timestamp_ns: Vec::new(),
+            ecu: Vec::new(),
+            apid: Vec::new(),
+            ctid: Vec::new(),
+            session_id: Vec::new(),
+            message_id: Vec::new(),
+            message_counter: Vec::new(),
+            payload_loc: Vec::new(),
+            frame_loc: Vec::new(),
+        }
+    }
+
+    /// Parse the frames in `data[start..]` into new rows referencing source `file_idx`.
+    ///
+    /// Also returns the offset in `data` where a later scan should resume;
+    /// with `following`, an incomplete trailing frame is left there.
+    fn ingest(
+        &mut self,
+        file_idx: u16,
+        data: &[u8],
+        start: usize,
+        following: bool,
+    ) -> (Vec<ParseError>, usize) {
+        let scan = if following {
+            scan_frames_following(&data[start..], file_idx)
+        } else {
+            scan_frames(&data[start..], file_idx)
+        };
+        let mut all_errors: Vec<ParseError> = scan
+            .errors
+            .into_iter()
+            .map(|error| ParseError {
+                byte_offset: error.byte_offset + start as u64,
+                ..error
+            })
+            .collect();
+
+        let mut next_override = 0usize;
+        for (frame_idx, frame) in scan.frames.into_iter().enumerate() {
+            let msg_start = start + frame.msg_start;
+            let msg = &data[msg_start..msg_start + frame.msg_len];
+
+            // Resolve this frame's storage ECU first so override cursor stays
+            // aligned even when message ECU is present and takes precedence.
+            let mut storage_ecu = scan.default_storage_ecu;
+            if next_override < scan.storage_ecu_overrides.len()
+                && scan.storage_ecu_overrides[next_override].0 == frame_idx
+            {
+                storage_ecu = Some(scan.storage_ecu_overrides[next_override].1);
+                next_override += 1;
+            }
+
+            let hdr = match parse_v1_header(msg) {
+                Ok(hdr) => hdr,
+                Err(kind) => {
+                    all_errors.push(ParseError {
+                        file_index: file_idx,
+                        byte_offset: msg_start as u64,
+                        kind,
+                    });
+                    continue;
                 }
+            };
 
-                let hdr = match parse_v1_header(msg) {
-                    Ok(hdr) => hdr,
-                    Err(kind) => {
+            let ecu_id = match &hdr.ecu {
+                Some(b) => {
+                    let ecu_str = std::str::from_utf8(b).unwrap_or("");
+                    self.intern.insert(ecu_str.trim_end_matches('\0'))
+                }
+                None => {
+                    let Some(storage_ecu) = storage_ecu else {
                         all_errors.push(ParseError {
-                            file_index: file_idx as u16,
-                            byte_offset: frame.msg_start as u64,
-                            kind,
+                            file_index: file_idx,
+                            byte_offset: msg_start as u64,
+                            kind: ParseErrorKind::InvalidStandardHeader,
                         });
                         continue;
-                    }
-                };
-
-                let ecu_id = match &hdr.ecu {
-                    Some(b) => {
-                        let ecu_str = std::str::from_utf8(b).unwrap_or("");
-                        intern.insert(ecu_str.trim_end_matches('\0'))
-                    }
-                    None => {
-                        let Some(storage_ecu) = storage_ecu else {
-                            all_errors.push(ParseError {
-                                file_index: file_idx as u16,
-                                byte_offset: frame.msg_start as u64,
-                                kind: ParseErrorKind::InvalidStandardHeader,
-                            });
-                            continue;
-                        };
-
-                        let ecu_str = std::str::from_utf8(&storage_ecu).unwrap_or("");
-                        intern.insert(ecu_str.trim_end_matches('\0'))
-                    }
-                };
-                let apid_id = match &hdr.apid {
-                    Some(b) => {
-                        let apid_str = std::str::from_utf8(b).unwrap_or("");
-                        intern.insert(apid_str.trim_end_matches('\0'))
-                    }
-                    None => intern.insert(""),
-                };
-                let ctid_id = match &hdr.ctid {
-                    Some(b) => {
-                        let ctid_str = std::str::from_utf8(b).unwrap_or("");
-                        intern.insert(ctid_str.trim_end_matches('\0'))
-                    }
-                    None => intern.insert(""),
-                };
-
-                htyp.push(hdr.htyp);
-                msin.push(hdr.msin);
-                ecu.push(ecu_id);
-                apid.push(apid_id);
-                ctid.push(ctid_id);
-                session_id.push(hdr.session_id.unwrap_or(0));
-                storage_timestamp_ns.push(frame.storage_timestamp_ns);
-                message_timestamp_ns.push(hdr.message_timestamp_ns);
-
-                let payload_offset_in_mmap = frame.msg_start + hdr.payload_offset;
-                payload_loc.push((
-                    file_idx as u16,
-                    payload_offset_in_mmap as u32,
-                    hdr.payload_len as u32,
-                ));
-            }
+                    };
 
-            mmaps.push(mmap);
+                    let ecu_str = std::str::from_utf8(&storage_ecu).unwrap_or("");
+                    self.intern.insert(ecu_str.trim_end_matches('\0'))
+                }
+            };
+            let apid_id = match &hdr.apid {
+                Some(b) => {
+                    let apid_str = std::str::from_utf8(b).unwrap_or("");
+                    self.intern.insert(apid_str.trim_end_matches('\0'))
+                }
+                None => self.intern.insert(""),
+            };
+            let ctid_id = match &hdr.ctid {
+                Some(b) => {
+                    let ctid_str = std::str::from_utf8(b).unwrap_or("");
+                    self.intern.insert(ctid_str.trim_end_matches('\0'))
+                }
+                None => self.intern.insert(""),
+            };
+
+            self.htyp.push(hdr.htyp);
+            self.msin.push(hdr.msin);
+            self.message_counter.push(hdr.message_counter);
+            self.ecu.push(ecu_id);
+            self.apid.push(apid_id);
+            self.ctid.push(ctid_id);
+            self.session_id.push(hdr.session_id.unwrap_or(0));
+            self.storage_timestamp_ns.push(frame.storage_timestamp_ns);
+            self.message_timestamp_ns.push(hdr.message_timestamp_ns);
+
+            let payload_offset_in_source = msg_start + hdr.payload_offset;
+            let payload =
+                &data[payload_offset_in_source..payload_offset_in_source + hdr.payload_len];
+            self.message_id
+                .push(payload::non_verbose_message_id(hdr.htyp, hdr.msin, payload));
+            self.payload_loc.push((
+                file_idx,
+                payload_offset_in_source as u32,
+                hdr.payload_len as u32,
+            ));
+            self.frame_loc.push((
+                (msg_start - STORAGE_HEADER_SIZE) as u32,
+                (STORAGE_HEADER_SIZE + frame.msg_len) as u32,
+            ));
         }
 
-        Ok((
-            Dlt {
-                mmaps,
-                intern,
-                htyp,
-                msin,
-                storage_timestamp_ns,
-                message_timestamp_ns,
-                ecu,
-                apid,
-                ctid,
-                session_id,
-                payload_loc,
-            },
-            all_errors,
-        ))
+        (all_errors, start + scan.resume_offset)
     }
 
     pub fn len(&self) -> usize {
@@ -198,9 +311,46 @@ impl Dlt {
         self.session_id[row]
     }
 
+    /// Message counter (MCNT) from the standard header.
+    pub fn message_counter(&self, row: usize) -> u8 {
+        self.message_counter[row]
+    }
+
+    /// Whether the payload uses verbose (self-describing) encoding.
+    pub fn is_verbose(&self, row: usize) -> bool {
+        htyp_has_ueh(self.htyp[row]) && msin_is_verb(self.msin[row])
+    }
+
+    /// Number of arguments (NOAR) from the extended header, 0 without one.
+    pub fn argument_count(&self, row: usize) -> u8 {
+        if !htyp_has_ueh(self.htyp[row]) {
+            return 0;
+        }
+        // The extended header ends where the payload starts; NOAR follows MSIN.
+        let (source_idx, offset, _) = self.payload_loc[row];
+        self.sources[source_idx as usize][offset as usize - EXT_HEADER_SIZE + 1]
+    }
+
+    /// Index of the source file in the paths list.
+    pub fn file_index(&self, row: usize) -> u16 {
+        self.payload_loc[row].0
+    }
+
+    /// Byte offset of the message's storage header within its source file.
+    pub fn byte_offset(&self, row: usize) -> u64 {
+        self.frame_loc[row].0 as u64
+    }
+
+    /// The complete stored frame (storage header and message) as it appears on disk.
+    pub fn frame_raw(&self, row: usize) -> &[u8] {
+        let (offset, len) = self.frame_loc[row];
+        let source_idx = self.payload_loc[row].0;
+        &self.sources[source_idx as usize][offset as usize..(offset + len) as usize]
+    }
+
     pub fn payload_raw(&self, row: usize) -> &[u8] {
-        let (mmap_idx, offset, len) = self.payload_loc[row];
-        &self.mmaps[mmap_idx as usize][offset as usize..(offset + len) as usize]
+        let (source_idx, offset, len) = self.payload_loc[row];
+        &self.sources[source_idx as usize][offset as usize..(offset + len) as usize]
     }
 
     pub fn payload_text(&self, row: usize) -> String {
@@ -210,6 +360,40 @@ impl Dlt {
         payload::decode_payload(htyp, msin, raw)
     }
 
+    /// Typed verbose arguments; empty for non-verbose and control messages.
+    pub fn payload_arguments(&self, row: usize) -> Vec<Argument> {
+        payload::decode_arguments(self.htyp[row], self.msin[row], self.payload_raw(row))
+    }
+
+    /// Message ID and argument bytes of a non-verbose message.
+    ///
+    /// v1 carries the message ID in the first four payload bytes, in the
+    /// byte order given by the MSBF flag.
+    pub fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>> {
+        let message_id = self.message_id[row]?;
+        Some(NonVerbosePayload {
+            message_id,
+            arguments: &self.payload_raw(row)[4..],
+            big_endian: htyp_has_msbf(self.htyp[row]),
+        })
+    }
+
+    /// Message ID of a non-verbose message; `None` for verbose and control
+    /// messages.
+    pub fn message_id(&self, row: usize) -> Option<u32> {
+        self.message_id[row]
+    }
+
+    /// Decoded control message; `None` for log, trace and network messages.
+    pub fn control_message(&self, row: usize) -> Option<ControlMessage> {
+        let htyp = self.htyp[row];
+        let msin = self.msin[row];
+        if !htyp_has_ueh(htyp) || msin_mstp(msin) != MESSAGE_TYPE_CONTROL {
+            return None;
+        }
+        control::decode(self.payload_raw(row), htyp_has_msbf(htyp), msin_mtin(msin))
+    }
+
     /// Sorted, deduplicated list of all APID strings seen.
     pub fn unique_apids(&self) -> Vec<&str> {
         unique_interned(&self.apid, &self.intern)
@@ -244,7 +428,7 @@ impl fmt::Debug for Dlt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Dlt")
             .field("messages", &self.len())
-            .field("files", &self.mmaps.len())
+            .field("files", &self.sources.len())
             .finish()
     }
 }
@@ -252,6 +436,7 @@ impl fmt::Debug for Dlt {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dlt::writer::MessageBuilder;
     use std::io::Write;
     use std::path::PathBuf;
 
@@ -309,6 +494,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn control_request_bodies_decoded() {
+        let path = test_data_path("testfile_control_messages.dlt");
+        let (dlt, _) = Dlt::open(vec![path]).unwrap();
+        let payloads: Vec<String> = (0..dlt.len()).map(|row| dlt.payload_text(row)).collect();
+        assert_eq!(
+            payloads,
+            vec![
+                "[control set_default_log_level] log_level=info com=remo",
+                "[control set_default_trace_status] trace_status=off com=remo",
+                "[control set_verbose_mode] on",
+                "[control set_timing_packets] off",
+            ]
+        );
+    }
+
     #[test]
     fn unique_apids_sorted_deduped() {
         let path = test_data_path("testfile_control_messages.dlt");
@@ -337,6 +538,27 @@ mod tests {
         assert!(!ecus.is_empty());
     }
 
+    #[test]
+    fn non_verbose_message_id_is_stored_per_row() {
+        let mut stream = Dlt::stream();
+        let non_verbose = MessageBuilder::new()
+            .with_non_verbose(0x1234, &[0xAB, 0xCD])
+            .build(1)
+            .unwrap();
+        let verbose = MessageBuilder::new()
+            .with_verbose_string("text")
+            .build(1)
+            .unwrap();
+        assert!(stream.append(&[non_verbose, verbose].concat()).is_empty());
+
+        assert_eq!(stream.message_id(0), Some(0x1234));
+        assert_eq!(
+            stream.non_verbose_payload(0).unwrap().arguments,
+            [0xAB, 0xCD]
+        );
+        assert_eq!(stream.message_id(1), None);
+    }
+
     #[test]
     fn debug_shows_counts() {
         let path = test_data_path("testfile_control_messages.dlt");
@@ -346,6 +568,64 @@ mod tests {
         assert!(dbg.contains("files"));
     }
 
+    #[test]
+    fn append_in_chunks_matches_open() {
+        let path = test_data_path("testfile_single_payloads.dlt");
+        let (opened, _) = Dlt::open(vec![path.clone()]).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let mut live = Dlt::stream();
+        let first_len = opened.frame_raw(0).len();
+        assert!(live.append(&bytes[..first_len]).is_empty());
+        assert_eq!(live.len(), 1);
+        assert!(live.append(&bytes[first_len..]).is_empty());
+
+        assert_eq!(live.len(), opened.len());
+        for row in 0..opened.len() {
+            assert_eq!(live.frame_raw(row), opened.frame_raw(row));
+            assert_eq!(live.byte_offset(row), opened.byte_offset(row));
+            assert_eq!(live.payload_text(row), opened.payload_text(row));
+            assert_eq!(live.apid(row), opened.apid(row));
+        }
+    }
+
+    #[test]
+    fn refresh_picks_up_appended_frames() {
+        use std::io::Write;
+
+        let path = test_data_path("testfile_single_payloads.dlt");
+        let (opened, _) = Dlt::open(vec![path.clone()]).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let first_len = opened.frame_raw(0).len();
+
+        let dir = tempfile::tempdir().unwrap();
+        let growing = dir.path().join("growing.dlt");
+        let mut file = std::fs::File::create(&growing).unwrap();
+        // End on a split `DLT\x01` marker of the second frame.
+        file.write_all(&bytes[..first_len + 2]).unwrap();
+
+        let (mut followed, errors) = Dlt::open_following(vec![growing]).unwrap();
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(followed.len(), 1);
+
+        // A partially written frame is left for the next refresh.
+        file.write_all(&bytes[first_len + 2..first_len + 20])
+            .unwrap();
+        assert!(followed.refresh().unwrap().is_empty());
+        assert_eq!(followed.len(), 1);
+
+        file.write_all(&bytes[first_len + 20..]).unwrap();
+        assert!(followed.refresh().unwrap().is_empty());
+        assert!(followed.refresh().unwrap().is_empty());
+
+        assert_eq!(followed.len(), opened.len());
+        for row in 0..opened.len() {
+            assert_eq!(followed.frame_raw(row), opened.frame_raw(row));
+            assert_eq!(followed.byte_offset(row), opened.byte_offset(row));
+            assert_eq!(followed.payload_text(row), opened.payload_text(row));
+        }
+    }
+
     #[test]
     fn multi_file_open() {
         let paths = vec![
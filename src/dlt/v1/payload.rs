@@ -1,5 +1,7 @@
-use super::protocol::{MESSAGE_TYPE_CONTROL, htyp_has_msbf, htyp_has_ueh, msin_is_verb, msin_mstp};
-use crate::dlt::payload;
+use super::protocol::{
+    MESSAGE_TYPE_CONTROL, htyp_has_msbf, htyp_has_ueh, msin_is_verb, msin_mstp, msin_mtin,
+};
+use crate::dlt::{payload, verbose};
 
 /// Decode a v1 payload slice into a human-readable string.
 ///
@@ -20,9 +22,33 @@ pub fn decode_payload(htyp: u8, msin: u8, raw: &[u8]) -> String {
             return payload::decode_verbose(raw, big_endian);
         }
         if msin_mstp(msin) == MESSAGE_TYPE_CONTROL {
-            return payload::decode_control(raw, big_endian);
+            return payload::decode_control(raw, big_endian, msin_mtin(msin));
         }
     }
 
     payload::decode_non_verbose(raw, big_endian)
 }
+
+/// Message ID of a v1 non-verbose payload: its first four bytes, in the
+/// byte order given by the MSBF flag. `None` for verbose and control
+/// messages and for payloads shorter than an ID.
+pub fn non_verbose_message_id(htyp: u8, msin: u8, raw: &[u8]) -> Option<u32> {
+    if htyp_has_ueh(htyp) && (msin_is_verb(msin) || msin_mstp(msin) == MESSAGE_TYPE_CONTROL) {
+        return None;
+    }
+    let id: [u8; 4] = raw.get(..4)?.try_into().ok()?;
+    Some(if htyp_has_msbf(htyp) {
+        u32::from_be_bytes(id)
+    } else {
+        u32::from_le_bytes(id)
+    })
+}
+
+/// Decode the typed arguments of a v1 verbose payload; empty for other payloads.
+pub fn decode_arguments(htyp: u8, msin: u8, raw: &[u8]) -> Vec<verbose::Argument> {
+    if htyp_has_ueh(htyp) && msin_is_verb(msin) {
+        verbose::decode_arguments(raw, htyp_has_msbf(htyp))
+    } else {
+        Vec::new()
+    }
+}
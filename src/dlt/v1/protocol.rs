@@ -1,11 +1,14 @@
 #![allow(dead_code)]
 
 /// HTYP bitfield definitions.
-const HTYP_UEH: u8 = 0x01; // use extended header
-const HTYP_MSBF: u8 = 0x02; // payload byte order: most-significant-byte first
-const HTYP_WEID: u8 = 0x04; // with ECU ID
-const HTYP_WSID: u8 = 0x08; // with session ID
-const HTYP_WTMS: u8 = 0x10; // with timestamp
+pub const HTYP_UEH: u8 = 0x01; // use extended header
+pub const HTYP_MSBF: u8 = 0x02; // payload byte order: most-significant-byte first
+pub const HTYP_WEID: u8 = 0x04; // with ECU ID
+pub const HTYP_WSID: u8 = 0x08; // with session ID
+pub const HTYP_WTMS: u8 = 0x10; // with timestamp
+
+/// Protocol version 1 in HTYP bits 5-7.
+pub const HTYP_VERSION_1: u8 = 0x20;
 
 pub const STD_HEADER_MIN: usize = 4; // HTYP(1) + MCNT(1) + LEN(2)
 pub const EXT_HEADER_SIZE: usize = 10; // MSIN(1) + NOAR(1) + APID(4) + CTID(4)
@@ -0,0 +1,120 @@
+use anyhow::{Result, anyhow};
+
+use super::header::Segment;
+use super::protocol::*;
+use crate::dlt::writer::{Content, MessageBuilder, message_len};
+
+/// Encode a v2 message: base header, extension header and big-endian
+/// payload. No storage header is added.
+///
+/// Control messages carry no TMSP2; non-verbose messages carry no MSIN or
+/// NOAR but their message ID (MSID).
+pub(crate) fn message(message: &MessageBuilder) -> Result<Vec<u8>> {
+    let cnti = match message.content {
+        Content::Verbose(_) => CNTI_VERBOSE,
+        Content::NonVerbose { .. } => CNTI_NON_VERBOSE,
+        Content::Control(_) => CNTI_CONTROL,
+    };
+    let wacid = message.apid.is_some() || message.ctid.is_some();
+    let htyp2 = build_htyp2_full(
+        cnti,
+        message.ecu.is_some(),
+        wacid,
+        message.session_id.is_some(),
+        PROTOCOL_VERSION_2,
+        message.source_location.is_some(),
+        !message.tags.is_empty(),
+        message.privacy_level.is_some(),
+        message.segment.is_some(),
+    );
+
+    let mut msg = Vec::new();
+    msg.extend_from_slice(&htyp2.to_be_bytes());
+    msg.push(message.counter);
+    msg.extend_from_slice(&[0, 0]); // LEN, patched below
+
+    if cnti != CNTI_NON_VERBOSE {
+        let (mstp, mtin) = message.message_info();
+        msg.push(build_msin(mstp, mtin));
+        msg.push(message.argument_count()?);
+    }
+    if cnti != CNTI_CONTROL {
+        msg.extend_from_slice(&encode_tmsp2(message.timestamp_ns.unwrap_or(0)));
+    }
+    if let Content::NonVerbose { message_id, .. } = message.content {
+        msg.extend_from_slice(&message_id.to_be_bytes());
+    }
+
+    // --- Extension header ---
+    if message.ecu.is_some() {
+        push_id(&mut msg, message.ecu);
+    }
+    if wacid {
+        push_id(&mut msg, message.apid);
+        push_id(&mut msg, message.ctid);
+    }
+    if let Some(session_id) = message.session_id {
+        msg.extend_from_slice(&session_id.to_be_bytes());
+    }
+    if let Some((file, line)) = &message.source_location {
+        push_short_string(&mut msg, file)?;
+        msg.extend_from_slice(&line.to_be_bytes());
+    }
+    if !message.tags.is_empty() {
+        let count = u8::try_from(message.tags.len())
+            .map_err(|_| anyhow!("{} tags exceed NOTG", message.tags.len()))?;
+        msg.push(count);
+        for tag in &message.tags {
+            push_short_string(&mut msg, tag)?;
+        }
+    }
+    if let Some(level) = message.privacy_level {
+        msg.push(level);
+    }
+    match &message.segment {
+        Some((segment, payload)) => {
+            push_segment(&mut msg, segment);
+            msg.extend_from_slice(payload);
+        }
+        None => msg.extend_from_slice(&message.payload(true)?),
+    }
+    let len = message_len(msg.len())?;
+    msg[5..7].copy_from_slice(&len.to_be_bytes());
+    Ok(msg)
+}
+
+/// A length-prefixed ID without its NUL padding, or an empty one.
+fn push_id(msg: &mut Vec<u8>, id: Option<[u8; 4]>) {
+    let id = id.unwrap_or_default();
+    let len = id.iter().position(|&b| b == 0).unwrap_or(id.len());
+    msg.push(len as u8);
+    msg.extend_from_slice(&id[..len]);
+}
+
+/// Segmentation information: its 8-bit length, the frame type, then the
+/// frame type's details.
+fn push_segment(msg: &mut Vec<u8>, segment: &Segment) {
+    let mut info = Vec::with_capacity(9);
+    match *segment {
+        Segment::First { total_length } => {
+            info.push(FRAME_TYPE_FIRST);
+            info.extend_from_slice(&total_length.to_be_bytes());
+        }
+        Segment::Consecutive { sequence } => {
+            info.push(FRAME_TYPE_CONSECUTIVE);
+            info.extend_from_slice(&sequence.to_be_bytes());
+        }
+        Segment::Last => info.push(FRAME_TYPE_LAST),
+        Segment::Abort { reason } => info.extend_from_slice(&[FRAME_TYPE_ABORT, reason]),
+    }
+    msg.push(info.len() as u8);
+    msg.extend_from_slice(&info);
+}
+
+/// A string preceded by its 8-bit length.
+fn push_short_string(msg: &mut Vec<u8>, text: &str) -> Result<()> {
+    let len = u8::try_from(text.len()).map_err(|_| anyhow!("{text:?} exceeds 255 bytes"))?;
+    msg.push(len);
+    msg.extend_from_slice(text.as_bytes());
+    Ok(())
+}
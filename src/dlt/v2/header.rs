@@ -1,9 +1,31 @@
 use super::protocol::*;
 use crate::dlt::error::ParseErrorKind;
 
+/// Segmentation information (WSGM) of one segment of a larger message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    /// Starts a sequence carrying `total_length` payload bytes in all.
+    First {
+        total_length: u64,
+    },
+    /// Numbered from 0 after each first segment.
+    Consecutive {
+        sequence: u32,
+    },
+    Last,
+    /// Discards the sequence in progress.
+    Abort {
+        reason: u8,
+    },
+}
+
 /// Parsed v2 header information.
-pub struct ParsedHeader {
+///
+/// Source file names and tags borrow from the message slice.
+pub struct ParsedHeader<'a> {
     pub htyp2: u32,
+    /// Message counter (MCNT).
+    pub message_counter: u8,
     pub apid: Option<[u8; 4]>,
     pub ctid: Option<[u8; 4]>,
     pub ecu: Option<[u8; 4]>,
@@ -11,6 +33,16 @@ pub struct ParsedHeader {
     pub message_timestamp_ns: u64,
     pub message_type: u8,
     pub message_type_info: u8,
+    /// Message ID (MSID) of non-verbose messages.
+    pub message_id: Option<u32>,
+    /// Source file name and line number (WSFLN).
+    pub source_location: Option<(&'a [u8], u32)>,
+    /// Tags (WTGS), in header order.
+    pub tags: Vec<&'a [u8]>,
+    /// Privacy level (WPVL).
+    pub privacy_level: Option<u8>,
+    /// Segmentation information (WSGM).
+    pub segment: Option<Segment>,
     /// Byte offset of the payload start within the message slice.
     pub payload_offset: usize,
     /// Payload length in bytes.
@@ -21,7 +53,7 @@ pub struct ParsedHeader {
 ///
 /// `msg` starts at the base header (HTYP2) and has length = LEN.
 /// Returns a specific `ParseErrorKind` when extension parsing fails.
-pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind> {
+pub(crate) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader<'_>, ParseErrorKind> {
     debug_assert!(msg.len() >= BASE_HEADER_MIN_SIZE);
 
     let htyp2 = u32::from_be_bytes(msg[0..4].try_into().unwrap());
@@ -56,10 +88,14 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
     }
 
     // MSID (4 bytes) for non-verbose
+    let mut message_id = None;
     if cnti == CNTI_NON_VERBOSE {
         if offset + 4 > msg.len() {
             return Err(ParseErrorKind::InvalidStandardHeader);
         }
+        message_id = Some(u32::from_be_bytes(
+            msg[offset..offset + 4].try_into().unwrap(),
+        ));
         offset += 4;
     }
 
@@ -68,6 +104,9 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
     let mut ctid = None;
     let mut ecu = None;
     let mut session_id = None;
+    let mut source_location = None;
+    let mut tags = Vec::new();
+    let mut privacy_level = None;
 
     // ECU ID (WEID) — length-prefixed
     if htyp2_has_weid(htyp2) {
@@ -142,10 +181,14 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
             return Err(ParseErrorKind::InvalidExtensionField);
         }
         let fina_len = msg[offset] as usize;
-        offset += 1 + fina_len;
-        if offset + 4 > msg.len() {
+        offset += 1;
+        if offset + fina_len + 4 > msg.len() {
             return Err(ParseErrorKind::InvalidExtensionField);
         }
+        let file_name = &msg[offset..offset + fina_len];
+        offset += fina_len;
+        let line = u32::from_be_bytes(msg[offset..offset + 4].try_into().unwrap());
+        source_location = Some((file_name, line));
         offset += 4; // LINR u32
     }
 
@@ -161,10 +204,12 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
                 return Err(ParseErrorKind::InvalidExtensionField);
             }
             let tag_len = msg[offset] as usize;
-            offset += 1 + tag_len;
-            if offset > msg.len() {
+            offset += 1;
+            if offset + tag_len > msg.len() {
                 return Err(ParseErrorKind::InvalidExtensionField);
             }
+            tags.push(&msg[offset..offset + tag_len]);
+            offset += tag_len;
         }
     }
 
@@ -173,14 +218,34 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
         if offset >= msg.len() {
             return Err(ParseErrorKind::InvalidExtensionField);
         }
+        privacy_level = Some(msg[offset]);
         offset += 1;
     }
 
-    // WSGM — segmentation (skip for now, not fully specified)
+    // WSGM — segmentation: length (u8) + frame type (u8) + frame type details
+    let mut segment = None;
     if htyp2_has_wsgm(htyp2) {
-        // Segmentation details are not yet specified in the issue;
-        // skip gracefully if present. We can't know the exact length,
-        // so we leave offset as-is and let payload_len absorb it.
+        if offset >= msg.len() {
+            return Err(ParseErrorKind::InvalidExtensionField);
+        }
+        let sgmt_len = msg[offset] as usize;
+        offset += 1;
+        if sgmt_len == 0 || offset + sgmt_len > msg.len() {
+            return Err(ParseErrorKind::InvalidExtensionField);
+        }
+        let details = &msg[offset + 1..offset + sgmt_len];
+        segment = Some(match msg[offset] {
+            FRAME_TYPE_FIRST if details.len() >= 8 => Segment::First {
+                total_length: u64::from_be_bytes(details[..8].try_into().unwrap()),
+            },
+            FRAME_TYPE_CONSECUTIVE if details.len() >= 4 => Segment::Consecutive {
+                sequence: u32::from_be_bytes(details[..4].try_into().unwrap()),
+            },
+            FRAME_TYPE_LAST => Segment::Last,
+            FRAME_TYPE_ABORT if !details.is_empty() => Segment::Abort { reason: details[0] },
+            _ => return Err(ParseErrorKind::InvalidExtensionField),
+        });
+        offset += sgmt_len;
     }
 
     let payload_offset = offset;
@@ -188,6 +253,7 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
 
     Ok(ParsedHeader {
         htyp2,
+        message_counter: msg[4],
         apid,
         ctid,
         ecu,
@@ -195,6 +261,11 @@ pub(super) fn parse_v2_header(msg: &[u8]) -> Result<ParsedHeader, ParseErrorKind
         message_timestamp_ns,
         message_type,
         message_type_info,
+        message_id,
+        source_location,
+        tags,
+        privacy_level,
+        segment,
         payload_offset,
         payload_len,
     })
@@ -317,6 +388,9 @@ mod tests {
         assert_eq!(header.message_timestamp_ns, ts_ns);
         assert_eq!(header.message_type, MESSAGE_TYPE_TRACE);
         assert_eq!(header.message_type_info, LOG_LEVEL_WARN);
+        assert_eq!(header.source_location, Some((&b"a.cpp"[..], 42)));
+        assert_eq!(header.tags, vec![&b"foo"[..]]);
+        assert_eq!(header.privacy_level, Some(7));
         assert_eq!(header.payload_len, 1);
     }
 
@@ -343,6 +417,9 @@ mod tests {
         assert_eq!(header.apid, None);
         assert_eq!(header.ctid, None);
         assert_eq!(header.session_id, None);
+        assert_eq!(header.source_location, None);
+        assert!(header.tags.is_empty());
+        assert_eq!(header.privacy_level, None);
         assert_eq!(header.message_type, MESSAGE_TYPE_LOG);
         assert_eq!(header.message_type_info, LOG_LEVEL_DEBUG);
     }
@@ -386,6 +463,73 @@ mod tests {
         assert_eq!(header.payload_len, 2);
     }
 
+    fn segment_message(sgmt: &[u8]) -> Vec<u8> {
+        let htyp2 = build_htyp2_full(
+            CNTI_VERBOSE,
+            false,
+            false,
+            false,
+            PROTOCOL_VERSION_2,
+            false,
+            false,
+            false,
+            true, // only WSGM
+        );
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&htyp2.to_be_bytes());
+        msg.push(0);
+        msg.extend_from_slice(&0u16.to_be_bytes());
+        msg.push(build_msin(MESSAGE_TYPE_LOG, LOG_LEVEL_INFO));
+        msg.push(1);
+        msg.extend_from_slice(&encode_tmsp2(0));
+        msg.push(sgmt.len() as u8);
+        msg.extend_from_slice(sgmt);
+        msg.extend_from_slice(&[0xAB, 0xCD]);
+        let len = msg.len() as u16;
+        msg[5..7].copy_from_slice(&len.to_be_bytes());
+        msg
+    }
+
+    #[test]
+    fn parse_segmentation_frame_types() {
+        let mut first = vec![FRAME_TYPE_FIRST];
+        first.extend_from_slice(&70_000u64.to_be_bytes());
+        let mut consecutive = vec![FRAME_TYPE_CONSECUTIVE];
+        consecutive.extend_from_slice(&3u32.to_be_bytes());
+        for (sgmt, expected) in [
+            (
+                first,
+                Segment::First {
+                    total_length: 70_000,
+                },
+            ),
+            (consecutive, Segment::Consecutive { sequence: 3 }),
+            (vec![FRAME_TYPE_LAST], Segment::Last),
+            (vec![FRAME_TYPE_ABORT, 2], Segment::Abort { reason: 2 }),
+        ] {
+            let msg = segment_message(&sgmt);
+            let header = parse_v2_header(&msg).unwrap();
+            assert_eq!(header.segment, Some(expected));
+            assert_eq!(header.payload_len, 2);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_segmentation() {
+        for sgmt in [
+            &[][..],
+            &[FRAME_TYPE_FIRST, 0, 0][..],
+            &[FRAME_TYPE_ABORT][..],
+            &[9][..],
+        ] {
+            assert_eq!(
+                parse_v2_header(&segment_message(sgmt)).err(),
+                Some(ParseErrorKind::InvalidExtensionField),
+                "{sgmt:?}"
+            );
+        }
+    }
+
     #[test]
     fn message_types_from_msin() {
         for (mstp, expected_name) in [
@@ -1,26 +1,35 @@
+pub(crate) mod encode;
 mod framer;
-mod header;
+pub(crate) mod header;
 mod payload;
-mod protocol;
+pub(crate) mod protocol;
 
 use anyhow::Result;
-use memmap2::Mmap;
+use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
 use std::path::PathBuf;
 
 use super::intern::InternTable;
-use crate::dlt::error::ParseError;
-use framer::scan_frames;
+use crate::dlt::control::{self, ControlMessage};
+use crate::dlt::error::{ParseError, ParseErrorKind};
+use crate::dlt::source::{MappedFile, Source, live_buffer_index};
+use crate::dlt::storage::STORAGE_HEADER_SIZE;
+use crate::dlt::store::NonVerbosePayload;
+use crate::dlt::verbose::Argument;
+use framer::{scan_frames, scan_frames_following};
+pub use header::Segment;
 use header::parse_v2_header;
-use protocol::htyp2_cnti;
+use protocol::{CNTI_CONTROL, CNTI_VERBOSE, htyp2_cnti};
 
 /// DLT v2 parsed data in columnar (struct-of-arrays) layout.
 ///
-/// Payloads are stored lazily as mmap-backed byte ranges, decoded on demand.
+/// Payloads are stored lazily as byte ranges into the mapped files (or the
+/// live-stream buffer), decoded on demand.
 pub struct Dlt {
-    mmaps: Vec<Mmap>,
+    sources: Vec<Source>,
     intern: InternTable,
+    /// Source file names and tags, which are not bounded like the IDs.
+    text: InternTable<u32>,
     apid: Vec<u16>,
     ctid: Vec<u16>,
     ecu: Vec<u16>,
@@ -29,8 +38,78 @@ pub struct Dlt {
     message_timestamp_ns: Vec<u64>,
     message_type: Vec<u8>,
     message_type_info: Vec<u8>,
+    message_counter: Vec<u8>,
+    /// Counter of the last segment of reassembled rows; equals
+    /// `message_counter` for all other rows.
+    last_message_counter: Vec<u8>,
     cnti: Vec<u8>,
-    payload_loc: Vec<(u16, u32, u32)>, // (mmap_index, offset, len)
+    message_id: Vec<Option<u32>>,
+    source_file: Vec<u32>,
+    source_line: Vec<Option<u32>>,
+    tag_loc: Vec<(u32, u8)>, // (first index into tag_ids, tag count)
+    tag_ids: Vec<u32>,
+    privacy_level: Vec<Option<u8>>,
+    payload_loc: Vec<(u16, u32, u32)>, // (source_index, offset, len)
+    /// Whether `payload_loc` points into `reassembled_payloads` instead of
+    /// the source; `frame_loc` then holds the first segment.
+    reassembled: Vec<bool>,
+    frame_loc: Vec<(u32, u32)>, // (storage header offset, frame len incl. storage header)
+    /// Joined payloads of segmented messages.
+    reassembled_payloads: Vec<u8>,
+    /// Frames of all segments of reassembled rows, keyed by row, as
+    /// (source_index, storage header offset, frame len).
+    segment_frames: HashMap<usize, Vec<(u16, u32, u32)>>,
+    /// Segmented messages still waiting for segments; kept across
+    /// `append` and `refresh` so sequences may span chunks.
+    pending_segments: HashMap<SegmentKey, PendingSegments>,
+}
+
+/// ECU, session ID, APID and CTID (interned) of a segmented message.
+type SegmentKey = (u16, u32, u16, u16);
+
+/// Column values of one row, gathered before it is pushed.
+struct Row {
+    apid: u16,
+    ctid: u16,
+    ecu: u16,
+    session_id: u32,
+    storage_timestamp_ns: u64,
+    message_timestamp_ns: u64,
+    message_type: u8,
+    message_type_info: u8,
+    message_counter: u8,
+    last_message_counter: u8,
+    cnti: u8,
+    message_id: Option<u32>,
+    source_file: u32,
+    source_line: Option<u32>,
+    tags: Vec<u32>,
+    privacy_level: Option<u8>,
+    file_idx: u16,
+    frame_loc: (u32, u32),
+}
+
+/// A segmented message whose last segment has not arrived yet.
+struct PendingSegments {
+    /// Header fields of the first segment.
+    row: Row,
+    payload: Vec<u8>,
+    /// (source_index, storage header offset, frame len) of each segment.
+    frames: Vec<(u16, u32, u32)>,
+    total_length: u64,
+    next_sequence: u32,
+    /// Offset of the first segment's message.
+    byte_offset: u64,
+}
+
+impl PendingSegments {
+    fn incomplete(self) -> ParseError {
+        ParseError {
+            file_index: self.row.file_idx,
+            byte_offset: self.byte_offset,
+            kind: ParseErrorKind::IncompleteSegmentedMessage,
+        }
+    }
 }
 
 impl Dlt {
@@ -39,117 +118,371 @@ impl Dlt {
     /// Returns successfully parsed messages alongside any errors encountered.
     /// Non-v2 messages and malformed frames are recorded as errors and skipped.
     pub fn open(paths: Vec<PathBuf>) -> Result<(Self, Vec<ParseError>)> {
-        let mut mmaps = Vec::with_capacity(paths.len());
-        let mut intern = InternTable::new();
-        let mut apid = Vec::new();
-        let mut ctid = Vec::new();
-        let mut ecu = Vec::new();
-        let mut session_id = Vec::new();
-        let mut storage_timestamp_ns = Vec::new();
-        let mut message_timestamp_ns = Vec::new();
-        let mut message_type = Vec::new();
-        let mut message_type_info = Vec::new();
-        let mut cnti = Vec::new();
-        let mut payload_loc = Vec::new();
+        Self::open_files(paths, false)
+    }
+
+    /// Open DLT v2 files that are still being written, for use with `refresh`.
+    ///
+    /// A trailing frame that is not complete yet is left for a later
+    /// `refresh` instead of being reported as an error. The files are read
+    /// into memory rather than mapped, so one truncated while it is followed
+    /// makes `refresh` fail instead of faulting on row access.
+    pub fn open_following(paths: Vec<PathBuf>) -> Result<(Self, Vec<ParseError>)> {
+        Self::open_files(paths, true)
+    }
+
+    fn open_files(paths: Vec<PathBuf>, following: bool) -> Result<(Self, Vec<ParseError>)> {
+        let mut dlt = Self::empty();
         let mut all_errors = Vec::new();
 
         for (file_idx, path) in paths.iter().enumerate() {
-            let file = File::open(path)?;
-            // SAFETY: the file is read-only and the Mmap is kept alive for the
-            // lifetime of the Dlt struct.
-            let mmap = unsafe { Mmap::map(&file)? };
-
-            let scan = scan_frames(&mmap, file_idx as u16);
-            all_errors.extend(scan.errors);
-            let default_storage_ecu = scan.default_storage_ecu;
-            let storage_ecu_overrides = scan.storage_ecu_overrides;
-
-            let mut next_override = 0usize;
-            for (frame_idx, frame) in scan.frames.into_iter().enumerate() {
-                let msg = &mmap[frame.msg_start..frame.msg_start + frame.msg_len];
-
-                // Resolve this frame's storage ECU first so override cursor stays
-                // aligned even when message ECU is present and takes precedence.
-                let mut storage_ecu = default_storage_ecu;
-                if next_override < storage_ecu_overrides.len()
-                    && storage_ecu_overrides[next_override].0 == frame_idx
-                {
-                    storage_ecu = Some(storage_ecu_overrides[next_override].1);
-                    next_override += 1;
+            let mut mapped = if following {
+                MappedFile::open_following(path)?
+            } else {
+                MappedFile::open(path)?
+            };
+            let (errors, resume_offset) = dlt.ingest(file_idx as u16, mapped.data(), 0, following);
+            mapped.resume_offset = resume_offset;
+            all_errors.extend(errors);
+            dlt.sources.push(Source::Mapped(mapped));
+        }
+        if !following {
+            all_errors.extend(dlt.take_incomplete_segments());
+        }
+
+        Ok((dlt, all_errors))
+    }
+
+    /// Parse frames appended to the files since they were opened or last
+    /// refreshed, resuming after the last complete frame of each file.
+    ///
+    /// New rows are appended in file order; existing rows are untouched.
+    pub fn refresh(&mut self) -> Result<Vec<ParseError>> {
+        let mut all_errors = Vec::new();
+        for source_idx in 0..self.sources.len() {
+            let Source::Mapped(mapped) = &mut self.sources[source_idx] else {
+                continue;
+            };
+            if !mapped.remap()? {
+                continue;
+            }
+            let Source::Mapped(mut mapped) =
+                std::mem::replace(&mut self.sources[source_idx], Source::Buffer(Vec::new()))
+            else {
+                unreachable!("source was just matched as mapped");
+            };
+            let (errors, resume_offset) =
+                self.ingest(source_idx as u16, mapped.data(), mapped.resume_offset, true);
+            mapped.resume_offset = resume_offset;
+            all_errors.extend(errors);
+            self.sources[source_idx] = Source::Mapped(mapped);
+        }
+        Ok(all_errors)
+    }
+
+    /// An empty store fed with stored frames through `append`.
+    pub fn stream() -> Self {
+        let mut dlt = Self::empty();
+        dlt.sources.push(Source::Buffer(Vec::new()));
+        dlt
+    }
+
+    /// Append stored frames (storage header and message) to the live-stream
+    /// buffer and parse them into new rows.
+    ///
+    /// `frames` must hold whole frames; byte offsets of the new rows continue
+    /// from the end of the previously appended data. Once the buffer would
+    /// outgrow 32-bit offsets, new rows go to a fresh buffer source with the
+    /// next file index.
+    pub fn append(&mut self, frames: &[u8]) -> Vec<ParseError> {
+        let source_idx = live_buffer_index(&mut self.sources, frames.len());
+        let Some(Source::Buffer(buffer)) = self.sources.last_mut() else {
+            unreachable!("a buffer source was just ensured");
+        };
+        let mut buffer = std::mem::take(buffer);
+        let start = buffer.len();
+        buffer.extend_from_slice(frames);
+
+        let (errors, _) = self.ingest(source_idx as u16, &buffer, start, false);
+        self.sources[source_idx] = Source::Buffer(buffer);
+        errors
+    }
+
+    fn empty() -> Self {
+        Dlt {
+            sources: Vec::new(),
+            intern: InternTable::new(),
+            text: InternTable::default(),
+            apid: Vec::new(),
+            ctid: Vec::new(),
+            ecu: Vec::new(),
+            session_id: Vec::new(),
+            storage_timestamp_ns: Vec::new(),
+            message_timestamp_ns: Vec::new(),
+            message_type: Vec::new(),
+            message_type_info: Vec::new(),
+            cnti: Vec::new(),
+            message_id: Vec::new(),
+            message_counter: Vec::new(),
+            last_message_counter: Vec::new(),
+            source_file: Vec::new(),
+            source_line: Vec::new(),
+            tag_loc: Vec::new(),
+            tag_ids: Vec::new(),
+            privacy_level: Vec::new(),
+            payload_loc: Vec::new(),
+            reassembled: Vec::new(),
+            frame_loc: Vec::new(),
+            reassembled_payloads: Vec::new(),
+            segment_frames: HashMap::new(),
+            pending_segments: HashMap::new(),
+        }
+    }
+
+    /// Parse the frames in `data[start..]` into new rows referencing source `file_idx`.
+    ///
+    /// Also returns the offset in `data` where a later scan should resume;
+    /// with `following`, an incomplete trailing frame is left there.
+    fn ingest(
+        &mut self,
+        file_idx: u16,
+        data: &[u8],
+        start: usize,
+        following: bool,
+    ) -> (Vec<ParseError>, usize) {
+        let scan = if following {
+            scan_frames_following(&data[start..], file_idx)
+        } else {
+            scan_frames(&data[start..], file_idx)
+        };
+        let mut all_errors: Vec<ParseError> = scan
+            .errors
+            .into_iter()
+            .map(|error| ParseError {
+                byte_offset: error.byte_offset + start as u64,
+                ..error
+            })
+            .collect();
+        let default_storage_ecu = scan.default_storage_ecu;
+        let storage_ecu_overrides = scan.storage_ecu_overrides;
+
+        let mut next_override = 0usize;
+        for (frame_idx, frame) in scan.frames.into_iter().enumerate() {
+            let msg_start = start + frame.msg_start;
+            let msg = &data[msg_start..msg_start + frame.msg_len];
+
+            // Resolve this frame's storage ECU first so override cursor stays
+            // aligned even when message ECU is present and takes precedence.
+            let mut storage_ecu = default_storage_ecu;
+            if next_override < storage_ecu_overrides.len()
+                && storage_ecu_overrides[next_override].0 == frame_idx
+            {
+                storage_ecu = Some(storage_ecu_overrides[next_override].1);
+                next_override += 1;
+            }
+
+            let hdr = match parse_v2_header(msg) {
+                Ok(hdr) => hdr,
+                Err(kind) => {
+                    all_errors.push(ParseError {
+                        file_index: file_idx,
+                        byte_offset: msg_start as u64,
+                        kind,
+                    });
+                    continue;
                 }
+            };
 
-                let hdr = match parse_v2_header(msg) {
-                    Ok(hdr) => hdr,
-                    Err(kind) => {
-                        all_errors.push(ParseError {
-                            file_index: file_idx as u16,
-                            byte_offset: frame.msg_start as u64,
-                            kind,
-                        });
-                        continue;
+            let apid_str = match &hdr.apid {
+                Some(b) => std::str::from_utf8(b).unwrap_or(""),
+                None => "",
+            };
+            let ctid_str = match &hdr.ctid {
+                Some(b) => std::str::from_utf8(b).unwrap_or(""),
+                None => "",
+            };
+            let ecu_id = match &hdr.ecu {
+                Some(b) => {
+                    let ecu_str = std::str::from_utf8(b).unwrap_or("");
+                    self.intern.insert(ecu_str)
+                }
+                None => match storage_ecu {
+                    Some(storage_ecu) => {
+                        let ecu_str = std::str::from_utf8(&storage_ecu).unwrap_or("");
+                        self.intern.insert(ecu_str)
                     }
-                };
+                    None => self.intern.insert(""),
+                },
+            };
+
+            let (file_name, line) = match hdr.source_location {
+                Some((file_name, line)) => (file_name, Some(line)),
+                None => (&[][..], None),
+            };
+            let row = Row {
+                apid: self.intern.insert(apid_str),
+                ctid: self.intern.insert(ctid_str),
+                ecu: ecu_id,
+                session_id: hdr.session_id.unwrap_or(0),
+                storage_timestamp_ns: frame.storage_timestamp_ns,
+                message_timestamp_ns: hdr.message_timestamp_ns,
+                message_type: hdr.message_type,
+                message_type_info: hdr.message_type_info,
+                message_counter: hdr.message_counter,
+                last_message_counter: hdr.message_counter,
+                cnti: htyp2_cnti(hdr.htyp2),
+                message_id: hdr.message_id,
+                source_file: self.text.insert(&String::from_utf8_lossy(file_name)),
+                source_line: line,
+                tags: hdr
+                    .tags
+                    .iter()
+                    .map(|tag| self.text.insert(&String::from_utf8_lossy(tag)))
+                    .collect(),
+                privacy_level: hdr.privacy_level,
+                file_idx,
+                frame_loc: (
+                    (msg_start - STORAGE_HEADER_SIZE) as u32,
+                    (STORAGE_HEADER_SIZE + frame.msg_len) as u32,
+                ),
+            };
+
+            let payload_offset_in_source = msg_start + hdr.payload_offset;
+            match hdr.segment {
+                None => self.push_row(
+                    row,
+                    (payload_offset_in_source as u32, hdr.payload_len as u32),
+                    false,
+                ),
+                Some(segment) => {
+                    let payload =
+                        &data[payload_offset_in_source..payload_offset_in_source + hdr.payload_len];
+                    all_errors.extend(self.add_segment(row, segment, payload, msg_start as u64));
+                }
+            }
+        }
 
-                let apid_str = match &hdr.apid {
-                    Some(b) => std::str::from_utf8(b).unwrap_or(""),
-                    None => "",
+        (all_errors, start + scan.resume_offset)
+    }
+
+    fn push_row(&mut self, row: Row, payload_loc: (u32, u32), reassembled: bool) {
+        self.apid.push(row.apid);
+        self.ctid.push(row.ctid);
+        self.ecu.push(row.ecu);
+        self.session_id.push(row.session_id);
+        self.storage_timestamp_ns.push(row.storage_timestamp_ns);
+        self.message_timestamp_ns.push(row.message_timestamp_ns);
+        self.message_type.push(row.message_type);
+        self.message_type_info.push(row.message_type_info);
+        self.message_counter.push(row.message_counter);
+        self.last_message_counter.push(row.last_message_counter);
+        self.cnti.push(row.cnti);
+        self.message_id.push(row.message_id);
+        self.source_file.push(row.source_file);
+        self.source_line.push(row.source_line);
+        self.tag_loc
+            .push((self.tag_ids.len() as u32, row.tags.len() as u8));
+        self.tag_ids.extend(row.tags);
+        self.privacy_level.push(row.privacy_level);
+        self.payload_loc
+            .push((row.file_idx, payload_loc.0, payload_loc.1));
+        self.reassembled.push(reassembled);
+        self.frame_loc.push(row.frame_loc);
+    }
+
+    /// Add one segment of a segmented message, keyed by ECU, session, APID
+    /// and CTID. The row is pushed, with the header fields of the first
+    /// segment, once the last segment completes the payload.
+    ///
+    /// Returns an error for a segment that does not continue a sequence and
+    /// for a sequence that is interrupted, aborted with a reason, or whose
+    /// payload does not add up to the announced total length.
+    fn add_segment(
+        &mut self,
+        row: Row,
+        segment: Segment,
+        payload: &[u8],
+        byte_offset: u64,
+    ) -> Option<ParseError> {
+        let key = (row.ecu, row.session_id, row.apid, row.ctid);
+        let frame = (row.file_idx, row.frame_loc.0, row.frame_loc.1);
+        let unexpected = ParseError {
+            file_index: row.file_idx,
+            byte_offset,
+            kind: ParseErrorKind::UnexpectedSegment,
+        };
+        match segment {
+            Segment::First { total_length } => {
+                let pending = PendingSegments {
+                    row,
+                    payload: payload.to_vec(),
+                    frames: vec![frame],
+                    total_length,
+                    next_sequence: 0,
+                    byte_offset,
                 };
-                let ctid_str = match &hdr.ctid {
-                    Some(b) => std::str::from_utf8(b).unwrap_or(""),
-                    None => "",
+                self.pending_segments
+                    .insert(key, pending)
+                    .map(PendingSegments::incomplete)
+            }
+            Segment::Consecutive { sequence } => {
+                let Some(pending) = self.pending_segments.get_mut(&key) else {
+                    return Some(unexpected);
                 };
-                let ecu_id = match &hdr.ecu {
-                    Some(b) => {
-                        let ecu_str = std::str::from_utf8(b).unwrap_or("");
-                        intern.insert(ecu_str)
-                    }
-                    None => match storage_ecu {
-                        Some(storage_ecu) => {
-                            let ecu_str = std::str::from_utf8(&storage_ecu).unwrap_or("");
-                            intern.insert(ecu_str)
-                        }
-                        None => intern.insert(""),
-                    },
+                pending.payload.extend_from_slice(payload);
+                pending.frames.push(frame);
+                if sequence != pending.next_sequence
+                    || pending.payload.len() as u64 > pending.total_length
+                {
+                    return self
+                        .pending_segments
+                        .remove(&key)
+                        .map(PendingSegments::incomplete);
+                }
+                pending.next_sequence = pending.next_sequence.wrapping_add(1);
+                None
+            }
+            Segment::Last => {
+                let Some(mut pending) = self.pending_segments.remove(&key) else {
+                    return Some(unexpected);
                 };
-
-                apid.push(intern.insert(apid_str));
-                ctid.push(intern.insert(ctid_str));
-                ecu.push(ecu_id);
-                session_id.push(hdr.session_id.unwrap_or(0));
-                storage_timestamp_ns.push(frame.storage_timestamp_ns);
-                message_timestamp_ns.push(hdr.message_timestamp_ns);
-                message_type.push(hdr.message_type);
-                message_type_info.push(hdr.message_type_info);
-                cnti.push(htyp2_cnti(hdr.htyp2));
-
-                let payload_offset_in_mmap = frame.msg_start + hdr.payload_offset;
-                payload_loc.push((
-                    file_idx as u16,
-                    payload_offset_in_mmap as u32,
-                    hdr.payload_len as u32,
-                ));
+                pending.payload.extend_from_slice(payload);
+                pending.frames.push(frame);
+                if pending.payload.len() as u64 != pending.total_length {
+                    return Some(pending.incomplete());
+                }
+                pending.row.last_message_counter = row.message_counter;
+                let offset = self.reassembled_payloads.len() as u32;
+                let len = pending.payload.len() as u32;
+                self.reassembled_payloads
+                    .extend_from_slice(&pending.payload);
+                self.segment_frames.insert(self.len(), pending.frames);
+                self.push_row(pending.row, (offset, len), true);
+                None
+            }
+            Segment::Abort { reason } => {
+                let Some(pending) = self.pending_segments.remove(&key) else {
+                    return Some(unexpected);
+                };
+                (reason != 0).then_some(ParseError {
+                    file_index: pending.row.file_idx,
+                    byte_offset: pending.byte_offset,
+                    kind: ParseErrorKind::SegmentationAborted { reason },
+                })
             }
-
-            mmaps.push(mmap);
         }
+    }
 
-        Ok((
-            Dlt {
-                mmaps,
-                intern,
-                apid,
-                ctid,
-                ecu,
-                session_id,
-                storage_timestamp_ns,
-                message_timestamp_ns,
-                message_type,
-                message_type_info,
-                cnti,
-                payload_loc,
-            },
-            all_errors,
-        ))
+    /// Report sequences still waiting for segments as incomplete, in file
+    /// order, and discard them.
+    fn take_incomplete_segments(&mut self) -> Vec<ParseError> {
+        let mut errors: Vec<ParseError> = self
+            .pending_segments
+            .drain()
+            .map(|(_, pending)| pending.incomplete())
+            .collect();
+        errors.sort_by_key(|error| (error.file_index, error.byte_offset));
+        errors
     }
 
     pub fn len(&self) -> usize {
@@ -192,15 +525,130 @@ impl Dlt {
         self.message_type[row]
     }
 
+    /// Message counter (MCNT) from the base header.
+    pub fn message_counter(&self, row: usize) -> u8 {
+        self.message_counter[row]
+    }
+
+    /// Message counter of the last segment for reassembled rows, so counter
+    /// continuity can be checked across them; `message_counter` otherwise.
+    pub fn last_message_counter(&self, row: usize) -> u8 {
+        self.last_message_counter[row]
+    }
+
+    /// Whether the payload uses verbose (self-describing) encoding.
+    pub fn is_verbose(&self, row: usize) -> bool {
+        self.cnti[row] == CNTI_VERBOSE
+    }
+
+    /// Source file name and line number (WSFLN), if the message carries them.
+    pub fn source_location(&self, row: usize) -> Option<(&str, u32)> {
+        let line = self.source_line[row]?;
+        Some((self.text.resolve(self.source_file[row]), line))
+    }
+
+    /// Tags (WTGS) in header order; empty when the message has none.
+    pub fn tags(&self, row: usize) -> Vec<&str> {
+        let (first, count) = self.tag_loc[row];
+        let first = first as usize;
+        self.tag_ids[first..first + count as usize]
+            .iter()
+            .map(|&id| self.text.resolve(id))
+            .collect()
+    }
+
+    /// Privacy level (WPVL), if the message carries one.
+    pub fn privacy_level(&self, row: usize) -> Option<u8> {
+        self.privacy_level[row]
+    }
+
+    /// Number of arguments (NOAR), 0 for non-verbose messages.
+    pub fn argument_count(&self, row: usize) -> u8 {
+        let cnti = self.cnti[row];
+        if cnti != CNTI_VERBOSE && cnti != CNTI_CONTROL {
+            return 0;
+        }
+        // NOAR follows HTYP2(4) + MCNT(1) + LEN(2) + MSIN(1).
+        self.frame_raw(row)[STORAGE_HEADER_SIZE + 8]
+    }
+
+    /// Index of the source file in the paths list.
+    pub fn file_index(&self, row: usize) -> u16 {
+        self.payload_loc[row].0
+    }
+
+    /// Byte offset of the message's storage header within its source file.
+    pub fn byte_offset(&self, row: usize) -> u64 {
+        self.frame_loc[row].0 as u64
+    }
+
+    /// The complete stored frame (storage header and message) as it appears
+    /// on disk; for a reassembled row, the frame of its first segment.
+    pub fn frame_raw(&self, row: usize) -> &[u8] {
+        let (offset, len) = self.frame_loc[row];
+        let source_idx = self.payload_loc[row].0;
+        &self.sources[source_idx as usize][offset as usize..(offset + len) as usize]
+    }
+
+    /// The stored frames of the row as they appear on disk: the frame of
+    /// every segment, in order, for a reassembled row.
+    pub fn frames_raw(&self, row: usize) -> Vec<&[u8]> {
+        let Some(frames) = self.segment_frames.get(&row) else {
+            return vec![self.frame_raw(row)];
+        };
+        frames
+            .iter()
+            .map(|&(source_idx, offset, len)| {
+                &self.sources[source_idx as usize][offset as usize..(offset + len) as usize]
+            })
+            .collect()
+    }
+
+    /// Payload bytes; for a segmented message, the payloads of all its
+    /// segments joined.
     pub fn payload_raw(&self, row: usize) -> &[u8] {
-        let (mmap_idx, offset, len) = self.payload_loc[row];
-        &self.mmaps[mmap_idx as usize][offset as usize..(offset + len) as usize]
+        let (source_idx, offset, len) = self.payload_loc[row];
+        if self.reassembled[row] {
+            return &self.reassembled_payloads[offset as usize..(offset + len) as usize];
+        }
+        &self.sources[source_idx as usize][offset as usize..(offset + len) as usize]
     }
 
     pub fn payload_text(&self, row: usize) -> String {
         let raw = self.payload_raw(row);
         let cnti = self.cnti[row];
-        payload::decode_payload(cnti, raw)
+        payload::decode_payload(cnti, self.message_type_info[row], self.message_id[row], raw)
+    }
+
+    /// Typed verbose arguments; empty for non-verbose and control messages.
+    pub fn payload_arguments(&self, row: usize) -> Vec<Argument> {
+        payload::decode_arguments(self.cnti[row], self.payload_raw(row))
+    }
+
+    /// Message ID and argument bytes of a non-verbose message.
+    ///
+    /// v2 carries the message ID (MSID) in the base header, after TMSP2;
+    /// the whole payload is argument data.
+    pub fn non_verbose_payload(&self, row: usize) -> Option<NonVerbosePayload<'_>> {
+        Some(NonVerbosePayload {
+            message_id: self.message_id[row]?,
+            arguments: self.payload_raw(row),
+            big_endian: true,
+        })
+    }
+
+    /// Message ID (MSID) of a non-verbose message; `None` for verbose and
+    /// control messages.
+    pub fn message_id(&self, row: usize) -> Option<u32> {
+        self.message_id[row]
+    }
+
+    /// Decoded control message; `None` for data messages.
+    pub fn control_message(&self, row: usize) -> Option<ControlMessage> {
+        if self.cnti[row] != CNTI_CONTROL {
+            return None;
+        }
+        control::decode(self.payload_raw(row), true, self.message_type_info[row])
     }
 
     /// Sorted, deduplicated list of all APID strings seen.
@@ -237,251 +685,30 @@ impl fmt::Debug for Dlt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Dlt")
             .field("messages", &self.len())
-            .field("files", &self.mmaps.len())
+            .field("files", &self.sources.len())
             .finish()
     }
 }
 
-// ---------------------------------------------------------------------------
-// V2MessageBuilder — helper for constructing synthetic v2 messages
-// ---------------------------------------------------------------------------
-
-pub mod test_helpers {
-    use super::protocol::*;
-
-    /// Builds a spec-compliant AUTOSAR PRS v2 DLT message as raw bytes.
-    ///
-    /// Produces: storage header + base header + extension header + payload.
-    pub struct V2MessageBuilder {
-        storage_seconds: u32,
-        storage_microseconds: u32,
-        storage_ecu: [u8; 4],
-        apid: Option<[u8; 4]>,
-        ctid: Option<[u8; 4]>,
-        ecu: Option<[u8; 4]>,
-        session_id: Option<u32>,
-        message_timestamp_ns: Option<u64>,
-        message_type: u8,
-        message_type_info: u8,
-        verbose_payload: Vec<u8>,
-        noar: u8,
-    }
-
-    impl V2MessageBuilder {
-        pub fn new() -> Self {
-            Self {
-                storage_seconds: 1000,
-                storage_microseconds: 500_000,
-                storage_ecu: *b"ECU1",
-                apid: None,
-                ctid: None,
-                ecu: None,
-                session_id: None,
-                message_timestamp_ns: None,
-                message_type: MESSAGE_TYPE_LOG,
-                message_type_info: LOG_LEVEL_INFO,
-                verbose_payload: Vec::new(),
-                noar: 0,
-            }
-        }
-
-        pub fn with_storage_ecu(mut self, ecu: &[u8; 4]) -> Self {
-            self.storage_ecu = *ecu;
-            self
-        }
-
-        pub fn with_storage_timestamp(mut self, seconds: u32, microseconds: u32) -> Self {
-            self.storage_seconds = seconds;
-            self.storage_microseconds = microseconds;
-            self
-        }
-
-        pub fn with_apid(mut self, apid: &str) -> Self {
-            let mut buf = [0u8; 4];
-            let bytes = apid.as_bytes();
-            let n = bytes.len().min(4);
-            buf[..n].copy_from_slice(&bytes[..n]);
-            self.apid = Some(buf);
-            self
-        }
-
-        pub fn with_ctid(mut self, ctid: &str) -> Self {
-            let mut buf = [0u8; 4];
-            let bytes = ctid.as_bytes();
-            let n = bytes.len().min(4);
-            buf[..n].copy_from_slice(&bytes[..n]);
-            self.ctid = Some(buf);
-            self
-        }
-
-        pub fn without_apid(mut self) -> Self {
-            self.apid = None;
-            self
-        }
-
-        pub fn without_ctid(mut self) -> Self {
-            self.ctid = None;
-            self
-        }
-
-        pub fn with_ecu(mut self, ecu: &str) -> Self {
-            let mut buf = [0u8; 4];
-            let bytes = ecu.as_bytes();
-            let n = bytes.len().min(4);
-            buf[..n].copy_from_slice(&bytes[..n]);
-            self.ecu = Some(buf);
-            self
-        }
-
-        pub fn with_session_id(mut self, id: u32) -> Self {
-            self.session_id = Some(id);
-            self
-        }
-
-        pub fn with_timestamp_ns(mut self, ns: u64) -> Self {
-            self.message_timestamp_ns = Some(ns);
-            self
-        }
-
-        pub fn with_message_type(mut self, mstp: u8) -> Self {
-            self.message_type = mstp;
-            self
-        }
-
-        pub fn with_message_type_info(mut self, mtin: u8) -> Self {
-            self.message_type_info = mtin;
-            self
-        }
-
-        /// Add a verbose UTF-8 string argument to the payload.
-        pub fn with_verbose_string(mut self, s: &str) -> Self {
-            // TypeInfo: STRG | SCOD_UTF8 = 0x00008200
-            let type_info: u32 = 0x0000_8200;
-            self.verbose_payload
-                .extend_from_slice(&type_info.to_be_bytes());
-            let bytes = s.as_bytes();
-            let len = (bytes.len() + 1) as u16; // +1 for null terminator
-            self.verbose_payload.extend_from_slice(&len.to_be_bytes());
-            self.verbose_payload.extend_from_slice(bytes);
-            self.verbose_payload.push(0); // null terminator
-            self.noar += 1;
-            self
-        }
-
-        /// Build the complete message as a byte vector.
-        pub fn build(self) -> Vec<u8> {
-            let mut msg = Vec::new();
-
-            // --- Storage header (16 bytes, per dlt-daemon DltStorageHeader) ---
-            msg.extend_from_slice(b"DLT\x01");
-            msg.extend_from_slice(&self.storage_seconds.to_le_bytes());
-            msg.extend_from_slice(&self.storage_microseconds.to_le_bytes());
-            msg.extend_from_slice(&self.storage_ecu);
-
-            // --- Build HTYP2 ---
-            let has_wacid = self.apid.is_some() || self.ctid.is_some();
-            let has_weid = self.ecu.is_some();
-            let has_wsid = self.session_id.is_some();
-
-            let htyp2 = build_htyp2_full(
-                CNTI_VERBOSE,
-                has_weid,
-                has_wacid,
-                has_wsid,
-                PROTOCOL_VERSION_2,
-                false, // WSFLN
-                false, // WTGS
-                false, // WPVL
-                false, // WSGM
-            );
-
-            // --- Compute message length ---
-            // Base header: HTYP2(4) + MCNT(1) + LEN(2) = 7
-            // + MSIN(1) + NOAR(1) = 2 (verbose)
-            // + TMSP2(9) (data message)
-            let base_size = 7 + 2 + 9;
-            let ext_size = {
-                let mut sz = 0;
-                if has_weid {
-                    sz += 1 + 4; // length byte + 4 value bytes
-                }
-                if has_wacid {
-                    sz += self.apid.map_or(1, |_| 5); // 1 len byte + 4 value (or just len=0)
-                    sz += self.ctid.map_or(1, |_| 5);
-                }
-                if has_wsid {
-                    sz += 4; // fixed 4 bytes
-                }
-                sz
-            };
-            let payload_size = self.verbose_payload.len();
-            let total_len = base_size + ext_size + payload_size;
-
-            // --- Base header ---
-            msg.extend_from_slice(&htyp2.to_be_bytes());
-            msg.push(0); // MCNT
-            msg.extend_from_slice(&(total_len as u16).to_be_bytes());
-
-            // MSIN
-            msg.push(build_msin(self.message_type, self.message_type_info));
-            msg.push(self.noar); // NOAR
-
-            // TMSP2
-            let ts_ns = self.message_timestamp_ns.unwrap_or(0);
-            msg.extend_from_slice(&encode_tmsp2(ts_ns));
-
-            // --- Extension header ---
-            if has_weid {
-                msg.push(4); // length
-                msg.extend_from_slice(&self.ecu.unwrap());
-            }
-
-            if has_wacid {
-                if let Some(apid) = self.apid {
-                    msg.push(4);
-                    msg.extend_from_slice(&apid);
-                } else {
-                    msg.push(0);
-                }
-
-                if let Some(ctid) = self.ctid {
-                    msg.push(4);
-                    msg.extend_from_slice(&ctid);
-                } else {
-                    msg.push(0);
-                }
-            }
-
-            if let Some(sid) = self.session_id {
-                msg.extend_from_slice(&sid.to_be_bytes());
-            }
-
-            // --- Payload ---
-            msg.extend_from_slice(&self.verbose_payload);
-
-            msg
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::protocol;
-    use super::test_helpers::V2MessageBuilder;
     use super::*;
     use crate::dlt::error::ParseErrorKind;
+    use crate::dlt::writer::MessageBuilder;
     use std::io::Write;
 
     #[test]
     fn v2_roundtrip_single_message() {
         let ts_ns = 1000u64 * 1_000_000_000 + 42;
-        let msg_bytes = V2MessageBuilder::new()
+        let msg_bytes = MessageBuilder::new()
             .with_apid("APP1")
             .with_ctid("CTX1")
             .with_storage_timestamp(1000, 500_000)
             .with_timestamp_ns(ts_ns)
             .with_verbose_string("hello")
-            .build();
+            .build(2)
+            .unwrap();
 
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("test.dlt");
@@ -513,7 +740,7 @@ mod tests {
     #[test]
     fn v2_all_fields_populated() {
         let ts_ns = 42u64 * 1_000_000_000 + 999_999_999;
-        let msg_bytes = V2MessageBuilder::new()
+        let msg_bytes = MessageBuilder::new()
             .with_apid("AP01")
             .with_ctid("CT01")
             .with_ecu("ECU2")
@@ -522,7 +749,8 @@ mod tests {
             .with_message_type(protocol::MESSAGE_TYPE_TRACE)
             .with_message_type_info(protocol::LOG_LEVEL_WARN)
             .with_verbose_string("world")
-            .build();
+            .build(2)
+            .unwrap();
 
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("all_fields.dlt");
@@ -547,6 +775,227 @@ mod tests {
         assert!(!raw.is_empty());
     }
 
+    #[test]
+    fn v2_keeps_source_location_tags_and_privacy_level() {
+        let mut stream = Dlt::stream();
+        let tagged = MessageBuilder::new()
+            .with_source_location("can_driver.c", 311)
+            .with_tag("safety")
+            .with_tag("can")
+            .with_privacy_level(2)
+            .with_verbose_string("bus off")
+            .build(2)
+            .unwrap();
+        let plain = MessageBuilder::new()
+            .with_verbose_string("plain")
+            .build(2)
+            .unwrap();
+        assert!(stream.append(&[tagged, plain].concat()).is_empty());
+
+        assert_eq!(stream.source_location(0), Some(("can_driver.c", 311)));
+        assert_eq!(stream.tags(0), vec!["safety", "can"]);
+        assert_eq!(stream.privacy_level(0), Some(2));
+        assert_eq!(stream.payload_text(0), "bus off");
+        assert_eq!(stream.source_location(1), None);
+        assert!(stream.tags(1).is_empty());
+        assert_eq!(stream.privacy_level(1), None);
+    }
+
+    #[test]
+    fn v2_source_files_and_tags_past_the_u16_id_range_resolve() {
+        let frames: Vec<u8> = (0..70_000)
+            .flat_map(|n| {
+                MessageBuilder::new()
+                    .with_apid("APP1")
+                    .with_source_location(&format!("src/file_{n}.c"), n)
+                    .with_tag(&format!("tag{n}"))
+                    .build(2)
+                    .unwrap()
+            })
+            .collect();
+        let mut stream = Dlt::stream();
+        assert!(stream.append(&frames).is_empty());
+
+        assert_eq!(stream.len(), 70_000);
+        for row in [0, 65_535, 65_536, 69_999] {
+            let expected_file = format!("src/file_{row}.c");
+            assert_eq!(
+                stream.source_location(row),
+                Some((expected_file.as_str(), row as u32))
+            );
+            assert_eq!(stream.tags(row), vec![format!("tag{row}")]);
+            assert_eq!(stream.apid(row), "APP1");
+        }
+    }
+
+    #[test]
+    fn v2_header_fields_are_kept_per_row_when_others_are_absent() {
+        let frames = [
+            MessageBuilder::new()
+                .with_tag("a")
+                .with_tag("b")
+                .with_tag("c")
+                .with_verbose_string("tags only")
+                .build(2)
+                .unwrap(),
+            MessageBuilder::new()
+                .with_source_location("src/main.c", 0)
+                .with_verbose_string("source only")
+                .build(2)
+                .unwrap(),
+            MessageBuilder::new()
+                .with_privacy_level(0)
+                .with_tag("d")
+                .with_verbose_string("privacy and one tag")
+                .build(2)
+                .unwrap(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fields.dlt");
+        std::fs::write(&path, frames.concat()).unwrap();
+
+        let (dlt, errors) = Dlt::open(vec![path]).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(dlt.tags(0), vec!["a", "b", "c"]);
+        assert_eq!(dlt.source_location(0), None);
+        assert_eq!(dlt.privacy_level(0), None);
+        assert!(dlt.tags(1).is_empty());
+        assert_eq!(dlt.source_location(1), Some(("src/main.c", 0)));
+        assert_eq!(dlt.privacy_level(1), None);
+        assert_eq!(dlt.tags(2), vec!["d"]);
+        assert_eq!(dlt.source_location(2), None);
+        assert_eq!(dlt.privacy_level(2), Some(0));
+        assert_eq!(dlt.payload_text(2), "privacy and one tag");
+    }
+
+    #[test]
+    fn v2_non_verbose_message_id_comes_from_msid() {
+        let mut stream = Dlt::stream();
+        let non_verbose = MessageBuilder::new()
+            .with_non_verbose(0x1234, &[0xAB, 0xCD, 0xEF, 0x01, 0x02])
+            .build(2)
+            .unwrap();
+        let verbose = MessageBuilder::new()
+            .with_verbose_string("text")
+            .build(2)
+            .unwrap();
+        assert!(stream.append(&[non_verbose, verbose].concat()).is_empty());
+
+        assert_eq!(stream.message_id(0), Some(0x1234));
+        let payload = stream.non_verbose_payload(0).unwrap();
+        assert_eq!(payload.message_id, 0x1234);
+        assert_eq!(payload.arguments, [0xAB, 0xCD, 0xEF, 0x01, 0x02]);
+        assert_eq!(
+            stream.payload_text(0),
+            "[non-verbose 0x00001234] ab cd ef 01 02"
+        );
+        assert_eq!(stream.message_id(1), None);
+    }
+
+    fn segmented(apid: &str, text: &str) -> Vec<Vec<u8>> {
+        MessageBuilder::new()
+            .with_apid(apid)
+            .with_ctid("CTX1")
+            .with_timestamp_ns(7_000)
+            .with_verbose_string(text)
+            .build_segments(10)
+            .unwrap()
+    }
+
+    #[test]
+    fn v2_reassembles_interleaved_segmented_messages() {
+        let long = "a payload that needs several segments";
+        let first = segmented("APP1", long);
+        let second = segmented("APP2", "short again");
+        assert!(first.len() > 3);
+        let plain = MessageBuilder::new()
+            .with_verbose_string("plain")
+            .build(2)
+            .unwrap();
+        let mut frames = Vec::new();
+        frames.extend_from_slice(&first[..2]);
+        frames.extend(second);
+        frames.push(plain.clone());
+        frames.extend_from_slice(&first[2..]);
+
+        let mut stream = Dlt::stream();
+        // Sequences continue across appended chunks.
+        assert!(stream.append(&frames[..3].concat()).is_empty());
+        assert!(stream.append(&frames[3..].concat()).is_empty());
+
+        assert_eq!(stream.len(), 3);
+        assert_eq!(stream.payload_text(0), "short again");
+        assert_eq!(stream.apid(0), "APP2");
+        assert_eq!(stream.payload_text(1), "plain");
+        assert_eq!(stream.payload_text(2), long);
+        assert_eq!(stream.apid(2), "APP1");
+        assert_eq!(stream.message_timestamp_ns(2), 7_000);
+        assert_eq!(stream.argument_count(2), 1);
+        // The row points at its first segment and keeps all of them.
+        assert_eq!(stream.frame_raw(2), first[0].as_slice());
+        assert_eq!(stream.frames_raw(2), first);
+        assert_eq!(stream.frames_raw(1), vec![plain.as_slice()]);
+    }
+
+    #[test]
+    fn v2_reports_broken_segment_sequences() {
+        let segments = segmented("APP1", "a payload that needs several segments");
+        let abort = |reason| {
+            MessageBuilder::new()
+                .with_apid("APP1")
+                .with_ctid("CTX1")
+                .with_segment(Segment::Abort { reason }, &[])
+                .build(2)
+                .unwrap()
+        };
+        let frames = [
+            // A consecutive segment without a first one.
+            segments[1].clone(),
+            // Aborted without a reason: discarded silently.
+            segments[0].clone(),
+            abort(0),
+            // Aborted for a reason.
+            segments[0].clone(),
+            abort(3),
+            // Interrupted by a new first segment, which never completes.
+            segments[0].clone(),
+            segments[1].clone(),
+            segments[0].clone(),
+        ];
+        let offsets: Vec<u64> = frames
+            .iter()
+            .scan(0, |offset, frame| {
+                let start = *offset;
+                *offset += frame.len() as u64;
+                Some(start + STORAGE_HEADER_SIZE as u64)
+            })
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("segments.dlt");
+        std::fs::write(&path, frames.concat()).unwrap();
+
+        let (dlt, errors) = Dlt::open(vec![path]).unwrap();
+
+        assert!(dlt.is_empty());
+        let reported: Vec<(u64, ParseErrorKind)> = errors
+            .into_iter()
+            .map(|error| (error.byte_offset, error.kind))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                (offsets[0], ParseErrorKind::UnexpectedSegment),
+                (
+                    offsets[3],
+                    ParseErrorKind::SegmentationAborted { reason: 3 }
+                ),
+                (offsets[5], ParseErrorKind::IncompleteSegmentedMessage),
+                (offsets[7], ParseErrorKind::IncompleteSegmentedMessage),
+            ]
+        );
+    }
+
     #[test]
     fn v2_message_type_info_and_message_type_variants() {
         let dir = tempfile::tempdir().unwrap();
@@ -559,12 +1008,13 @@ mod tests {
                 (protocol::MESSAGE_TYPE_NETWORK, protocol::LOG_LEVEL_WARN),
                 (protocol::MESSAGE_TYPE_CONTROL, protocol::LOG_LEVEL_INFO),
             ] {
-                let msg = V2MessageBuilder::new()
+                let msg = MessageBuilder::new()
                     .with_apid("APP1")
                     .with_ctid("CTX1")
                     .with_message_type(mstp)
                     .with_message_type_info(mtin)
-                    .build();
+                    .build(2)
+                    .unwrap();
                 f.write_all(&msg).unwrap();
             }
         }
@@ -584,7 +1034,7 @@ mod tests {
 
     #[test]
     fn v2_flags_cleared_uses_storage_ecu_and_other_empty_sentinels() {
-        let msg_bytes = V2MessageBuilder::new().build();
+        let msg_bytes = MessageBuilder::new().build(2).unwrap();
 
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("no_ext.dlt");
@@ -609,13 +1059,23 @@ mod tests {
         {
             let mut f = std::fs::File::create(&path).unwrap();
             for msg in [
-                V2MessageBuilder::new().with_storage_ecu(b"ST01").build(),
-                V2MessageBuilder::new()
+                MessageBuilder::new()
+                    .with_storage_ecu(b"ST01")
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_storage_ecu(b"ST02")
                     .with_ecu("MS02")
-                    .build(),
-                V2MessageBuilder::new().with_storage_ecu(b"ST03").build(),
-                V2MessageBuilder::new().with_storage_ecu(b"ST01").build(),
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
+                    .with_storage_ecu(b"ST03")
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
+                    .with_storage_ecu(b"ST01")
+                    .build(2)
+                    .unwrap(),
             ] {
                 f.write_all(&msg).unwrap();
             }
@@ -676,10 +1136,11 @@ mod tests {
         {
             let mut f = std::fs::File::create(&path).unwrap();
             // Write a valid message followed by a truncated storage header
-            let msg = V2MessageBuilder::new()
+            let msg = MessageBuilder::new()
                 .with_apid("APP1")
                 .with_ctid("CTX1")
-                .build();
+                .build(2)
+                .unwrap();
             f.write_all(&msg).unwrap();
             // Truncated: DLT\x01 + a few bytes, not enough for storage + base header
             f.write_all(b"DLT\x01\x00\x00").unwrap();
@@ -699,10 +1160,11 @@ mod tests {
             let mut f = std::fs::File::create(&path).unwrap();
             // Message 1 (valid)
             f.write_all(
-                &V2MessageBuilder::new()
+                &MessageBuilder::new()
                     .with_apid("APP1")
                     .with_ctid("CTX1")
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             )
             .unwrap();
             // Corrupted message: valid storage header, but version=3
@@ -721,18 +1183,20 @@ mod tests {
             f.write_all(&bad).unwrap();
             // Message 2 (valid)
             f.write_all(
-                &V2MessageBuilder::new()
+                &MessageBuilder::new()
                     .with_apid("APP2")
                     .with_ctid("CTX2")
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             )
             .unwrap();
             // Message 3 (valid)
             f.write_all(
-                &V2MessageBuilder::new()
+                &MessageBuilder::new()
                     .with_apid("APP3")
                     .with_ctid("CTX3")
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             )
             .unwrap();
         }
@@ -744,11 +1208,12 @@ mod tests {
 
     #[test]
     fn v2_payload_text_verbose_string() {
-        let msg_bytes = V2MessageBuilder::new()
+        let msg_bytes = MessageBuilder::new()
             .with_apid("APP1")
             .with_ctid("CTX1")
             .with_verbose_string("hello payload")
-            .build();
+            .build(2)
+            .unwrap();
 
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("payload_text.dlt");
@@ -784,29 +1249,32 @@ mod tests {
             dir.path(),
             "a.dlt",
             &[
-                V2MessageBuilder::new()
+                MessageBuilder::new()
                     .with_ecu("ECU1")
                     .with_apid("AP01")
                     .with_ctid("CT01")
                     .with_storage_timestamp(100, 0)
-                    .build(),
-                V2MessageBuilder::new()
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_ecu("ECU1")
                     .with_apid("AP01")
                     .with_ctid("CT02")
                     .with_storage_timestamp(200, 0)
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             ],
         );
         let file2 = write_v2_file(
             dir.path(),
             "b.dlt",
-            &[V2MessageBuilder::new()
+            &[MessageBuilder::new()
                 .with_ecu("ECU2")
                 .with_apid("AP02")
                 .with_ctid("CT03")
                 .with_storage_timestamp(150, 0)
-                .build()],
+                .build(2)
+                .unwrap()],
         );
 
         let (dlt, errors) = Dlt::open(vec![file1, file2]).unwrap();
@@ -820,24 +1288,26 @@ mod tests {
         let file1 = write_v2_file(
             dir.path(),
             "a.dlt",
-            &[V2MessageBuilder::new()
+            &[MessageBuilder::new()
                 .with_ecu("ECU1")
                 .with_apid("AP01")
                 .with_ctid("CT01")
                 .with_storage_timestamp(100, 0)
                 .with_verbose_string("from-file-1")
-                .build()],
+                .build(2)
+                .unwrap()],
         );
         let file2 = write_v2_file(
             dir.path(),
             "b.dlt",
-            &[V2MessageBuilder::new()
+            &[MessageBuilder::new()
                 .with_ecu("ECU2")
                 .with_apid("AP02")
                 .with_ctid("CT02")
                 .with_storage_timestamp(200, 0)
                 .with_verbose_string("from-file-2")
-                .build()],
+                .build(2)
+                .unwrap()],
         );
 
         let (dlt, errors) = Dlt::open(vec![file1, file2]).unwrap();
@@ -855,18 +1325,20 @@ mod tests {
         let file1 = write_v2_file(
             dir.path(),
             "a.dlt",
-            &[V2MessageBuilder::new()
+            &[MessageBuilder::new()
                 .with_ecu("ZZZ1")
                 .with_storage_timestamp(100, 0)
-                .build()],
+                .build(2)
+                .unwrap()],
         );
         let file2 = write_v2_file(
             dir.path(),
             "b.dlt",
-            &[V2MessageBuilder::new()
+            &[MessageBuilder::new()
                 .with_ecu("AAA1")
                 .with_storage_timestamp(200, 0)
-                .build()],
+                .build(2)
+                .unwrap()],
         );
 
         let (dlt, _) = Dlt::open(vec![file1, file2]).unwrap();
@@ -881,32 +1353,36 @@ mod tests {
             dir.path(),
             "a.dlt",
             &[
-                V2MessageBuilder::new()
+                MessageBuilder::new()
                     .with_apid("APP1")
                     .with_ctid("CTX1")
                     .with_storage_timestamp(100, 0)
-                    .build(),
-                V2MessageBuilder::new()
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_apid("APP2")
                     .with_ctid("CTX1")
                     .with_storage_timestamp(200, 0)
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             ],
         );
         let file2 = write_v2_file(
             dir.path(),
             "b.dlt",
             &[
-                V2MessageBuilder::new()
+                MessageBuilder::new()
                     .with_apid("APP1")
                     .with_ctid("CTX2")
                     .with_storage_timestamp(150, 0)
-                    .build(),
-                V2MessageBuilder::new()
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_apid("APP3")
                     .with_ctid("CTX2")
                     .with_storage_timestamp(250, 0)
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             ],
         );
 
@@ -927,14 +1403,16 @@ mod tests {
             dir.path(),
             "a.dlt",
             &[
-                V2MessageBuilder::new()
+                MessageBuilder::new()
                     .with_ecu("ECU1")
                     .with_storage_timestamp(100, 0)
-                    .build(),
-                V2MessageBuilder::new()
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_ecu("ECU1")
                     .with_storage_timestamp(300, 0)
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             ],
         );
         // File 2: timestamps 50, 200
@@ -942,14 +1420,16 @@ mod tests {
             dir.path(),
             "b.dlt",
             &[
-                V2MessageBuilder::new()
+                MessageBuilder::new()
                     .with_ecu("ECU2")
                     .with_storage_timestamp(50, 0)
-                    .build(),
-                V2MessageBuilder::new()
+                    .build(2)
+                    .unwrap(),
+                MessageBuilder::new()
                     .with_ecu("ECU2")
                     .with_storage_timestamp(200, 0)
-                    .build(),
+                    .build(2)
+                    .unwrap(),
             ],
         );
 
@@ -988,7 +1468,7 @@ mod tests {
         let path = write_v2_file(
             dir.path(),
             "one.dlt",
-            &[V2MessageBuilder::new().with_apid("APP1").build()],
+            &[MessageBuilder::new().with_apid("APP1").build(2).unwrap()],
         );
 
         let (dlt, _) = Dlt::open(vec![path]).unwrap();
@@ -1,21 +1,34 @@
 use super::protocol::{CNTI_CONTROL, CNTI_NON_VERBOSE, CNTI_VERBOSE};
-use crate::dlt::payload;
+use crate::dlt::{payload, verbose};
 
 /// Decode a payload slice into a human-readable string based on content type.
 ///
-/// v2 always uses big-endian byte order for payload fields.
-pub fn decode_payload(cnti: u8, raw: &[u8]) -> String {
+/// v2 always uses big-endian byte order for payload fields. `mtin` is only
+/// used for control messages, to tell requests from responses. Non-verbose
+/// messages carry their `message_id` (MSID) in the header, so the whole
+/// payload is argument data.
+pub fn decode_payload(cnti: u8, mtin: u8, message_id: Option<u32>, raw: &[u8]) -> String {
     match cnti {
         CNTI_VERBOSE => payload::decode_verbose(raw, true),
-        CNTI_NON_VERBOSE => payload::decode_non_verbose(raw, true),
-        CNTI_CONTROL => payload::decode_control(raw, true),
+        CNTI_NON_VERBOSE => payload::format_non_verbose(message_id, raw),
+        CNTI_CONTROL => payload::decode_control(raw, true, mtin),
         _ => format!("[unknown cnti={cnti}] {}", payload::hex_dump(raw)),
     }
 }
 
+/// Decode the typed arguments of a verbose payload; empty for other content types.
+pub fn decode_arguments(cnti: u8, raw: &[u8]) -> Vec<verbose::Argument> {
+    if cnti == CNTI_VERBOSE {
+        verbose::decode_arguments(raw, true)
+    } else {
+        Vec::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dlt::control::CONTROL_RESPONSE;
     use crate::dlt::payload::*;
 
     // Helper to build a verbose TypeInfo + data
@@ -128,93 +141,111 @@ mod tests {
     #[test]
     fn verbose_bool_true() {
         let payload = make_bool(true);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "true");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "true");
     }
 
     #[test]
     fn verbose_bool_false() {
         let payload = make_bool(false);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "false");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "false");
     }
 
     #[test]
     fn verbose_sint8() {
         let payload = make_sint8(-42);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "-42");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "-42");
     }
 
     #[test]
     fn verbose_sint16() {
         let payload = make_sint16(-1000);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "-1000");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "-1000");
     }
 
     #[test]
     fn verbose_sint32() {
         let payload = make_sint32(-100_000);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "-100000");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "-100000");
     }
 
     #[test]
     fn verbose_sint64() {
         let payload = make_sint64(-9_000_000_000);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "-9000000000");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "-9000000000"
+        );
     }
 
     #[test]
     fn verbose_uint8() {
         let payload = make_uint8(255);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "255");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "255");
     }
 
     #[test]
     fn verbose_uint16() {
         let payload = make_uint16(65535);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "65535");
+        assert_eq!(decode_payload(CNTI_VERBOSE, 0, None, &payload), "65535");
     }
 
     #[test]
     fn verbose_uint32() {
         let payload = make_uint32(4_000_000_000);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "4000000000");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "4000000000"
+        );
     }
 
     #[test]
     fn verbose_uint64() {
         let payload = make_uint64(18_000_000_000_000);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "18000000000000");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "18000000000000"
+        );
     }
 
     #[test]
     fn verbose_floa32() {
         let payload = make_floa32(1.23);
-        let result = decode_payload(CNTI_VERBOSE, &payload);
+        let result = decode_payload(CNTI_VERBOSE, 0, None, &payload);
         assert!(result.starts_with("1.23"), "got: {result}");
     }
 
     #[test]
     fn verbose_floa64() {
         let payload = make_floa64(1.23);
-        let result = decode_payload(CNTI_VERBOSE, &payload);
+        let result = decode_payload(CNTI_VERBOSE, 0, None, &payload);
         assert!(result.starts_with("1.23"), "got: {result}");
     }
 
     #[test]
     fn verbose_strg_utf8() {
         let payload = make_strg_utf8("hello world");
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "hello world");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "hello world"
+        );
     }
 
     #[test]
     fn verbose_strg_ascii() {
         let payload = make_strg_ascii("test message");
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "test message");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "test message"
+        );
     }
 
     #[test]
     fn verbose_rawd() {
         let payload = make_rawd(&[0xDE, 0xAD, 0xBE, 0xEF]);
-        assert_eq!(decode_payload(CNTI_VERBOSE, &payload), "de ad be ef");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "de ad be ef"
+        );
     }
 
     #[test]
@@ -223,7 +254,7 @@ mod tests {
         payload.extend_from_slice(&make_uint32(42));
         payload.extend_from_slice(&make_strg_utf8("hello"));
         payload.extend_from_slice(&make_floa32(1.23));
-        let result = decode_payload(CNTI_VERBOSE, &payload);
+        let result = decode_payload(CNTI_VERBOSE, 0, None, &payload);
         let parts: Vec<&str> = result.split(' ').collect();
         assert_eq!(parts[0], "42");
         assert_eq!(parts[1], "hello");
@@ -231,12 +262,31 @@ mod tests {
     }
 
     #[test]
-    fn non_verbose_payload() {
+    fn verbose_array_struct_and_trace_info() {
         let mut payload = Vec::new();
-        let msg_id: u32 = 0x0000_1234;
-        payload.extend_from_slice(&msg_id.to_be_bytes());
-        payload.extend_from_slice(&[0xAB, 0xCD]);
-        let result = decode_payload(CNTI_NON_VERBOSE, &payload);
+        payload.extend_from_slice(
+            &(DLT_TYPE_INFO_ARAY | DLT_TYPE_INFO_SINT | DLT_TYLE_16BIT).to_be_bytes(),
+        );
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&2u16.to_be_bytes());
+        payload.extend_from_slice(&(-1i16).to_be_bytes());
+        payload.extend_from_slice(&300i16.to_be_bytes());
+        payload.extend_from_slice(&DLT_TYPE_INFO_STRU.to_be_bytes());
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&make_uint8(9));
+        payload.extend_from_slice(&DLT_TYPE_INFO_TRAI.to_be_bytes());
+        payload.extend_from_slice(&3u16.to_be_bytes());
+        payload.extend_from_slice(b"fn\0");
+        assert_eq!(
+            decode_payload(CNTI_VERBOSE, 0, None, &payload),
+            "[-1, 300] {9} fn"
+        );
+    }
+
+    #[test]
+    fn non_verbose_payload() {
+        // The message ID comes from the header; the payload is all arguments.
+        let result = decode_payload(CNTI_NON_VERBOSE, 0, Some(0x1234), &[0xAB, 0xCD]);
         assert_eq!(result, "[non-verbose 0x00001234] ab cd");
     }
 
@@ -246,7 +296,7 @@ mod tests {
         let service_id: u32 = 1; // set_log_level
         payload.extend_from_slice(&service_id.to_be_bytes());
         payload.push(0); // return type: ok
-        let result = decode_payload(CNTI_CONTROL, &payload);
+        let result = decode_payload(CNTI_CONTROL, CONTROL_RESPONSE, None, &payload);
         assert_eq!(result, "[control set_log_level] ok");
     }
 }
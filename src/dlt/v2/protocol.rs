@@ -23,6 +23,12 @@ pub const HTYP2_WTGS: u32 = 1 << 17;
 pub const HTYP2_WPVL: u32 = 1 << 18;
 pub const HTYP2_WSGM: u32 = 1 << 19;
 
+// Segmentation frame types (SGMT).
+pub const FRAME_TYPE_FIRST: u8 = 0;
+pub const FRAME_TYPE_CONSECUTIVE: u8 = 1;
+pub const FRAME_TYPE_LAST: u8 = 2;
+pub const FRAME_TYPE_ABORT: u8 = 3;
+
 // MSIN layout: bits 1..3 = MSTP, bits 4..7 = MTIN.
 pub const MESSAGE_TYPE_LOG: u8 = 0x00;
 pub const MESSAGE_TYPE_TRACE: u8 = 0x01;
@@ -36,6 +42,9 @@ pub const LOG_LEVEL_INFO: u8 = 0x04;
 pub const LOG_LEVEL_DEBUG: u8 = 0x05;
 pub const LOG_LEVEL_VERBOSE: u8 = 0x06;
 
+pub const CONTROL_REQUEST: u8 = 0x01;
+pub const CONTROL_RESPONSE: u8 = 0x02;
+
 #[inline]
 pub fn htyp2_cnti(htyp2: u32) -> u8 {
     ((htyp2 & HTYP2_CNTI_MASK) >> HTYP2_CNTI_SHIFT) as u8
@@ -111,6 +120,7 @@ pub fn build_htyp2(cnti: u8, weid: bool, wacid: bool, wsid: bool, vers: u8) -> u
     (byte0 as u32) << 24
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_htyp2_full(
     cnti: u8,
     weid: bool,
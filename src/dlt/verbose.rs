@@ -0,0 +1,1069 @@
+use anyhow::{Result, anyhow, bail};
+use std::fmt;
+
+use crate::dlt::payload::{
+    DLT_SCOD_UTF8, DLT_TYLE_8BIT, DLT_TYLE_16BIT, DLT_TYLE_32BIT, DLT_TYLE_64BIT, DLT_TYLE_128BIT,
+    DLT_TYPE_INFO_ARAY, DLT_TYPE_INFO_BOOL, DLT_TYPE_INFO_FIXP, DLT_TYPE_INFO_FLOA,
+    DLT_TYPE_INFO_RAWD, DLT_TYPE_INFO_SINT, DLT_TYPE_INFO_STRG, DLT_TYPE_INFO_STRU,
+    DLT_TYPE_INFO_TRAI, DLT_TYPE_INFO_TYLE, DLT_TYPE_INFO_UINT, DLT_TYPE_INFO_VARI, hex_dump,
+};
+
+/// Deepest struct nesting decoded before giving up on a payload.
+const MAX_STRUCT_DEPTH: usize = 16;
+
+/// One decoded verbose-mode argument.
+///
+/// `name` and `unit` are only present when the sender set the VARI bit;
+/// `unit` is only defined for numeric types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: Option<String>,
+    pub unit: Option<String>,
+    pub value: Value,
+}
+
+/// Typed value of a verbose argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    /// IEEE 754 half precision, widened to `f32`.
+    F16(f32),
+    F32(f32),
+    F64(f64),
+    /// IEEE 754 quadruple precision, narrowed to `f64`.
+    F128(f64),
+    String(String),
+    Raw(Vec<u8>),
+    /// Fixed-point value; the physical value is `raw * quantization + offset`.
+    FixedPoint {
+        raw: i128,
+        quantization: f32,
+        offset: i128,
+    },
+    /// Multi-dimensional array, elements stored in row-major order.
+    Array {
+        dimensions: Vec<u16>,
+        values: Vec<Value>,
+    },
+    Struct(Vec<Argument>),
+    /// Trace info (TRAI), e.g. the name of the traced function.
+    TraceInfo(String),
+}
+
+impl Value {
+    /// The value as a number, for plotting and numeric comparisons.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Bool(v) => Some(if v { 1.0 } else { 0.0 }),
+            Value::I8(v) => Some(v.into()),
+            Value::I16(v) => Some(v.into()),
+            Value::I32(v) => Some(v.into()),
+            Value::I64(v) => Some(v as f64),
+            Value::I128(v) => Some(v as f64),
+            Value::U8(v) => Some(v.into()),
+            Value::U16(v) => Some(v.into()),
+            Value::U32(v) => Some(v.into()),
+            Value::U64(v) => Some(v as f64),
+            Value::U128(v) => Some(v as f64),
+            Value::F16(v) | Value::F32(v) => Some(v.into()),
+            Value::F64(v) | Value::F128(v) => Some(v),
+            Value::FixedPoint {
+                raw,
+                quantization,
+                offset,
+            } => Some(raw as f64 * f64::from(quantization) + offset as f64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(v) => write!(f, "{v}"),
+            Value::I8(v) => write!(f, "{v}"),
+            Value::I16(v) => write!(f, "{v}"),
+            Value::I32(v) => write!(f, "{v}"),
+            Value::I64(v) => write!(f, "{v}"),
+            Value::I128(v) => write!(f, "{v}"),
+            Value::U8(v) => write!(f, "{v}"),
+            Value::U16(v) => write!(f, "{v}"),
+            Value::U32(v) => write!(f, "{v}"),
+            Value::U64(v) => write!(f, "{v}"),
+            Value::U128(v) => write!(f, "{v}"),
+            Value::F16(v) | Value::F32(v) => write!(f, "{v}"),
+            Value::F64(v) | Value::F128(v) => write!(f, "{v}"),
+            Value::String(v) | Value::TraceInfo(v) => f.write_str(v),
+            Value::Raw(v) => f.write_str(&hex_dump(v)),
+            Value::FixedPoint { .. } => write!(f, "{}", self.as_f64().unwrap_or_default()),
+            Value::Array { dimensions, values } => write_nested(f, dimensions, values),
+            Value::Struct(fields) => {
+                f.write_str("{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    if let Some(name) = &field.name {
+                        write!(f, "{name}: ")?;
+                    }
+                    write!(f, "{}", field.value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Render `values` as nested lists, one level per dimension.
+fn write_nested(f: &mut fmt::Formatter<'_>, dimensions: &[u16], values: &[Value]) -> fmt::Result {
+    f.write_str("[")?;
+    match dimensions {
+        [] | [_] => {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{value}")?;
+            }
+        }
+        [_, inner @ ..] => {
+            let stride = inner.iter().map(|&d| d as usize).product::<usize>().max(1);
+            for (i, chunk) in values.chunks(stride).enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_nested(f, inner, chunk)?;
+            }
+        }
+    }
+    f.write_str("]")
+}
+
+/// Decode a verbose payload into typed arguments.
+///
+/// `big_endian` controls byte order for multi-byte reads (type_info, lengths, values).
+/// Decoding stops at the first truncated or unsupported argument; the
+/// arguments decoded up to that point are returned.
+pub fn decode_arguments(data: &[u8], big_endian: bool) -> Vec<Argument> {
+    decode_arguments_prefix(data, big_endian).0
+}
+
+/// Decode like `decode_arguments`, also returning how many leading bytes of
+/// `data` the decoded arguments span.
+pub(crate) fn decode_arguments_prefix(data: &[u8], big_endian: bool) -> (Vec<Argument>, usize) {
+    let mut reader = Reader::new(data, big_endian);
+    let mut arguments = Vec::new();
+    let mut consumed = 0;
+
+    while reader.data.len() >= 4 {
+        match decode_argument(&mut reader, 0) {
+            Some(argument) => {
+                arguments.push(argument);
+                consumed = data.len() - reader.remaining();
+            }
+            None => break,
+        }
+    }
+
+    (arguments, consumed)
+}
+
+fn decode_argument(reader: &mut Reader, depth: usize) -> Option<Argument> {
+    let type_info = reader.u32()?;
+    let vari = type_info & DLT_TYPE_INFO_VARI != 0;
+    let numeric = type_info & (DLT_TYPE_INFO_SINT | DLT_TYPE_INFO_UINT | DLT_TYPE_INFO_FLOA) != 0;
+
+    if type_info & DLT_TYPE_INFO_STRU != 0 {
+        if depth >= MAX_STRUCT_DEPTH {
+            return None;
+        }
+        let entries = reader.u16()?;
+        let name = if vari { Some(reader.name()?) } else { None };
+        let fields = (0..entries)
+            .map(|_| decode_argument(reader, depth + 1))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Argument {
+            name,
+            unit: None,
+            value: Value::Struct(fields),
+        })
+    } else if type_info & DLT_TYPE_INFO_ARAY != 0 {
+        let dimension_count = reader.u16()?;
+        let dimensions = (0..dimension_count)
+            .map(|_| reader.u16())
+            .collect::<Option<Vec<_>>>()?;
+        let (name, unit) = reader.variable_info(vari, numeric)?;
+        let fixed_point = reader.fixed_point_info(type_info)?;
+        let len = dimensions
+            .iter()
+            .try_fold(1usize, |len, &d| len.checked_mul(d as usize))?;
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(reader.scalar(type_info, fixed_point)?);
+        }
+        Some(Argument {
+            name,
+            unit,
+            value: Value::Array { dimensions, values },
+        })
+    } else if type_info & DLT_TYPE_INFO_BOOL != 0 || numeric {
+        let (name, unit) = reader.variable_info(vari, numeric)?;
+        let fixed_point = reader.fixed_point_info(type_info)?;
+        let value = reader.scalar(type_info, fixed_point)?;
+        Some(Argument { name, unit, value })
+    } else if type_info & (DLT_TYPE_INFO_STRG | DLT_TYPE_INFO_TRAI) != 0 {
+        let len = reader.u16()? as usize;
+        let name = if vari { Some(reader.name()?) } else { None };
+        let text = reader.text(len)?;
+        let value = if type_info & DLT_TYPE_INFO_STRG != 0 {
+            Value::String(text)
+        } else {
+            Value::TraceInfo(text)
+        };
+        Some(Argument {
+            name,
+            unit: None,
+            value,
+        })
+    } else if type_info & DLT_TYPE_INFO_RAWD != 0 {
+        let len = reader.u16()? as usize;
+        let name = if vari { Some(reader.name()?) } else { None };
+        let value = Value::Raw(reader.take(len)?.to_vec());
+        Some(Argument {
+            name,
+            unit: None,
+            value,
+        })
+    } else {
+        // Unsupported type: the argument length is unknown, so nothing after
+        // it can be decoded either.
+        None
+    }
+}
+
+/// Encode typed arguments into a verbose payload; the inverse of
+/// `decode_arguments`.
+///
+/// Strings are written as UTF-8 with a NUL terminator. Fixed-point values
+/// are written as signed integers of the narrowest type length (32 bits or
+/// wider) that holds both the raw value and the offset.
+pub fn encode_arguments(arguments: &[Argument], big_endian: bool) -> Result<Vec<u8>> {
+    let mut writer = Writer {
+        data: Vec::new(),
+        big_endian,
+    };
+    for argument in arguments {
+        writer.argument(argument, 0)?;
+    }
+    Ok(writer.data)
+}
+
+/// Byte-order-aware sink for an encoded payload; the counterpart of `Reader`.
+struct Writer {
+    data: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Writer {
+    /// Append big-endian `bytes` in the payload's byte order.
+    fn array<const N: usize>(&mut self, mut bytes: [u8; N]) {
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        self.data.extend_from_slice(&bytes);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.array(value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.array(value.to_be_bytes());
+    }
+
+    fn length(&mut self, len: usize) -> Result<()> {
+        let len = u16::try_from(len).map_err(|_| anyhow!("{len} bytes exceed an argument"))?;
+        self.u16(len);
+        Ok(())
+    }
+
+    fn argument(&mut self, argument: &Argument, depth: usize) -> Result<()> {
+        let vari = argument.name.is_some() || argument.unit.is_some();
+        let vari_bit = if vari { DLT_TYPE_INFO_VARI } else { 0 };
+        let name = argument.name.as_deref().unwrap_or_default();
+
+        match &argument.value {
+            Value::Struct(fields) => {
+                if depth >= MAX_STRUCT_DEPTH {
+                    bail!("structs nest deeper than {MAX_STRUCT_DEPTH} levels");
+                }
+                self.u32(DLT_TYPE_INFO_STRU | vari_bit);
+                self.length(fields.len())?;
+                if vari {
+                    self.name(name)?;
+                }
+                for field in fields {
+                    self.argument(field, depth + 1)?;
+                }
+            }
+            Value::Array { dimensions, values } => {
+                let len = dimensions
+                    .iter()
+                    .try_fold(1usize, |len, &d| len.checked_mul(d as usize));
+                if len != Some(values.len()) {
+                    bail!(
+                        "array of {} values does not match dimensions {dimensions:?}",
+                        values.len()
+                    );
+                }
+                let type_info = match values.first() {
+                    Some(first) => scalar_type(first)?,
+                    None => DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT,
+                };
+                if values.iter().any(|value| {
+                    scalar_type(value).ok() != Some(type_info)
+                        || fixed_point(value) != fixed_point(&values[0])
+                }) {
+                    bail!("array elements must share one type");
+                }
+                self.u32(DLT_TYPE_INFO_ARAY | type_info | vari_bit);
+                self.length(dimensions.len())?;
+                for &dimension in dimensions {
+                    self.u16(dimension);
+                }
+                self.variable_info(vari, type_info, name, argument.unit.as_deref())?;
+                if let Some(first) = values.first() {
+                    self.fixed_point_info(first, type_info)?;
+                }
+                for value in values {
+                    self.scalar(value, type_info);
+                }
+            }
+            Value::String(text) | Value::TraceInfo(text) => {
+                let kind = if matches!(argument.value, Value::String(_)) {
+                    DLT_TYPE_INFO_STRG | DLT_SCOD_UTF8
+                } else {
+                    DLT_TYPE_INFO_TRAI
+                };
+                self.u32(kind | vari_bit);
+                self.length(text.len() + 1)?;
+                if vari {
+                    self.name(name)?;
+                }
+                self.data.extend_from_slice(text.as_bytes());
+                self.data.push(0);
+            }
+            Value::Raw(bytes) => {
+                self.u32(DLT_TYPE_INFO_RAWD | vari_bit);
+                self.length(bytes.len())?;
+                if vari {
+                    self.name(name)?;
+                }
+                self.data.extend_from_slice(bytes);
+            }
+            value => {
+                let type_info = scalar_type(value)?;
+                self.u32(type_info | vari_bit);
+                self.variable_info(vari, type_info, name, argument.unit.as_deref())?;
+                self.fixed_point_info(value, type_info)?;
+                self.scalar(value, type_info);
+            }
+        }
+        Ok(())
+    }
+
+    /// Value bytes of a scalar whose type info came from `scalar_type`.
+    fn scalar(&mut self, value: &Value, type_info: u32) {
+        match *value {
+            Value::Bool(v) => self.data.push(u8::from(v)),
+            Value::I8(v) => self.array(v.to_be_bytes()),
+            Value::I16(v) => self.array(v.to_be_bytes()),
+            Value::I32(v) => self.array(v.to_be_bytes()),
+            Value::I64(v) => self.array(v.to_be_bytes()),
+            Value::I128(v) => self.array(v.to_be_bytes()),
+            Value::U8(v) => self.array(v.to_be_bytes()),
+            Value::U16(v) => self.array(v.to_be_bytes()),
+            Value::U32(v) => self.array(v.to_be_bytes()),
+            Value::U64(v) => self.array(v.to_be_bytes()),
+            Value::U128(v) => self.array(v.to_be_bytes()),
+            Value::F16(v) => self.array(f32_to_f16(v).to_be_bytes()),
+            Value::F32(v) => self.array(v.to_be_bytes()),
+            Value::F64(v) => self.array(v.to_be_bytes()),
+            Value::F128(v) => self.array(f64_to_f128(v).to_be_bytes()),
+            // `scalar_type` picked a width that holds the raw value.
+            Value::FixedPoint { raw, .. } => match type_info & DLT_TYPE_INFO_TYLE {
+                DLT_TYLE_32BIT => self.array((raw as i32).to_be_bytes()),
+                DLT_TYLE_64BIT => self.array((raw as i64).to_be_bytes()),
+                _ => self.array(raw.to_be_bytes()),
+            },
+            _ => {}
+        }
+    }
+
+    /// Quantization and offset ahead of a FIXP value, as `fixed_point_info`
+    /// reads them.
+    fn fixed_point_info(&mut self, value: &Value, type_info: u32) -> Result<()> {
+        let Some((quantization, offset)) = fixed_point(value) else {
+            return Ok(());
+        };
+        self.array(quantization.to_be_bytes());
+        match type_info & DLT_TYPE_INFO_TYLE {
+            DLT_TYLE_128BIT => self.array(offset.to_be_bytes()),
+            DLT_TYLE_64BIT => self.array(i64::try_from(offset)?.to_be_bytes()),
+            _ => self.array(i32::try_from(offset)?.to_be_bytes()),
+        }
+        Ok(())
+    }
+
+    /// VARI name (and unit for numeric types), as `variable_info` reads them.
+    fn variable_info(
+        &mut self,
+        vari: bool,
+        type_info: u32,
+        name: &str,
+        unit: Option<&str>,
+    ) -> Result<()> {
+        if !vari {
+            return Ok(());
+        }
+        if type_info & (DLT_TYPE_INFO_SINT | DLT_TYPE_INFO_UINT | DLT_TYPE_INFO_FLOA) == 0 {
+            return self.name(name);
+        }
+        let unit = unit.unwrap_or_default();
+        self.length(name.len() + 1)?;
+        self.length(unit.len() + 1)?;
+        self.text(name);
+        self.text(unit);
+        Ok(())
+    }
+
+    /// VARI name for non-numeric types: length, then name.
+    fn name(&mut self, name: &str) -> Result<()> {
+        self.length(name.len() + 1)?;
+        self.text(name);
+        Ok(())
+    }
+
+    /// NUL-terminated string data.
+    fn text(&mut self, text: &str) {
+        self.data.extend_from_slice(text.as_bytes());
+        self.data.push(0);
+    }
+}
+
+/// Type info (without VARI) of a BOOL, SINT, UINT, FLOA or FIXP value.
+fn scalar_type(value: &Value) -> Result<u32> {
+    let type_info = match *value {
+        Value::Bool(_) => DLT_TYPE_INFO_BOOL | DLT_TYLE_8BIT,
+        Value::I8(_) => DLT_TYPE_INFO_SINT | DLT_TYLE_8BIT,
+        Value::I16(_) => DLT_TYPE_INFO_SINT | DLT_TYLE_16BIT,
+        Value::I32(_) => DLT_TYPE_INFO_SINT | DLT_TYLE_32BIT,
+        Value::I64(_) => DLT_TYPE_INFO_SINT | DLT_TYLE_64BIT,
+        Value::I128(_) => DLT_TYPE_INFO_SINT | DLT_TYLE_128BIT,
+        Value::U8(_) => DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT,
+        Value::U16(_) => DLT_TYPE_INFO_UINT | DLT_TYLE_16BIT,
+        Value::U32(_) => DLT_TYPE_INFO_UINT | DLT_TYLE_32BIT,
+        Value::U64(_) => DLT_TYPE_INFO_UINT | DLT_TYLE_64BIT,
+        Value::U128(_) => DLT_TYPE_INFO_UINT | DLT_TYLE_128BIT,
+        Value::F16(_) => DLT_TYPE_INFO_FLOA | DLT_TYLE_16BIT,
+        Value::F32(_) => DLT_TYPE_INFO_FLOA | DLT_TYLE_32BIT,
+        Value::F64(_) => DLT_TYPE_INFO_FLOA | DLT_TYLE_64BIT,
+        Value::F128(_) => DLT_TYPE_INFO_FLOA | DLT_TYLE_128BIT,
+        Value::FixedPoint { raw, offset, .. } => {
+            let fits = |bits: u32| {
+                let min = -(1i128 << (bits - 1));
+                let max = (1i128 << (bits - 1)) - 1;
+                (min..=max).contains(&raw) && (min..=max).contains(&offset)
+            };
+            let tyle = if fits(32) {
+                DLT_TYLE_32BIT
+            } else if fits(64) {
+                DLT_TYLE_64BIT
+            } else {
+                DLT_TYLE_128BIT
+            };
+            DLT_TYPE_INFO_SINT | DLT_TYPE_INFO_FIXP | tyle
+        }
+        ref other => bail!("{other:?} is not a scalar value"),
+    };
+    Ok(type_info)
+}
+
+/// Quantization and offset of a fixed-point value.
+fn fixed_point(value: &Value) -> Option<(f32, i128)> {
+    match *value {
+        Value::FixedPoint {
+            quantization,
+            offset,
+            ..
+        } => Some((quantization, offset)),
+        _ => None,
+    }
+}
+
+/// Byte-order-aware cursor over a payload slice.
+#[derive(Clone)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Self { data, big_endian }
+    }
+
+    /// Bytes left to read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let mut bytes: [u8; N] = self.take(N)?.try_into().ok()?;
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    /// One BOOL, SINT, UINT or FLOA value, applying fixed-point scaling if given.
+    fn scalar(&mut self, type_info: u32, fixed_point: Option<(f32, i128)>) -> Option<Value> {
+        let tyle = type_info & DLT_TYPE_INFO_TYLE;
+        if type_info & DLT_TYPE_INFO_BOOL != 0 {
+            return Some(Value::Bool(self.take(1)?[0] != 0));
+        }
+        if type_info & DLT_TYPE_INFO_FLOA != 0 {
+            return self.float(tyle);
+        }
+
+        let value = if type_info & DLT_TYPE_INFO_SINT != 0 {
+            self.signed(tyle)?
+        } else {
+            self.unsigned(tyle)?
+        };
+        let Some((quantization, offset)) = fixed_point else {
+            return Some(value);
+        };
+        let raw = match value {
+            Value::I8(v) => v.into(),
+            Value::I16(v) => v.into(),
+            Value::I32(v) => v.into(),
+            Value::I64(v) => v.into(),
+            Value::I128(v) => v,
+            Value::U8(v) => v.into(),
+            Value::U16(v) => v.into(),
+            Value::U32(v) => v.into(),
+            Value::U64(v) => v.into(),
+            Value::U128(v) => v as i128,
+            _ => return None,
+        };
+        Some(Value::FixedPoint {
+            raw,
+            quantization,
+            offset,
+        })
+    }
+
+    pub(crate) fn signed(&mut self, tyle: u32) -> Option<Value> {
+        match tyle {
+            DLT_TYLE_8BIT => self.array().map(|b| Value::I8(i8::from_be_bytes(b))),
+            DLT_TYLE_16BIT => self.array().map(|b| Value::I16(i16::from_be_bytes(b))),
+            DLT_TYLE_32BIT => self.array().map(|b| Value::I32(i32::from_be_bytes(b))),
+            DLT_TYLE_64BIT => self.array().map(|b| Value::I64(i64::from_be_bytes(b))),
+            DLT_TYLE_128BIT => self.array().map(|b| Value::I128(i128::from_be_bytes(b))),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn unsigned(&mut self, tyle: u32) -> Option<Value> {
+        match tyle {
+            DLT_TYLE_8BIT => self.array().map(|b| Value::U8(u8::from_be_bytes(b))),
+            DLT_TYLE_16BIT => self.array().map(|b| Value::U16(u16::from_be_bytes(b))),
+            DLT_TYLE_32BIT => self.array().map(|b| Value::U32(u32::from_be_bytes(b))),
+            DLT_TYLE_64BIT => self.array().map(|b| Value::U64(u64::from_be_bytes(b))),
+            DLT_TYLE_128BIT => self.array().map(|b| Value::U128(u128::from_be_bytes(b))),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn float(&mut self, tyle: u32) -> Option<Value> {
+        match tyle {
+            DLT_TYLE_16BIT => self
+                .array()
+                .map(|b| Value::F16(f16_to_f32(u16::from_be_bytes(b)))),
+            DLT_TYLE_32BIT => self.array().map(|b| Value::F32(f32::from_be_bytes(b))),
+            DLT_TYLE_64BIT => self.array().map(|b| Value::F64(f64::from_be_bytes(b))),
+            DLT_TYLE_128BIT => self
+                .array()
+                .map(|b| Value::F128(f128_to_f64(u128::from_be_bytes(b)))),
+            _ => None,
+        }
+    }
+
+    /// Quantization and offset that precede a FIXP value; the offset is
+    /// 32 bits wide for values up to 32 bits, otherwise as wide as the value.
+    fn fixed_point_info(&mut self, type_info: u32) -> Option<Option<(f32, i128)>> {
+        if type_info & DLT_TYPE_INFO_FIXP == 0 {
+            return Some(None);
+        }
+        let quantization = self.array().map(f32::from_be_bytes)?;
+        let offset = match type_info & DLT_TYPE_INFO_TYLE {
+            DLT_TYLE_64BIT => self.array().map(i64::from_be_bytes)?.into(),
+            DLT_TYLE_128BIT => self.array().map(i128::from_be_bytes)?,
+            _ => self.array().map(i32::from_be_bytes)?.into(),
+        };
+        Some(Some((quantization, offset)))
+    }
+
+    /// VARI name (and unit for numeric types), if the VARI bit is set.
+    fn variable_info(
+        &mut self,
+        vari: bool,
+        numeric: bool,
+    ) -> Option<(Option<String>, Option<String>)> {
+        match (vari, numeric) {
+            (false, _) => Some((None, None)),
+            (true, false) => Some((Some(self.name()?), None)),
+            (true, true) => {
+                let (name, unit) = self.name_and_unit()?;
+                Some((Some(name), Some(unit)))
+            }
+        }
+    }
+
+    /// String data of `len` bytes, without its NUL terminator.
+    pub(crate) fn text(&mut self, len: usize) -> Option<String> {
+        let bytes = self.take(len)?;
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// VARI name for non-numeric types: length, then name.
+    fn name(&mut self) -> Option<String> {
+        let len = self.u16()? as usize;
+        self.text(len)
+    }
+
+    /// VARI name and unit for numeric types: both lengths, then both strings.
+    fn name_and_unit(&mut self) -> Option<(String, String)> {
+        let name_len = self.u16()? as usize;
+        let unit_len = self.u16()? as usize;
+        let name = self.text(name_len)?;
+        let unit = self.text(unit_len)?;
+        Some((name, unit))
+    }
+}
+
+/// Widen IEEE 754 binary16 bits to `f32`.
+fn f16_to_f32(bits: u16) -> f32 {
+    let negative = bits & 0x8000 != 0;
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x03ff) as f32;
+    let magnitude = match exponent {
+        0 => fraction * 2f32.powi(-24),
+        0x1f if fraction == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f32.powi(exponent - 15),
+    };
+    if negative { -magnitude } else { magnitude }
+}
+
+/// Narrow IEEE 754 binary128 bits to `f64`, keeping the top 52 fraction bits.
+fn f128_to_f64(bits: u128) -> f64 {
+    let negative = bits >> 127 != 0;
+    let exponent = ((bits >> 112) & 0x7fff) as i32;
+    let fraction = ((bits >> 60) & ((1 << 52) - 1)) as f64 / 2f64.powi(52);
+    let magnitude = match exponent {
+        0 => fraction * 2f64.powi(-16382),
+        0x7fff if fraction == 0.0 => f64::INFINITY,
+        0x7fff => f64::NAN,
+        _ => (1.0 + fraction) * 2f64.powi(exponent - 16383),
+    };
+    if negative { -magnitude } else { magnitude }
+}
+
+/// Narrow `f32` to IEEE 754 binary16 bits, rounding toward zero.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let fraction = bits & 0x007f_ffff;
+    if exponent == 0xff {
+        let nan = if fraction != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    match exponent {
+        0x1f.. => sign | 0x7c00,
+        1.. => sign | ((exponent as u16) << 10) | (fraction >> 13) as u16,
+        -10..=0 => sign | ((fraction | 0x0080_0000) >> (14 - exponent)) as u16,
+        _ => sign,
+    }
+}
+
+/// Widen `f64` to IEEE 754 binary128 bits; every `f64` is exact.
+fn f64_to_f128(value: f64) -> u128 {
+    let bits = value.to_bits();
+    let sign = u128::from(bits >> 63) << 127;
+    let exponent = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = u128::from(bits & ((1 << 52) - 1));
+    match exponent {
+        0 if fraction == 0 => sign,
+        0 => {
+            // Subnormal: normalize around the highest set fraction bit.
+            let top = 127 - fraction.leading_zeros() as i32;
+            let fraction = (fraction << (52 - top)) & ((1 << 52) - 1);
+            sign | (((top - 1074 + 16383) as u128) << 112) | (fraction << 60)
+        }
+        0x7ff => sign | (0x7fff << 112) | (fraction << 60),
+        _ => sign | (((exponent - 1023 + 16383) as u128) << 112) | (fraction << 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_info(bits: u32) -> Vec<u8> {
+        bits.to_le_bytes().to_vec()
+    }
+
+    fn arg(value: Value) -> Argument {
+        Argument {
+            name: None,
+            unit: None,
+            value,
+        }
+    }
+
+    #[test]
+    fn decodes_typed_scalars() {
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_BOOL | DLT_TYLE_8BIT));
+        data.push(1);
+        data.extend(type_info(DLT_TYPE_INFO_SINT | DLT_TYLE_16BIT));
+        data.extend((-2i16).to_le_bytes());
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_64BIT));
+        data.extend(7u64.to_le_bytes());
+        data.extend(type_info(DLT_TYPE_INFO_FLOA | DLT_TYLE_32BIT));
+        data.extend(1.5f32.to_le_bytes());
+        data.extend(type_info(DLT_TYPE_INFO_STRG));
+        data.extend(3u16.to_le_bytes());
+        data.extend(b"hi\0");
+        data.extend(type_info(DLT_TYPE_INFO_RAWD));
+        data.extend(2u16.to_le_bytes());
+        data.extend([0xde, 0xad]);
+
+        assert_eq!(
+            decode_arguments(&data, false),
+            vec![
+                arg(Value::Bool(true)),
+                arg(Value::I16(-2)),
+                arg(Value::U64(7)),
+                arg(Value::F32(1.5)),
+                arg(Value::String("hi".into())),
+                arg(Value::Raw(vec![0xde, 0xad])),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_big_endian_values() {
+        let mut data = (DLT_TYPE_INFO_UINT | DLT_TYLE_32BIT).to_be_bytes().to_vec();
+        data.extend(0x0102_0304u32.to_be_bytes());
+
+        assert_eq!(
+            decode_arguments(&data, true),
+            vec![arg(Value::U32(0x0102_0304))]
+        );
+    }
+
+    #[test]
+    fn keeps_variable_names_and_units() {
+        let mut data = Vec::new();
+        data.extend(type_info(
+            DLT_TYPE_INFO_UINT | DLT_TYLE_16BIT | DLT_TYPE_INFO_VARI,
+        ));
+        data.extend(6u16.to_le_bytes());
+        data.extend(3u16.to_le_bytes());
+        data.extend(b"speed\0");
+        data.extend(b"km\0");
+        data.extend(120u16.to_le_bytes());
+        data.extend(type_info(DLT_TYPE_INFO_STRG | DLT_TYPE_INFO_VARI));
+        data.extend(3u16.to_le_bytes());
+        data.extend(5u16.to_le_bytes());
+        data.extend(b"gear");
+        data.push(0);
+        data.extend(b"D1\0");
+
+        assert_eq!(
+            decode_arguments(&data, false),
+            vec![
+                Argument {
+                    name: Some("speed".into()),
+                    unit: Some("km".into()),
+                    value: Value::U16(120),
+                },
+                Argument {
+                    name: Some("gear".into()),
+                    unit: None,
+                    value: Value::String("D1".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_truncated_argument() {
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT));
+        data.push(9);
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_32BIT));
+        data.extend([1, 2]);
+
+        assert_eq!(decode_arguments(&data, false), vec![arg(Value::U8(9))]);
+    }
+
+    #[test]
+    fn numeric_values_convert_to_f64() {
+        assert_eq!(Value::I8(-3).as_f64(), Some(-3.0));
+        assert_eq!(Value::Bool(true).as_f64(), Some(1.0));
+        assert_eq!(
+            Value::FixedPoint {
+                raw: 10,
+                quantization: 0.5,
+                offset: 2,
+            }
+            .as_f64(),
+            Some(7.0)
+        );
+        assert_eq!(Value::String("1".into()).as_f64(), None);
+    }
+
+    #[test]
+    fn decodes_128_bit_integers() {
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_SINT | DLT_TYLE_128BIT));
+        data.extend((-5i128).to_le_bytes());
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_128BIT));
+        data.extend(u128::MAX.to_le_bytes());
+
+        let arguments = decode_arguments(&data, false);
+
+        assert_eq!(
+            arguments,
+            vec![arg(Value::I128(-5)), arg(Value::U128(u128::MAX))]
+        );
+        assert_eq!(
+            arguments[1].value.to_string(),
+            "340282366920938463463374607431768211455"
+        );
+    }
+
+    #[test]
+    fn decodes_half_and_quad_precision_floats() {
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_FLOA | DLT_TYLE_16BIT));
+        data.extend(0xc140u16.to_le_bytes()); // -2.625
+        data.extend(type_info(DLT_TYPE_INFO_FLOA | DLT_TYLE_16BIT));
+        data.extend(0x0001u16.to_le_bytes()); // smallest subnormal
+        data.extend(type_info(DLT_TYPE_INFO_FLOA | DLT_TYLE_128BIT));
+        // 1.5 in binary128: exponent 0x3fff, top fraction bit set.
+        data.extend((0x3fff_8000_0000_0000_0000_0000_0000_0000u128).to_le_bytes());
+
+        assert_eq!(
+            decode_arguments(&data, false),
+            vec![
+                arg(Value::F16(-2.625)),
+                arg(Value::F16(2f32.powi(-24))),
+                arg(Value::F128(1.5)),
+            ]
+        );
+        assert!(f16_to_f32(0x7c00).is_infinite());
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn applies_fixed_point_quantization_and_offset() {
+        let mut data = Vec::new();
+        data.extend(type_info(
+            DLT_TYPE_INFO_SINT | DLT_TYLE_16BIT | DLT_TYPE_INFO_FIXP | DLT_TYPE_INFO_VARI,
+        ));
+        data.extend(5u16.to_le_bytes());
+        data.extend(2u16.to_le_bytes());
+        data.extend(b"temp\0");
+        data.extend(b"C\0");
+        data.extend(0.5f32.to_le_bytes());
+        data.extend((-40i32).to_le_bytes());
+        data.extend(130i16.to_le_bytes());
+        data.extend(type_info(
+            DLT_TYPE_INFO_UINT | DLT_TYLE_64BIT | DLT_TYPE_INFO_FIXP,
+        ));
+        data.extend(0.25f32.to_le_bytes());
+        data.extend(1_000i64.to_le_bytes());
+        data.extend(8u64.to_le_bytes());
+
+        let arguments = decode_arguments(&data, false);
+
+        assert_eq!(
+            arguments,
+            vec![
+                Argument {
+                    name: Some("temp".into()),
+                    unit: Some("C".into()),
+                    value: Value::FixedPoint {
+                        raw: 130,
+                        quantization: 0.5,
+                        offset: -40,
+                    },
+                },
+                arg(Value::FixedPoint {
+                    raw: 8,
+                    quantization: 0.25,
+                    offset: 1_000,
+                }),
+            ]
+        );
+        assert_eq!(arguments[0].value.to_string(), "25");
+        assert_eq!(arguments[1].value.to_string(), "1002");
+    }
+
+    #[test]
+    fn decodes_multi_dimensional_arrays() {
+        let mut data = Vec::new();
+        data.extend(type_info(
+            DLT_TYPE_INFO_ARAY | DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT,
+        ));
+        data.extend(2u16.to_le_bytes());
+        data.extend(2u16.to_le_bytes());
+        data.extend(3u16.to_le_bytes());
+        data.extend([1, 2, 3, 4, 5, 6]);
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT));
+        data.push(7);
+
+        let arguments = decode_arguments(&data, false);
+
+        assert_eq!(arguments.len(), 2);
+        assert_eq!(
+            arguments[0].value,
+            Value::Array {
+                dimensions: vec![2, 3],
+                values: (1..=6).map(Value::U8).collect(),
+            }
+        );
+        assert_eq!(arguments[0].value.to_string(), "[[1, 2, 3], [4, 5, 6]]");
+        assert_eq!(arguments[1].value, Value::U8(7));
+    }
+
+    #[test]
+    fn decodes_nested_structs() {
+        let mut inner = Vec::new();
+        inner.extend(type_info(DLT_TYPE_INFO_STRU | DLT_TYPE_INFO_VARI));
+        inner.extend(1u16.to_le_bytes());
+        inner.extend(4u16.to_le_bytes());
+        inner.extend(b"pos\0");
+        inner.extend(type_info(DLT_TYPE_INFO_SINT | DLT_TYLE_32BIT));
+        inner.extend((-3i32).to_le_bytes());
+
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_STRU));
+        data.extend(2u16.to_le_bytes());
+        data.extend(type_info(
+            DLT_TYPE_INFO_BOOL | DLT_TYLE_8BIT | DLT_TYPE_INFO_VARI,
+        ));
+        data.extend(3u16.to_le_bytes());
+        data.extend(b"ok\0");
+        data.push(1);
+        data.extend(&inner);
+
+        let arguments = decode_arguments(&data, false);
+
+        assert_eq!(arguments.len(), 1);
+        assert_eq!(arguments[0].value.to_string(), "{ok: true, pos: {-3}}");
+    }
+
+    #[test]
+    fn rejects_structs_nested_too_deeply() {
+        let mut data = Vec::new();
+        for _ in 0..=MAX_STRUCT_DEPTH {
+            data.extend(type_info(DLT_TYPE_INFO_STRU));
+            data.extend(1u16.to_le_bytes());
+        }
+        data.extend(type_info(DLT_TYPE_INFO_UINT | DLT_TYLE_8BIT));
+        data.push(1);
+
+        assert!(decode_arguments(&data, false).is_empty());
+    }
+
+    #[test]
+    fn rejects_arguments_that_cannot_be_encoded() {
+        let array = |dimensions: Vec<u16>, values| arg(Value::Array { dimensions, values });
+
+        assert!(encode_arguments(&[array(vec![3], vec![Value::U8(1)])], false).is_err());
+        assert!(
+            encode_arguments(&[array(vec![2], vec![Value::U8(1), Value::I8(1)])], false).is_err()
+        );
+        assert!(
+            encode_arguments(&[array(vec![1], vec![Value::String("s".into())])], false).is_err()
+        );
+        assert_eq!(
+            decode_arguments(
+                &encode_arguments(&[array(vec![0], vec![])], true).unwrap(),
+                true
+            ),
+            vec![array(vec![0], vec![])]
+        );
+    }
+
+    #[test]
+    fn narrows_and_widens_floats_for_encoding() {
+        for value in [0.0f32, -1.5, 65504.0, 6.1035156e-5, 5.9604645e-8] {
+            assert_eq!(f16_to_f32(f32_to_f16(value)), value);
+        }
+        assert_eq!(f16_to_f32(f32_to_f16(1e6)), f32::INFINITY);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        for value in [0.0f64, -2.25, 1e300, 1e-300, f64::NEG_INFINITY] {
+            assert_eq!(f128_to_f64(f64_to_f128(value)), value);
+        }
+    }
+
+    #[test]
+    fn decodes_trace_info() {
+        let mut data = Vec::new();
+        data.extend(type_info(DLT_TYPE_INFO_TRAI));
+        data.extend(5u16.to_le_bytes());
+        data.extend(b"main\0");
+
+        assert_eq!(
+            decode_arguments(&data, false),
+            vec![arg(Value::TraceInfo("main".into()))]
+        );
+    }
+}
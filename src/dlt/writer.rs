@@ -0,0 +1,537 @@
+use anyhow::{Result, anyhow, bail};
+use std::io::Write;
+
+use crate::dlt::control::{CONTROL_REQUEST, CONTROL_RESPONSE, ControlMessage};
+use crate::dlt::storage::{STORAGE_HEADER_PATTERN, STORAGE_HEADER_SIZE};
+use crate::dlt::v2::Segment;
+use crate::dlt::verbose::{Argument, Value, encode_arguments};
+use crate::dlt::{SUPPORTED_VERSIONS, v1, v2};
+
+/// MSTP of control messages, shared by both protocol versions.
+const MESSAGE_TYPE_CONTROL: u8 = 0x03;
+
+/// What a message carries after its headers.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Content {
+    Verbose(Vec<Argument>),
+    /// Message ID and argument bytes, laid out as a FIBEX frame describes.
+    NonVerbose {
+        message_id: u32,
+        arguments: Vec<u8>,
+    },
+    Control(ControlMessage),
+}
+
+/// Builds a DLT message from typed fields and encodes it as v1 or v2.
+///
+/// Fields a version has no place for are rejected when encoding rather than
+/// dropped: v1 has no source location, tags, privacy level or segments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageBuilder {
+    pub(crate) storage_seconds: u32,
+    pub(crate) storage_microseconds: u32,
+    pub(crate) storage_ecu: [u8; 4],
+    pub(crate) ecu: Option<[u8; 4]>,
+    pub(crate) apid: Option<[u8; 4]>,
+    pub(crate) ctid: Option<[u8; 4]>,
+    pub(crate) session_id: Option<u32>,
+    pub(crate) timestamp_ns: Option<u64>,
+    pub(crate) counter: u8,
+    pub(crate) message_type: u8,
+    pub(crate) message_type_info: u8,
+    /// v1 payload byte order (MSBF); v2 payloads are always big-endian.
+    pub(crate) big_endian: bool,
+    pub(crate) content: Content,
+    /// v2 source file name and line number (WSFLN).
+    pub(crate) source_location: Option<(String, u32)>,
+    /// v2 tags (WTGS).
+    pub(crate) tags: Vec<String>,
+    /// v2 privacy level (WPVL).
+    pub(crate) privacy_level: Option<u8>,
+    /// v2 segmentation information (WSGM) and the slice of the payload
+    /// this segment carries in place of the whole payload.
+    pub(crate) segment: Option<(Segment, Vec<u8>)>,
+}
+
+impl Default for MessageBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageBuilder {
+    /// An info-level verbose log message without arguments, stored at
+    /// 1000.5 s by `ECU1`.
+    pub fn new() -> Self {
+        Self {
+            storage_seconds: 1000,
+            storage_microseconds: 500_000,
+            storage_ecu: *b"ECU1",
+            ecu: None,
+            apid: None,
+            ctid: None,
+            session_id: None,
+            timestamp_ns: None,
+            counter: 0,
+            message_type: 0x00,
+            message_type_info: 0x04,
+            big_endian: false,
+            content: Content::Verbose(Vec::new()),
+            source_location: None,
+            tags: Vec::new(),
+            privacy_level: None,
+            segment: None,
+        }
+    }
+
+    pub fn with_storage_ecu(mut self, ecu: &[u8; 4]) -> Self {
+        self.storage_ecu = *ecu;
+        self
+    }
+
+    pub fn with_storage_timestamp(mut self, seconds: u32, microseconds: u32) -> Self {
+        self.storage_seconds = seconds;
+        self.storage_microseconds = microseconds;
+        self
+    }
+
+    /// IDs longer than 4 bytes are truncated, shorter ones NUL-padded.
+    pub fn with_ecu(mut self, ecu: &str) -> Self {
+        self.ecu = Some(id(ecu));
+        self
+    }
+
+    pub fn with_apid(mut self, apid: &str) -> Self {
+        self.apid = Some(id(apid));
+        self
+    }
+
+    pub fn with_ctid(mut self, ctid: &str) -> Self {
+        self.ctid = Some(id(ctid));
+        self
+    }
+
+    pub fn without_apid(mut self) -> Self {
+        self.apid = None;
+        self
+    }
+
+    pub fn without_ctid(mut self) -> Self {
+        self.ctid = None;
+        self
+    }
+
+    pub fn with_session_id(mut self, id: u32) -> Self {
+        self.session_id = Some(id);
+        self
+    }
+
+    /// Message timestamp; v1 keeps it in 0.1 ms ticks.
+    pub fn with_timestamp_ns(mut self, ns: u64) -> Self {
+        self.timestamp_ns = Some(ns);
+        self
+    }
+
+    /// Message counter (MCNT).
+    pub fn with_counter(mut self, counter: u8) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    pub fn with_message_type(mut self, mstp: u8) -> Self {
+        self.message_type = mstp;
+        self
+    }
+
+    pub fn with_message_type_info(mut self, mtin: u8) -> Self {
+        self.message_type_info = mtin;
+        self
+    }
+
+    /// Write the v1 payload most-significant byte first (MSBF).
+    pub fn with_big_endian(mut self, big_endian: bool) -> Self {
+        self.big_endian = big_endian;
+        self
+    }
+
+    /// Add a verbose argument; turns a non-verbose or control message back
+    /// into a verbose one.
+    pub fn with_argument(mut self, argument: Argument) -> Self {
+        match &mut self.content {
+            Content::Verbose(arguments) => arguments.push(argument),
+            content => *content = Content::Verbose(vec![argument]),
+        }
+        self
+    }
+
+    /// Add an unnamed verbose UTF-8 string argument.
+    pub fn with_verbose_string(self, s: &str) -> Self {
+        self.with_argument(Argument {
+            name: None,
+            unit: None,
+            value: Value::String(s.to_string()),
+        })
+    }
+
+    /// Make this a non-verbose message with the given message ID and
+    /// argument bytes; verbose arguments are dropped.
+    pub fn with_non_verbose(mut self, message_id: u32, payload: &[u8]) -> Self {
+        self.content = Content::NonVerbose {
+            message_id,
+            arguments: payload.to_vec(),
+        };
+        self
+    }
+
+    /// Make this a control message; its status tells requests from
+    /// responses.
+    pub fn with_control(mut self, message: ControlMessage) -> Self {
+        self.content = Content::Control(message);
+        self
+    }
+
+    /// v2 source file name and line number (WSFLN).
+    pub fn with_source_location(mut self, file: &str, line: u32) -> Self {
+        self.source_location = Some((file.to_string(), line));
+        self
+    }
+
+    /// Add a v2 tag (WTGS).
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// v2 privacy level (WPVL).
+    pub fn with_privacy_level(mut self, level: u8) -> Self {
+        self.privacy_level = Some(level);
+        self
+    }
+
+    /// Send this message as one v2 segment (WSGM) carrying `payload`, a
+    /// slice of the encoded payload, instead of the whole payload.
+    ///
+    /// `build_segments` splits a message into a complete sequence.
+    pub fn with_segment(mut self, segment: Segment, payload: &[u8]) -> Self {
+        self.segment = Some((segment, payload.to_vec()));
+        self
+    }
+
+    /// The message as v2 file frames of segments carrying at most
+    /// `max_payload` payload bytes each: a first segment, consecutive
+    /// segments, and a last segment. Like a sender, each segment takes the
+    /// next message counter.
+    pub fn build_segments(&self, max_payload: usize) -> Result<Vec<Vec<u8>>> {
+        if max_payload == 0 {
+            bail!("segments must carry at least one payload byte");
+        }
+        let payload = self.payload(true)?;
+        let mut chunks = payload.chunks(max_payload);
+        let first = chunks.next().unwrap_or_default();
+        let mut segments = vec![Segment::First {
+            total_length: payload.len() as u64,
+        }];
+        let mut slices = vec![first];
+        let rest: Vec<&[u8]> = chunks.collect();
+        let last = rest.len().checked_sub(1);
+        for (sequence, chunk) in rest.iter().enumerate() {
+            if Some(sequence) == last {
+                segments.push(Segment::Last);
+            } else {
+                let sequence = u32::try_from(sequence)
+                    .map_err(|_| anyhow!("payload needs too many segments"))?;
+                segments.push(Segment::Consecutive { sequence });
+            }
+            slices.push(chunk);
+        }
+        if last.is_none() {
+            segments.push(Segment::Last);
+            slices.push(&[]);
+        }
+        segments
+            .into_iter()
+            .zip(slices)
+            .enumerate()
+            .map(|(index, (segment, slice))| {
+                self.clone()
+                    .with_counter(self.counter.wrapping_add(index as u8))
+                    .with_segment(segment, slice)
+                    .build(2)
+            })
+            .collect()
+    }
+
+    /// The message as a frame of a DLT file: storage header, then the
+    /// message in the given protocol version.
+    pub fn build(&self, version: u8) -> Result<Vec<u8>> {
+        let message = self.build_message(version)?;
+        let mut frame = Vec::with_capacity(STORAGE_HEADER_SIZE + message.len());
+        frame.extend_from_slice(STORAGE_HEADER_PATTERN);
+        frame.extend_from_slice(&self.storage_seconds.to_le_bytes());
+        frame.extend_from_slice(&self.storage_microseconds.to_le_bytes());
+        frame.extend_from_slice(&self.storage_ecu);
+        frame.extend_from_slice(&message);
+        Ok(frame)
+    }
+
+    /// The message without a storage header, as sent on a live connection.
+    pub fn build_message(&self, version: u8) -> Result<Vec<u8>> {
+        match version {
+            1 => v1::encode::message(self),
+            2 => v2::encode::message(self),
+            other => Err(anyhow!("Unsupported DLT version: {}", other)),
+        }
+    }
+
+    /// MSTP and MTIN; control messages take them from their status.
+    pub(crate) fn message_info(&self) -> (u8, u8) {
+        match &self.content {
+            Content::Control(control) if control.status.is_some() => {
+                (MESSAGE_TYPE_CONTROL, CONTROL_RESPONSE)
+            }
+            Content::Control(_) => (MESSAGE_TYPE_CONTROL, CONTROL_REQUEST),
+            _ => (self.message_type, self.message_type_info),
+        }
+    }
+
+    /// Number of arguments (NOAR); control messages count the service ID.
+    pub(crate) fn argument_count(&self) -> Result<u8> {
+        match &self.content {
+            Content::Verbose(arguments) => u8::try_from(arguments.len())
+                .map_err(|_| anyhow!("{} arguments exceed NOAR", arguments.len())),
+            Content::NonVerbose { .. } => Ok(0),
+            Content::Control(_) => Ok(1),
+        }
+    }
+
+    /// Verbose arguments or control payload in the given byte order; the
+    /// argument bytes for non-verbose messages.
+    pub(crate) fn payload(&self, big_endian: bool) -> Result<Vec<u8>> {
+        match &self.content {
+            Content::Verbose(arguments) => encode_arguments(arguments, big_endian),
+            Content::NonVerbose { arguments, .. } => Ok(arguments.clone()),
+            Content::Control(control) => control.encode(big_endian),
+        }
+    }
+}
+
+/// A NUL-padded 4-byte ID, truncated if longer.
+fn id(value: &str) -> [u8; 4] {
+    let mut buf = [0u8; 4];
+    let bytes = value.as_bytes();
+    let n = bytes.len().min(4);
+    buf[..n].copy_from_slice(&bytes[..n]);
+    buf
+}
+
+/// Checked 16-bit LEN of a message.
+pub(crate) fn message_len(len: usize) -> Result<u16> {
+    u16::try_from(len).map_err(|_| anyhow!("{len} bytes exceed the 16-bit message length"))
+}
+
+/// Writes messages as a DLT file of one protocol version.
+pub struct DltWriter<W: Write> {
+    inner: W,
+    version: u8,
+}
+
+impl<W: Write> DltWriter<W> {
+    pub fn new(inner: W, version: u8) -> Result<Self> {
+        if !SUPPORTED_VERSIONS.contains(&version) {
+            bail!("Unsupported DLT version: {}", version);
+        }
+        Ok(Self { inner, version })
+    }
+
+    /// Append one frame: storage header and message.
+    pub fn write(&mut self, message: &MessageBuilder) -> Result<()> {
+        self.inner.write_all(&message.build(self.version)?)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::control::{
+        ApplicationLogInfo, ContextLogInfo, ControlBody, LOG_INFO_WITH_DESCRIPTIONS,
+        SERVICE_GET_LOG_INFO,
+    };
+    use crate::dlt::store::DltStore;
+
+    fn arguments() -> Vec<Argument> {
+        let arg = |name: Option<&str>, unit: Option<&str>, value| Argument {
+            name: name.map(str::to_string),
+            unit: unit.map(str::to_string),
+            value,
+        };
+        vec![
+            arg(None, None, Value::String("hello".into())),
+            arg(Some("speed"), Some("km/h"), Value::U16(120)),
+            arg(Some("ok"), None, Value::Bool(true)),
+            arg(None, None, Value::I64(-7)),
+            arg(None, None, Value::F16(1.5)),
+            arg(None, None, Value::F128(-2.25)),
+            arg(None, None, Value::Raw(vec![0xde, 0xad])),
+            arg(
+                Some("temp"),
+                Some("C"),
+                Value::FixedPoint {
+                    raw: -40,
+                    quantization: 0.5,
+                    offset: 10,
+                },
+            ),
+            arg(
+                None,
+                None,
+                Value::Array {
+                    dimensions: vec![2, 2],
+                    values: vec![Value::I8(1), Value::I8(2), Value::I8(3), Value::I8(4)],
+                },
+            ),
+            arg(
+                Some("point"),
+                None,
+                Value::Struct(vec![
+                    arg(Some("x"), Some("m"), Value::F32(0.25)),
+                    arg(None, None, Value::TraceInfo("main".into())),
+                ]),
+            ),
+        ]
+    }
+
+    fn open(frames: &[Vec<u8>], version: u8) -> Box<dyn DltStore> {
+        let mut store = crate::dlt::open_stream(version).unwrap();
+        assert!(store.append(&frames.concat()).unwrap().is_empty());
+        store
+    }
+
+    #[test]
+    fn verbose_messages_round_trip_in_both_versions() {
+        for (version, big_endian) in [(1, false), (1, true), (2, true)] {
+            let mut builder = MessageBuilder::new()
+                .with_ecu("ECU9")
+                .with_apid("APP1")
+                .with_ctid("CTX1")
+                .with_session_id(42)
+                .with_timestamp_ns(1_234_500_000)
+                .with_counter(9)
+                .with_message_type_info(0x02)
+                .with_big_endian(big_endian);
+            for argument in arguments() {
+                builder = builder.with_argument(argument);
+            }
+
+            let store = open(&[builder.build(version).unwrap()], version);
+
+            assert_eq!(store.len(), 1);
+            assert_eq!(store.protocol_version(0), version);
+            assert_eq!(store.ecu(0), "ECU9");
+            assert_eq!(store.apid(0), "APP1");
+            assert_eq!(store.ctid(0), "CTX1");
+            assert_eq!(store.session_id(0), 42);
+            assert_eq!(store.message_timestamp_ns(0), 1_234_500_000);
+            assert_eq!(store.message_counter(0), 9);
+            assert_eq!(store.message_type_info(0), 0x02);
+            assert_eq!(store.storage_timestamp_ns(0), 1_000_500_000_000);
+            assert_eq!(store.payload_arguments(0), arguments());
+        }
+    }
+
+    #[test]
+    fn non_verbose_and_control_messages_round_trip() {
+        let log_info = ControlMessage {
+            service_id: SERVICE_GET_LOG_INFO,
+            status: Some(LOG_INFO_WITH_DESCRIPTIONS),
+            body: ControlBody::LogInfo {
+                applications: vec![ApplicationLogInfo {
+                    apid: "APP1".into(),
+                    contexts: vec![ContextLogInfo {
+                        ctid: "CTX1".into(),
+                        log_level: Some(4),
+                        trace_status: Some(0),
+                        description: Some("context".into()),
+                    }],
+                    description: Some("application".into()),
+                }],
+                com: "remo".into(),
+            },
+        };
+        for version in [1, 2] {
+            let frames = [
+                MessageBuilder::new()
+                    .with_apid("APP1")
+                    .with_non_verbose(0x1234, &[1, 2, 3])
+                    .build(version)
+                    .unwrap(),
+                MessageBuilder::new()
+                    .with_ecu("ECU1")
+                    .with_control(log_info.clone())
+                    .build(version)
+                    .unwrap(),
+            ];
+
+            let store = open(&frames, version);
+
+            let non_verbose = store.non_verbose_payload(0).expect("non-verbose row");
+            assert_eq!(non_verbose.message_id, 0x1234);
+            assert_eq!(non_verbose.arguments, &[1, 2, 3]);
+            assert_eq!(store.message_type_info(1), CONTROL_RESPONSE);
+            assert_eq!(store.control_message(1).as_ref(), Some(&log_info));
+        }
+    }
+
+    #[test]
+    fn v2_extension_fields_are_encoded_and_rejected_for_v1() {
+        let builder = MessageBuilder::new()
+            .with_apid("APP1")
+            .with_source_location("main.c", 42)
+            .with_tag("boot")
+            .with_privacy_level(2)
+            .with_verbose_string("tagged");
+
+        let store = open(&[builder.build(2).unwrap()], 2);
+        assert_eq!(store.apid(0), "APP1");
+        assert_eq!(store.payload_text(0), "tagged");
+
+        let err = builder.build(1).unwrap_err();
+        assert!(err.to_string().contains("v1 messages cannot carry"));
+        assert!(builder.build(3).is_err());
+    }
+
+    #[test]
+    fn writer_produces_a_file_that_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("written.dlt");
+        let mut writer = DltWriter::new(std::fs::File::create(&path).unwrap(), 1).unwrap();
+        for counter in 0..3 {
+            writer
+                .write(
+                    &MessageBuilder::new()
+                        .with_storage_timestamp(10 + u32::from(counter), 0)
+                        .with_apid("APP1")
+                        .with_counter(counter)
+                        .with_verbose_string("line"),
+                )
+                .unwrap();
+        }
+        writer.flush().unwrap();
+
+        let (store, errors) = crate::dlt::open(vec![path]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.message_counter(2), 2);
+        assert_eq!(store.payload_text(1), "line");
+        assert!(DltWriter::new(Vec::new(), 4).is_err());
+    }
+}
@@ -1,29 +1,69 @@
 mod cmd_line_parser;
 pub mod desktop;
 pub mod dlt;
+mod output;
 
-use crate::dlt::payload::{MESSAGE_TYPE, decode_message_type_info};
+use crate::desktop::FilterPreset;
+use crate::dlt::counter::CounterTracker;
+use crate::dlt::fibex::{FibexDatabase, FibexDlt};
+use crate::dlt::filter::FilterSet;
+use crate::dlt::store::DltStore;
+use crate::dlt::stream::LiveSource;
+use crate::dlt::time_window::TimeWindow;
+use crate::dlt::{convert, export};
 use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Duration;
 
-pub use cmd_line_parser::{Cli, Parser};
+pub use cmd_line_parser::{Cli, Command, MergeOrder, Parser, TimeSource};
+pub use output::OutputFormat;
+
+/// How often `--follow` checks the files for appended bytes.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
 
 pub fn process_dlt(args: Cli) -> Result<()> {
+    if let Some(Command::Export { .. }) = &args.command {
+        return process_export(args);
+    }
+    if let Some(Command::Convert { .. }) = &args.command {
+        return process_convert(args);
+    }
+    if let Some(Command::Stats { .. }) = &args.command {
+        return process_stats(args);
+    }
+
     if !args.terminal {
         println!("Entering Gui");
         process_in_gui(args)
     } else {
-        println!("Using terminal");
+        status(args.output_format, "Using terminal");
         process_in_terminal(args)
     }
 }
 
-fn process_in_gui(_args: Cli) -> Result<()> {
-    desktop::run_desktop_shell()?;
+fn process_in_gui(args: Cli) -> Result<()> {
+    let merge = args.merge_mode();
+    let live = args.live_source();
+    let presets = args.preset_options();
+    desktop::run_desktop_shell(args.filter, merge, args.fibex, live, args.follow, presets)?;
 
     Ok(())
 }
 
 fn process_in_terminal(args: Cli) -> Result<()> {
+    if let Some(source) = args.live_source() {
+        return process_stream_in_terminal(args, &source);
+    }
+
+    let merge = args.merge_mode();
+    let selection = load_selection(&args)?;
+    let fibex = load_fibex(&args)?;
     let Some(mut paths) = args.paths else {
         return Err(anyhow!("No DLT paths"));
     };
@@ -32,92 +72,355 @@ fn process_in_terminal(args: Cli) -> Result<()> {
         paths.sort();
     }
 
-    let version = dlt::detect_version(&paths[0])?;
-    for path in &paths[1..] {
-        let v = dlt::detect_version(path)?;
-        if v != version {
-            return Err(anyhow!(
-                "Mixed DLT versions: first file is v{} but {:?} is v{}",
-                version,
-                path,
-                v
-            ));
-        }
+    if args.follow {
+        return follow_in_terminal(paths, &selection, fibex, args.limit, args.output_format);
     }
 
-    println!("DLT Version: {}", version);
+    let (dlt, errors) = dlt::open_with_merge(paths, merge.as_ref())?;
+    let dlt = with_fibex(dlt, fibex);
+    let versions: Vec<String> = dlt
+        .protocol_versions()
+        .iter()
+        .map(|version| version.to_string())
+        .collect();
+    status(
+        args.output_format,
+        &format!("DLT Version: {}", versions.join(", ")),
+    );
+    if !errors.is_empty() {
+        eprintln!("{} parse error(s) encountered", errors.len());
+    }
+    print_terminal_rows(dlt.as_ref(), &selection, args.limit, args.output_format)?;
 
-    if version == 1 {
-        let (dlt, errors) = dlt::v1::Dlt::open(paths)?;
-        if !errors.is_empty() {
-            eprintln!("{} parse error(s) encountered", errors.len());
+    Ok(())
+}
+
+/// Print the rows of files that are still being written, then keep polling
+/// them and print appended rows until `--limit` rows were printed.
+fn follow_in_terminal(
+    paths: Vec<PathBuf>,
+    selection: &RowSelection,
+    fibex: Option<Arc<FibexDatabase>>,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    let version = dlt::wait_for_follow_version(&paths, &AtomicBool::new(false), |path| {
+        status(
+            format,
+            &format!("Waiting for a first message in {}", path.display()),
+        );
+    })?;
+    let (dlt, errors) = dlt::open_following(paths, version)?;
+    let mut dlt = with_fibex(dlt, fibex);
+    status(format, &format!("DLT Version: {version}"));
+    if !errors.is_empty() {
+        eprintln!("{} parse error(s) encountered", errors.len());
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    output::write_header(format, &mut out)?;
+
+    let mut counters = CounterTracker::new();
+    let mut printed = 0;
+    let mut new_rows = 0..dlt.len();
+    loop {
+        let remaining = limit.map_or(usize::MAX, |limit| limit - printed);
+        counters.scan(dlt.as_ref(), new_rows.clone());
+        let mut rows = selection.rows(dlt.as_ref(), new_rows);
+        rows.truncate(remaining);
+        output::write_records(dlt.as_ref(), &counters, &rows, format, &mut out)?;
+        out.flush()?;
+        printed += rows.len();
+        if limit.is_some_and(|limit| printed >= limit) {
+            return Ok(());
         }
-        print_terminal_rows_v1(&dlt, args.limit);
-    } else {
-        let (dlt, errors) = dlt::v2::Dlt::open(paths)?;
+
+        thread::sleep(FOLLOW_INTERVAL);
+        let first_row = dlt.len();
+        let errors = dlt.refresh()?;
         if !errors.is_empty() {
             eprintln!("{} parse error(s) encountered", errors.len());
         }
-        print_terminal_rows_v2(&dlt, args.limit);
+        new_rows = first_row..dlt.len();
     }
+}
+
+/// Print rows from a live source as they arrive, until the source ends or
+/// `--limit` rows were printed.
+fn process_stream_in_terminal(args: Cli, source: &LiveSource) -> Result<()> {
+    let selection = load_selection(&args)?;
+    let fibex = load_fibex(&args)?;
+    let format = args.output_format;
+    let reader = source.open()?;
+    status(format, &format!("Reading from {source}"));
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    output::write_header(format, &mut out)?;
+    out.flush()?;
 
+    let mut live: Option<Box<dyn DltStore>> = None;
+    let mut counters = CounterTracker::new();
+    let mut printed = 0;
+    let mut error_count = 0;
+    let mut outcome: Result<()> = Ok(());
+    let framer = reader.read_frames(&AtomicBool::new(false), |version, frames| {
+        outcome = (|| {
+            let (dlt, rows, errors) = append_live_rows(&mut live, version, &frames, &fibex)?;
+            error_count += errors;
+            let remaining = args.limit.map_or(usize::MAX, |limit| limit - printed);
+            counters.scan(dlt, rows.clone());
+            let mut rows = selection.rows(dlt, rows);
+            rows.truncate(remaining);
+            output::write_records(dlt, &counters, &rows, format, &mut out)?;
+            out.flush()?;
+            printed += rows.len();
+            Ok(())
+        })();
+        outcome.is_ok() && args.limit.is_none_or(|limit| printed < limit)
+    })?;
+    outcome?;
+
+    if error_count > 0 {
+        eprintln!("{error_count} parse error(s) encountered");
+    }
+    if framer.skipped_bytes() > 0 {
+        eprintln!("{} byte(s) skipped while resyncing", framer.skipped_bytes());
+    }
     Ok(())
 }
 
-fn print_terminal_rows_v1(dlt: &dlt::v1::Dlt, limit: Option<usize>) {
-    println!("idx\ttype\ttype_info\tecu\tapid\tctid\tpayload");
-    let total = dlt.len();
-    let rows_to_print = limit.unwrap_or(total).min(total);
-    for i in 0..rows_to_print {
-        let mstp = dlt.message_type(i) as usize;
-        let mtin = dlt.message_type_info(i) as usize;
-        let msg_type = MESSAGE_TYPE.get(mstp).copied().unwrap_or("");
-        let type_info = decode_message_type_info(mstp, mtin);
-        println!(
-            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
-            i,
-            msg_type,
-            type_info,
-            dlt.ecu(i),
-            dlt.apid(i),
-            dlt.ctid(i),
-            dlt.payload_text(i)
-        );
+/// Append stored frames to the live store, creating it for the first batch.
+/// Returns the store, the new rows and the number of parse errors.
+fn append_live_rows<'a>(
+    live: &'a mut Option<Box<dyn DltStore>>,
+    version: u8,
+    frames: &[u8],
+    fibex: &Option<Arc<FibexDatabase>>,
+) -> Result<(&'a dyn DltStore, Range<usize>, usize)> {
+    let dlt = match live {
+        Some(dlt) => dlt.as_mut(),
+        None => live
+            .insert(with_fibex(dlt::open_stream(version)?, fibex.clone()))
+            .as_mut(),
+    };
+    let first_row = dlt.len();
+    let errors = dlt.append(frames)?;
+    let rows = first_row..dlt.len();
+    Ok((dlt, rows, errors.len()))
+}
+
+fn process_export(args: Cli) -> Result<()> {
+    let merge = args.merge_mode();
+    let selection = load_selection(&args)?;
+    let fibex = load_fibex(&args)?;
+    let Some(Command::Export { output, mut paths }) = args.command else {
+        return Err(anyhow!("Not an export command"));
+    };
+
+    if args.sort {
+        paths.sort();
     }
-    if rows_to_print < total {
-        println!(
-            "... truncated: showing {} of {} rows (use --limit to adjust)",
-            rows_to_print, total
-        );
+
+    let (dlt, errors) = dlt::open_with_merge(paths, merge.as_ref())?;
+    let dlt = with_fibex(dlt, fibex);
+    if !errors.is_empty() {
+        eprintln!("{} parse error(s) encountered", errors.len());
     }
+    let rows = selection.rows(dlt.as_ref(), 0..dlt.len());
+    let summary = export::export_rows(dlt.as_ref(), rows, &output)?;
+    println!(
+        "Exported {} of {} rows ({} bytes) to {}",
+        summary.rows,
+        dlt.len(),
+        summary.bytes,
+        output.display()
+    );
+
+    Ok(())
 }
 
-fn print_terminal_rows_v2(dlt: &dlt::v2::Dlt, limit: Option<usize>) {
-    println!("idx\ttype\ttype_info\tecu\tapid\tctid\tpayload");
-    let total = dlt.len();
-    let rows_to_print = limit.unwrap_or(total).min(total);
-    for i in 0..rows_to_print {
-        let mstp = dlt.message_type(i) as usize;
-        let mtin = dlt.message_type_info(i) as usize;
-        let msg_type = MESSAGE_TYPE.get(mstp).copied().unwrap_or("");
-        let type_info = decode_message_type_info(mstp, mtin);
-        println!(
-            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
-            i,
-            msg_type,
-            type_info,
-            dlt.ecu(i),
-            dlt.apid(i),
-            dlt.ctid(i),
-            dlt.payload_text(i)
-        );
+fn process_convert(args: Cli) -> Result<()> {
+    let merge = args.merge_mode();
+    let selection = load_selection(&args)?;
+    let fibex = load_fibex(&args)?;
+    let Some(Command::Convert {
+        output,
+        to,
+        mut paths,
+    }) = args.command
+    else {
+        return Err(anyhow!("Not a convert command"));
+    };
+
+    if args.sort {
+        paths.sort();
+    }
+
+    let (dlt, errors) = dlt::open_with_merge(paths, merge.as_ref())?;
+    let dlt = with_fibex(dlt, fibex);
+    if !errors.is_empty() {
+        eprintln!("{} parse error(s) encountered", errors.len());
     }
+    let rows = selection.rows(dlt.as_ref(), 0..dlt.len());
+    let summary = convert::convert_to_file(dlt.as_ref(), rows, to, &output)?;
+    println!(
+        "Converted {} of {} rows to DLT v{} ({} bytes) to {}",
+        summary.rows,
+        dlt.len(),
+        to,
+        summary.bytes,
+        output.display()
+    );
+    for (loss, count) in &summary.losses {
+        println!("  {count} row(s): {loss}");
+    }
+
+    Ok(())
+}
+
+/// Print row and ID counts and the messages lost to counter gaps. Gaps are
+/// found over all rows; `--filter` and `--preset` select the rows and gaps
+/// reported.
+fn process_stats(args: Cli) -> Result<()> {
+    let merge = args.merge_mode();
+    let selection = load_selection(&args)?;
+    let Some(Command::Stats { mut paths }) = args.command else {
+        return Err(anyhow!("Not a stats command"));
+    };
+
+    if args.sort {
+        paths.sort();
+    }
+
+    let (dlt, errors) = dlt::open_with_merge(paths, merge.as_ref())?;
+    let rows = selection.rows(dlt.as_ref(), 0..dlt.len());
+    let tracker = CounterTracker::scan_all(dlt.as_ref());
+    let versions: Vec<String> = dlt
+        .protocol_versions()
+        .iter()
+        .map(|version| version.to_string())
+        .collect();
+    println!("Messages: {} of {}", rows.len(), dlt.len());
+    println!("DLT Version: {}", versions.join(", "));
+    println!(
+        "ECUs: {}, APIDs: {}, CTIDs: {}",
+        dlt.unique_ecus().len(),
+        dlt.unique_apids().len(),
+        dlt.unique_ctids().len()
+    );
+    println!("Parse errors: {}", errors.len());
+
+    let mut senders: BTreeMap<_, (u64, usize)> = BTreeMap::new();
+    for gap in tracker.gaps() {
+        if !selection.matches(dlt.as_ref(), gap.row) {
+            continue;
+        }
+        let sender = (&gap.ecu, gap.session_id, &gap.apid, &gap.ctid);
+        let (dropped, gaps) = senders.entry(sender).or_default();
+        *dropped += u64::from(gap.dropped());
+        *gaps += 1;
+    }
+    let dropped: u64 = senders.values().map(|(dropped, _)| dropped).sum();
+    let gaps: usize = senders.values().map(|(_, gaps)| gaps).sum();
+    println!("Dropped messages: {dropped} in {gaps} gap(s)");
+    for ((ecu, session_id, apid, ctid), (dropped, gaps)) in &senders {
+        println!("  {ecu} session {session_id} {apid}/{ctid}: {dropped} in {gaps} gap(s)");
+    }
+
+    Ok(())
+}
+
+/// Rows selected by `--filter`, `--preset`, `--from` and `--to`.
+struct RowSelection {
+    filter: Option<FilterSet>,
+    preset: Option<FilterPreset>,
+    window: Option<TimeWindow>,
+}
+
+impl RowSelection {
+    fn matches(&self, dlt: &dyn DltStore, row: usize) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches_row(dlt, row))
+            && self
+                .preset
+                .as_ref()
+                .is_none_or(|preset| preset.matches_row(dlt, row))
+            && self
+                .window
+                .as_ref()
+                .is_none_or(|window| window.matches(dlt, row))
+    }
+
+    fn rows(&self, dlt: &dyn DltStore, rows: Range<usize>) -> Vec<usize> {
+        rows.filter(|&row| self.matches(dlt, row)).collect()
+    }
+}
+
+fn load_selection(args: &Cli) -> Result<RowSelection> {
+    Ok(RowSelection {
+        filter: load_filter(args)?,
+        preset: args.preset_options().load_preset()?,
+        window: args.time_window(),
+    })
+}
+
+fn load_filter(args: &Cli) -> Result<Option<FilterSet>> {
+    args.filter
+        .as_deref()
+        .map(FilterSet::from_dlf_file)
+        .transpose()
+}
+
+fn load_fibex(args: &Cli) -> Result<Option<Arc<FibexDatabase>>> {
+    if args.fibex.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(FibexDatabase::from_files(&args.fibex)?)))
+}
+
+fn with_fibex(dlt: Box<dyn DltStore>, fibex: Option<Arc<FibexDatabase>>) -> Box<dyn DltStore> {
+    match fibex {
+        Some(database) => Box::new(FibexDlt::new(dlt, database)),
+        None => dlt,
+    }
+}
+
+/// Print a status line; it goes to stderr when stdout carries machine-readable rows.
+fn status(format: OutputFormat, message: &str) {
+    if format.is_machine_readable() {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
+fn print_terminal_rows(
+    dlt: &dyn DltStore,
+    selection: &RowSelection,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    let rows = selection.rows(dlt, 0..dlt.len());
+    let total = rows.len();
+    let rows_to_print = limit.unwrap_or(total).min(total);
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let counters = CounterTracker::scan_all(dlt);
+    output::write_rows(dlt, &counters, &rows[..rows_to_print], format, &mut out)?;
+    out.flush()?;
     if rows_to_print < total {
-        println!(
-            "... truncated: showing {} of {} rows (use --limit to adjust)",
-            rows_to_print, total
+        status(
+            format,
+            &format!(
+                "... truncated: showing {} of {} rows (use --limit to adjust)",
+                rows_to_print, total
+            ),
         );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -128,11 +431,26 @@ mod tests {
     #[test]
     fn process_dlt_terminal_with_no_paths() {
         let args = Cli {
+            command: None,
             paths: None,
             filter: None,
+            preset: None,
+            preset_file: None,
             terminal: true,
             sort: true,
             limit: None,
+            from: None,
+            to: None,
+            time: TimeSource::Storage,
+            output_format: OutputFormat::Table,
+            merge: None,
+            ecu_offsets: Vec::new(),
+            fibex: Vec::new(),
+            tcp: None,
+            udp: None,
+            serial: None,
+            baud_rate: dlt::stream::DEFAULT_BAUD_RATE,
+            follow: false,
         };
 
         let result = process_dlt(args);
@@ -143,14 +461,29 @@ mod tests {
     #[test]
     fn process_dlt_with_one_path() {
         let args = Cli {
+            command: None,
             paths: Some(vec![PathBuf::from(
                 env!("CARGO_MANIFEST_DIR").to_string()
                     + "/tests/data/testfile_control_messages.dlt",
             )]),
             filter: None,
+            preset: None,
+            preset_file: None,
             terminal: true,
             sort: true,
             limit: None,
+            from: None,
+            to: None,
+            time: TimeSource::Storage,
+            output_format: OutputFormat::Table,
+            merge: None,
+            ecu_offsets: Vec::new(),
+            fibex: Vec::new(),
+            tcp: None,
+            udp: None,
+            serial: None,
+            baud_rate: dlt::stream::DEFAULT_BAUD_RATE,
+            follow: false,
         };
 
         let result = process_dlt(args);
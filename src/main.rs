@@ -3,7 +3,7 @@ use dlt_explorer::{Cli, Parser};
 
 fn main() -> Result<()> {
     let args = Cli::parse();
-    println!("{:?}", args);
+    eprintln!("{:?}", args);
 
     dlt_explorer::process_dlt(args)
 }
@@ -0,0 +1,449 @@
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+use crate::dlt::counter::CounterTracker;
+use crate::dlt::payload::{MESSAGE_TYPE, decode_message_type_info, format_message_type};
+use crate::dlt::storage::format_timestamp_ns;
+use crate::dlt::store::DltStore;
+
+/// Row format for terminal output, selected with `--output-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Tab-separated table for reading in a terminal
+    #[default]
+    Table,
+    /// RFC 4180 CSV with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    /// dlt-viewer ASCII export layout
+    Text,
+}
+
+impl OutputFormat {
+    /// Whether the output is meant for other programs, so status lines must
+    /// stay off stdout.
+    pub fn is_machine_readable(self) -> bool {
+        self != OutputFormat::Table
+    }
+}
+
+/// Columns of CSV and JSONL records. `tags` is a JSON array; in CSV the tags
+/// are joined with `|`, and a `|` or `\` inside a tag is escaped with `\`.
+/// `dropped_before` is the number of messages lost to a message counter gap
+/// right before the row.
+const RECORD_COLUMNS: [&str; 18] = [
+    "index",
+    "timestamp",
+    "ecu",
+    "apid",
+    "ctid",
+    "version",
+    "kind",
+    "payload",
+    "session_id",
+    "message_timestamp_ns",
+    "file_index",
+    "byte_offset",
+    "source_file",
+    "source_line",
+    "tags",
+    "privacy_level",
+    "message_id",
+    "dropped_before",
+];
+
+/// Write the header line (if the format has one) followed by one line per row.
+pub fn write_rows<W: Write>(
+    dlt: &dyn DltStore,
+    counters: &CounterTracker,
+    rows: &[usize],
+    format: OutputFormat,
+    out: &mut W,
+) -> io::Result<()> {
+    write_header(format, out)?;
+    write_records(dlt, counters, rows, format, out)
+}
+
+/// Write the header line, if the format has one.
+pub fn write_header<W: Write>(format: OutputFormat, out: &mut W) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => writeln!(out, "idx\tver\ttype\ttype_info\tecu\tapid\tctid\tpayload"),
+        OutputFormat::Csv => writeln!(out, "{}", RECORD_COLUMNS.join(",")),
+        OutputFormat::Jsonl | OutputFormat::Text => Ok(()),
+    }
+}
+
+/// Write one line per row, without a header. `counters` must have scanned
+/// the rows for their counter gaps.
+pub fn write_records<W: Write>(
+    dlt: &dyn DltStore,
+    counters: &CounterTracker,
+    rows: &[usize],
+    format: OutputFormat,
+    out: &mut W,
+) -> io::Result<()> {
+    for &row in rows {
+        match format {
+            OutputFormat::Table => write_table_row(dlt, row, out)?,
+            OutputFormat::Csv => write_csv_row(dlt, counters, row, out)?,
+            OutputFormat::Jsonl => write_json_row(dlt, counters, row, out)?,
+            OutputFormat::Text => write_text_row(dlt, row, out)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_table_row<W: Write>(dlt: &dyn DltStore, row: usize, out: &mut W) -> io::Result<()> {
+    let mstp = dlt.message_type(row) as usize;
+    let mtin = dlt.message_type_info(row) as usize;
+    let msg_type = MESSAGE_TYPE.get(mstp).copied().unwrap_or("");
+    let type_info = decode_message_type_info(mstp, mtin);
+    writeln!(
+        out,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        row,
+        dlt.protocol_version(row),
+        msg_type,
+        type_info,
+        dlt.ecu(row),
+        dlt.apid(row),
+        dlt.ctid(row),
+        dlt.payload_text(row)
+    )
+}
+
+/// One value of a CSV or JSONL record.
+enum Field {
+    Text(String),
+    /// Unquoted in JSON; an absent value is `null` there and empty in CSV.
+    Number(Option<String>),
+    /// An array in JSON; joined with `|` in CSV, see `RECORD_COLUMNS`.
+    List(Vec<String>),
+}
+
+impl Field {
+    fn number(value: impl ToString) -> Self {
+        Field::Number(Some(value.to_string()))
+    }
+
+    fn optional(value: Option<impl ToString>) -> Self {
+        Field::Number(value.map(|value| value.to_string()))
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Field::Text(value) => csv_field(value),
+            Field::Number(value) => value.clone().unwrap_or_default(),
+            Field::List(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| item.replace('\\', "\\\\").replace('|', "\\|"))
+                    .collect();
+                csv_field(&items.join("|"))
+            }
+        }
+    }
+
+    fn json(&self) -> String {
+        match self {
+            Field::Text(value) => json_string(value),
+            Field::Number(value) => value.clone().unwrap_or_else(|| "null".to_string()),
+            Field::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| json_string(item)).collect();
+                format!("[{}]", items.join(","))
+            }
+        }
+    }
+}
+
+/// Field values in `RECORD_COLUMNS` order.
+fn record_fields(dlt: &dyn DltStore, counters: &CounterTracker, row: usize) -> [Field; 18] {
+    let source = dlt.source_location(row);
+    [
+        Field::number(row),
+        Field::Text(format_timestamp_ns(dlt.storage_timestamp_ns(row))),
+        Field::Text(dlt.ecu(row).to_string()),
+        Field::Text(dlt.apid(row).to_string()),
+        Field::Text(dlt.ctid(row).to_string()),
+        Field::number(dlt.protocol_version(row)),
+        Field::Text(format_message_type(
+            dlt.message_type(row),
+            dlt.message_type_info(row),
+        )),
+        Field::Text(dlt.payload_text(row)),
+        Field::number(dlt.session_id(row)),
+        Field::number(dlt.message_timestamp_ns(row)),
+        Field::number(dlt.file_index(row)),
+        Field::number(dlt.byte_offset(row)),
+        Field::Text(source.map(|(file, _)| file.to_string()).unwrap_or_default()),
+        Field::optional(source.map(|(_, line)| line)),
+        Field::List(dlt.tags(row).into_iter().map(str::to_string).collect()),
+        Field::optional(dlt.privacy_level(row)),
+        Field::optional(dlt.message_id(row)),
+        Field::optional(counters.gap_at(row).map(|gap| gap.dropped())),
+    ]
+}
+
+fn write_csv_row<W: Write>(
+    dlt: &dyn DltStore,
+    counters: &CounterTracker,
+    row: usize,
+    out: &mut W,
+) -> io::Result<()> {
+    let fields = record_fields(dlt, counters, row);
+    let line: Vec<String> = fields.iter().map(Field::csv).collect();
+    writeln!(out, "{}", line.join(","))
+}
+
+fn write_json_row<W: Write>(
+    dlt: &dyn DltStore,
+    counters: &CounterTracker,
+    row: usize,
+    out: &mut W,
+) -> io::Result<()> {
+    let fields = record_fields(dlt, counters, row);
+    let members: Vec<String> = RECORD_COLUMNS
+        .iter()
+        .zip(fields.iter())
+        .map(|(name, field)| format!("\"{name}\":{}", field.json()))
+        .collect();
+    writeln!(out, "{{{}}}", members.join(","))
+}
+
+/// One line in dlt-viewer's ASCII export layout:
+/// `index date time timestamp count ecu apid ctid type subtype mode args payload`.
+///
+/// Storage time is printed in UTC, the message timestamp in seconds with
+/// 0.1 ms resolution, and line breaks or tabs in the payload become spaces
+/// so every message stays on one line.
+fn write_text_row<W: Write>(dlt: &dyn DltStore, row: usize, out: &mut W) -> io::Result<()> {
+    let mstp = dlt.message_type(row) as usize;
+    let mtin = dlt.message_type_info(row) as usize;
+    let ticks = dlt.message_timestamp_ns(row) / 100_000;
+    let payload: String = dlt
+        .payload_text(row)
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    writeln!(
+        out,
+        "{} {} {}.{:04} {} {} {} {} {} {} {} {} {}",
+        row,
+        format_utc_datetime(dlt.storage_timestamp_ns(row)),
+        ticks / 10_000,
+        ticks % 10_000,
+        dlt.message_counter(row),
+        text_field(dlt.ecu(row)),
+        text_field(dlt.apid(row)),
+        text_field(dlt.ctid(row)),
+        MESSAGE_TYPE.get(mstp).copied().unwrap_or("unknown"),
+        text_field(decode_message_type_info(mstp, mtin)),
+        if dlt.is_verbose(row) {
+            "verbose"
+        } else {
+            "non-verbose"
+        },
+        dlt.argument_count(row),
+        payload
+    )
+}
+
+fn text_field(value: &str) -> &str {
+    if value.is_empty() { "-" } else { value }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r', '\t']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// `YYYY/MM/DD hh:mm:ss.uuuuuu` in UTC.
+fn format_utc_datetime(ns: u64) -> String {
+    let secs = ns / 1_000_000_000;
+    let micros = (ns % 1_000_000_000) / 1_000;
+    let days = (secs / 86_400) as i64;
+    let time_of_day = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}/{month:02}/{day:02} {:02}:{:02}:{:02}.{micros:06}",
+        time_of_day / 3_600,
+        (time_of_day % 3_600) / 60,
+        time_of_day % 60
+    )
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlt::writer::MessageBuilder;
+
+    fn open_messages(messages: &[Vec<u8>]) -> (tempfile::TempDir, Box<dyn DltStore>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output.dlt");
+        std::fs::write(&path, messages.concat()).unwrap();
+        let (dlt, errors) = crate::dlt::open(vec![path]).unwrap();
+        assert!(errors.is_empty());
+        (dir, dlt)
+    }
+
+    fn render(dlt: &dyn DltStore, format: OutputFormat) -> String {
+        let rows: Vec<usize> = (0..dlt.len()).collect();
+        let mut out = Vec::new();
+        let counters = CounterTracker::scan_all(dlt);
+        write_rows(dlt, &counters, &rows, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn awkward_message() -> Vec<u8> {
+        MessageBuilder::new()
+            .with_apid("APP1")
+            .with_ctid("CTX1")
+            .with_ecu("ECU1")
+            .with_session_id(42)
+            .with_timestamp_ns(1_234_500_000)
+            .with_storage_timestamp(86_400, 250)
+            .with_verbose_string("a,\"b\"\tc\nd")
+            .build(2)
+            .unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators_and_line_breaks() {
+        let (_dir, dlt) = open_messages(&[awkward_message()]);
+
+        let csv = render(dlt.as_ref(), OutputFormat::Csv);
+
+        assert_eq!(
+            csv,
+            "index,timestamp,ecu,apid,ctid,version,kind,payload,session_id,\
+             message_timestamp_ns,file_index,byte_offset,\
+             source_file,source_line,tags,privacy_level,message_id,dropped_before\n\
+             0,86400.000250,ECU1,APP1,CTX1,2,log/info,\"a,\"\"b\"\"\tc\nd\",42,1234500000,0,0,,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn jsonl_escapes_payload_and_keeps_one_object_per_line() {
+        let (_dir, dlt) = open_messages(&[awkward_message(), awkward_message()]);
+
+        let jsonl = render(dlt.as_ref(), OutputFormat::Jsonl);
+        let lines: Vec<&str> = jsonl.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "{\"index\":0,\"timestamp\":\"86400.000250\",\"ecu\":\"ECU1\",\"apid\":\"APP1\",\
+             \"ctid\":\"CTX1\",\"version\":2,\"kind\":\"log/info\",\
+             \"payload\":\"a,\\\"b\\\"\\tc\\nd\",\"session_id\":42,\
+             \"message_timestamp_ns\":1234500000,\"file_index\":0,\"byte_offset\":0,\
+             \"source_file\":\"\",\"source_line\":null,\"tags\":[],\"privacy_level\":null,\
+             \"message_id\":null,\"dropped_before\":null}"
+        );
+        assert!(lines[1].contains(&format!("\"byte_offset\":{}", awkward_message().len())));
+    }
+
+    #[test]
+    fn records_carry_v2_header_fields_and_message_id() {
+        let tagged = MessageBuilder::new()
+            .with_source_location("src/can_driver.c", 311)
+            .with_tag("safety, can")
+            .with_tag("a|b\\c")
+            .with_privacy_level(2)
+            .with_non_verbose(0x1234, &[])
+            .build(2)
+            .unwrap();
+        let (_dir, dlt) = open_messages(&[tagged]);
+
+        let csv = render(dlt.as_ref(), OutputFormat::Csv);
+        let jsonl = render(dlt.as_ref(), OutputFormat::Jsonl);
+
+        let record = csv.lines().nth(1).unwrap();
+        assert!(record.ends_with(",src/can_driver.c,311,\"safety, can|a\\|b\\\\c\",2,4660,"));
+        assert!(jsonl.trim_end().ends_with(
+            "\"source_file\":\"src/can_driver.c\",\"source_line\":311,\
+             \"tags\":[\"safety, can\",\"a|b\\\\c\"],\"privacy_level\":2,\"message_id\":4660,\
+             \"dropped_before\":null}"
+        ));
+    }
+
+    #[test]
+    fn records_carry_messages_dropped_before_a_counter_gap() {
+        let messages: Vec<Vec<u8>> = [1, 2, 5]
+            .into_iter()
+            .map(|counter| {
+                MessageBuilder::new()
+                    .with_apid("APP1")
+                    .with_counter(counter)
+                    .with_verbose_string("tick")
+                    .build(1)
+                    .unwrap()
+            })
+            .collect();
+        let (_dir, dlt) = open_messages(&messages);
+
+        let csv = render(dlt.as_ref(), OutputFormat::Csv);
+        let jsonl = render(dlt.as_ref(), OutputFormat::Jsonl);
+
+        let records: Vec<&str> = csv.lines().skip(1).collect();
+        assert!(records[1].ends_with(','));
+        assert!(records[2].ends_with(",2"));
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert!(lines[1].ends_with("\"dropped_before\":null}"));
+        assert!(lines[2].ends_with("\"dropped_before\":2}"));
+    }
+
+    #[test]
+    fn text_matches_dlt_viewer_layout() {
+        let (_dir, dlt) = open_messages(&[awkward_message()]);
+
+        let text = render(dlt.as_ref(), OutputFormat::Text);
+
+        assert_eq!(
+            text,
+            "0 1970/01/02 00:00:00.000250 1.2345 0 ECU1 APP1 CTX1 log info verbose 1 a,\"b\" c d\n"
+        );
+    }
+
+    #[test]
+    fn civil_from_days_handles_leap_years() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_089), (2025, 1, 1));
+    }
+}
@@ -1,6 +1,7 @@
 use assert_cmd::prelude::*; // Add methods on commands
+use dlt_explorer::dlt::writer::MessageBuilder;
 // use assert_fs::prelude::*;
-// use predicates::prelude::*; // Used for writing assertions
+use predicates::prelude::*; // Used for writing assertions
 use std::path::PathBuf;
 use std::process::Command; // Run programs
 
@@ -40,7 +41,9 @@ fn check_existence_of_filter_option() -> Result<(), Box<dyn std::error::Error>>
     let path1 = PathBuf::from(
         env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
     );
-    cmd.arg("-t").arg(path1).arg("-f").arg("path/to/filter");
+    let filter =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_filter.dlf");
+    cmd.arg("-t").arg(path1).arg("-f").arg(filter);
     cmd.assert().success();
 
     Ok(())
@@ -91,12 +94,757 @@ fn call_with_multiple_parameters() -> Result<(), Box<dyn std::error::Error>> {
     let path2 = PathBuf::from(
         env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
     );
-    cmd.arg(path1)
-        .arg(path2)
-        .arg("-f")
-        .arg("path/to/filter")
-        .arg("-t");
+    let filter =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_filter.dlf");
+    cmd.arg(path1).arg(path2).arg("-f").arg(filter).arg("-t");
     cmd.assert().success();
 
     Ok(())
 }
+
+#[test]
+fn filter_file_selects_rows() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    let path2 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let filter =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_filter.dlf");
+    cmd.arg("-t").arg("-f").arg(filter).arg(path1).arg(path2);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\tLOG\tTES2\t101"))
+        .stdout(predicate::str::contains("\tLOG\tTES2\t108"))
+        .stdout(predicate::str::contains("\t105").not())
+        .stdout(predicate::str::contains("\tcontrol\t").not());
+
+    Ok(())
+}
+
+#[test]
+fn missing_filter_file_fails() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t").arg(path1).arg("-f").arg("path/to/filter");
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn mixed_version_files_show_per_row_version() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let v2_path = dir.path().join("v2.dlt");
+    std::fs::write(
+        &v2_path,
+        MessageBuilder::new()
+            .with_apid("APP2")
+            .with_ctid("CTX2")
+            .with_verbose_string("from v2")
+            .build(2)
+            .unwrap(),
+    )?;
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let v1_path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    cmd.arg("-t").arg(v1_path).arg(v2_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("DLT Version: 1, 2"))
+        .stdout(predicate::str::contains(
+            "\t1\tlog\tinfo\tECU\tLOG\tTES2\t101",
+        ))
+        .stdout(predicate::str::contains(
+            "\t2\tlog\tinfo\tECU1\tAPP2\tCTX2\tfrom v2",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn merge_flag_interleaves_files_by_storage_time() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let frame = |seconds: u32, payload: &str| {
+        MessageBuilder::new()
+            .with_storage_timestamp(seconds, 0)
+            .with_apid("APP")
+            .with_ctid("CTX")
+            .with_verbose_string(payload)
+            .build(2)
+            .unwrap()
+    };
+    let first = dir.path().join("first.dlt");
+    let second = dir.path().join("second.dlt");
+    std::fs::write(&first, [frame(10, "early"), frame(30, "late")].concat())?;
+    std::fs::write(&second, frame(20, "middle"))?;
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t")
+        .arg("--merge")
+        .arg("storage")
+        .arg(&first)
+        .arg(&second);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match("early\n.*middle\n.*late\n")?);
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t")
+        .arg("--merge")
+        .arg("concat")
+        .arg(&first)
+        .arg(&second);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match("early\n.*late\n.*middle\n")?);
+
+    Ok(())
+}
+
+#[test]
+fn from_and_to_select_a_time_window() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("times.dlt");
+    let frames: Vec<_> = [
+        (1_700_000_000, "first"),
+        (1_700_000_010, "second"),
+        (1_700_000_020, "third"),
+    ]
+    .into_iter()
+    .map(|(seconds, payload)| {
+        MessageBuilder::new()
+            .with_storage_timestamp(seconds, 0)
+            .with_timestamp_ns(u64::from(seconds - 1_700_000_000) * 1_000_000_000)
+            .with_verbose_string(payload)
+            .build(1)
+            .unwrap()
+    })
+    .collect();
+    std::fs::write(&path, frames.concat())?;
+    let run = |args: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let output = Command::cargo_bin("dlt-explorer")?
+            .arg("-t")
+            .args(args)
+            .arg(&path)
+            .output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    let output = run(&["--from", "1700000005"])?;
+    assert!(!output.contains("first") && output.contains("second") && output.contains("third"));
+    let output = run(&["--from", "+5", "--to", "2023-11-14 22:13:40"])?;
+    assert!(!output.contains("first") && output.contains("second") && !output.contains("third"));
+    let output = run(&["--time", "message", "--to", "10"])?;
+    assert!(output.contains("first") && !output.contains("second"));
+
+    let exported = dir.path().join("window.dlt");
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("--from")
+        .arg("+5")
+        .arg("export")
+        .arg("-o")
+        .arg(&exported)
+        .arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 of 3 rows"));
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("--to").arg("+5").arg("stats").arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Messages: 1 of 3"));
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t").arg("--from").arg("yesterday").arg(&path);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn preset_applies_a_saved_filter_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("apps.dlt");
+    let frames: Vec<_> = [("APP1", "first"), ("APP2", "second"), ("APP1", "third")]
+        .into_iter()
+        .map(|(apid, payload)| {
+            MessageBuilder::new()
+                .with_apid(apid)
+                .with_verbose_string(payload)
+                .build(1)
+                .unwrap()
+        })
+        .collect();
+    std::fs::write(&path, frames.concat())?;
+    let presets = dir.path().join("presets.toml");
+    std::fs::write(
+        &presets,
+        r#"
+        [[preset]]
+        name = "Only APP2"
+
+        [preset.filter]
+        apid_contains = "APP2"
+        "#,
+    )?;
+
+    let output = Command::cargo_bin("dlt-explorer")?
+        .arg("-t")
+        .arg("--preset-file")
+        .arg(&presets)
+        .arg("--preset")
+        .arg("Only APP2")
+        .arg(&path)
+        .output()?;
+    assert!(output.status.success());
+    let output = String::from_utf8(output.stdout)?;
+    assert!(!output.contains("first") && output.contains("second") && !output.contains("third"));
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t")
+        .arg("--preset-file")
+        .arg(&presets)
+        .arg("--preset")
+        .arg("Missing")
+        .arg(&path);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("known presets: Only APP2"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_ecu_offset_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t")
+        .arg("--merge")
+        .arg("message")
+        .arg("--ecu-offset")
+        .arg("ECU1")
+        .arg(path1);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn export_subcommand_writes_filtered_frames() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let output = dir.path().join("trimmed.dlt");
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    let path2 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let filter =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_filter.dlf");
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("export")
+        .arg("-o")
+        .arg(&output)
+        .arg("-f")
+        .arg(filter)
+        .arg(path1)
+        .arg(path2);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Exported"));
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t").arg(&output);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\tLOG\tTES2\t101"))
+        .stdout(predicate::str::contains("\t105").not())
+        .stdout(predicate::str::contains("\tcontrol\t").not());
+
+    Ok(())
+}
+
+#[test]
+fn export_subcommand_requires_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("export").arg(path1);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn convert_subcommand_round_trips_between_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let v2 = dir.path().join("v2.dlt");
+    let v1 = dir.path().join("v1.dlt");
+    let path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("convert")
+        .arg("--to")
+        .arg("2")
+        .arg("-o")
+        .arg(&v2)
+        .arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("to DLT v2"));
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("convert")
+        .arg("--to")
+        .arg("1")
+        .arg("-o")
+        .arg(&v1)
+        .arg(&v2);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("to DLT v1"));
+
+    let print = |path: &PathBuf| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(Command::cargo_bin("dlt-explorer")?
+            .arg("-t")
+            .arg(path)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone())
+    };
+    assert_eq!(
+        String::from_utf8(print(&path)?)?,
+        String::from_utf8(print(&v1)?)?
+    );
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("convert")
+        .arg("--to")
+        .arg("3")
+        .arg("-o")
+        .arg(&v1)
+        .arg(&path);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn stats_subcommand_reports_message_counter_gaps() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("gaps.dlt");
+    let mut frames = Vec::new();
+    for (apid, counter) in [
+        ("APP1", 7),
+        ("APP2", 254),
+        ("APP1", 8),
+        ("APP2", 3),
+        ("APP1", 12),
+    ] {
+        frames.extend(
+            MessageBuilder::new()
+                .with_ecu("ECU1")
+                .with_apid(apid)
+                .with_ctid("CTX1")
+                .with_counter(counter)
+                .with_verbose_string("text")
+                .build(1)?,
+        );
+    }
+    std::fs::write(&path, frames)?;
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("stats").arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Messages: 5 of 5"))
+        .stdout(predicate::str::contains("Dropped messages: 7 in 2 gap(s)"))
+        .stdout(predicate::str::contains(
+            "ECU1 session 0 APP1/CTX1: 3 in 1 gap(s)",
+        ))
+        .stdout(predicate::str::contains(
+            "ECU1 session 0 APP2/CTX1: 4 in 1 gap(s)",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn csv_output_format_prints_header_and_keeps_status_off_stdout()
+-> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    cmd.arg("-t").arg("--output-format").arg("csv").arg(path1);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "index,timestamp,ecu,apid,ctid,version,kind,payload,session_id,\
+             message_timestamp_ns,file_index,byte_offset,\
+             source_file,source_line,tags,privacy_level,message_id,dropped_before\n",
+        ))
+        .stdout(predicate::str::contains(",ECU,LOG,TES2,1,log/info,101,"))
+        .stdout(predicate::str::contains("DLT Version").not())
+        .stderr(predicate::str::contains("DLT Version: 1"));
+
+    Ok(())
+}
+
+#[test]
+fn jsonl_output_format_escapes_multiline_payloads() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("multiline.dlt");
+    let message = MessageBuilder::new()
+        .with_apid("APP1")
+        .with_ctid("CTX1")
+        .with_verbose_string("first\tline\nsecond \"line\"")
+        .build(2)
+        .unwrap();
+    std::fs::write(&path, message)?;
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t").arg("--output-format").arg("jsonl").arg(&path);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("\"payload\":\"first\\tline\\nsecond \\\"line\\\"\""));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_output_format_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t").arg("--output-format").arg("xml").arg(path1);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn fibex_flag_decodes_non_verbose_payloads() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("non_verbose.dlt");
+    let mut arguments = 88u32.to_be_bytes().to_vec();
+    arguments.push(1);
+    let message = MessageBuilder::new()
+        .with_ecu("ECU1")
+        .with_non_verbose(11, &arguments)
+        .build(2)
+        .unwrap();
+    std::fs::write(&path, message)?;
+    let fibex =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_fibex.xml");
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t").arg("--fibex").arg(&fibex).arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ECU1\tCAR\tDRV\t88 true"));
+
+    Ok(())
+}
+
+#[test]
+fn missing_fibex_file_fails() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t")
+        .arg("--fibex")
+        .arg("does_not_exist.xml")
+        .arg(path1);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to read FIBEX file"));
+
+    Ok(())
+}
+
+/// Serve `path` once over TCP the way dlt-daemon sends it: bare messages
+/// without storage headers, written in small chunks.
+fn replay_as_daemon(
+    path: &std::path::Path,
+) -> Result<std::net::SocketAddr, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let (store, _) = dlt_explorer::dlt::open(vec![path.to_path_buf()])?;
+    let stream: Vec<u8> = (0..store.len())
+        .flat_map(|row| store.frame_raw(row)[16..].to_vec())
+        .collect();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    std::thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        for chunk in stream.chunks(7) {
+            if socket.write_all(chunk).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(address)
+}
+
+#[test]
+fn tcp_stream_prints_same_rows_as_file() -> Result<(), Box<dyn std::error::Error>> {
+    let path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let from_file = Command::cargo_bin("dlt-explorer")?
+        .arg("-t")
+        .arg(&path)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let address = replay_as_daemon(&path)?;
+
+    let from_stream = Command::cargo_bin("dlt-explorer")?
+        .arg("-t")
+        .arg("--tcp")
+        .arg(address.to_string())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let from_file = String::from_utf8(from_file)?;
+    let from_stream = String::from_utf8(from_stream)?;
+    // Both start with "Using terminal" and a connection or version status line.
+    // The fixture's messages carry no ECU ID; it only exists in the storage
+    // header, which the daemon does not send, so the ECU column is skipped.
+    let rows = |stdout: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .skip(2)
+            .map(|line| {
+                let mut fields: Vec<&str> = line.split('\t').collect();
+                fields.remove(4);
+                fields.join("\t")
+            })
+            .collect()
+    };
+    assert!(from_stream.contains(&format!("Reading from tcp {address}")));
+    assert_eq!(rows(&from_stream), rows(&from_file));
+
+    Ok(())
+}
+
+#[test]
+fn tcp_stream_stops_at_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let address = replay_as_daemon(&path)?;
+
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    cmd.arg("-t")
+        .arg("--output-format")
+        .arg("jsonl")
+        .arg("--limit")
+        .arg("2")
+        .arg("--tcp")
+        .arg(address.to_string());
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    assert_eq!(String::from_utf8(output)?.lines().count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn tcp_flag_conflicts_with_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t").arg("--tcp").arg("localhost").arg(path1);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn serial_flag_reads_dls_framed_messages_from_pty() -> Result<(), Box<dyn std::error::Error>> {
+    use serialport::SerialPort;
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    let path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let (store, _) = dlt_explorer::dlt::open(vec![path])?;
+    let mut serial = b"\xffnoise".to_vec();
+    for row in 0..2 {
+        serial.extend_from_slice(b"DLS\x01");
+        serial.extend_from_slice(&store.frame_raw(row)[16..]);
+    }
+    let (mut master, slave) = serialport::TTYPort::pair()?;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("dlt-explorer"))
+        .arg("-t")
+        .arg("--limit")
+        .arg("2")
+        .arg("--serial")
+        .arg(slave.name().expect("pty has a name"))
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    // The reader may not be listening yet, so keep sending until it has enough rows.
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait()?.is_none() && Instant::now() < deadline {
+        master.write_all(&serial)?;
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+    }
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("0\t1\tlog\tinfo\t\tLOG\tTES2\t101"));
+    assert!(stdout.contains("1\t1\tlog\tinfo\t\tLOG\tTES2\t102"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_prints_rows_appended_to_the_file() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    let path = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_single_payloads.dlt",
+    );
+    let (store, _) = dlt_explorer::dlt::open(vec![path.clone()])?;
+    let from_file = Command::cargo_bin("dlt-explorer")?
+        .arg("-t")
+        .arg(&path)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let bytes = std::fs::read(&path)?;
+    let dir = tempfile::tempdir()?;
+    let growing = dir.path().join("growing.dlt");
+    let mut file = std::fs::File::create(&growing)?;
+    // Stop in the middle of the second frame, as dlt-receive might.
+    let split = store.frame_raw(0).len() + 10;
+    file.write_all(&bytes[..split])?;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("dlt-explorer"))
+        .arg("-t")
+        .arg("--follow")
+        .arg("--limit")
+        .arg(store.len().to_string())
+        .arg(&growing)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    std::thread::sleep(Duration::from_millis(300));
+    file.write_all(&bytes[split..])?;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait()?.is_none() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+    }
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(from_file)?
+    );
+    assert!(!String::from_utf8(output.stderr)?.contains("parse error"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_waits_for_an_empty_file_to_be_written() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir()?;
+    let growing = dir.path().join("empty.dlt");
+    let mut file = std::fs::File::create(&growing)?;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("dlt-explorer"))
+        .arg("-t")
+        .arg("--follow")
+        .arg("--limit")
+        .arg("1")
+        .arg(&growing)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    std::thread::sleep(Duration::from_millis(300));
+    // Less than a storage header and HTYP byte, then the rest.
+    let frame = MessageBuilder::new()
+        .with_verbose_string("late message")
+        .build(2)?;
+    file.write_all(&frame[..10])?;
+    std::thread::sleep(Duration::from_millis(300));
+    file.write_all(&frame[10..])?;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait()?.is_none() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if child.try_wait()?.is_none() {
+        child.kill()?;
+    }
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Waiting for a first message in"));
+    assert!(stdout.contains("DLT Version: 2"));
+    assert!(stdout.contains("late message"));
+
+    Ok(())
+}
+
+#[test]
+fn follow_flag_conflicts_with_merge() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("dlt-explorer")?;
+    let path1 = PathBuf::from(
+        env!("CARGO_MANIFEST_DIR").to_string() + "/tests/data/testfile_control_messages.dlt",
+    );
+    cmd.arg("-t")
+        .arg("--follow")
+        .arg("--merge")
+        .arg("storage")
+        .arg(path1);
+    cmd.assert().failure();
+
+    Ok(())
+}